@@ -0,0 +1,77 @@
+//! # log_lookup
+//!
+//! why: show that log lookups, and the handlers built on them, cost the same however long the log is
+//! relations: drives raft-core's RaftNode directly, no storage or transport
+//! what: times get_entry, get_term_at, a follower's AppendEntries and a leader's ack handling at growing log lengths
+//!
+//! `cargo bench -p raft-core --bench log_lookup`
+//!
+//! prints ns per call for each log length. before the log was indexed,
+//! and before commits were computed from one sort of the match indexes,
+//! these grew linearly with the length; now each column should stay flat.
+
+use raft_core::{LogEntry, RaftNode};
+use std::hint::black_box;
+use std::time::Instant;
+
+const LENGTHS: [u64; 4] = [1_000, 10_000, 100_000, 1_000_000];
+const ROUNDS: u64 = 10_000;
+
+/// follower of term 1 holding `len` entries
+fn follower(len: u64) -> RaftNode {
+    with_log(2, len)
+}
+
+/// node `id` of term 1 holding `len` entries
+fn with_log(id: u64, len: u64) -> RaftNode {
+    let mut node = RaftNode::new(id, vec![1, 2, 3]);
+    node.current_term = 1;
+    node.log = (1..=len).map(|i| LogEntry::new(1, i, Vec::new())).collect();
+    node
+}
+
+/// leader of term 2 over `len` entries from term 1, plus its no-op
+fn leader(len: u64) -> RaftNode {
+    let mut node = with_log(1, len);
+    node.start_election();
+    node.handle_vote_response(2, true, 3);
+    node.ready();
+    node
+}
+
+/// ns per call of `op(round)` over ROUNDS rounds
+fn time(mut op: impl FnMut(u64)) -> u64 {
+    let start = Instant::now();
+    for round in 0..ROUNDS {
+        op(round);
+    }
+    (start.elapsed().as_nanos() / u128::from(ROUNDS)) as u64
+}
+
+fn main() {
+    println!("{:>10} {:>12} {:>14} {:>12} {:>12}", "entries", "get_entry", "get_term_at", "append", "append ack");
+    for len in LENGTHS {
+        let node = follower(len);
+        let get_entry = time(|round| {
+            black_box(node.get_entry(1 + (round * 7919) % len));
+        });
+        let get_term_at = time(|round| {
+            black_box(node.get_term_at(1 + (round * 7919) % len));
+        });
+
+        // a heartbeat-sized append at the end of the log: the consistency
+        // check and the duplicate check both look entries up
+        let mut node = follower(len);
+        let append = time(|_| {
+            black_box(node.handle_append_entries(1, 1, len - 1, 1, vec![LogEntry::new(1, len, Vec::new())], 0));
+        });
+
+        // acks anywhere in the log: the leader works out what a quorum holds
+        let mut node = leader(len);
+        let ack = time(|round| {
+            black_box(node.handle_append_entries_response(2, true, 3, 1 + (round * 7919) % (len + 1)));
+        });
+
+        println!("{:>10} {:>12} {:>14} {:>12} {:>12}", len, get_entry, get_term_at, append, ack);
+    }
+}
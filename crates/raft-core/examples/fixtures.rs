@@ -0,0 +1,130 @@
+//! # fixtures
+//!
+//! why: freeze what each released format looks like so later versions are tested against it
+//! relations: writes the fixtures read by raft-storage's upgrade tests (crates/raft-storage/tests/fixtures)
+//! what: writes meta.json, log.json, messages.json and snapshot.json for the current format
+//!
+//! `cargo run -p raft-core --example fixtures -- crates/raft-storage/tests/fixtures/v<N>`
+//!
+//! run it once per release that changes a format, with N = the new
+//! raft_storage::FORMAT_VERSION, and check the output in. files that already
+//! exist are never rewritten: a checked-in fixture is a record of what an
+//! older build wrote, and the tests must keep reading it as-is.
+
+use raft_core::{EntryType, LogEntry, RaftMessage, RejoinError, Snapshot, StorageHealth};
+use raft_storage::{FileStorage, Storage};
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// the hard state every fixture directory holds
+const TERM: u64 = 3;
+const VOTED_FOR: u64 = 2;
+
+/// the log every fixture directory holds
+fn log() -> Vec<LogEntry> {
+    vec![LogEntry::new(1, 1, b"set".to_vec()), LogEntry::new(3, 2, Vec::new())]
+}
+
+/// one of every message, fields set to distinct non-default values
+fn messages() -> Vec<RaftMessage> {
+    vec![
+        RaftMessage::PreVoteRequest { term: 4, candidate_id: 2, last_log_index: 2, last_log_term: 3, group_id: Some(1) },
+        RaftMessage::PreVoteResponse { term: 3, vote_granted: true, commit_index: 1, last_log_index: 2, group_id: Some(1) },
+        RaftMessage::VoteRequest { term: 4, candidate_id: 2, last_log_index: 2, last_log_term: 3, transfer: true, group_id: Some(1) },
+        RaftMessage::VoteResponse { term: 4, vote_granted: true, commit_index: 1, last_log_index: 2, group_id: Some(1) },
+        RaftMessage::AppendEntries {
+            term: 4,
+            leader_id: 2,
+            prev_log_index: 1,
+            prev_log_term: 1,
+            entries: vec![
+                LogEntry::new(3, 2, Vec::new()),
+                LogEntry::with_type(4, 3, EntryType::NoOp, Vec::new()),
+                LogEntry::with_type(4, 4, EntryType::Config, b"add 4".to_vec()),
+            ],
+            leader_commit: 2,
+            seq: 6,
+            group_id: Some(1),
+        },
+        RaftMessage::AppendEntriesResponse {
+            term: 4,
+            success: true,
+            match_index: 4,
+            storage_health: Some(StorageHealth { last_fsync_ms: 7, ..StorageHealth::default() }),
+            backoff: true,
+            seq: 6,
+            group_id: Some(1),
+        },
+        RaftMessage::InstallSnapshot { term: 4, leader_id: 2, snapshot: snapshot(), group_id: Some(1) },
+        RaftMessage::InstallSnapshotResponse { term: 4, match_index: 2, group_id: Some(1) },
+        RaftMessage::InstallSnapshotChunk {
+            term: 4,
+            leader_id: 2,
+            last_included_index: 2,
+            last_included_term: 3,
+            offset: 5,
+            data: b"chunk".to_vec(),
+            done: true,
+            group_id: Some(1),
+        },
+        RaftMessage::InstallSnapshotChunkResponse { term: 4, last_included_index: 2, next_offset: 10, group_id: Some(1) },
+        RaftMessage::Heartbeat { term: 4, leader_id: 2, commit: 2, seq: 7, group_id: Some(1) },
+        RaftMessage::HeartbeatResponse { term: 4, last_log_index: 4, seq: 7, group_id: Some(1) },
+        RaftMessage::TimeoutNow { term: 4, group_id: Some(1) },
+        RaftMessage::ReadQuorumRequest { read_id: 9, group_id: Some(1) },
+        RaftMessage::ReadQuorumResponse { read_id: 9, last_log_index: 4, group_id: Some(1) },
+        RaftMessage::RejoinRequest { cluster_id: Some(5), last_log_index: 4, last_log_term: 3, snapshot_index: 2, group_id: Some(1) },
+        RaftMessage::RejoinResponse {
+            term: 4,
+            refusal: Some(RejoinError::SnapshotAhead { snapshot_index: 2, leader_last_index: 1 }),
+            group_id: Some(1),
+        },
+    ]
+}
+
+/// the snapshot every fixture directory holds
+fn snapshot() -> Snapshot {
+    Snapshot::new(2, 3, b"state@2".to_vec())
+}
+
+/// pretty-printed, like the storage files, so fixture diffs stay readable
+fn to_json<T: Serialize>(value: &T) -> io::Result<String> {
+    serde_json::to_string_pretty(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// write `contents` to `dir/name` unless a fixture is already there
+fn write_new(dir: &Path, name: &str, contents: &[u8]) -> io::Result<()> {
+    let path = dir.join(name);
+    if path.exists() {
+        println!("kept    {}", path.display());
+        return Ok(());
+    }
+    fs::write(&path, contents)?;
+    println!("wrote   {}", path.display());
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let Some(dir) = std::env::args().nth(1) else {
+        eprintln!("usage: fixtures <output dir, e.g. crates/raft-storage/tests/fixtures/v2>");
+        std::process::exit(2);
+    };
+    let dir = Path::new(&dir);
+    fs::create_dir_all(dir)?;
+
+    // storage files come from FileStorage itself, so they are exactly what it writes
+    let scratch = std::env::temp_dir().join(format!("raft-fixtures-{}", std::process::id()));
+    let mut storage = FileStorage::new(&scratch)?;
+    storage.save_term_and_vote(TERM, Some(VOTED_FOR))?;
+    storage.append_entries(&log())?;
+    for name in ["meta.json", "log.json"] {
+        write_new(dir, name, &fs::read(scratch.join(name))?)?;
+    }
+    fs::remove_dir_all(&scratch)?;
+
+    write_new(dir, "messages.json", to_json(&messages())?.as_bytes())?;
+    write_new(dir, "snapshot.json", to_json(&snapshot())?.as_bytes())?;
+    Ok(())
+}
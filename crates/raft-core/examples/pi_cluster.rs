@@ -0,0 +1,395 @@
+//! # pi_cluster
+//!
+//! why: run one real cluster member per machine (e.g. the raspberry pi demo) over tcp
+//! relations: drives raft-core's RaftNode, persists through raft-storage's FileStorage
+//! what: config-file driven node with a newline-delimited tcp transport (versioned json messages) and a kv
+//!
+//! run one per machine: `cargo run -p raft-core --example pi_cluster -- node1.json`
+//!
+//! ```json
+//! {
+//!   "node_id": 1,
+//!   "listen": "0.0.0.0:7001",
+//!   "data_dir": "/var/lib/raft/node1",
+//!   "peers": { "2": "192.168.40.5:7001", "3": "192.168.40.6:7001" },
+//!   "capture": "/var/lib/raft/node1/capture.jsonl",
+//!   "max_frame_bytes": 1048576,
+//!   "max_snapshot_frame_bytes": 67108864
+//! }
+//! ```
+//!
+//! then type `set <key> <value>`, `get <key>` or `stats` on any node's stdin.
+//! `capture` is optional: when set, every message is recorded for the
+//! `replay` example. the frame limits are optional too (defaults above):
+//! a peer that sends a longer line is disconnected before the line is
+//! decoded, so it can't make this node buffer more than the limit.
+
+use raft_core::capture::Recorder;
+use raft_core::{RaftMessage, RaftNode, Ready};
+use raft_storage::{FileStorage, Storage};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// how long the node loop waits for input before ticking anyway
+const TICK: Duration = Duration::from_millis(10);
+
+/// per-machine settings, loaded from the json file given on the command line
+#[derive(Deserialize)]
+struct NodeConfig {
+    node_id: u64,
+    /// address to accept peer connections on
+    listen: String,
+    /// directory for FileStorage (term, vote, log)
+    data_dir: String,
+    /// every other member: node id -> address
+    peers: HashMap<u64, String>,
+    /// record every message to this file (see the replay example)
+    #[serde(default)]
+    capture: Option<String>,
+    /// longest line accepted from a peer for anything but InstallSnapshot
+    #[serde(default = "default_max_frame_bytes")]
+    max_frame_bytes: usize,
+    /// longest line accepted from a peer for InstallSnapshot
+    #[serde(default = "default_max_snapshot_frame_bytes")]
+    max_snapshot_frame_bytes: usize,
+}
+
+fn default_max_frame_bytes() -> usize {
+    1 << 20
+}
+
+fn default_max_snapshot_frame_bytes() -> usize {
+    64 << 20
+}
+
+/// one line on the wire: the sender's id, a space, then `RaftMessage::to_wire`
+fn encode_frame(from: u64, msg: &RaftMessage) -> Vec<u8> {
+    let mut frame = format!("{} ", from).into_bytes();
+    frame.extend(msg.to_wire());
+    frame.push(b'\n');
+    frame
+}
+
+/// read back a line written by `encode_frame`
+fn decode_frame(frame: &[u8]) -> Option<(u64, RaftMessage)> {
+    let space = frame.iter().position(|&b| b == b' ')?;
+    let from = std::str::from_utf8(&frame[..space]).ok()?.parse().ok()?;
+    let msg = RaftMessage::from_wire(&frame[space + 1..]).ok()?;
+    Some((from, msg))
+}
+
+/// everything the node loop reacts to
+enum Input {
+    Raft { from: u64, msg: RaftMessage },
+    Command(String),
+}
+
+// -- tcp transport --
+
+/// maximum line lengths for incoming frames
+#[derive(Clone, Copy)]
+struct FrameLimits {
+    rpc: usize,
+    snapshot: usize,
+}
+
+/// what the listener accepted and refused, shared by every connection
+#[derive(Default)]
+struct TransportMetrics {
+    frames: AtomicU64,
+    oversized_rpc: AtomicU64,
+    oversized_snapshot: AtomicU64,
+    malformed: AtomicU64,
+}
+
+impl TransportMetrics {
+    fn print(&self) {
+        println!(
+            "transport: {} frames, {} oversized rpc, {} oversized snapshot, {} malformed",
+            self.frames.load(Ordering::Relaxed),
+            self.oversized_rpc.load(Ordering::Relaxed),
+            self.oversized_snapshot.load(Ordering::Relaxed),
+            self.malformed.load(Ordering::Relaxed),
+        );
+    }
+}
+
+/// the wire encoding writes the type first: `1 {"message_version":1,"type":"install_snapshot",...`
+fn is_snapshot_frame(prefix: &[u8]) -> bool {
+    const TAG: &[u8] = b"\"type\":\"install_snapshot\"";
+    let header = &prefix[..prefix.len().min(64)];
+    header.windows(TAG.len()).any(|w| w == TAG)
+}
+
+/// read one newline-terminated frame, never buffering more than the limit
+///
+/// Ok(None) on a clean end of stream. a frame that runs past its limit is an
+/// InvalidData error: the rest of the line is never read, so the connection
+/// can't be resynchronized and the caller drops it
+fn read_frame(
+    reader: &mut impl BufRead,
+    limits: FrameLimits,
+    metrics: &TransportMetrics,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut frame = Vec::new();
+    // only snapshots may grow past the rpc limit, and the header names the
+    // message type long before that limit is reached
+    (&mut *reader).take(limits.rpc as u64 + 1).read_until(b'\n', &mut frame)?;
+    if frame.is_empty() {
+        return Ok(None);
+    }
+    if frame.last() != Some(&b'\n') && frame.len() > limits.rpc {
+        let (limit, counter) = if is_snapshot_frame(&frame) {
+            let rest = (limits.snapshot + 1).saturating_sub(frame.len());
+            (&mut *reader).take(rest as u64).read_until(b'\n', &mut frame)?;
+            (limits.snapshot, &metrics.oversized_snapshot)
+        } else {
+            (limits.rpc, &metrics.oversized_rpc)
+        };
+        if frame.last() != Some(&b'\n') && frame.len() > limit {
+            counter.fetch_add(1, Ordering::Relaxed);
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("frame exceeds the {} byte limit", limit),
+            ));
+        }
+    }
+    Ok(Some(frame))
+}
+
+/// accept peer connections and forward every decoded message to the node loop
+fn spawn_listener(
+    listen: &str,
+    limits: FrameLimits,
+    metrics: Arc<TransportMetrics>,
+    inbox: Sender<Input>,
+) -> io::Result<()> {
+    let listener = TcpListener::bind(listen)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let inbox = inbox.clone();
+            let metrics = metrics.clone();
+            thread::spawn(move || {
+                let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+                let mut reader = BufReader::new(stream);
+                loop {
+                    let frame = match read_frame(&mut reader, limits, &metrics) {
+                        Ok(Some(frame)) => frame,
+                        Ok(None) => return,
+                        Err(err) => {
+                            eprintln!("dropping connection from {}: {}", peer, err);
+                            return;
+                        }
+                    };
+                    let Some((from, msg)) = decode_frame(&frame) else {
+                        metrics.malformed.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    };
+                    metrics.frames.fetch_add(1, Ordering::Relaxed);
+                    if inbox.send(Input::Raft { from, msg }).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+/// one outbound connection per peer, reconnecting lazily
+///
+/// messages that can't be written are dropped: raft retries on its own
+fn spawn_sender(from: u64, addr: String) -> Sender<RaftMessage> {
+    let (tx, rx) = mpsc::channel::<RaftMessage>();
+    thread::spawn(move || {
+        let mut conn: Option<TcpStream> = None;
+        for msg in rx {
+            if conn.is_none() {
+                conn = TcpStream::connect(&addr).ok();
+            }
+            let Some(stream) = conn.as_mut() else { continue };
+            if stream.write_all(&encode_frame(from, &msg)).is_err() {
+                conn = None;
+            }
+        }
+    });
+    tx
+}
+
+// -- node loop --
+
+struct NodeHost {
+    node: RaftNode,
+    storage: FileStorage,
+    peers: HashMap<u64, Sender<RaftMessage>>,
+    kv: HashMap<String, String>,
+    /// highest log index applied to `kv`
+    applied: u64,
+    /// reads confirmed by ReadIndex, waiting for `applied` to catch up
+    confirmed_reads: Vec<(u64, String)>,
+    /// message capture, if enabled in the config
+    recorder: Option<Recorder<BufWriter<File>>>,
+    /// listener counters, printed by `stats`
+    transport: Arc<TransportMetrics>,
+}
+
+impl NodeHost {
+    fn run(mut self, inbox: Receiver<Input>) -> io::Result<()> {
+        let mut last_tick = Instant::now();
+        loop {
+            match inbox.recv_timeout(TICK) {
+                Ok(Input::Raft { from, msg }) => {
+                    let ready = match self.recorder.as_mut() {
+                        Some(recorder) => recorder.step(&mut self.node, from, msg)?,
+                        None => self.node.step(from, msg),
+                    };
+                    self.handle_ready(ready)?;
+                }
+                Ok(Input::Command(line)) => self.command(&line)?,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+
+            let elapsed = last_tick.elapsed().as_millis() as u64;
+            if elapsed > 0 {
+                last_tick += Duration::from_millis(elapsed);
+                let ready = match self.recorder.as_mut() {
+                    Some(recorder) => recorder.tick(&mut self.node, elapsed)?,
+                    None => self.node.tick(elapsed),
+                };
+                self.handle_ready(ready)?;
+            }
+        }
+    }
+
+    /// handle one stdin line: `set <key> <value>` or `get <key>`
+    fn command(&mut self, line: &str) -> io::Result<()> {
+        let mut parts = line.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some("set"), Some(_)) => {
+                if let Err(err) = self.node.propose(line.trim().as_bytes().to_vec()) {
+                    println!("{}; try another node", err);
+                    return Ok(());
+                }
+                let ready = self.node.replicate();
+                if let Some(recorder) = self.recorder.as_mut() {
+                    recorder.record_sent(&self.node, &ready)?;
+                }
+                return self.handle_ready(ready);
+            }
+            (Some("get"), Some(key)) => {
+                if !self.node.read_index(key.as_bytes().to_vec()) {
+                    println!("not leader (this node is {:?}); try another node", self.node.state);
+                    return Ok(());
+                }
+            }
+            (Some("stats"), None) => {
+                self.transport.print();
+                return Ok(());
+            }
+            _ => println!("usage: set <key> <value> | get <key> | stats"),
+        }
+
+        let ready = self.node.ready();
+        self.handle_ready(ready)
+    }
+
+    /// persist, then send, then apply, then answer reads
+    fn handle_ready(&mut self, ready: Ready) -> io::Result<()> {
+        raft_storage::persist_ready(&mut self.storage, &ready)?;
+
+        for (to, msg) in ready.messages {
+            if let Some(peer) = self.peers.get(&to) {
+                let _ = peer.send(msg);
+            }
+        }
+
+        for entry in &ready.committed_entries {
+            let command = String::from_utf8_lossy(&entry.command);
+            let mut parts = command.splitn(3, ' ');
+            if let (Some("set"), Some(key), Some(value)) = (parts.next(), parts.next(), parts.next()) {
+                self.kv.insert(key.to_string(), value.to_string());
+                println!("applied #{}: {} = {}", entry.index, key, value);
+            }
+            self.applied = entry.index;
+        }
+
+        for state in ready.read_states {
+            self.confirmed_reads.push((state.index, String::from_utf8_lossy(&state.request_ctx).into_owned()));
+        }
+        let applied = self.applied;
+        let kv = &self.kv;
+        self.confirmed_reads.retain(|(index, key)| {
+            if *index > applied {
+                return true;
+            }
+            println!("{} = {:?}", key, kv.get(key));
+            false
+        });
+        Ok(())
+    }
+}
+
+fn main() -> io::Result<()> {
+    let path = std::env::args().nth(1).unwrap_or_else(|| "node.json".to_string());
+    let config: NodeConfig = serde_json::from_str(&std::fs::read_to_string(&path)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    // restore persistent state from the previous run
+    let storage = FileStorage::new(&config.data_dir)?;
+    let mut cluster: Vec<u64> = config.peers.keys().copied().collect();
+    cluster.push(config.node_id);
+    cluster.sort_unstable();
+    let mut node = RaftNode::new(config.node_id, cluster);
+    (node.current_term, node.voted_for) = storage.load_term_and_vote()?;
+    node.log = storage.load_log()?;
+    println!("node {} restored term {} with {} log entries", node.id, node.current_term, node.log.len());
+
+    let (inbox_tx, inbox) = mpsc::channel();
+    let limits = FrameLimits {
+        rpc: config.max_frame_bytes,
+        snapshot: config.max_snapshot_frame_bytes.max(config.max_frame_bytes),
+    };
+    let transport = Arc::new(TransportMetrics::default());
+    spawn_listener(&config.listen, limits, transport.clone(), inbox_tx.clone())?;
+
+    let stdin_tx = inbox_tx;
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines().map_while(Result::ok) {
+            if stdin_tx.send(Input::Command(line)).is_err() {
+                return;
+            }
+        }
+    });
+
+    let recorder = match &config.capture {
+        Some(path) => Some(Recorder::new(BufWriter::new(File::create(path)?))),
+        None => None,
+    };
+
+    let peers = config
+        .peers
+        .into_iter()
+        .map(|(id, addr)| (id, spawn_sender(config.node_id, addr)))
+        .collect();
+
+    NodeHost {
+        node,
+        storage,
+        peers,
+        kv: HashMap::new(),
+        applied: 0,
+        confirmed_reads: Vec::new(),
+        recorder,
+        transport,
+    }
+    .run(inbox)
+}
@@ -0,0 +1,57 @@
+//! # replay
+//!
+//! why: reproduce a captured production anomaly deterministically against a candidate fix
+//! relations: reads captures written by raft_core::capture::Recorder (e.g. pi_cluster's `capture`)
+//! what: feeds one node's inbound messages into a fresh RaftNode and prints every state change
+//!
+//! `cargo run -p raft-core --example replay -- capture.jsonl <node_id> <cluster ids, e.g. 1,2,3>`
+
+use raft_core::capture::{read_capture, replay};
+use raft_core::RaftNode;
+use std::fs::File;
+use std::io::{self, BufReader};
+
+fn main() -> io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let (Some(path), Some(node_id), Some(cluster)) = (args.next(), args.next(), args.next()) else {
+        eprintln!("usage: replay <capture.jsonl> <node_id> <cluster ids, e.g. 1,2,3>");
+        std::process::exit(2);
+    };
+    let invalid = |e| io::Error::new(io::ErrorKind::InvalidInput, e);
+    let node_id: u64 = node_id.parse().map_err(invalid)?;
+    let cluster = cluster
+        .split(',')
+        .map(|id| id.trim().parse())
+        .collect::<Result<Vec<u64>, _>>()
+        .map_err(invalid)?;
+
+    let capture = read_capture(BufReader::new(File::open(&path)?))?;
+    let inbound = capture.iter().filter(|c| c.to == node_id).count();
+    println!("{}: {} messages, {} to node {}", path, capture.len(), inbound, node_id);
+
+    let mut node = RaftNode::new(node_id, cluster);
+    let mut last = None;
+    for (i, captured) in capture.iter().enumerate() {
+        // one message at a time, so the state can be printed in between
+        for ready in replay(&mut node, std::slice::from_ref(captured)) {
+            // print only steps that changed something visible
+            let now = (node.state, node.current_term, node.last_log_index(), node.commit_index);
+            if last == Some(now) && ready.committed_entries.is_empty() {
+                continue;
+            }
+            println!(
+                "#{:<5} t={:>6}ms {:?} term {} last {} commit {} (+{} applied, {} sent)",
+                i,
+                node.now_ms(),
+                node.state,
+                node.current_term,
+                node.last_log_index(),
+                node.commit_index,
+                ready.committed_entries.len(),
+                ready.messages.len()
+            );
+            last = Some(now);
+        }
+    }
+    Ok(())
+}
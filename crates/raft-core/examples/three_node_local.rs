@@ -0,0 +1,271 @@
+//! # three_node_local
+//!
+//! why: show the whole propose → replicate → apply → read path in one runnable file
+//! relations: drives raft-core's RaftNode through step/tick/ready, no wasm or browser needed
+//! what: 3 node threads wired with std channels, a tiny key-value state machine, a client
+//!
+//! run with: `cargo run -p raft-core --example three_node_local`
+
+use raft_core::{LogEntry, NodeState, RaftMessage, RaftNode, Ready, StateMachine};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// how long a node thread waits for input before ticking anyway
+const TICK: Duration = Duration::from_millis(10);
+
+/// everything a node thread can be asked to do
+enum Input {
+    /// a raft message from a peer
+    Raft { from: u64, msg: RaftMessage },
+    /// a client write ("set key value"); replies false if we're not leader
+    Propose { command: String, reply: Sender<bool> },
+    /// a linearizable client read; replies None if we're not leader
+    Read { key: String, reply: Sender<Option<Option<String>>> },
+    /// report our current role
+    Status { reply: Sender<NodeState> },
+    /// stop the node thread
+    Shutdown,
+}
+
+// -- channel transport --
+
+/// one sender per node, shared by every node thread and the client
+#[derive(Clone)]
+struct Transport {
+    peers: HashMap<u64, Sender<Input>>,
+}
+
+impl Transport {
+    fn send(&self, from: u64, to: u64, msg: RaftMessage) {
+        if let Some(peer) = self.peers.get(&to) {
+            // a stopped peer just drops the message, like a dead link
+            let _ = peer.send(Input::Raft { from, msg });
+        }
+    }
+}
+
+// -- key-value state machine --
+
+#[derive(Default)]
+struct KvStore {
+    data: HashMap<String, String>,
+}
+
+impl StateMachine for KvStore {
+    fn apply(&mut self, entry: &LogEntry) -> Vec<u8> {
+        let command = String::from_utf8_lossy(&entry.command);
+        let mut parts = command.splitn(3, ' ');
+        if let (Some("set"), Some(key), Some(value)) = (parts.next(), parts.next(), parts.next()) {
+            self.data.insert(key.to_string(), value.to_string());
+        }
+        Vec::new()
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        let mut pairs: Vec<_> = self.data.iter().map(|(k, v)| format!("{} {}", k, v)).collect();
+        pairs.sort();
+        pairs.join("\n").into_bytes()
+    }
+
+    fn restore(&mut self, data: &[u8]) {
+        self.data = String::from_utf8_lossy(data)
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+    }
+}
+
+// -- node thread --
+
+/// a read that has been confirmed (or is waiting) and must be answered
+struct PendingRead {
+    key: String,
+    reply: Sender<Option<Option<String>>>,
+}
+
+struct NodeHost {
+    node: RaftNode,
+    kv: KvStore,
+    transport: Transport,
+    /// reads waiting for their ReadIndex round, by request context
+    reads: HashMap<u64, PendingRead>,
+    /// confirmed reads waiting for the state machine to catch up
+    confirmed: Vec<(u64, PendingRead)>,
+    next_read_id: u64,
+}
+
+impl NodeHost {
+    fn run(mut self, inbox: Receiver<Input>) {
+        let mut last_tick = Instant::now();
+        loop {
+            match inbox.recv_timeout(TICK) {
+                Ok(Input::Raft { from, msg }) => {
+                    let ready = self.node.step(from, msg);
+                    self.handle_ready(ready);
+                }
+                Ok(Input::Propose { command, reply }) => {
+                    let _ = reply.send(self.propose(command));
+                }
+                Ok(Input::Read { key, reply }) => self.read(key, reply),
+                Ok(Input::Status { reply }) => {
+                    let _ = reply.send(self.node.state);
+                }
+                Ok(Input::Shutdown) | Err(RecvTimeoutError::Disconnected) => return,
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+
+            // feed real elapsed time into the node's timers
+            let elapsed = last_tick.elapsed().as_millis() as u64;
+            if elapsed > 0 {
+                last_tick += Duration::from_millis(elapsed);
+                let ready = self.node.tick(elapsed);
+                self.handle_ready(ready);
+            }
+        }
+    }
+
+    /// append a client command and push it to every follower
+    fn propose(&mut self, command: String) -> bool {
+        if self.node.propose(command.into_bytes()).is_err() {
+            return false;
+        }
+        for peer in self.node.cluster_nodes.clone() {
+            if let Some(msg) = self.node.create_append_entries(peer) {
+                self.transport.send(self.node.id, peer, msg);
+            }
+        }
+        let ready = self.node.ready();
+        self.handle_ready(ready);
+        true
+    }
+
+    /// start a ReadIndex round for a client read
+    fn read(&mut self, key: String, reply: Sender<Option<Option<String>>>) {
+        let id = self.next_read_id;
+        self.next_read_id += 1;
+        if self.node.read_index(id.to_be_bytes().to_vec()) {
+            self.reads.insert(id, PendingRead { key, reply });
+        } else {
+            let _ = reply.send(None);
+        }
+        let ready = self.node.ready();
+        self.handle_ready(ready);
+    }
+
+    /// process a Ready in the documented order
+    fn handle_ready(&mut self, mut ready: Ready) {
+        // 1. persist: this example keeps everything in memory, so there is
+        //    nothing to write; a real host writes hard_state and entries here
+        //    (raft_storage::persist_ready) whenever ready.must_sync() is true
+
+        // 2. send
+        for (to, msg) in ready.messages.drain(..) {
+            self.transport.send(self.node.id, to, msg);
+        }
+
+        // 3. apply
+        for (index, _) in self.node.apply_ready(&ready, &mut self.kv) {
+            if self.node.state == NodeState::Leader {
+                println!("[node {}] applied #{}", self.node.id, index);
+            }
+        }
+
+        // 4. answer reads once the state machine has caught up
+        for state in ready.read_states {
+            let id = u64::from_be_bytes(state.request_ctx.try_into().unwrap_or_default());
+            if let Some(read) = self.reads.remove(&id) {
+                self.confirmed.push((state.index, read));
+            }
+        }
+        let applied = self.node.last_applied;
+        let (ready_now, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.confirmed)
+            .into_iter()
+            .partition(|(index, _)| *index <= applied);
+        self.confirmed = waiting;
+        for (_, read) in ready_now {
+            let _ = read.reply.send(Some(self.kv.data.get(&read.key).cloned()));
+        }
+    }
+}
+
+// -- client side --
+
+/// ask every node for its role until one claims leadership
+fn wait_for_leader(transport: &Transport) -> u64 {
+    loop {
+        for (&id, peer) in &transport.peers {
+            let (reply, answer) = mpsc::channel();
+            let _ = peer.send(Input::Status { reply });
+            if answer.recv_timeout(Duration::from_secs(1)) == Ok(NodeState::Leader) {
+                return id;
+            }
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn main() {
+    let ids = vec![1, 2, 3];
+
+    let mut inboxes = HashMap::new();
+    let mut peers = HashMap::new();
+    for &id in &ids {
+        let (tx, rx) = mpsc::channel();
+        peers.insert(id, tx);
+        inboxes.insert(id, rx);
+    }
+    let transport = Transport { peers };
+
+    let mut handles = Vec::new();
+    for &id in &ids {
+        let host = NodeHost {
+            node: RaftNode::new(id, ids.clone()),
+            kv: KvStore::default(),
+            transport: transport.clone(),
+            reads: HashMap::new(),
+            confirmed: Vec::new(),
+            next_read_id: 1,
+        };
+        let inbox = inboxes.remove(&id).unwrap();
+        handles.push(thread::spawn(move || host.run(inbox)));
+    }
+
+    let leader = wait_for_leader(&transport);
+    println!("node {} is leader", leader);
+
+    for command in ["set color blue", "set shape circle", "set color green"] {
+        let (reply, accepted) = mpsc::channel();
+        transport.peers[&leader]
+            .send(Input::Propose { command: command.to_string(), reply })
+            .unwrap();
+        println!("propose {:?}: accepted = {}", command, accepted.recv().unwrap());
+    }
+
+    // a linearizable read only returns once the write above has been applied
+    for key in ["color", "shape", "size"] {
+        loop {
+            let (reply, answer) = mpsc::channel();
+            transport.peers[&leader]
+                .send(Input::Read { key: key.to_string(), reply })
+                .unwrap();
+            match answer.recv_timeout(Duration::from_secs(2)) {
+                Ok(Some(value)) => {
+                    println!("read {:?} = {:?}", key, value);
+                    break;
+                }
+                // not confirmed yet (e.g. first commit still in flight): retry
+                _ => thread::sleep(Duration::from_millis(20)),
+            }
+        }
+    }
+
+    for peer in transport.peers.values() {
+        let _ = peer.send(Input::Shutdown);
+    }
+    for handle in handles {
+        let _ = handle.join();
+    }
+}
@@ -0,0 +1,114 @@
+//! # capture
+//!
+//! why: replay a node's real message stream against a fresh node to reproduce anomalies
+//! relations: wraps RaftNode::step/tick on the host side, replays through the same calls
+//! what: CapturedMessage, Recorder (newline-delimited json), read_capture, replay
+
+use crate::{RaftMessage, RaftNode, Ready};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+
+/// One message seen by a node, stamped with the node's logical clock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedMessage {
+    /// `RaftNode::now_ms()` on the recording node when the message passed
+    pub at_ms: u64,
+    pub from: u64,
+    pub to: u64,
+    pub msg: RaftMessage,
+}
+
+/// borrowed form written by the recorder, so recording never clones a message
+#[derive(Serialize)]
+struct CapturedRef<'a> {
+    at_ms: u64,
+    from: u64,
+    to: u64,
+    msg: &'a RaftMessage,
+}
+
+/// Records every message a node receives and sends as newline-delimited JSON
+///
+/// Drive the node through `step` and `tick` here instead of on the node
+/// directly; both return the Ready exactly as the node produced it.
+pub struct Recorder<W: Write> {
+    out: W,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Record into `out` (a file, a socket, a Vec in tests)
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// Record a single message
+    pub fn record(&mut self, at_ms: u64, from: u64, to: u64, msg: &RaftMessage) -> io::Result<()> {
+        let line = CapturedRef { at_ms, from, to, msg };
+        serde_json::to_writer(&mut self.out, &line)?;
+        self.out.write_all(b"\n")
+    }
+
+    /// Record an inbound message, step the node with it, and record its replies
+    pub fn step(&mut self, node: &mut RaftNode, from: u64, msg: RaftMessage) -> io::Result<Ready> {
+        self.record(node.now_ms(), from, node.id, &msg)?;
+        let ready = node.step(from, msg);
+        self.record_sent(node, &ready)?;
+        Ok(ready)
+    }
+
+    /// Tick the node and record whatever it sends
+    pub fn tick(&mut self, node: &mut RaftNode, elapsed_ms: u64) -> io::Result<Ready> {
+        let ready = node.tick(elapsed_ms);
+        self.record_sent(node, &ready)?;
+        Ok(ready)
+    }
+
+    /// Record the outbox of a Ready the node produced
+    pub fn record_sent(&mut self, node: &RaftNode, ready: &Ready) -> io::Result<()> {
+        for (to, msg) in &ready.messages {
+            self.record(node.now_ms(), node.id, *to, msg)?;
+        }
+        self.out.flush()
+    }
+
+    /// Stop recording and hand back the writer
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Read a capture written by `Recorder`
+pub fn read_capture<R: BufRead>(input: R) -> io::Result<Vec<CapturedMessage>> {
+    let mut messages = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message = serde_json::from_str(&line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        messages.push(message);
+    }
+    Ok(messages)
+}
+
+/// Feed the messages `node` received during a capture back through it
+///
+/// Messages addressed to other nodes are skipped. Before each one, the
+/// node's clock is ticked forward to the message's capture time, so
+/// elections and heartbeats fire in the same order they did live. Returns
+/// every Ready the node produced, in order. Proposals made directly on
+/// the node (`append_entry`) aren't messages, so a leader's own writes
+/// are not part of a capture.
+pub fn replay(node: &mut RaftNode, capture: &[CapturedMessage]) -> Vec<Ready> {
+    let mut readies = Vec::new();
+    let id = node.id;
+    for captured in capture.iter().filter(|c| c.to == id) {
+        let behind = captured.at_ms.saturating_sub(node.now_ms());
+        if behind > 0 {
+            readies.push(node.tick(behind));
+        }
+        readies.push(node.step(captured.from, captured.msg.clone()));
+    }
+    readies
+}
@@ -0,0 +1,87 @@
+//! # inflights
+//!
+//! why: let the leader pipeline AppendEntries without resending blindly or stalling
+//! relations: one window per follower, owned by RaftNode in node.rs, freed by append responses
+//! what: Inflights window of unacknowledged messages (count and bytes)
+
+use std::collections::VecDeque;
+
+/// messages sent to one follower and not yet acknowledged
+///
+/// every AppendEntries carrying entries occupies a slot until a response
+/// proves the follower holds its last entry. once the window is full (by
+/// message count or bytes) the leader stops sending new entries to that
+/// follower; acknowledgements free slots oldest first.
+#[derive(Debug, Clone)]
+pub struct Inflights {
+    /// (first index, last index, bytes) carried per outstanding message, oldest first
+    window: VecDeque<(u64, u64, u64)>,
+    /// bytes carried by everything in the window
+    bytes: u64,
+    /// most messages allowed in flight
+    max_msgs: usize,
+    /// most bytes allowed in flight (None = unbounded)
+    max_bytes: Option<u64>,
+}
+
+impl Inflights {
+    /// an empty window with the given limits
+    pub fn new(max_msgs: usize, max_bytes: Option<u64>) -> Self {
+        Self {
+            window: VecDeque::new(),
+            bytes: 0,
+            max_msgs,
+            max_bytes,
+        }
+    }
+
+    /// record a message carrying entries `first_index..=last_index`
+    pub fn add(&mut self, first_index: u64, last_index: u64, bytes: u64) {
+        self.window.push_back((first_index, last_index, bytes));
+        self.bytes += bytes;
+    }
+
+    /// free every message whose entries end at or before `index`
+    pub fn free_to(&mut self, index: u64) {
+        while let Some(&(_, last, bytes)) = self.window.front() {
+            if last > index {
+                break;
+            }
+            self.window.pop_front();
+            self.bytes -= bytes;
+        }
+    }
+
+    /// forget everything in flight (the follower rejected, or we lost track)
+    pub fn reset(&mut self) {
+        self.window.clear();
+        self.bytes = 0;
+    }
+
+    /// true if no more messages may be sent
+    ///
+    /// the byte limit only blocks once something is in flight, so a
+    /// single oversized message can still make progress
+    pub fn is_full(&self) -> bool {
+        self.window.len() >= self.max_msgs
+            || self
+                .max_bytes
+                .is_some_and(|max| !self.window.is_empty() && self.bytes >= max)
+    }
+
+    /// first index carried by the oldest unacknowledged message
+    pub fn oldest_first_index(&self) -> Option<u64> {
+        self.window.front().map(|&(first, _, _)| first)
+    }
+
+    /// messages in flight
+    pub fn count(&self) -> usize {
+        self.window.len()
+    }
+
+    /// bytes in flight
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
@@ -0,0 +1,200 @@
+//! # invariants
+//!
+//! why: catch integration bugs at the step that caused them, not three elections later
+//! relations: compiled only with the `strict-checks` feature (alias `strict-invariants`), called from RaftNode::ready
+//! what: check() asserting log/commit/term/vote invariants, panicking with a state dump
+
+use crate::RaftNode;
+use std::fmt::Write;
+
+/// how many trailing log entries to include in a state dump
+const DUMP_LOG_TAIL: usize = 10;
+
+/// hard state as of the previous check, to catch changes that go backwards
+#[derive(Debug, Default)]
+pub(crate) struct Seen {
+    term: u64,
+    voted_for: Option<u64>,
+}
+
+/// assert every invariant the node must hold between steps
+///
+/// - log indices are contiguous and start right after the snapshot
+/// - no entry has a term above current_term (a leader only appends in its own term)
+/// - commit_index <= last log index
+/// - last_applied <= commit_index
+/// - current_term never decreases
+/// - at most one vote per term: once cast, it is neither changed nor
+///   forgotten until the term moves on
+pub(crate) fn check(node: &RaftNode, seen: &mut Seen) {
+    if node.current_term < seen.term {
+        violated(node, &format!("term went back from {} to {}", seen.term, node.current_term));
+    }
+    if node.current_term == seen.term && seen.voted_for.is_some() && node.voted_for != seen.voted_for {
+        violated(node, &format!(
+            "vote in term {} changed from {:?} to {:?}",
+            node.current_term, seen.voted_for, node.voted_for
+        ));
+    }
+    seen.term = node.current_term;
+    seen.voted_for = node.voted_for;
+
+    if let Some(first) = node.log.first() {
+        if node.snapshot.is_some() && node.spilled_index() <= node.snapshot_index() && first.index != node.snapshot_index() + 1 {
+            violated(node, &format!(
+                "log starts at {} but the snapshot ends at {}",
+                first.index,
+                node.snapshot_index()
+            ));
+        }
+        if node.spilled_index() > node.snapshot_index() && first.index != node.spilled_index() + 1 {
+            violated(node, &format!(
+                "log starts at {} but spilled entries end at {}",
+                first.index,
+                node.spilled_index()
+            ));
+        }
+        for (offset, entry) in node.log.iter().enumerate() {
+            let expected = first.index + offset as u64;
+            if entry.index != expected {
+                violated(node, &format!(
+                    "log index gap: position {} holds index {}, expected {}",
+                    offset, entry.index, expected
+                ));
+            }
+        }
+    }
+
+    if let Some(entry) = node.log.iter().find(|e| e.term > node.current_term) {
+        violated(node, &format!(
+            "entry {} has term {} above current term {}",
+            entry.index, entry.term, node.current_term
+        ));
+    }
+
+    if node.commit_index > node.last_log_index() {
+        violated(node, &format!(
+            "commit_index {} beyond last log index {}",
+            node.commit_index,
+            node.last_log_index()
+        ));
+    }
+
+    if node.commit_index < node.snapshot_index() {
+        violated(node, &format!(
+            "commit_index {} below snapshot index {}",
+            node.commit_index,
+            node.snapshot_index()
+        ));
+    }
+
+    if node.last_applied > node.commit_index {
+        violated(node, &format!(
+            "last_applied {} beyond commit_index {}",
+            node.last_applied, node.commit_index
+        ));
+    }
+}
+
+/// panic with the broken invariant and enough state to reproduce it
+fn violated(node: &RaftNode, what: &str) -> ! {
+    panic!("raft invariant violated on node {}: {}\n{}", node.id, what, dump(node));
+}
+
+/// render the node's state for a panic message
+fn dump(node: &RaftNode) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "  state:        {:?} (term {}, voted_for {:?})", node.state, node.current_term, node.voted_for);
+    let _ = writeln!(out, "  commit/apply: {} / {}", node.commit_index, node.last_applied);
+    let _ = writeln!(out, "  last log:     index {} term {} ({} entries)", node.last_log_index(), node.last_log_term(), node.log.len());
+    let _ = writeln!(out, "  snapshot:     index {} term {}", node.snapshot_index(), node.snapshot_term());
+    let _ = writeln!(out, "  cluster:      {:?}", node.cluster_nodes);
+
+    let mut peers: Vec<_> = node.progress.keys().copied().collect();
+    peers.sort_unstable();
+    for peer in peers {
+        let progress = &node.progress[&peer];
+        let _ = writeln!(
+            out,
+            "  peer {}:       next {} match {} ({:?})",
+            peer, progress.next_index, progress.match_index, progress.state
+        );
+    }
+
+    let tail = node.log.len().saturating_sub(DUMP_LOG_TAIL);
+    for entry in &node.log[tail..] {
+        let _ = writeln!(out, "  log[{}]:       term {} ({} bytes)", entry.index, entry.term, entry.command.len());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{LogEntry, RaftNode};
+
+    #[test]
+    fn healthy_node_passes() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        node.append_entry(b"x".to_vec());
+        node.ready();
+    }
+
+    #[test]
+    #[should_panic(expected = "commit_index 5 beyond last log index 0")]
+    fn commit_past_log_panics() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.commit_index = 5;
+        node.ready();
+    }
+
+    #[test]
+    #[should_panic(expected = "log starts at 4 but the snapshot ends at 2")]
+    fn log_detached_from_snapshot_panics() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.current_term = 1;
+        node.restore_snapshot(crate::Snapshot::new(2, 1, vec![]));
+        node.log.push(LogEntry::new(1, 4, vec![]));
+        node.ready();
+    }
+
+    #[test]
+    #[should_panic(expected = "log index gap")]
+    fn gap_in_log_panics() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.current_term = 1;
+        node.log.push(LogEntry::new(1, 1, vec![]));
+        node.log.push(LogEntry::new(1, 3, vec![]));
+        node.ready();
+    }
+
+    #[test]
+    #[should_panic(expected = "above current term")]
+    fn entry_from_future_term_panics() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.log.push(LogEntry::new(4, 1, vec![]));
+        node.ready();
+    }
+
+    #[test]
+    #[should_panic(expected = "term went back from 3 to 2")]
+    fn term_going_back_panics() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.current_term = 3;
+        node.ready();
+        node.current_term = 2;
+        node.ready();
+    }
+
+    #[test]
+    #[should_panic(expected = "vote in term 2 changed from Some(2) to Some(3)")]
+    fn second_vote_in_a_term_panics() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.current_term = 2;
+        node.voted_for = Some(2);
+        node.ready();
+        node.voted_for = Some(3);
+        node.ready();
+    }
+}
@@ -2,12 +2,33 @@
 //!
 //! why: implement the core raft consensus algorithm in pure, portable rust
 //! relations: used by raft-wasm for browser execution, raft-storage for persistence
-//! what: state machine, election logic, log management, message types
+//! what: state machine, election logic, log management, message types, snapshots
 
+pub mod capture;
+pub mod inflights;
+#[cfg(feature = "strict-checks")]
+mod invariants;
 pub mod log;
 pub mod message;
 pub mod node;
+pub mod progress;
+pub mod quorum;
+pub mod read_only;
+pub mod ready;
+pub mod session;
+pub mod snapshot;
+pub mod state_machine;
+pub mod testing;
+pub mod wire;
 
-pub use node::{NodeState, RaftNode, RaftConfig};
-pub use message::RaftMessage;
-pub use log::LogEntry;
+pub use node::{CatchUpProgress, CompactError, ConfChangeError, ConfigError, NodeState, PauseMode, PromoteError, ProposeError, RaftConfigBuilder, RaftNode, RaftConfig};
+pub use message::{RaftMessage, RejoinError, StorageHealth};
+pub use log::{ChainBreak, ChangelogError, ColdLog, CommitProof, CommitStatus, EntryType, LogEntry, Membership, ProposalOutcome};
+pub use read_only::{ReadMode, ReadState};
+pub use progress::{Progress, ProgressState};
+pub use quorum::{QuorumKind, QuorumPolicy};
+pub use ready::{HardState, Ready, SoftState};
+pub use session::{ClientRequest, Dedup, SessionTable};
+pub use snapshot::{Snapshot, SnapshotMetadata, SnapshotSealer};
+pub use state_machine::StateMachine;
+pub use wire::{WireError, MESSAGE_VERSION};
@@ -2,9 +2,97 @@
 //!
 //! why: manage the append-only log of commands that raft replicates
 //! relations: used by node.rs for replication, persisted via raft-storage
-//! what: LogEntry struct, log consistency checking, commit index management
+//! what: LogEntry struct (with optional client session and chain hash), ChainBreak, EntryType, Membership, CommitProof, ColdLog, log consistency checking, commit index management
 
+use crate::session::{ClientRequest, Dedup};
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use thiserror::Error;
+
+/// Bytes counted per entry on top of its command (term and index)
+pub(crate) const ENTRY_OVERHEAD_BYTES: u64 = 16;
+
+/// What a log entry carries
+///
+/// Normal entries are left out when serializing, so logs written before
+/// entry types existed read back unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EntryType {
+    /// A client command for the state machine
+    #[default]
+    Normal,
+    /// Appended by a new leader to commit earlier terms; carries nothing
+    NoOp,
+    /// A membership change, encoded by the host
+    Config,
+}
+
+impl EntryType {
+    /// True for client commands
+    pub fn is_normal(&self) -> bool {
+        *self == EntryType::Normal
+    }
+}
+
+/// A committed log position a client can hold on to
+///
+/// A (term, index) pair names exactly one entry: if two logs agree on the
+/// term at an index, they agree on everything before it. Check a proof
+/// later with `RaftNode::verify_commit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitProof {
+    /// Term the entry was written in
+    pub term: u64,
+    /// Index of the entry
+    pub index: u64,
+}
+
+/// What a node knows about a `CommitProof`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitStatus {
+    /// The entry is committed and part of the canonical history for good
+    Committed,
+    /// The entry is in our log but not committed yet; it may still be lost
+    Uncommitted,
+    /// A different committed entry holds that index: the proof's entry was
+    /// never committed and has been discarded
+    Lost,
+    /// We can't tell yet: our log doesn't reach that index, or holds an
+    /// uncommitted entry of its own there
+    Unknown,
+    /// The index was compacted into our snapshot, so its term can't be checked
+    Compacted,
+}
+
+/// How a proposal tracked with `RaftNode::propose_tracked` ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalOutcome {
+    /// The entry committed at the proposed (term, index)
+    Committed,
+    /// A newer leader truncated the entry away; it will never commit, so the
+    /// command can be retried
+    Superseded,
+    /// A snapshot from the leader replaced the entry before we saw how it
+    /// ended; ask the leader with `RaftNode::verify_commit`
+    Compacted,
+}
+
+/// Why `RaftNode::committed_since` can't continue from a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ChangelogError {
+    /// Entries after the position were compacted; rebuild from a snapshot
+    /// taken at or after `snapshot_index` and resume from there
+    #[error("changes compacted up to index {snapshot_index}")]
+    Compacted { snapshot_index: u64 },
+    /// The position names an entry that isn't in our committed history
+    /// (e.g. it was read from another cluster)
+    #[error("position is not in the committed log")]
+    Diverged,
+    /// The entries were spilled and the cold log couldn't read them back;
+    /// retry later
+    #[error("spilled entries could not be read back")]
+    Unavailable,
+}
 
 /// A single entry in the replicated log
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,13 +101,160 @@ pub struct LogEntry {
     pub term: u64,
     /// The index of this entry in the log (1-indexed)
     pub index: u64,
+    /// What the command is (client command, no-op or membership change)
+    #[serde(default, skip_serializing_if = "EntryType::is_normal")]
+    pub entry_type: EntryType,
     /// The command to be applied to the state machine
     pub command: Vec<u8>,
+    /// The client session and sequence that proposed it, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<ClientRequest>,
+    /// Link in the log's hash chain (see `RaftConfig::hash_chain`); None
+    /// for entries written without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<u64>,
+    /// Set on entries returned by `get_entries_to_apply` that carry a
+    /// client request; never persisted or replicated
+    #[serde(skip)]
+    pub dedup: Option<Dedup>,
+}
+
+/// The voting members recorded by `RaftNode::bootstrap`
+///
+/// Written as JSON under a `raft_membership` key in a `Config` entry, so
+/// it can't be mistaken for a config change encoded by the host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Membership {
+    /// Ids of the voting members
+    pub voters: Vec<u64>,
+}
+
+/// The JSON envelope around a Membership
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MembershipRecord {
+    raft_membership: Membership,
+}
+
+impl Membership {
+    /// Encode as the command of a `Config` entry
+    pub fn encode(&self) -> Vec<u8> {
+        let record = MembershipRecord { raft_membership: self.clone() };
+        serde_json::to_vec(&record).expect("a list of ids always serializes")
+    }
+
+    /// The membership an entry records, if it is a membership record
+    pub fn decode(entry: &LogEntry) -> Option<Self> {
+        if entry.entry_type != EntryType::Config {
+            return None;
+        }
+        serde_json::from_slice::<MembershipRecord>(&entry.command)
+            .ok()
+            .map(|record| record.raft_membership)
+    }
 }
 
 impl LogEntry {
-    /// Create a new log entry
+    /// Create a new log entry carrying a client command
     pub fn new(term: u64, index: u64, command: Vec<u8>) -> Self {
-        Self { term, index, command }
+        Self::with_type(term, index, EntryType::Normal, command)
+    }
+
+    /// Create a new log entry of a given type
+    pub fn with_type(term: u64, index: u64, entry_type: EntryType, command: Vec<u8>) -> Self {
+        Self { term, index, entry_type, command, client: None, hash: None, dedup: None }
+    }
+
+    /// Bytes this entry holds in memory: the command plus term and index
+    pub fn size_bytes(&self) -> u64 {
+        self.command.len() as u64 + ENTRY_OVERHEAD_BYTES
+    }
+
+    /// This entry's hash, chained to `prev`, the hash of the entry before it
+    ///
+    /// 64-bit FNV-1a over everything that is persisted and replicated, so
+    /// it catches corruption and hand edits, not a forger who recomputes
+    /// the chain. The algorithm is part of the log format: never change it.
+    pub fn chain_hash(&self, prev: Option<u64>) -> u64 {
+        let entry_type: u8 = match self.entry_type {
+            EntryType::Normal => 0,
+            EntryType::NoOp => 1,
+            EntryType::Config => 2,
+        };
+        let client = self.client.map_or((0, 0), |c| (c.client_id, c.sequence));
+        let mut hasher = Fnv1a::default();
+        hasher.write(&[prev.is_some() as u8]);
+        hasher.write(&prev.unwrap_or(0).to_be_bytes());
+        hasher.write(&self.term.to_be_bytes());
+        hasher.write(&self.index.to_be_bytes());
+        hasher.write(&[entry_type, self.client.is_some() as u8]);
+        hasher.write(&client.0.to_be_bytes());
+        hasher.write(&client.1.to_be_bytes());
+        hasher.write(&(self.command.len() as u64).to_be_bytes());
+        hasher.write(&self.command);
+        hasher.0
+    }
+
+    /// Whether this entry's hash links to `prev`
+    ///
+    /// An entry without a hash only links to one without a hash either:
+    /// once a log starts chaining, dropping a hash breaks the chain.
+    pub fn links_to(&self, prev: Option<u64>) -> bool {
+        match self.hash {
+            Some(hash) => hash == self.chain_hash(prev),
+            None => prev.is_none(),
+        }
     }
+
+    /// Check that every entry links to the one before it; `prev` is the
+    /// hash of the entry before the first
+    pub fn verify_chain(prev: Option<u64>, entries: &[LogEntry]) -> Result<(), ChainBreak> {
+        let mut prev = prev;
+        for entry in entries {
+            if !entry.links_to(prev) {
+                return Err(ChainBreak { index: entry.index });
+            }
+            prev = entry.hash;
+        }
+        Ok(())
+    }
+}
+
+/// 64-bit FNV-1a, kept here so the chain doesn't depend on std's unstable hasher
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Where a log's hash chain stops linking (see `RaftConfig::hash_chain`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("log hash chain broken at index {index}")]
+pub struct ChainBreak {
+    /// The first entry whose hash doesn't follow from its predecessor's
+    pub index: u64,
+}
+
+/// Where a node reads back log entries it spilled out of memory
+///
+/// With `RaftConfig::max_hot_entries` set, applied entries beyond the hot
+/// tail are dropped from `RaftNode::log`. They are still in the host's
+/// storage, which this reads from when a lagging follower needs them.
+/// Only applied (so committed and durable) entries are ever spilled, so
+/// storage always holds them.
+pub trait ColdLog: std::fmt::Debug + Send {
+    /// Entries with indexes in `range`, in order, or None if storage
+    /// can't supply all of them
+    fn read_entries(&self, range: Range<u64>) -> Option<Vec<LogEntry>>;
 }
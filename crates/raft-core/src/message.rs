@@ -2,9 +2,50 @@
 //!
 //! why: define all raft rpc message types for node communication
 //! relations: used by node.rs for state transitions, serialized for network
-//! what: VoteRequest, VoteResponse, AppendEntries, PreVote messages
+//! what: VoteRequest, VoteResponse, AppendEntries, InstallSnapshot (whole or chunked), PreVote, quorum read and rejoin handshake messages, each tagged with an optional raft group
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Storage health a follower reports back to the leader
+///
+/// Optional extension: followers that know how their disk is doing can
+/// piggyback it on AppendEntries responses so the leader can build a
+/// cluster-wide health view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct StorageHealth {
+    /// latency of the most recent fsync in milliseconds
+    pub last_fsync_ms: u64,
+    /// the backing disk reported out-of-space on the last write
+    pub disk_full: bool,
+}
+
+impl StorageHealth {
+    /// a node is healthy as long as its disk can still accept writes
+    pub fn is_healthy(&self) -> bool {
+        !self.disk_full
+    }
+}
+
+/// Why a leader refused a restarted node back into the cluster
+///
+/// Sent in a RejoinResponse (see `RaftNode::begin_rejoin`). Every case
+/// means the node's state can't have come from this cluster's history, so
+/// it quarantines itself instead of voting or campaigning with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Error)]
+pub enum RejoinError {
+    /// The node's data belongs to another cluster (see `RaftConfig::cluster_id`)
+    #[error("node data belongs to cluster {local}, but the leader's cluster is {cluster}")]
+    ClusterMismatch { local: u64, cluster: u64 },
+    /// The node's snapshot covers entries past the end of the leader's log,
+    /// which holds every committed entry
+    #[error("snapshot at index {snapshot_index} is past the leader's last index {leader_last_index}: restored from a later backup, or a split history")]
+    SnapshotAhead { snapshot_index: u64, leader_last_index: u64 },
+    /// The node's log ends in entries the leader never wrote: from a term
+    /// the leader hasn't reached, or past the leader's own entries in its term
+    #[error("log ends at index {last_log_index} term {last_log_term}, which the leader never wrote: restored from a later backup, or a split history")]
+    LogAhead { last_log_index: u64, last_log_term: u64 },
+}
 
 /// All possible Raft messages between nodes
 /// 
@@ -27,12 +68,26 @@ pub enum RaftMessage {
         candidate_id: u64,
         last_log_index: u64,
         last_log_term: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
     },
     /// Response to a pre-vote request
     PreVoteResponse {
         term: u64,
         /// Would this node vote for the candidate if a real election started?
         vote_granted: bool,
+        /// responder's commit index (lets a new leader skip probing)
+        #[serde(default)]
+        commit_index: u64,
+        /// responder's last log index (lets a new leader skip probing)
+        #[serde(default)]
+        last_log_index: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
     },
     
     // -- Standard Raft Messages --
@@ -43,13 +98,31 @@ pub enum RaftMessage {
         candidate_id: u64,
         last_log_index: u64,
         last_log_term: u64,
+        /// Campaigning on the leader's TimeoutNow: voters grant it even
+        /// though they've just heard from that leader
+        #[serde(default)]
+        transfer: bool,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
     },
     /// Response to a vote request
     VoteResponse {
         term: u64,
         vote_granted: bool,
+        /// responder's commit index (lets a new leader skip probing)
+        #[serde(default)]
+        commit_index: u64,
+        /// responder's last log index (lets a new leader skip probing)
+        #[serde(default)]
+        last_log_index: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
     },
-    /// Replicate log entries (also serves as heartbeat when entries is empty)
+    /// Replicate log entries (empty when probing for where logs match)
     AppendEntries {
         term: u64,
         leader_id: u64,
@@ -57,11 +130,236 @@ pub enum RaftMessage {
         prev_log_term: u64,
         entries: Vec<crate::LogEntry>,
         leader_commit: u64,
+        /// Leader's sequence number for the request, echoed in the response
+        /// (0 from older versions)
+        #[serde(default)]
+        seq: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
     },
     /// Response to AppendEntries
     AppendEntriesResponse {
         term: u64,
         success: bool,
+        /// last log index the follower now shares with the leader; on a
+        /// log mismatch, the follower's last log index (0 from older versions)
+        #[serde(default)]
+        match_index: u64,
+        /// follower storage health, if the follower reports it
+        #[serde(default)]
+        storage_health: Option<StorageHealth>,
+        /// follower's apply/persist pipeline is saturated: send less per append
+        #[serde(default)]
+        backoff: bool,
+        /// `seq` of the AppendEntries this answers (0 from older versions)
+        #[serde(default)]
+        seq: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
+    },
+    /// Replace a follower's log prefix with a snapshot
+    /// 
+    /// Sent instead of AppendEntries when the entries the follower needs
+    /// next are no longer in the leader's log.
+    InstallSnapshot {
+        term: u64,
+        leader_id: u64,
+        snapshot: crate::Snapshot,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
+    },
+    /// Response to InstallSnapshot, and to the last InstallSnapshotChunk
+    InstallSnapshotResponse {
+        term: u64,
+        /// last log index the follower now shares with the leader (0 if rejected)
+        match_index: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
+    },
+    /// One piece of a snapshot streamed in order, instead of InstallSnapshot
+    /// 
+    /// `data` holds bytes `offset..` of `Snapshot::to_bytes`; the follower
+    /// installs the snapshot once the chunk marked `done` completes it.
+    InstallSnapshotChunk {
+        term: u64,
+        leader_id: u64,
+        /// Which snapshot the chunk belongs to
+        last_included_index: u64,
+        last_included_term: u64,
+        offset: u64,
+        data: Vec<u8>,
+        /// This is the last chunk
+        done: bool,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
+    },
+    /// Response to an InstallSnapshotChunk that didn't complete the snapshot
+    InstallSnapshotChunkResponse {
+        term: u64,
+        last_included_index: u64,
+        /// Snapshot bytes the follower holds: where the leader continues
+        next_offset: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
+    },
+    /// Leader to follower: "I'm still leader"
+    /// 
+    /// Carries no log position, so followers never check it against their
+    /// log. `commit` is the leader's commit index capped at the follower's
+    /// match index, so it never points past what the two logs share.
+    Heartbeat {
+        term: u64,
+        leader_id: u64,
+        commit: u64,
+        /// Leader's sequence number for the request, echoed in the response
+        /// (0 from older versions)
+        #[serde(default)]
+        seq: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
+    },
+    /// Response to a heartbeat
+    HeartbeatResponse {
+        term: u64,
+        /// responder's last log index (tells the leader whether to resend)
+        last_log_index: u64,
+        /// `seq` of the Heartbeat this answers (0 from older versions)
+        #[serde(default)]
+        seq: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
+    },
+    
+    // -- Rejoin Handshake (after a restart) --
+    
+    /// Restarted node's state, sent to every peer before it takes part in
+    /// elections; only the leader answers
+    /// 
+    /// Carries no term: a node restored from a later backup may hold a term
+    /// the cluster never reached, and must not depose the leader with it.
+    RejoinRequest {
+        /// cluster the node's data belongs to (see `RaftConfig::cluster_id`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cluster_id: Option<u64>,
+        last_log_index: u64,
+        last_log_term: u64,
+        /// last index the node's snapshot covers (0 if none)
+        snapshot_index: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
+    },
+    /// Leader's verdict on a RejoinRequest
+    RejoinResponse {
+        /// leader's term
+        term: u64,
+        /// why the node must stay out, or None if it may rejoin
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        refusal: Option<RejoinError>,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
+    },
+    
+    /// Leader to a caught-up voter: "take over now"
+    /// 
+    /// The receiver campaigns immediately in the next term, without a
+    /// pre-vote round (leadership transfer).
+    TimeoutNow {
+        term: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
+    },
+    
+    // -- Quorum Reads (no leader involved) --
+    
+    /// "How far does your log reach?"
+    /// 
+    /// Carries no term: answering it changes nothing on the receiver, so it
+    /// can't disrupt an election or a leader.
+    ReadQuorumRequest {
+        read_id: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
+    },
+    /// Response to a quorum read
+    ReadQuorumResponse {
+        read_id: u64,
+        /// responder's last log index
+        last_log_index: u64,
+        /// Raft group the message belongs to (None outside multi-group
+        /// deployments, and from older versions)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group_id: Option<u64>,
     },
 }
 
+impl RaftMessage {
+    /// The sequence number an AppendEntries or Heartbeat carries, or its
+    /// response echoes (0 for every other message, and from older versions)
+    pub fn seq(&self) -> u64 {
+        match self {
+            RaftMessage::AppendEntries { seq, .. }
+            | RaftMessage::AppendEntriesResponse { seq, .. }
+            | RaftMessage::Heartbeat { seq, .. }
+            | RaftMessage::HeartbeatResponse { seq, .. } => *seq,
+            _ => 0,
+        }
+    }
+
+    /// Set the sequence number, on the messages that carry one
+    pub(crate) fn set_seq(&mut self, value: u64) {
+        match self {
+            RaftMessage::AppendEntries { seq, .. }
+            | RaftMessage::AppendEntriesResponse { seq, .. }
+            | RaftMessage::Heartbeat { seq, .. }
+            | RaftMessage::HeartbeatResponse { seq, .. } => *seq = value,
+            _ => {}
+        }
+    }
+
+    /// The raft group the message belongs to, if the sender has one
+    pub fn group_id(&self) -> Option<u64> {
+        match self {
+            RaftMessage::PreVoteRequest { group_id, .. }
+            | RaftMessage::PreVoteResponse { group_id, .. }
+            | RaftMessage::VoteRequest { group_id, .. }
+            | RaftMessage::VoteResponse { group_id, .. }
+            | RaftMessage::AppendEntries { group_id, .. }
+            | RaftMessage::AppendEntriesResponse { group_id, .. }
+            | RaftMessage::InstallSnapshot { group_id, .. }
+            | RaftMessage::InstallSnapshotResponse { group_id, .. }
+            | RaftMessage::InstallSnapshotChunk { group_id, .. }
+            | RaftMessage::InstallSnapshotChunkResponse { group_id, .. }
+            | RaftMessage::Heartbeat { group_id, .. }
+            | RaftMessage::HeartbeatResponse { group_id, .. }
+            | RaftMessage::TimeoutNow { group_id, .. }
+            | RaftMessage::ReadQuorumRequest { group_id, .. }
+            | RaftMessage::ReadQuorumResponse { group_id, .. }
+            | RaftMessage::RejoinRequest { group_id, .. }
+            | RaftMessage::RejoinResponse { group_id, .. } => *group_id,
+        }
+    }
+}
@@ -5,16 +5,29 @@
 //! what: NodeState enum, RaftNode struct, election/heartbeat timers
 
 use serde::{Deserialize, Serialize};
-use crate::{LogEntry, RaftMessage};
-use std::collections::HashMap;
+use crate::log::ENTRY_OVERHEAD_BYTES;
+use crate::progress::{Progress, ProgressState};
+use crate::quorum::{QuorumKind, QuorumPolicy};
+use crate::read_only::{QuorumReads, ReadOnly};
+use crate::state_machine::StateMachine;
+use crate::session::{ClientRequest, Dedup, SessionTable};
+use crate::snapshot::{SnapshotAssembly, SnapshotMetadata};
+use crate::{ChainBreak, ChangelogError, ColdLog, CommitProof, CommitStatus, EntryType, HardState, LogEntry, Membership, ProposalOutcome, RaftMessage, ReadMode, ReadState, Ready, RejoinError, Snapshot, SnapshotSealer, SoftState, StorageHealth};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use thiserror::Error;
+
+/// entries per AppendEntries while a follower asks us to back off
+pub const BACKOFF_MAX_ENTRIES: usize = 1;
 
 /// the possible states a raft node can be in
 /// 
 /// includes PreCandidate for the PreVote protocol (Raft thesis Section 9.6)
 /// which prevents disconnected nodes from disrupting the cluster
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum NodeState {
     /// passive state - listens for heartbeats, votes when asked
+    #[default]
     Follower,
     /// pre-election state - gathering pre-votes before incrementing term
     /// prevents "disruptive server" problem
@@ -25,13 +38,12 @@ pub enum NodeState {
     Leader,
 }
 
-impl Default for NodeState {
-    fn default() -> Self {
-        Self::Follower
-    }
-}
-
 /// configuration for raft timing (in milliseconds)
+/// 
+/// timeouts can also be expressed in ticks (like etcd/raft): pick a
+/// `tick_interval` that matches how often the host can reliably call into
+/// the node, and use the `*_ticks()` helpers. hosts with coarse timers
+/// (background browser tabs, wasi pollers) then behave predictably.
 #[derive(Debug, Clone)]
 pub struct RaftConfig {
     /// minimum election timeout in ms (default: 150)
@@ -40,6 +52,111 @@ pub struct RaftConfig {
     pub election_timeout_max: u64,
     /// heartbeat interval in ms (default: 50)
     pub heartbeat_interval: u64,
+    /// duration of one logical tick in ms (default: 10)
+    pub tick_interval: u64,
+    /// how linearizable reads are confirmed (default: Safe)
+    pub read_mode: ReadMode,
+    /// worst-case clock drift between nodes in ms, shaved off every
+    /// leader lease (default: 15)
+    pub max_clock_drift: u64,
+    /// in-memory log size in bytes past which the host is asked to
+    /// compact (default: None, unbounded)
+    pub log_bytes_soft_limit: Option<u64>,
+    /// applied entries to keep in memory once a cold log is installed
+    /// (default: None, keep them all). when the log holds twice this many,
+    /// older applied entries are spilled: dropped from memory and read back
+    /// from `RaftNode::set_cold_log` when a follower needs them
+    pub max_hot_entries: Option<usize>,
+    /// AppendEntries messages the leader may have unacknowledged per
+    /// follower (default: 64)
+    pub max_inflight_msgs: usize,
+    /// entry bytes the leader may have unacknowledged per follower
+    /// (default: None, unbounded)
+    pub max_inflight_bytes: Option<u64>,
+    /// cluster this node's data belongs to (default: None). a restarting
+    /// node sends it in its rejoin handshake, and a leader with a
+    /// different one refuses it
+    pub cluster_id: Option<u64>,
+    /// after a restart, stay out of elections until a leader has checked
+    /// our state against its own (default: false). hosts that load a node
+    /// from storage call `begin_rejoin` when it is set
+    pub verify_rejoin: bool,
+    /// most entries one AppendEntries may carry (default: None, unbounded)
+    pub max_entries_per_msg: Option<usize>,
+    /// stream snapshots in InstallSnapshotChunk messages of at most this
+    /// many bytes (default: None, one InstallSnapshot per snapshot)
+    pub snapshot_chunk_bytes: Option<u64>,
+    /// followers the leader sends snapshots to at once (default: 1); the
+    /// rest wait, so catch-up traffic doesn't crowd out replication
+    pub max_snapshots_in_flight: usize,
+    /// snapshot bytes per second the leader may send, all followers
+    /// together (default: None, unlimited)
+    pub snapshot_bytes_per_sec: Option<u64>,
+    /// largest command `propose` accepts, in bytes (default: None, unbounded)
+    pub max_proposal_bytes: Option<u64>,
+    /// entry bytes the leader may hold uncommitted before `propose` refuses
+    /// more, so a leader whose followers are down can't grow its log
+    /// forever (default: None, unbounded)
+    pub max_uncommitted_bytes: Option<u64>,
+    /// seed for the election timeout jitter (default: None, seeded from
+    /// the node id). mixed with the node id, so a simulation can give every
+    /// node the same seed and still get distinct but reproducible timeouts
+    pub rng_seed: Option<u64>,
+    /// election priority per node id, higher preferred (default: empty, all
+    /// equal; unlisted nodes rank 0). lower-ranked nodes wait longer before
+    /// campaigning, and a leader hands over to a caught-up voter that
+    /// outranks it
+    pub priorities: HashMap<u64, u64>,
+    /// extra election timeout in ms per voter rank (default: 0, off); see
+    /// `RaftNode::election_offset`
+    pub election_stagger: u64,
+    /// upper bound in ms on the election timeout after repeated lost
+    /// campaigns (default: 1200). every failed round doubles the randomized
+    /// window, up to this bound; hearing a leader or winning resets it
+    pub election_backoff_max: u64,
+    /// run a pre-vote round before every election (default: true). off,
+    /// a node that rejoins after a partition disrupts the leader with its
+    /// inflated term
+    pub pre_vote: bool,
+    /// a leader steps down once it hasn't heard from a quorum of voters for
+    /// election_timeout_min (default: false), so a partitioned leader stops
+    /// taking proposals that can never commit
+    pub check_quorum: bool,
+    /// which sets of voters count as a quorum (default: simple majority)
+    pub quorum_policy: QuorumPolicy,
+    /// how many entries a learner may trail the leader's last index by and
+    /// still be promoted (default: 100); see `RaftNode::promote_learner`
+    pub max_promotion_lag: u64,
+    /// log entries stay unstable until the host reports them durable with
+    /// `RaftNode::report_persisted` (default: false, durable once handed
+    /// out in a Ready). lets the host send messages while it writes
+    /// `Ready::entries` in the background
+    pub async_persist: bool,
+    /// most committed entries one Ready hands out to apply (default: None,
+    /// all of them). the rest come in later Readies, so a long catch-up
+    /// doesn't stall the host's event loop in one go
+    pub max_apply_batch: Option<usize>,
+    /// committed entry bytes one Ready hands out to apply (default: None,
+    /// unbounded); a batch always holds at least one entry
+    pub max_apply_batch_bytes: Option<u64>,
+    /// back off heartbeats to a follower that stopped answering, up to
+    /// this gap in ms (default: None, a heartbeat every interval). each
+    /// unanswered heartbeat doubles the gap, starting from
+    /// heartbeat_interval; any response resets it. must stay below
+    /// election_timeout_min: a follower that hears us but can't answer
+    /// still needs heartbeats to hold off its election
+    pub probe_backoff_max: Option<u64>,
+    /// raft group this node belongs to, for processes that run several
+    /// groups over one transport (default: None). every message we send
+    /// is tagged with it, and messages tagged with any other group (or
+    /// untagged, when we have one) are dropped unread
+    pub group_id: Option<u64>,
+    /// chain every entry we append to the one before it with a hash
+    /// (default: false). followers refuse entries that don't link to their
+    /// log, and `recover` refuses a log whose chain is broken, so corrupt
+    /// or hand-edited entries are caught. every node must agree: a leader
+    /// without it sends unhashed entries, which break a chained log
+    pub hash_chain: bool,
 }
 
 impl Default for RaftConfig {
@@ -48,10 +165,474 @@ impl Default for RaftConfig {
             election_timeout_min: 150,
             election_timeout_max: 300,
             heartbeat_interval: 50,
+            tick_interval: 10,
+            read_mode: ReadMode::Safe,
+            max_clock_drift: 15,
+            log_bytes_soft_limit: None,
+            max_hot_entries: None,
+            max_inflight_msgs: 64,
+            max_inflight_bytes: None,
+            cluster_id: None,
+            verify_rejoin: false,
+            max_entries_per_msg: None,
+            snapshot_chunk_bytes: None,
+            max_snapshots_in_flight: 1,
+            snapshot_bytes_per_sec: None,
+            max_proposal_bytes: None,
+            max_uncommitted_bytes: None,
+            rng_seed: None,
+            priorities: HashMap::new(),
+            election_stagger: 0,
+            election_backoff_max: 1200,
+            pre_vote: true,
+            check_quorum: false,
+            quorum_policy: QuorumPolicy::Majority,
+            max_promotion_lag: 100,
+            async_persist: false,
+            max_apply_batch: None,
+            max_apply_batch_bytes: None,
+            probe_backoff_max: None,
+            group_id: None,
+            hash_chain: false,
+        }
+    }
+}
+
+impl RaftConfig {
+    /// start from the defaults and check the result in `build`
+    /// 
+    /// ```
+    /// use raft_core::RaftConfig;
+    ///
+    /// let config = RaftConfig::builder()
+    ///     .election_timeout(300, 600)
+    ///     .heartbeat_interval(100)
+    ///     .check_quorum(true)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(config.election_timeout_max, 600);
+    /// ```
+    pub fn builder() -> RaftConfigBuilder {
+        RaftConfigBuilder::default()
+    }
+    
+    /// check the relationships between settings
+    /// 
+    /// the builder calls this; configs built as struct literals or with
+    /// `from_ticks` can call it themselves
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let zero = [
+            ("election_timeout_min", self.election_timeout_min == 0),
+            ("heartbeat_interval", self.heartbeat_interval == 0),
+            ("tick_interval", self.tick_interval == 0),
+            ("max_hot_entries", self.max_hot_entries == Some(0)),
+            ("max_inflight_msgs", self.max_inflight_msgs == 0),
+            ("max_inflight_bytes", self.max_inflight_bytes == Some(0)),
+            ("max_entries_per_msg", self.max_entries_per_msg == Some(0)),
+            ("snapshot_chunk_bytes", self.snapshot_chunk_bytes == Some(0)),
+            ("max_snapshots_in_flight", self.max_snapshots_in_flight == 0),
+            ("snapshot_bytes_per_sec", self.snapshot_bytes_per_sec == Some(0)),
+            ("max_proposal_bytes", self.max_proposal_bytes == Some(0)),
+            ("max_uncommitted_bytes", self.max_uncommitted_bytes == Some(0)),
+            ("max_apply_batch", self.max_apply_batch == Some(0)),
+            ("max_apply_batch_bytes", self.max_apply_batch_bytes == Some(0)),
+            ("probe_backoff_max", self.probe_backoff_max == Some(0)),
+            ("quorum_policy.replication", self.quorum_policy == QuorumPolicy::Flexible { replication: 0 }),
+        ];
+        if let Some(&(field, _)) = zero.iter().find(|(_, is_zero)| *is_zero) {
+            return Err(ConfigError::Zero { field });
+        }
+        if self.election_timeout_min >= self.election_timeout_max {
+            return Err(ConfigError::ElectionWindowEmpty {
+                min: self.election_timeout_min,
+                max: self.election_timeout_max,
+            });
+        }
+        if self.heartbeat_interval >= self.election_timeout_min {
+            return Err(ConfigError::HeartbeatTooSlow {
+                heartbeat_interval: self.heartbeat_interval,
+                election_timeout_min: self.election_timeout_min,
+            });
+        }
+        if let Some(probe_backoff_max) = self.probe_backoff_max.filter(|&ms| ms >= self.election_timeout_min) {
+            return Err(ConfigError::ProbeBackoffTooLong {
+                probe_backoff_max,
+                election_timeout_min: self.election_timeout_min,
+            });
+        }
+        if self.election_backoff_max < self.election_timeout_max {
+            return Err(ConfigError::BackoffBelowTimeout {
+                election_backoff_max: self.election_backoff_max,
+                election_timeout_max: self.election_timeout_max,
+            });
+        }
+        Ok(())
+    }
+    
+    /// build a config from tick counts and a tick duration
+    /// 
+    /// e.g. `from_ticks(10, 5, 15, 30)` = 50ms heartbeats, 150-300ms elections
+    pub fn from_ticks(
+        tick_interval: u64,
+        heartbeat_ticks: u64,
+        election_timeout_min_ticks: u64,
+        election_timeout_max_ticks: u64,
+    ) -> Self {
+        Self {
+            election_timeout_min: election_timeout_min_ticks * tick_interval,
+            election_timeout_max: election_timeout_max_ticks * tick_interval,
+            heartbeat_interval: heartbeat_ticks * tick_interval,
+            tick_interval,
+            ..Self::default()
+        }
+    }
+    
+    /// heartbeat interval expressed in ticks (at least 1)
+    pub fn heartbeat_ticks(&self) -> u64 {
+        self.ms_to_ticks(self.heartbeat_interval)
+    }
+    
+    /// minimum election timeout expressed in ticks (at least 1)
+    pub fn election_timeout_min_ticks(&self) -> u64 {
+        self.ms_to_ticks(self.election_timeout_min)
+    }
+    
+    /// maximum election timeout expressed in ticks (at least 1)
+    pub fn election_timeout_max_ticks(&self) -> u64 {
+        self.ms_to_ticks(self.election_timeout_max)
+    }
+    
+    /// convert a duration to whole ticks, rounding up so timeouts never shrink
+    fn ms_to_ticks(&self, ms: u64) -> u64 {
+        if self.tick_interval == 0 {
+            return ms.max(1);
         }
+        ms.div_ceil(self.tick_interval).max(1)
+    }
+}
+
+/// a RaftConfig under construction (see `RaftConfig::builder`)
+#[derive(Debug, Clone, Default)]
+pub struct RaftConfigBuilder {
+    config: RaftConfig,
+}
+
+impl RaftConfigBuilder {
+    /// election timeout window in ms; each timeout is drawn from [min, max]
+    pub fn election_timeout(mut self, min: u64, max: u64) -> Self {
+        self.config.election_timeout_min = min;
+        self.config.election_timeout_max = max;
+        self
+    }
+    
+    /// heartbeat interval in ms
+    pub fn heartbeat_interval(mut self, ms: u64) -> Self {
+        self.config.heartbeat_interval = ms;
+        self
+    }
+    
+    /// duration of one logical tick in ms
+    pub fn tick_interval(mut self, ms: u64) -> Self {
+        self.config.tick_interval = ms;
+        self
+    }
+    
+    /// how linearizable reads are confirmed
+    pub fn read_mode(mut self, mode: ReadMode) -> Self {
+        self.config.read_mode = mode;
+        self
+    }
+    
+    /// worst-case clock drift in ms, shaved off every leader lease
+    pub fn max_clock_drift(mut self, ms: u64) -> Self {
+        self.config.max_clock_drift = ms;
+        self
+    }
+    
+    /// log size in bytes past which the host is asked to compact
+    pub fn log_bytes_soft_limit(mut self, bytes: u64) -> Self {
+        self.config.log_bytes_soft_limit = Some(bytes);
+        self
+    }
+    
+    /// entries kept in memory before older applied ones are spilled
+    pub fn max_hot_entries(mut self, entries: usize) -> Self {
+        self.config.max_hot_entries = Some(entries);
+        self
+    }
+    
+    /// unacknowledged AppendEntries allowed per follower
+    pub fn max_inflight_msgs(mut self, msgs: usize) -> Self {
+        self.config.max_inflight_msgs = msgs;
+        self
+    }
+    
+    /// unacknowledged entry bytes allowed per follower
+    pub fn max_inflight_bytes(mut self, bytes: u64) -> Self {
+        self.config.max_inflight_bytes = Some(bytes);
+        self
+    }
+    
+    /// cluster this node's data belongs to, checked when it rejoins
+    pub fn cluster_id(mut self, id: u64) -> Self {
+        self.config.cluster_id = Some(id);
+        self
+    }
+    
+    /// whether a restarted node has a leader verify its state before it
+    /// takes part in elections
+    pub fn verify_rejoin(mut self, enabled: bool) -> Self {
+        self.config.verify_rejoin = enabled;
+        self
+    }
+    
+    /// most entries one AppendEntries may carry
+    pub fn max_entries_per_msg(mut self, entries: usize) -> Self {
+        self.config.max_entries_per_msg = Some(entries);
+        self
+    }
+    
+    /// stream snapshots in chunks of at most `bytes`
+    pub fn snapshot_chunk_bytes(mut self, bytes: u64) -> Self {
+        self.config.snapshot_chunk_bytes = Some(bytes);
+        self
+    }
+    
+    /// followers that may receive a snapshot at once
+    pub fn max_snapshots_in_flight(mut self, followers: usize) -> Self {
+        self.config.max_snapshots_in_flight = followers;
+        self
+    }
+    
+    /// snapshot bytes per second the leader may send
+    pub fn snapshot_bytes_per_sec(mut self, bytes: u64) -> Self {
+        self.config.snapshot_bytes_per_sec = Some(bytes);
+        self
+    }
+    
+    /// largest command `propose` accepts, in bytes
+    pub fn max_proposal_bytes(mut self, bytes: u64) -> Self {
+        self.config.max_proposal_bytes = Some(bytes);
+        self
+    }
+    
+    /// entry bytes the leader may hold uncommitted
+    pub fn max_uncommitted_bytes(mut self, bytes: u64) -> Self {
+        self.config.max_uncommitted_bytes = Some(bytes);
+        self
+    }
+    
+    /// seed for the election timeout jitter
+    pub fn rng_seed(mut self, seed: u64) -> Self {
+        self.config.rng_seed = Some(seed);
+        self
+    }
+    
+    /// election priority of `node_id` (higher preferred)
+    pub fn priority(mut self, node_id: u64, priority: u64) -> Self {
+        self.config.priorities.insert(node_id, priority);
+        self
+    }
+    
+    /// extra election timeout in ms per voter rank
+    pub fn election_stagger(mut self, ms: u64) -> Self {
+        self.config.election_stagger = ms;
+        self
+    }
+    
+    /// upper bound in ms on the election timeout after lost campaigns
+    pub fn election_backoff_max(mut self, ms: u64) -> Self {
+        self.config.election_backoff_max = ms;
+        self
+    }
+    
+    /// run a pre-vote round before every election
+    pub fn pre_vote(mut self, enabled: bool) -> Self {
+        self.config.pre_vote = enabled;
+        self
+    }
+    
+    /// step down as leader once a quorum goes quiet
+    pub fn check_quorum(mut self, enabled: bool) -> Self {
+        self.config.check_quorum = enabled;
+        self
+    }
+    
+    /// which sets of voters count as a quorum
+    pub fn quorum_policy(mut self, policy: QuorumPolicy) -> Self {
+        self.config.quorum_policy = policy;
+        self
+    }
+    
+    /// entries a learner may trail by and still be promoted
+    pub fn max_promotion_lag(mut self, entries: u64) -> Self {
+        self.config.max_promotion_lag = entries;
+        self
+    }
+    
+    /// keep entries unstable until `RaftNode::report_persisted`
+    pub fn async_persist(mut self, enabled: bool) -> Self {
+        self.config.async_persist = enabled;
+        self
+    }
+    
+    /// most committed entries handed out to apply per Ready
+    pub fn max_apply_batch(mut self, entries: usize) -> Self {
+        self.config.max_apply_batch = Some(entries);
+        self
+    }
+    
+    /// committed entry bytes handed out to apply per Ready
+    pub fn max_apply_batch_bytes(mut self, bytes: u64) -> Self {
+        self.config.max_apply_batch_bytes = Some(bytes);
+        self
+    }
+    
+    /// longest gap in ms between heartbeats to an unresponsive follower
+    pub fn probe_backoff_max(mut self, ms: u64) -> Self {
+        self.config.probe_backoff_max = Some(ms);
+        self
+    }
+    
+    /// raft group this node's messages belong to
+    pub fn group_id(mut self, group: u64) -> Self {
+        self.config.group_id = Some(group);
+        self
+    }
+    
+    /// whether entries carry a hash chained to the previous entry's
+    pub fn hash_chain(mut self, enabled: bool) -> Self {
+        self.config.hash_chain = enabled;
+        self
+    }
+    
+    /// the config, if its settings fit together (see `RaftConfig::validate`)
+    pub fn build(self) -> Result<RaftConfig, ConfigError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// why a config was rejected (see `RaftConfig::validate`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ConfigError {
+    /// a setting that must be positive is zero
+    #[error("{field} must not be zero")]
+    Zero { field: &'static str },
+    /// election_timeout_min must be below election_timeout_max: the gap is
+    /// what keeps nodes from timing out together and splitting the vote
+    #[error("election_timeout_min ({min}) must be below election_timeout_max ({max})")]
+    ElectionWindowEmpty { min: u64, max: u64 },
+    /// heartbeats must come more often than the shortest election timeout,
+    /// or followers campaign against a healthy leader
+    #[error("heartbeat_interval ({heartbeat_interval}) must be below election_timeout_min ({election_timeout_min})")]
+    HeartbeatTooSlow { heartbeat_interval: u64, election_timeout_min: u64 },
+    /// backoff can only widen the election timeout, never cap it below max
+    #[error("election_backoff_max ({election_backoff_max}) must be at least election_timeout_max ({election_timeout_max})")]
+    BackoffBelowTimeout { election_backoff_max: u64, election_timeout_max: u64 },
+    /// backed-off heartbeats must still come more often than the shortest
+    /// election timeout, or a follower whose answers are lost campaigns
+    #[error("probe_backoff_max ({probe_backoff_max}) must be below election_timeout_min ({election_timeout_min})")]
+    ProbeBackoffTooLong { probe_backoff_max: u64, election_timeout_min: u64 },
+}
+
+/// how far a newly added member has caught up (see `RaftNode::catch_up_progress`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CatchUpProgress {
+    /// highest index known to be replicated on the member
+    pub match_index: u64,
+    /// the leader's last log index
+    pub target_index: u64,
+    /// match_index as a percentage of target_index (100 once caught up)
+    pub percent: u8,
+    /// estimated ms until caught up at the rate seen since the member was added
+    /// (None until it has made progress)
+    pub eta_ms: Option<u64>,
+    /// a snapshot has been sent and not yet acknowledged
+    pub installing_snapshot: bool,
+}
+
+/// what a paused node does with incoming messages (see `RaftNode::pause`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseMode {
+    /// hold them and deliver them on `resume`, like a stopped process's
+    /// socket buffers
+    Queue,
+    /// discard them, like a stopped process behind a lossy network
+    Drop,
+}
+
+/// why a proposal was refused (see `RaftNode::propose`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ProposeError {
+    /// only the leader can append; clients should retry on `leader_hint`
+    /// (None if this node doesn't know who leads)
+    #[error("not the leader ({})", leader_hint_text(.leader_hint))]
+    NotLeader { leader_hint: Option<u64> },
+    /// a command is over `RaftConfig::max_proposal_bytes`
+    #[error("command of {size} bytes is over the {limit} byte limit")]
+    TooLarge { size: u64, limit: u64 },
+    /// the uncommitted tail of the log is at `RaftConfig::max_uncommitted_bytes`
+    /// (followers are down or slow); retry once it commits
+    #[error("{uncommitted} uncommitted bytes, at the {limit} byte limit")]
+    UncommittedFull { uncommitted: u64, limit: u64 },
+    /// this node was removed from the cluster; no node here will take it
+    #[error("this node was removed from the cluster")]
+    Removed,
+}
+
+/// why a membership change was refused (see `RaftNode::propose_conf_change`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ConfChangeError {
+    /// only the leader can change membership; retry on `leader_hint`
+    #[error("not the leader ({})", leader_hint_text(.leader_hint))]
+    NotLeader { leader_hint: Option<u64> },
+    /// the config change at `index` hasn't committed yet, and only one may
+    /// be in flight at a time (`queue_conf_change` waits for it instead)
+    #[error("config change at index {index} is not committed yet")]
+    Pending { index: u64 },
+}
+
+/// why a learner wasn't promoted (see `RaftNode::promote_learner`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum PromoteError {
+    /// `node_id` isn't a learner
+    #[error("node {node_id} is not a learner")]
+    NotLearner { node_id: u64 },
+    /// the learner trails our last index by more than
+    /// `RaftConfig::max_promotion_lag`; retry once it has caught up
+    #[error("learner has entries up to {match_index} of {last_index}, too far behind to vote")]
+    Lagging { match_index: u64, last_index: u64 },
+}
+
+/// why the log wasn't compacted (see `RaftNode::compact_checked`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum CompactError {
+    /// `index` is at or behind the current snapshot, or not applied yet
+    #[error("can't compact to {index}: snapshot is at {snapshot_index}, applied up to {last_applied}")]
+    OutOfRange { index: u64, snapshot_index: u64, last_applied: u64 },
+    /// follower `node_id` only holds entries up to `match_index`: it would
+    /// have to catch up from a snapshot
+    #[error("node {node_id} still needs entries after {match_index}")]
+    FollowerBehind { node_id: u64, match_index: u64 },
+}
+
+/// where a NotLeader error points the client
+fn leader_hint_text(leader_hint: &Option<u64>) -> String {
+    match leader_hint {
+        Some(leader) => format!("try node {}", leader),
+        None => "leader unknown".to_string(),
     }
 }
 
+/// initial xorshift state for node `id`
+/// 
+/// seeded per node so peers don't all draw the same timeouts; xorshift
+/// must never start at zero, hence the low bit
+fn rng_seed_state(id: u64, seed: u64) -> u64 {
+    id.wrapping_add(seed).wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1
+}
+
 /// a single raft node in the cluster
 /// 
 /// implements the raft consensus algorithm including:
@@ -68,8 +649,13 @@ pub struct RaftNode {
     pub current_term: u64,
     /// node id that received our vote in current term (if any)
     pub voted_for: Option<u64>,
-    /// the replicated log entries
+    /// the replicated log entries (after the snapshot and any spilled
+    /// entries); contiguous, so lookups find index `i` at position
+    /// `i - first_log_index()`
     pub log: Vec<LogEntry>,
+    /// latest snapshot; replaces every entry up to its last included index
+    /// (data is sealed if a snapshot sealer is installed)
+    pub snapshot: Option<Snapshot>,
     
     // -- volatile state (all nodes) --
     
@@ -79,20 +665,59 @@ pub struct RaftNode {
     pub commit_index: u64,
     /// index of highest log entry applied to state machine
     pub last_applied: u64,
+    /// client sessions as of last_applied (rebuilt from the log and snapshots)
+    pub sessions: SessionTable,
+    /// who we believe leads the current term (ourselves when leader);
+    /// None during elections and until a new term's leader is heard from
+    pub leader_id: Option<u64>,
     
     // -- volatile state (leaders only, reinitialized after election) --
     
-    /// for each server, index of next log entry to send (leader only)
-    pub next_index: HashMap<u64, u64>,
-    /// for each server, index of highest log entry known to be replicated (leader only)
-    pub match_index: HashMap<u64, u64>,
+    /// replication progress of every other member (leader only)
+    pub progress: HashMap<u64, Progress>,
+    /// leadership handover in progress: (target, when TimeoutNow was sent)
+    transfer: Option<(u64, u64)>,
+    /// `step_down` is waiting on the handover: step down even if it fails
+    stepping_down: bool,
+    
+    // -- pause --
+    
+    /// set while frozen by `pause`
+    paused: Option<PauseMode>,
+    /// messages that arrived while paused with `PauseMode::Queue`
+    paused_inbox: Vec<(u64, RaftMessage)>,
     
     // -- cluster configuration --
     
     /// list of all node ids in the cluster (including self)
     pub cluster_nodes: Vec<u64>,
+    /// warm standbys: replicate the log but never vote or campaign
+    pub standby_nodes: Vec<u64>,
+    /// learners: non-voting members catching up the log before joining
+    pub learner_nodes: Vec<u64>,
+    /// old members cut off by `force_new_cluster`; their messages are dropped
+    fenced_peers: HashSet<u64>,
+    /// we were removed from the cluster (see `remove_member`)
+    removed: bool,
+    /// `Ready::removed` has been reported
+    removal_reported: bool,
+    /// config changes waiting for the pending one to commit (leader only)
+    queued_conf_changes: Vec<Vec<u8>>,
     /// timing configuration
     pub config: RaftConfig,
+    /// seals snapshots before storage/transfer and opens them on install
+    snapshot_sealer: Option<Box<dyn SnapshotSealer>>,
+    /// restarted and waiting for a leader to answer our rejoin handshake
+    rejoining: bool,
+    /// why a leader refused us back in; set for good (see `begin_rejoin`)
+    quarantine: Option<RejoinError>,
+    /// reads back entries spilled out of `log` (see `config.max_hot_entries`)
+    cold_log: Option<Box<dyn ColdLog>>,
+    /// (index, term) of the last entry spilled to the cold log ((0, 0) if none)
+    spilled: (u64, u64),
+    /// the last entry from a leader refused for not linking to our log
+    /// (see `config.hash_chain`)
+    chain_break: Option<ChainBreak>,
     
     // -- election state --
     
@@ -104,46 +729,191 @@ pub struct RaftNode {
     
     /// timestamp of last heartbeat received (for PreVote decision)
     pub last_heartbeat_time: Option<u64>,
+    
+    /// (commit_index, last_log_index) piggybacked on (pre-)vote responses,
+    /// used to seed replication progress when we win
+    peer_log_hints: HashMap<u64, (u64, u64)>,
+    
+    // -- storage health --
+    
+    /// our own storage health as reported by the host (sent to the leader)
+    pub storage_health: Option<StorageHealth>,
+    /// latest storage health reported by each follower (leader only)
+    pub peer_storage_health: HashMap<u64, StorageHealth>,
+    
+    // -- flow control --
+    
+    /// our apply/persist pipeline is saturated (sent as a backoff hint)
+    pub saturated: bool,
+    
+    // -- pending output (drained into Ready by step) --
+    
+    /// outgoing messages not yet handed to the host, as (to_node, message)
+    msgs: Vec<(u64, RaftMessage)>,
+    /// `seq` of the last AppendEntries or Heartbeat we sent; followers
+    /// echo it back, so reordered or duplicated responses can be told apart
+    last_seq: u64,
+    /// lowest log index appended since the last Ready
+    unpersisted_from: Option<u64>,
+    /// async_persist: lowest log index storage hasn't acknowledged yet
+    unstable_from: Option<u64>,
+    /// async_persist: match index we owe the leader an ack for, once stable
+    pending_ack: Option<u64>,
+    /// lowest log index truncated since the last Ready
+    truncated_from: Option<u64>,
+    /// hard state as of the last Ready, to detect changes
+    reported_hard_state: HardState,
+    /// soft state as of the last Ready, to detect changes
+    reported_soft_state: SoftState,
+    /// snapshot installed from the leader, not yet handed to the host
+    pending_snapshot: Option<Snapshot>,
+    /// chunked snapshot being received from the leader
+    snapshot_assembly: Option<SnapshotAssembly>,
+    /// `Snapshot::to_bytes` of our snapshot, for streaming it in chunks
+    snapshot_wire: Option<(SnapshotMetadata, Vec<u8>)>,
+    /// logical time (ms) before which snapshot_bytes_per_sec allows no
+    /// more snapshot sends
+    snapshot_send_at_ms: u64,
+    /// applied index we last asked the host to compact to
+    compaction_requested_at: u64,
+    /// hard state at the last invariant check
+    #[cfg(feature = "strict-checks")]
+    invariants_seen: crate::invariants::Seen,
+    /// proposals from `propose_tracked` that haven't committed or been superseded
+    tracked_proposals: Vec<CommitProof>,
+    
+    // -- linearizable reads (leader only) --
+    
+    /// ReadIndex requests waiting for heartbeat confirmation
+    read_only: ReadOnly,
+    /// quorum reads waiting for a majority of voters (any role)
+    quorum_reads: QuorumReads,
+    /// confirmed reads not yet handed to the host
+    read_states: Vec<ReadState>,
+    /// heartbeat round feeding the lease: (start time, last_seq at the
+    /// start, voters that acked)
+    lease_round: Option<(u64, u64, HashSet<u64>)>,
+    /// leader lease is valid until this logical time (LeaseBased reads)
+    lease_expiry: Option<u64>,
+    
+    // -- timers (driven by tick) --
+    
+    /// total ms the host has reported through tick (logical clock)
+    now_ms: u64,
+    /// ms since we last heard from a leader or started an election
+    election_elapsed: u64,
+    /// ms since the leader last broadcast a heartbeat
+    heartbeat_elapsed: u64,
+    /// election timeout for the current round, drawn from the config range
+    randomized_election_timeout: u64,
+    /// xorshift state used to randomize election timeouts
+    rng_state: u64,
+    /// campaigns in a row that ended without a leader (widens the timeout)
+    failed_elections: u32,
 }
 
 impl RaftNode {
     /// create a new raft node in follower state
     pub fn new(id: u64, cluster_nodes: Vec<u64>) -> Self {
-        Self {
+        let mut node = Self {
             id,
             current_term: 0,
             voted_for: None,
             log: Vec::new(),
+            snapshot: None,
             state: NodeState::Follower,
             commit_index: 0,
             last_applied: 0,
-            next_index: HashMap::new(),
-            match_index: HashMap::new(),
+            sessions: SessionTable::new(),
+            leader_id: None,
+            progress: HashMap::new(),
+            transfer: None,
             cluster_nodes,
+            standby_nodes: Vec::new(),
+            learner_nodes: Vec::new(),
+            fenced_peers: HashSet::new(),
+            removed: false,
+            removal_reported: false,
+            queued_conf_changes: Vec::new(),
             config: RaftConfig::default(),
+            snapshot_sealer: None,
+            rejoining: false,
+            quarantine: None,
+            cold_log: None,
+            spilled: (0, 0),
+            chain_break: None,
             votes_received: Vec::new(),
             prevotes_received: Vec::new(),
             last_heartbeat_time: None,
-        }
+            peer_log_hints: HashMap::new(),
+            storage_health: None,
+            peer_storage_health: HashMap::new(),
+            saturated: false,
+            msgs: Vec::new(),
+            last_seq: 0,
+            unpersisted_from: None,
+            unstable_from: None,
+            pending_ack: None,
+            truncated_from: None,
+            reported_hard_state: HardState::default(),
+            reported_soft_state: SoftState::default(),
+            pending_snapshot: None,
+            snapshot_assembly: None,
+            snapshot_wire: None,
+            snapshot_send_at_ms: 0,
+            compaction_requested_at: 0,
+            #[cfg(feature = "strict-checks")]
+            invariants_seen: Default::default(),
+            tracked_proposals: Vec::new(),
+            read_only: ReadOnly::default(),
+            quorum_reads: QuorumReads::default(),
+            read_states: Vec::new(),
+            lease_round: None,
+            lease_expiry: None,
+            now_ms: 0,
+            election_elapsed: 0,
+            heartbeat_elapsed: 0,
+            randomized_election_timeout: 0,
+            rng_state: rng_seed_state(id, 0),
+            failed_elections: 0,
+            stepping_down: false,
+            paused: None,
+            paused_inbox: Vec::new(),
+        };
+        node.reset_election_timer();
+        node
     }
     
     /// create a node with custom configuration
     pub fn with_config(id: u64, cluster_nodes: Vec<u64>, config: RaftConfig) -> Self {
         let mut node = Self::new(id, cluster_nodes);
+        node.rng_state = rng_seed_state(id, config.rng_seed.unwrap_or(0));
         node.config = config;
+        node.reset_election_timer();
         node
     }
     
     // -- state transitions --
     
-    /// get the number of nodes needed for quorum (majority)
+    /// get the number of voters needed to commit (a majority unless
+    /// `RaftConfig::quorum_policy` says otherwise)
     pub fn quorum_size(&self) -> usize {
-        (self.cluster_nodes.len() / 2) + 1
+        self.config.quorum_policy.quorum_size(QuorumKind::Replication, self.cluster_nodes.len())
+    }
+    
+    /// get the number of votes needed to win an election
+    pub fn election_quorum_size(&self) -> usize {
+        self.config.quorum_policy.quorum_size(QuorumKind::Election, self.cluster_nodes.len())
+    }
+    
+    /// whether `acks` form a `kind` quorum of the current voters
+    fn is_quorum(&self, kind: QuorumKind, acks: impl IntoIterator<Item = u64>) -> bool {
+        self.config.quorum_policy.is_quorum(kind, &self.cluster_nodes, acks)
     }
     
     /// check if we have enough pre-votes to proceed with real election
     pub fn has_prevote_quorum(&self) -> bool {
-        self.prevotes_received.len() >= self.quorum_size()
+        self.is_quorum(QuorumKind::Election, self.prevotes_received.iter().copied())
     }
     
     /// start pre-vote phase (Raft thesis Section 9.6)
@@ -155,7 +925,9 @@ impl RaftNode {
     /// - other nodes say "no, we have a leader" and rogue node stays quiet
     pub fn start_prevote(&mut self) -> RaftMessage {
         self.state = NodeState::PreCandidate;
+        self.leader_id = None; // we've given up on hearing from it
         self.prevotes_received = vec![self.id]; // pre-vote for ourselves
+        self.peer_log_hints.clear();
         
         // ask for pre-votes WITHOUT incrementing term
         // we use current_term + 1 as the "proposed" term
@@ -164,16 +936,20 @@ impl RaftNode {
             candidate_id: self.id,
             last_log_index: self.last_log_index(),
             last_log_term: self.last_log_term(),
+            group_id: self.config.group_id,
         }
     }
     
     /// handle a pre-vote request from another node
     /// 
     /// returns (response, should_reset_election_timer)
+    /// 
+    /// granting a pre-vote records nothing: unlike a real vote it doesn't
+    /// set voted_for, so the candidate's id plays no part in the decision
     pub fn handle_prevote_request(
         &mut self,
         term: u64,
-        candidate_id: u64,
+        _candidate_id: u64,
         last_log_index: u64,
         last_log_term: u64,
     ) -> (RaftMessage, bool) {
@@ -190,10 +966,10 @@ impl RaftNode {
             || (last_log_term == our_last_term && last_log_index >= our_last_index);
         
         // check if we've heard from leader recently
-        // (if we have, we don't need an election - leader is alive)
-        let heard_from_leader_recently = self.last_heartbeat_time.is_some() 
-            && self.state != NodeState::Candidate 
-            && self.state != NodeState::PreCandidate;
+        // (if we have, we don't need an election - leader is alive). a
+        // leader is that live leader, so it never grants one either
+        let heard_from_leader_recently = self.state == NodeState::Leader
+            || self.leader_recently_heard();
         
         // don't grant pre-vote if we have a healthy leader
         // this is the KEY to preventing disruptive servers
@@ -204,6 +980,9 @@ impl RaftNode {
         let response = RaftMessage::PreVoteResponse {
             term: self.current_term,
             vote_granted,
+            commit_index: self.commit_index,
+            last_log_index: self.last_log_index(),
+            group_id: self.config.group_id,
         };
         
         // don't reset election timer for pre-vote (it's just a query)
@@ -215,7 +994,7 @@ impl RaftNode {
     /// returns true if we should now start a real election
     pub fn handle_prevote_response(
         &mut self, 
-        term: u64, 
+        _term: u64, 
         vote_granted: bool, 
         from_node: u64
     ) -> bool {
@@ -225,9 +1004,9 @@ impl RaftNode {
         }
         
         // if responder has higher term, something's wrong but don't step down
-        // (pre-vote responses don't affect our term)
+        // (pre-vote responses don't affect our term, so `_term` is unused)
         
-        if vote_granted {
+        if vote_granted && self.is_voter(from_node) {
             if !self.prevotes_received.contains(&from_node) {
                 self.prevotes_received.push(from_node);
             }
@@ -246,9 +1025,11 @@ impl RaftNode {
     pub fn start_election(&mut self) -> RaftMessage {
         self.state = NodeState::Candidate;
         self.current_term += 1;
+        self.leader_id = None;
         self.voted_for = Some(self.id);
         self.votes_received = vec![self.id]; // vote for ourselves
         self.prevotes_received.clear();
+        self.snapshot_assembly = None;
         
         // create vote request to send to all peers
         RaftMessage::VoteRequest {
@@ -256,41 +1037,98 @@ impl RaftNode {
             candidate_id: self.id,
             last_log_index: self.last_log_index(),
             last_log_term: self.last_log_term(),
+            transfer: false,
+            group_id: self.config.group_id,
         }
     }
     
     /// become leader: initialize leader state
     pub fn become_leader(&mut self) {
         self.state = NodeState::Leader;
+        self.leader_id = Some(self.id);
         self.votes_received.clear();
+        self.heartbeat_elapsed = 0;
+        self.election_elapsed = 0;
+        self.failed_elections = 0;
+        self.progress.clear();
+        self.transfer = None;
+        self.lease_round = None;
+        self.lease_expiry = None;
         
-        // initialize next_index and match_index for all peers
+        // initialize progress for all peers (non-voters included)
         let last_log_idx = self.last_log_index();
-        for &node_id in &self.cluster_nodes {
-            if node_id != self.id {
-                self.next_index.insert(node_id, last_log_idx + 1);
-                self.match_index.insert(node_id, 0);
+        let hints = std::mem::take(&mut self.peer_log_hints);
+        for node_id in self.replication_targets() {
+            let (next, matched) = match hints.get(&node_id) {
+                // start right after the peer's last entry instead of probing
+                // down from ours. everything up to its commit index is
+                // committed, so (leader completeness) it matches our log
+                Some(&(commit, last)) => (last.min(last_log_idx) + 1, commit.min(last_log_idx)),
+                None => (last_log_idx + 1, 0),
+            };
+            // we don't know where its log matches ours until the probe
+            // below (or the first append) is answered
+            let mut progress = Progress::new(next, matched, &self.config);
+            progress.become_probe();
+            self.progress.insert(node_id, progress);
+        }
+        
+        // entries from earlier terms only commit once one from our own term
+        // does (Raft 5.4.2); a no-op gets commits moving without a proposal
+        self.append_entry_of_type(EntryType::NoOp, Vec::new());
+        
+        // probe every follower right away, so they learn of the new leader
+        // within one message delay instead of at the first heartbeat
+        for node_id in self.replication_targets() {
+            if let Some(probe) = self.create_probe(node_id) {
+                self.send(node_id, probe);
             }
         }
     }
     
     /// step down to follower (e.g., when seeing higher term)
     pub fn become_follower(&mut self, term: u64) {
+        if term != self.current_term || self.state == NodeState::Leader {
+            self.leader_id = None;
+        }
+        if term != self.current_term {
+            // a vote is only released by a new term; forgetting it within the
+            // term would let us vote twice
+            self.voted_for = None;
+            // a new leader streams its own snapshot, and gets its own acks
+            self.snapshot_assembly = None;
+            self.pending_ack = None;
+        }
         self.state = NodeState::Follower;
         self.current_term = term;
-        self.voted_for = None;
         self.votes_received.clear();
         self.prevotes_received.clear();
+        // outstanding reads can no longer be confirmed by us
+        self.read_only.clear();
+        self.lease_round = None;
+        self.lease_expiry = None;
+        self.transfer = None;
+        self.stepping_down = false;
+        // a new leader decides its own membership changes
+        self.queued_conf_changes.clear();
+    }
+    
+    /// remember a peer's commit/last index from its (pre-)vote response
+    pub fn record_peer_log_hint(&mut self, from: u64, commit_index: u64, last_log_index: u64) {
+        if from != self.id {
+            self.peer_log_hints.insert(from, (commit_index, last_log_index));
+        }
     }
     
     /// check if we have enough votes to become leader
     pub fn has_quorum(&self) -> bool {
-        self.votes_received.len() >= self.quorum_size()
+        self.is_quorum(QuorumKind::Election, self.votes_received.iter().copied())
     }
     
     /// record that we heard from the leader (for PreVote decisions)
     pub fn record_heartbeat(&mut self, current_time: u64) {
         self.last_heartbeat_time = Some(current_time);
+        self.failed_elections = 0;
     }
     
     /// clear heartbeat timestamp (for testing or when leader is suspected dead)
@@ -298,341 +1136,3178 @@ impl RaftNode {
         self.last_heartbeat_time = None;
     }
     
-    // -- log helpers --
-    
-    /// get the index of the last log entry (0 if log is empty)
-    pub fn last_log_index(&self) -> u64 {
-        self.log.last().map(|e| e.index).unwrap_or(0)
+    /// true while a follower is within election_timeout_min of last hearing
+    /// from the leader; votes and pre-votes are withheld until then (sticky
+    /// leader)
+    pub fn leader_recently_heard(&self) -> bool {
+        self.state == NodeState::Follower
+            && self
+                .last_heartbeat_time
+                .is_some_and(|at| self.now_ms.saturating_sub(at) < self.config.election_timeout_min)
     }
     
-    /// get the term of the last log entry (0 if log is empty)
-    pub fn last_log_term(&self) -> u64 {
-        self.log.last().map(|e| e.term).unwrap_or(0)
+    // -- non-voting members --
+    
+    /// register a warm standby (leaders start replicating to it at once)
+    /// 
+    /// standbys catch up the log like any follower but are excluded from
+    /// quorum, elections and automatic promotion
+    pub fn add_standby(&mut self, node_id: u64) {
+        if self.is_member(node_id) {
+            return;
+        }
+        self.standby_nodes.push(node_id);
+        self.start_replicating(node_id);
     }
     
-    /// get log entry at a specific index (1-indexed)
-    pub fn get_entry(&self, index: u64) -> Option<&LogEntry> {
-        if index == 0 {
-            return None;
+    /// register a learner (leaders start replicating to it at once)
+    /// 
+    /// learners receive AppendEntries and catch up the log without
+    /// counting toward quorum or taking part in elections, so a new node
+    /// can be added without weakening availability while it syncs
+    pub fn add_learner(&mut self, node_id: u64) {
+        if self.is_member(node_id) {
+            return;
         }
-        self.log.iter().find(|e| e.index == index)
+        self.learner_nodes.push(node_id);
+        self.start_replicating(node_id);
     }
     
-    /// get the term of entry at a specific index (0 if not found)
-    pub fn get_term_at(&self, index: u64) -> u64 {
-        self.get_entry(index).map(|e| e.term).unwrap_or(0)
+    /// whether this node is a learner
+    pub fn is_learner(&self) -> bool {
+        self.learner_nodes.contains(&self.id)
     }
     
-    /// append a new entry to the log (leader only)
-    pub fn append_entry(&mut self, command: Vec<u8>) -> &LogEntry {
-        let entry = LogEntry::new(
-            self.current_term,
-            self.last_log_index() + 1,
-            command,
-        );
-        self.log.push(entry);
-        self.log.last().unwrap()
+    /// admin operation: make a learner a voter
+    /// 
+    /// a voter that is far behind can't help commit until it catches up,
+    /// yet it already counts toward quorum size, so promoting a stale
+    /// learner makes the cluster less available for a while. the leader
+    /// therefore refuses until the learner is within
+    /// `RaftConfig::max_promotion_lag` entries of its last index; the
+    /// host retries later. other nodes have no replication progress to
+    /// check, so they promote unconditionally: the host applies a
+    /// promotion there once the leader has accepted it
+    pub fn promote_learner(&mut self, node_id: u64) -> Result<(), PromoteError> {
+        if !self.learner_nodes.contains(&node_id) {
+            return Err(PromoteError::NotLearner { node_id });
+        }
+        if self.state == NodeState::Leader {
+            let match_index = self.progress.get(&node_id).map_or(0, |p| p.match_index);
+            let last_index = self.last_log_index();
+            if last_index.saturating_sub(match_index) > self.config.max_promotion_lag {
+                return Err(PromoteError::Lagging { match_index, last_index });
+            }
+        }
+        self.learner_nodes.retain(|&id| id != node_id);
+        self.cluster_nodes.push(node_id);
+        Ok(())
     }
     
-    // -- message handling --
+    /// whether `node_id` is known in any role
+    fn is_member(&self, node_id: u64) -> bool {
+        self.cluster_nodes.contains(&node_id)
+            || self.standby_nodes.contains(&node_id)
+            || self.learner_nodes.contains(&node_id)
+    }
     
-    /// handle a vote request from a candidate
-    /// returns (response, should_reset_election_timer)
-    pub fn handle_vote_request(
-        &mut self,
-        term: u64,
-        candidate_id: u64,
-        last_log_index: u64,
-        last_log_term: u64,
-    ) -> (RaftMessage, bool) {
-        // if candidate's term is less than ours, reject
-        if term < self.current_term {
-            return (
-                RaftMessage::VoteResponse {
-                    term: self.current_term,
-                    vote_granted: false,
-                },
-                false,
-            );
+    /// begin tracking replication progress for a newly added node (leader only)
+    /// 
+    /// a new node has nothing, so if we have a snapshot it is seeded from
+    /// that right away instead of probing down and replaying the whole log
+    fn start_replicating(&mut self, node_id: u64) {
+        if self.state != NodeState::Leader || node_id == self.id {
+            return;
         }
+        let next = if self.snapshot.is_some() {
+            self.snapshot_index()
+        } else {
+            self.last_log_index() + 1
+        };
+        let mut progress = Progress::new(next, 0, &self.config);
+        progress.catch_up_from = Some((self.now_ms, 0));
+        self.progress.insert(node_id, progress);
         
-        // if we see a higher term, become follower
-        if term > self.current_term {
-            self.become_follower(term);
+        if self.snapshot.is_some() {
+            self.send_append(node_id);
         }
+    }
+    
+    /// admin view: how far `node_id` has caught up with our log (leader only)
+    /// 
+    /// the ETA extrapolates the member's rate since it was added while we
+    /// were leader; members we inherited on election have no ETA
+    pub fn catch_up_progress(&self, node_id: u64) -> Option<CatchUpProgress> {
+        if self.state != NodeState::Leader {
+            return None;
+        }
+        let progress = self.progress.get(&node_id)?;
+        let match_index = progress.match_index;
+        let target_index = self.last_log_index();
         
-        // check if we can grant the vote:
-        // 1. we haven't voted for anyone else this term
-        // 2. candidate's log is at least as up-to-date as ours
-        let can_vote = self.voted_for.is_none() || self.voted_for == Some(candidate_id);
-        let log_ok = self.is_log_up_to_date(last_log_index, last_log_term);
-        
-        let vote_granted = can_vote && log_ok;
+        // an empty log is trivially caught up
+        let percent = (match_index.min(target_index) * 100)
+            .checked_div(target_index)
+            .map_or(100, |p| p as u8);
         
-        if vote_granted {
-            self.voted_for = Some(candidate_id);
-        }
+        let remaining = target_index.saturating_sub(match_index);
+        let eta_ms = match progress.catch_up_from {
+            _ if remaining == 0 => Some(0),
+            Some((started, start_match)) if match_index > start_match => {
+                let elapsed = self.now_ms.saturating_sub(started);
+                let done = match_index - start_match;
+                Some(remaining.saturating_mul(elapsed) / done)
+            }
+            _ => None,
+        };
         
-        (
-            RaftMessage::VoteResponse {
-                term: self.current_term,
-                vote_granted,
-            },
-            vote_granted, // reset election timer if we granted vote
-        )
+        Some(CatchUpProgress {
+            match_index,
+            target_index,
+            percent,
+            eta_ms,
+            installing_snapshot: progress.state == ProgressState::Snapshot,
+        })
     }
     
-    /// handle a vote response (candidate only)
-    /// returns true if we just became leader
-    pub fn handle_vote_response(&mut self, term: u64, vote_granted: bool, from: u64) -> bool {
-        // if we see a higher term, step down
-        if term > self.current_term {
-            self.become_follower(term);
+    /// whether this node is a standby
+    pub fn is_standby(&self) -> bool {
+        self.standby_nodes.contains(&self.id)
+    }
+    
+    /// whether `node_id` is a voting member
+    pub fn is_voter(&self, node_id: u64) -> bool {
+        self.cluster_nodes.contains(&node_id)
+    }
+    
+    /// admin operation: swap a standby in for a failed voter
+    /// 
+    /// the voter set changes in one step and keeps its size, so quorum
+    /// size is unchanged. the host applies it on every member. returns
+    /// false if `standby` isn't a standby or `failed` isn't a voter.
+    pub fn activate_standby(&mut self, standby: u64, failed: u64) -> bool {
+        let Some(slot) = self.cluster_nodes.iter().position(|&id| id == failed) else {
             return false;
-        }
-        
-        // ignore if we're not a candidate anymore
-        if self.state != NodeState::Candidate {
+        };
+        if !self.standby_nodes.contains(&standby) {
             return false;
         }
         
-        // ignore if term doesn't match (stale response)
-        if term != self.current_term {
-            return false;
+        self.cluster_nodes[slot] = standby;
+        self.standby_nodes.retain(|&id| id != standby);
+        
+        // the standby keeps its replication progress; the failed node loses it
+        self.progress.remove(&failed);
+        self.peer_storage_health.remove(&failed);
+        if self.leader_id == Some(failed) {
+            self.leader_id = None;
         }
         
-        if vote_granted && !self.votes_received.contains(&from) {
-            self.votes_received.push(from);
-            
+        if failed == self.id && self.state != NodeState::Follower {
+            // we were swapped out: stop acting for the cluster
+            self.state = NodeState::Follower;
+            self.read_only.clear();
+        }
+        true
+    }
+    
+    /// last-resort admin operation: make this node the only voter after a
+    /// quorum is lost for good (like etcd's `--force-new-cluster`)
+    /// 
+    /// keeps our log and drops every other member, fencing them: their
+    /// messages are ignored from now on, so an old peer that comes back
+    /// can't start elections in the new cluster or feed it stale entries.
+    /// we then win an election alone in a new term and commit a membership
+    /// record naming us the only voter, which `restore_membership` reads
+    /// back after a restart. entries that only reached the lost peers are
+    /// gone for good; that is the price. returns the fenced ids, which the
+    /// host persists on their own and re-applies with `fence_peer` after a
+    /// restart: the log doesn't carry them
+    pub fn force_new_cluster(&mut self) -> Vec<u64> {
+        let fenced = self.replication_targets();
+        for &node_id in &fenced {
+            self.fence_peer(node_id);
+        }
+        self.cluster_nodes = vec![self.id];
+        self.standby_nodes.clear();
+        self.learner_nodes.clear();
+        self.progress.clear();
+        self.peer_log_hints.clear();
+        self.peer_storage_health.clear();
+        
+        self.start_election();
+        self.become_leader();
+        let membership = Membership { voters: vec![self.id] };
+        self.append_entry_of_type(EntryType::Config, membership.encode());
+        self.try_advance_commit_index();
+        fenced
+    }
+    
+    /// drop every future message from `node_id` (see `force_new_cluster`)
+    pub fn fence_peer(&mut self, node_id: u64) {
+        if node_id != self.id {
+            self.fenced_peers.insert(node_id);
+        }
+    }
+    
+    /// whether messages from `node_id` are dropped
+    pub fn is_fenced(&self, node_id: u64) -> bool {
+        self.fenced_peers.contains(&node_id)
+    }
+    
+    /// admin operation: drop `node_id` from the cluster, whatever its role
+    /// 
+    /// the host applies it on every member, including the removed node:
+    /// that is how it learns it is out. from then on it never campaigns,
+    /// refuses proposals with `ProposeError::Removed`, and sets
+    /// `Ready::removed` once so the host can shut it down. a leader
+    /// removing itself steps down. returns false if `node_id` isn't a member
+    pub fn remove_member(&mut self, node_id: u64) -> bool {
+        if !self.is_member(node_id) {
+            return false;
+        }
+        self.cluster_nodes.retain(|&id| id != node_id);
+        self.standby_nodes.retain(|&id| id != node_id);
+        self.learner_nodes.retain(|&id| id != node_id);
+        self.progress.remove(&node_id);
+        self.peer_log_hints.remove(&node_id);
+        self.peer_storage_health.remove(&node_id);
+        if self.leader_id == Some(node_id) {
+            self.leader_id = None;
+        }
+        if self.transfer.is_some_and(|(target, _)| target == node_id) {
+            self.transfer = None;
+        }
+        
+        if node_id == self.id {
+            self.removed = true;
+            self.become_follower(self.current_term);
+        } else if self.state == NodeState::Leader {
+            // a smaller voter set may already hold a quorum for pending entries
+            self.try_advance_commit_index();
+        }
+        true
+    }
+    
+    /// whether this node was removed from the cluster
+    pub fn is_removed(&self) -> bool {
+        self.removed
+    }
+    
+    // -- membership changes --
+    
+    /// write the initial membership as the first log entry (new nodes only)
+    /// 
+    /// every founding member calls this with the same `initial_members`
+    /// before handling any message. each writes the same entry at index 1
+    /// in term 1, so it is committed from the start, and after a restart
+    /// `restore_membership` reads the voters back from the log (or the
+    /// snapshot that replaced it) instead of trusting what was passed to
+    /// `new`. returns false, changing nothing, if the node already has a
+    /// term, a log or a snapshot
+    pub fn bootstrap(&mut self, initial_members: Vec<u64>) -> bool {
+        if initial_members.is_empty()
+            || self.current_term != 0
+            || !self.log.is_empty()
+            || self.snapshot.is_some()
+        {
+            return false;
+        }
+        self.current_term = 1;
+        self.cluster_nodes = initial_members;
+        let membership = Membership { voters: self.cluster_nodes.clone() };
+        self.append_entry_of_type(EntryType::Config, membership.encode());
+        self.commit_index = 1;
+        true
+    }
+    
+    /// set the voters from the newest membership record in the log, or
+    /// else from the snapshot; returns false if neither has one
+    /// 
+    /// hosts call this on restart once the log (and snapshot) are loaded
+    pub fn restore_membership(&mut self) -> bool {
+        let voters = self
+            .log
+            .iter()
+            .rev()
+            .find_map(Membership::decode)
+            .map(|m| m.voters)
+            .or_else(|| self.snapshot.as_ref().map(|s| s.voters.clone()))
+            .filter(|voters| !voters.is_empty());
+        let Some(voters) = voters else { return false };
+        self.cluster_nodes = voters;
+        true
+    }
+    
+    /// propose a membership change as a `Config` entry (leader only)
+    /// 
+    /// at most one config change may be uncommitted at a time: two
+    /// overlapping changes can leave no majority shared by the old and new
+    /// configurations, so a second one is refused until the first commits.
+    /// the proposal size limits don't apply: removing a dead member must
+    /// work even while its absence backs up the log
+    pub fn propose_conf_change(&mut self, change: Vec<u8>) -> Result<u64, ConfChangeError> {
+        if let Some(leader_hint) = self.redirect() {
+            return Err(ConfChangeError::NotLeader { leader_hint });
+        }
+        if let Some(index) = self.pending_conf_index() {
+            return Err(ConfChangeError::Pending { index });
+        }
+        Ok(self.append_entry_of_type(EntryType::Config, change).index)
+    }
+    
+    /// propose a membership change now, or once the pending one commits
+    /// 
+    /// returns the index if it was appended right away, None if it was
+    /// queued. queued changes are proposed in order, each after the one
+    /// before it commits. they are dropped if we stop leading, since a new
+    /// leader decides its own changes: check `queued_conf_changes`
+    pub fn queue_conf_change(&mut self, change: Vec<u8>) -> Result<Option<u64>, ConfChangeError> {
+        if let Some(leader_hint) = self.redirect() {
+            return Err(ConfChangeError::NotLeader { leader_hint });
+        }
+        if self.pending_conf_index().is_some() || !self.queued_conf_changes.is_empty() {
+            self.queued_conf_changes.push(change);
+            return Ok(None);
+        }
+        Ok(Some(self.append_entry_of_type(EntryType::Config, change).index))
+    }
+    
+    /// index of the config change still waiting to commit, if any
+    pub fn pending_conf_index(&self) -> Option<u64> {
+        self.log
+            .iter()
+            .rev()
+            .take_while(|e| e.index > self.commit_index)
+            .find(|e| e.entry_type == EntryType::Config)
+            .map(|e| e.index)
+    }
+    
+    /// config changes queued behind the pending one, oldest first
+    pub fn queued_conf_changes(&self) -> &[Vec<u8>] {
+        &self.queued_conf_changes
+    }
+    
+    /// propose the next queued config change once nothing is pending
+    fn propose_queued_conf_change(&mut self) {
+        if self.queued_conf_changes.is_empty() || self.pending_conf_index().is_some() {
+            return;
+        }
+        if self.redirect().is_some() {
+            return;
+        }
+        let change = self.queued_conf_changes.remove(0);
+        self.append_entry_of_type(EntryType::Config, change);
+    }
+    
+    // -- election priority --
+    
+    /// election priority of `node_id` (0 if not configured)
+    pub fn priority(&self, node_id: u64) -> u64 {
+        self.config.priorities.get(&node_id).copied().unwrap_or(0)
+    }
+    
+    /// extra wait before campaigning, proportional to how far we rank below
+    /// the top priority (a whole election_timeout_min at priority 0)
+    fn priority_delay(&self) -> u64 {
+        let top = self.config.priorities.values().copied().max().unwrap_or(0);
+        if top == 0 {
+            return 0;
+        }
+        let mine = self.priority(self.id).min(top);
+        self.config.election_timeout_min * (top - mine) / top
+    }
+    
+    /// hand leadership to `target` (leader only)
+    /// 
+    /// `target` must be a caught-up voter. it gets a TimeoutNow and
+    /// campaigns right away in the next term; until it wins (or an election
+    /// timeout passes) proposals are refused with `target` as the hint.
+    /// returns false if the transfer can't start
+    pub fn transfer_leadership(&mut self, target: u64) -> bool {
+        if self.state != NodeState::Leader || target == self.id || !self.is_voter(target) {
+            return false;
+        }
+        if self.is_replication_paused(target) {
+            return false;
+        }
+        let last = self.last_log_index();
+        if self.progress.get(&target).is_none_or(|p| p.match_index < last) {
+            return false;
+        }
+        self.transfer = Some((target, self.now_ms));
+        self.send(target, RaftMessage::TimeoutNow { term: self.current_term, group_id: self.config.group_id });
+        true
+    }
+    
+    /// stop leading without starting a new term (leader only)
+    /// 
+    /// for rolling restarts: with no `transfer_to` we become a follower of
+    /// the same term at once, and the followers elect a successor once they
+    /// stop hearing from us. with a target, leadership is handed over first
+    /// (`transfer_leadership`); the target's election deposes us, and if it
+    /// hasn't won within an election timeout we step down anyway. returns
+    /// false if we aren't leader or the handover can't start
+    pub fn step_down(&mut self, transfer_to: Option<u64>) -> bool {
+        if self.state != NodeState::Leader {
+            return false;
+        }
+        if let Some(target) = transfer_to {
+            self.stepping_down = self.transfer_leadership(target);
+            return self.stepping_down;
+        }
+        self.become_follower(self.current_term);
+        self.reset_election_timer();
+        true
+    }
+    
+    /// the follower best placed to take over leadership (leader only)
+    /// 
+    /// a voter that answered us within election_timeout_min, with healthy
+    /// storage and replication not paused, holding the most of our log;
+    /// ties go to the higher priority, then the lower id. it may still
+    /// trail our last entry, in which case `transfer_leadership` refuses
+    /// until it catches up. None if no follower qualifies
+    pub fn best_transfer_target(&self) -> Option<u64> {
+        if self.state != NodeState::Leader {
+            return None;
+        }
+        self.cluster_nodes
+            .iter()
+            .copied()
+            .filter(|&id| id != self.id && self.is_recently_active(id) && self.is_storage_healthy(id))
+            .filter(|&id| !self.is_replication_paused(id))
+            .filter_map(|id| self.progress.get(&id).map(|p| (id, p.match_index)))
+            .max_by_key(|&(id, match_index)| (match_index, self.priority(id), std::cmp::Reverse(id)))
+            .map(|(id, _)| id)
+    }
+    
+    /// target of the leadership handover in progress, if any
+    pub fn transfer_target(&self) -> Option<u64> {
+        self.transfer.map(|(target, _)| target)
+    }
+    
+    /// yield to `from` if it outranks us (transfer_leadership checks it's caught up)
+    fn maybe_yield_leadership(&mut self, from: u64) {
+        if self.transfer.is_none() && self.priority(from) > self.priority(self.id) {
+            self.transfer_leadership(from);
+        }
+    }
+    
+    /// every node the leader replicates to: voters, standbys and learners, minus self
+    fn replication_targets(&self) -> Vec<u64> {
+        self.cluster_nodes
+            .iter()
+            .chain(&self.standby_nodes)
+            .chain(&self.learner_nodes)
+            .copied()
+            .filter(|&id| id != self.id)
+            .collect()
+    }
+    
+    // -- storage health --
+    
+    /// record our own storage health (called by the host after each fsync)
+    /// 
+    /// the value is piggybacked on every AppendEntries response we send
+    pub fn report_storage_health(&mut self, health: StorageHealth) {
+        self.storage_health = Some(health);
+    }
+    
+    /// record the storage health a follower included in its append response
+    pub fn record_peer_storage_health(&mut self, from: u64, health: StorageHealth) {
+        if from != self.id {
+            self.peer_storage_health.insert(from, health);
+        }
+    }
+    
+    /// cluster health matrix: every node with its last known storage health
+    /// 
+    /// our own entry comes from `report_storage_health`, peers from their
+    /// append responses. `None` means the node never reported.
+    pub fn storage_health_matrix(&self) -> Vec<(u64, Option<StorageHealth>)> {
+        self.cluster_nodes
+            .iter()
+            .map(|&node_id| {
+                let health = if node_id == self.id {
+                    self.storage_health
+                } else {
+                    self.peer_storage_health.get(&node_id).copied()
+                };
+                (node_id, health)
+            })
+            .collect()
+    }
+    
+    /// check whether a node may safely take over leadership storage-wise
+    /// 
+    /// nodes that never reported are assumed healthy (the extension is optional)
+    pub fn is_storage_healthy(&self, node_id: u64) -> bool {
+        self.storage_health_matrix()
+            .into_iter()
+            .find(|(id, _)| *id == node_id)
+            .and_then(|(_, health)| health)
+            .map(|health| health.is_healthy())
+            .unwrap_or(true)
+    }
+    
+    // -- flow control --
+    
+    /// tell the leader whether our apply/persist pipeline is keeping up
+    /// 
+    /// while saturated, every AppendEntries response carries a backoff
+    /// hint and the leader shrinks what it sends us to a trickle
+    pub fn report_backpressure(&mut self, saturated: bool) {
+        self.saturated = saturated;
+    }
+    
+    /// whether a follower currently asked us to back off (leader only)
+    pub fn is_throttled(&self, node_id: u64) -> bool {
+        self.progress.get(&node_id).is_some_and(|p| p.throttled)
+    }
+    
+    /// record the backoff hint from a follower's append response
+    fn record_peer_backoff(&mut self, from: u64, backoff: bool) {
+        if let Some(progress) = self.progress.get_mut(&from) {
+            progress.throttled = backoff;
+        }
+    }
+    
+    /// note that `from` answered us (leader only)
+    fn record_peer_activity(&mut self, from: u64) {
+        if let Some(progress) = self.progress.get_mut(&from) {
+            progress.last_active_ms = Some(self.now_ms);
+            progress.probe_failures = 0;
+        }
+    }
+    
+    /// whether a quorum of voters (us included) answered within the last
+    /// election_timeout_min (leader only; see `RaftConfig::check_quorum`)
+    pub fn quorum_active(&self) -> bool {
+        let active = self.cluster_nodes.iter().copied().filter(|&id| id == self.id || self.is_recently_active(id));
+        self.is_quorum(QuorumKind::Replication, active)
+    }
+    
+    /// whether `peer` answered us within election_timeout_min (leader only)
+    fn is_recently_active(&self, peer: u64) -> bool {
+        self.progress
+            .get(&peer)
+            .and_then(|p| p.last_active_ms)
+            .is_some_and(|at| self.now_ms.saturating_sub(at) < self.config.election_timeout_min)
+    }
+    
+    /// the transport couldn't reach `peer` (leader only)
+    /// 
+    /// whatever was pipelined to it is presumed lost: stop streaming and
+    /// probe one message at a time from its last known match, so a dead
+    /// peer costs one outstanding message instead of a full window
+    pub fn report_unreachable(&mut self, peer: u64) {
+        if self.state != NodeState::Leader {
+            return;
+        }
+        if let Some(progress) = self.progress.get_mut(&peer) {
+            if progress.state == ProgressState::Replicate {
+                progress.become_probe();
+                progress.next_index = progress.match_index + 1;
+            }
+        }
+    }
+    
+    /// the transport finished (or gave up on) sending a snapshot to `peer` (leader only)
+    /// 
+    /// on success we probe from just past the snapshot without waiting for
+    /// the follower's InstallSnapshotResponse; on failure we probe from the
+    /// last match, which sends the snapshot again if it's still needed.
+    /// either way the follower's max_snapshots_in_flight slot is freed
+    pub fn report_snapshot(&mut self, peer: u64, success: bool) {
+        if self.state != NodeState::Leader {
+            return;
+        }
+        let Some(progress) = self.progress.get_mut(&peer) else {
+            return;
+        };
+        if progress.state != ProgressState::Snapshot {
+            return;
+        }
+        let snapshot_index = progress.inflights.oldest_first_index().unwrap_or(0);
+        progress.become_probe();
+        progress.next_index = if success {
+            progress.match_index.max(snapshot_index) + 1
+        } else {
+            progress.match_index + 1
+        };
+    }
+    
+    // -- operator controls --
+    
+    /// stop replicating to `peer` without removing it from the cluster (leader only)
+    /// 
+    /// for a peer under maintenance: it gets no entries, snapshots,
+    /// heartbeats or leadership handovers until `resume_replication`, and
+    /// stays a member throughout. a paused voter still counts toward every
+    /// quorum, so commits (and leadership, with check_quorum) depend on the
+    /// voters left. the pause belongs to this leadership: a new leader
+    /// replicates to the peer as usual. returns false if we aren't leader
+    /// or `peer` isn't one of our followers
+    pub fn pause_replication(&mut self, peer: u64) -> bool {
+        if self.state != NodeState::Leader {
+            return false;
+        }
+        let Some(progress) = self.progress.get_mut(&peer) else {
+            return false;
+        };
+        // whatever was in flight is forgotten, freeing its snapshot slot
+        progress.replication_paused = true;
+        progress.become_probe();
+        progress.next_index = progress.match_index + 1;
+        true
+    }
+    
+    /// replicate to `peer` again after `pause_replication` (leader only)
+    /// 
+    /// probing restarts from its last match right away, or a heartbeat
+    /// goes out if it already holds our whole log. returns false if we
+    /// aren't leader or replication to `peer` wasn't paused
+    pub fn resume_replication(&mut self, peer: u64) -> bool {
+        if !self.is_replication_paused(peer) {
+            return false;
+        }
+        if let Some(progress) = self.progress.get_mut(&peer) {
+            progress.replication_paused = false;
+        }
+        if !self.send_append(peer) {
+            if let Some(heartbeat) = self.create_heartbeat(peer) {
+                self.send(peer, heartbeat);
+            }
+        }
+        true
+    }
+    
+    /// whether replication to `peer` is paused (see `pause_replication`)
+    pub fn is_replication_paused(&self, peer: u64) -> bool {
+        self.state == NodeState::Leader && self.progress.get(&peer).is_some_and(|p| p.replication_paused)
+    }
+    
+    // -- log helpers --
+    
+    /// get the index of the last log entry (the snapshot's, or the last
+    /// spilled entry's, if the log is empty)
+    pub fn last_log_index(&self) -> u64 {
+        self.log.last().map(|e| e.index).unwrap_or_else(|| self.log_base().0)
+    }
+    
+    /// get the term of the last log entry (the snapshot's, or the last
+    /// spilled entry's, if the log is empty)
+    pub fn last_log_term(&self) -> u64 {
+        self.log.last().map(|e| e.term).unwrap_or_else(|| self.log_base().1)
+    }
+    
+    /// index of the first entry still in the log
+    /// 
+    /// 1 until the log is compacted or spilled, then the entry right after
+    /// the snapshot or the last spilled entry
+    pub fn first_log_index(&self) -> u64 {
+        self.log.first().map(|e| e.index).unwrap_or_else(|| self.log_base().0 + 1)
+    }
+    
+    /// (index, term) of the entry just before the in-memory log: the last
+    /// spilled entry, or the snapshot's last included one
+    fn log_base(&self) -> (u64, u64) {
+        if self.spilled.0 > self.snapshot_index() {
+            self.spilled
+        } else {
+            (self.snapshot_index(), self.snapshot_term())
+        }
+    }
+    
+    /// index of the last entry spilled to the cold log (0 if none)
+    /// 
+    /// entries from the snapshot up to here are only in storage
+    pub fn spilled_index(&self) -> u64 {
+        self.spilled.0
+    }
+    
+    /// last index covered by our snapshot (0 without one)
+    pub fn snapshot_index(&self) -> u64 {
+        self.snapshot.as_ref().map_or(0, |s| s.metadata.last_included_index)
+    }
+    
+    /// term of the last entry covered by our snapshot (0 without one)
+    pub fn snapshot_term(&self) -> u64 {
+        self.snapshot.as_ref().map_or(0, |s| s.metadata.last_included_term)
+    }
+    
+    /// get log entry at a specific index (1-indexed)
+    pub fn get_entry(&self, index: u64) -> Option<&LogEntry> {
+        self.log_position(index).map(|pos| &self.log[pos])
+    }
+    
+    /// position of `index` in `self.log`, if the log holds it
+    /// 
+    /// the log is contiguous, so this is an offset from its first index
+    /// rather than a scan
+    fn log_position(&self, index: u64) -> Option<usize> {
+        let first = self.log.first()?.index;
+        if index < first {
+            return None;
+        }
+        let pos = usize::try_from(index - first).ok()?;
+        self.log.get(pos).filter(|e| e.index == index).map(|_| pos)
+    }
+    
+    /// the log from `index` on (all of it if `index` is before its start)
+    fn log_from(&self, index: u64) -> &[LogEntry] {
+        let skip = index.saturating_sub(self.first_log_index());
+        let start = usize::try_from(skip).map_or(self.log.len(), |skip| skip.min(self.log.len()));
+        &self.log[start..]
+    }
+    
+    /// get the term of entry at a specific index (0 if not found)
+    /// 
+    /// the snapshot's last included index still has a known term, which is
+    /// what lets replication resume right after a snapshot. anything before
+    /// it was compacted away and reports 0. spilled entries are read back
+    /// from the cold log
+    pub fn get_term_at(&self, index: u64) -> u64 {
+        if index != 0 && index == self.snapshot_index() {
+            return self.snapshot_term();
+        }
+        if index == self.spilled.0 && index > self.snapshot_index() {
+            return self.spilled.1;
+        }
+        if index > self.snapshot_index() && index < self.spilled.0 {
+            return self.read_log(index, 1).and_then(|e| e.first().map(|e| e.term)).unwrap_or(0);
+        }
+        self.get_entry(index).map(|e| e.term).unwrap_or(0)
+    }
+    
+    /// the hash chain link of the entry at `index` (None at 0, for entries
+    /// written without one, or if it can't be read back)
+    fn hash_at(&self, index: u64) -> Option<u64> {
+        if index == 0 {
+            return None;
+        }
+        if index == self.snapshot_index() {
+            return self.snapshot.as_ref()?.metadata.last_included_hash;
+        }
+        if index > self.snapshot_index() && index <= self.spilled.0 {
+            return self.read_log(index, 1)?.first()?.hash;
+        }
+        self.get_entry(index)?.hash
+    }
+    
+    /// check that every entry in the log links to the one before it, from
+    /// the snapshot or spilled entry the log starts after
+    pub fn verify_log(&self) -> Result<(), ChainBreak> {
+        LogEntry::verify_chain(self.hash_at(self.log_base().0), &self.log)
+    }
+    
+    /// the last entry a leader sent that didn't link to our log (hash_chain
+    /// only; None once a later append links)
+    pub fn chain_break(&self) -> Option<ChainBreak> {
+        self.chain_break
+    }
+    
+    /// up to `max` entries from `index` on, spilled ones read back from the
+    /// cold log (None if it can't supply them)
+    /// 
+    /// `index` must be past the snapshot
+    fn read_log(&self, index: u64, max: usize) -> Option<Vec<LogEntry>> {
+        let mut entries = Vec::new();
+        if index <= self.spilled.0 && max > 0 {
+            let end = (self.spilled.0 + 1).min(index.saturating_add(max as u64));
+            entries = self.cold_log.as_ref()?.read_entries(index..end)?;
+            if !entries.iter().map(|e| e.index).eq(index..end) {
+                return None;
+            }
+        }
+        let hot = max - entries.len();
+        entries.extend(self.log_from(index).iter().take(hot).cloned());
+        Some(entries)
+    }
+    
+    /// read spilled entries back from `cold_log` from now on
+    /// 
+    /// nothing is spilled until this is installed, whatever
+    /// `config.max_hot_entries` says
+    pub fn set_cold_log(&mut self, cold_log: Box<dyn ColdLog>) {
+        self.cold_log = Some(cold_log);
+    }
+    
+    /// drop applied entries past the hot tail from memory (see
+    /// `config.max_hot_entries`)
+    /// 
+    /// only entries that are applied and durable go, so storage is sure to
+    /// hold every one of them
+    fn spill_cold_entries(&mut self) {
+        let Some(hot) = self.config.max_hot_entries.filter(|_| self.cold_log.is_some()) else {
+            return;
+        };
+        if self.log.len() < hot.saturating_mul(2) {
+            return;
+        }
+        let keep_from = self.last_log_index().saturating_sub(hot as u64) + 1;
+        let through = (keep_from - 1).min(self.last_applied).min(self.stable_index());
+        let Some(pos) = self.log_position(through) else {
+            return;
+        };
+        self.spilled = (through, self.log[pos].term);
+        self.log.drain(..=pos);
+    }
+    
+    /// propose a client command, returning the index it was appended at
+    /// 
+    /// unlike `append_entry`, this refuses on anything but the leader, so
+    /// hosts can redirect clients instead of writing to a follower's log.
+    /// the entry still has to be replicated (`replicate`) and committed
+    pub fn propose(&mut self, command: Vec<u8>) -> Result<u64, ProposeError> {
+        self.check_proposable()?;
+        self.check_proposal_size(std::slice::from_ref(&command))?;
+        Ok(self.append_entry(command).index)
+    }
+    
+    /// propose several commands at once, returning the indexes they took
+    /// 
+    /// all or nothing: the commands land back to back in one index range,
+    /// so they come out in a single Ready (one write for the host) and
+    /// replicate together. an empty batch appends nothing and returns the
+    /// empty range at the next index
+    pub fn propose_many(&mut self, commands: Vec<Vec<u8>>) -> Result<Range<u64>, ProposeError> {
+        self.check_proposable()?;
+        self.check_proposal_size(&commands)?;
+        let first = self.last_log_index() + 1;
+        if commands.is_empty() {
+            return Ok(first..first);
+        }
+        let term = self.current_term;
+        self.log.reserve(commands.len());
+        for (command, index) in commands.into_iter().zip(first..) {
+            self.push_entry(LogEntry::new(term, index, command));
+        }
+        Ok(first..self.last_log_index() + 1)
+    }
+    
+    /// refuse proposals off the leader, or while leadership is being handed over
+    fn check_proposable(&self) -> Result<(), ProposeError> {
+        if self.removed {
+            return Err(ProposeError::Removed);
+        }
+        match self.redirect() {
+            Some(leader_hint) => Err(ProposeError::NotLeader { leader_hint }),
+            None => Ok(()),
+        }
+    }
+    
+    /// None if we can take proposals, else where to send them (if known)
+    fn redirect(&self) -> Option<Option<u64>> {
+        if self.state != NodeState::Leader {
+            return Some(self.leader_id.filter(|&id| id != self.id));
+        }
+        // anything appended during a handover would leave the target's log behind
+        self.transfer.map(|(target, _)| Some(target))
+    }
+    
+    /// refuse commands over `max_proposal_bytes`, or that would take the
+    /// uncommitted tail of the log past `max_uncommitted_bytes`
+    fn check_proposal_size(&self, commands: &[Vec<u8>]) -> Result<(), ProposeError> {
+        if let Some(limit) = self.config.max_proposal_bytes {
+            if let Some(size) = commands.iter().map(|c| c.len() as u64).find(|&size| size > limit) {
+                return Err(ProposeError::TooLarge { size, limit });
+            }
+        }
+        if let Some(limit) = self.config.max_uncommitted_bytes {
+            let uncommitted = self.uncommitted_bytes();
+            let adding: u64 = commands.iter().map(|c| c.len() as u64 + ENTRY_OVERHEAD_BYTES).sum();
+            // over the limit on its own is fine when nothing is waiting:
+            // otherwise one big batch could never be proposed at all
+            if uncommitted > 0 && uncommitted + adding > limit {
+                return Err(ProposeError::UncommittedFull { uncommitted, limit });
+            }
+        }
+        Ok(())
+    }
+    
+    /// bytes held by entries past the commit index (as `LogEntry::size_bytes`)
+    pub fn uncommitted_bytes(&self) -> u64 {
+        self.log
+            .iter()
+            .rev()
+            .take_while(|e| e.index > self.commit_index)
+            .map(LogEntry::size_bytes)
+            .sum()
+    }
+    
+    /// `propose`, returning a handle that a later Ready resolves
+    /// 
+    /// the handle is the (term, index) the entry was appended at. once the
+    /// entry commits, or a newer leader overwrites it, the handle comes back
+    /// in `Ready::proposals` with its outcome, exactly once. hosts keep a map
+    /// from handle to waiting client instead of polling `verify_commit`
+    pub fn propose_tracked(&mut self, command: Vec<u8>) -> Result<CommitProof, ProposeError> {
+        let index = self.propose(command)?;
+        let proof = CommitProof { term: self.current_term, index };
+        self.tracked_proposals.push(proof);
+        Ok(proof)
+    }
+    
+    /// tracked proposals not resolved yet
+    pub fn pending_proposals(&self) -> &[CommitProof] {
+        &self.tracked_proposals
+    }
+    
+    /// settle every tracked proposal whose fate is known, keeping the rest
+    /// 
+    /// an entry we appended can only leave our log by truncation, so a
+    /// log that no longer reaches the index, or holds another term there,
+    /// means it was superseded even if nothing is committed there yet
+    fn resolve_proposals(&mut self) -> Vec<(CommitProof, ProposalOutcome)> {
+        let mut resolved = Vec::new();
+        let last = self.last_log_index();
+        let tracked = std::mem::take(&mut self.tracked_proposals);
+        for proof in tracked {
+            let outcome = if proof.index > last {
+                Some(ProposalOutcome::Superseded)
+            } else {
+                match self.get_term_at(proof.index) {
+                    0 => Some(ProposalOutcome::Compacted),
+                    term if term != proof.term => Some(ProposalOutcome::Superseded),
+                    _ if proof.index <= self.commit_index => Some(ProposalOutcome::Committed),
+                    _ => None,
+                }
+            };
+            match outcome {
+                Some(outcome) => resolved.push((proof, outcome)),
+                None => self.tracked_proposals.push(proof),
+            }
+        }
+        resolved
+    }
+    
+    /// the proof to hand a client once `index` is committed (None before that)
+    pub fn commit_proof(&self, index: u64) -> Option<CommitProof> {
+        if index == 0 || index > self.commit_index {
+            return None;
+        }
+        match self.get_term_at(index) {
+            0 => None, // compacted past
+            term => Some(CommitProof { term, index }),
+        }
+    }
+    
+    /// check a proof against our log (see `CommitStatus`)
+    /// 
+    /// a committed entry never changes, so `Committed` and `Lost` are final.
+    /// past our commit index a mismatch only means our own entry may be
+    /// overwritten, so it reads `Unknown`, as does an index our log doesn't
+    /// reach yet; ask the leader
+    pub fn verify_commit(&self, proof: CommitProof) -> CommitStatus {
+        if proof.index == 0 || proof.index > self.last_log_index() {
+            return CommitStatus::Unknown;
+        }
+        let committed = proof.index <= self.commit_index;
+        match self.get_term_at(proof.index) {
+            0 => CommitStatus::Compacted,
+            term if term == proof.term && committed => CommitStatus::Committed,
+            term if term == proof.term => CommitStatus::Uncommitted,
+            _ if committed => CommitStatus::Lost,
+            _ => CommitStatus::Unknown,
+        }
+    }
+    
+    /// committed entries after `after`, oldest first, at most `max` of them
+    /// 
+    /// a change feed for downstream indexes and caches. `after` is the proof
+    /// of the last entry the consumer handled (None to start from the
+    /// beginning); resume with `commit_proof` of the last entry returned.
+    /// entries come back whatever their `entry_type`, so positions always
+    /// advance; consumers skip the no-ops and config changes they don't need.
+    /// a position past our commit index just returns nothing yet
+    pub fn committed_since(&self, after: Option<CommitProof>, max: usize) -> Result<Vec<LogEntry>, ChangelogError> {
+        let after = after.unwrap_or(CommitProof { term: 0, index: 0 });
+        if after.index < self.snapshot_index() {
+            return Err(ChangelogError::Compacted { snapshot_index: self.snapshot_index() });
+        }
+        if after.index > 0 && after.index <= self.commit_index && self.get_term_at(after.index) != after.term {
+            return Err(ChangelogError::Diverged);
+        }
+        let committed = usize::try_from(self.commit_index.saturating_sub(after.index)).unwrap_or(usize::MAX);
+        self.read_log(after.index + 1, max.min(committed)).ok_or(ChangelogError::Unavailable)
+    }
+    
+    /// append a new entry to the log (leader only)
+    pub fn append_entry(&mut self, command: Vec<u8>) -> &LogEntry {
+        self.append_entry_of_type(EntryType::Normal, command)
+    }
+    
+    /// append a new entry of `entry_type` to the log (leader only)
+    /// 
+    /// membership changes go in as `EntryType::Config`, so hosts can tell
+    /// them apart from client commands when they are applied
+    pub fn append_entry_of_type(&mut self, entry_type: EntryType, command: Vec<u8>) -> &LogEntry {
+        let entry = LogEntry::with_type(
+            self.current_term,
+            self.last_log_index() + 1,
+            entry_type,
+            command,
+        );
+        self.push_entry(entry);
+        self.log.last().unwrap()
+    }
+    
+    /// open a client session (leader only), returning its client id
+    /// 
+    /// the id is the index of the registration entry; the client can use it
+    /// once that entry commits
+    pub fn register_session(&mut self) -> u64 {
+        let client_id = self.last_log_index() + 1;
+        self.append_client_entry(client_id, 0, Vec::new()).index
+    }
+    
+    /// append a client command tagged with its session and sequence (leader only)
+    /// 
+    /// a retry must reuse the original sequence: once either copy is
+    /// applied, the other comes out of `get_entries_to_apply` marked
+    /// `Dedup::Duplicate`
+    pub fn append_client_entry(&mut self, client_id: u64, sequence: u64, command: Vec<u8>) -> &LogEntry {
+        let mut entry = LogEntry::new(self.current_term, self.last_log_index() + 1, command);
+        entry.client = Some(ClientRequest { client_id, sequence });
+        self.push_entry(entry);
+        self.log.last().unwrap()
+    }
+    
+    /// append an entry we wrote, chaining its hash to our last entry's
+    fn push_entry(&mut self, mut entry: LogEntry) {
+        if self.config.hash_chain {
+            entry.hash = Some(entry.chain_hash(self.hash_at(self.last_log_index())));
+        }
+        self.mark_unpersisted(entry.index);
+        self.log.push(entry);
+    }
+    
+    /// remember that entries from `index` onward still need persisting
+    fn mark_unpersisted(&mut self, index: u64) {
+        self.unpersisted_from = Some(self.unpersisted_from.map_or(index, |i| i.min(index)));
+        if self.config.async_persist {
+            self.unstable_from = Some(self.unstable_from.map_or(index, |i| i.min(index)));
+        }
+    }
+    
+    /// entries up to `index` are durable (storage acked them, or a snapshot covers them)
+    fn mark_stable(&mut self, index: u64) {
+        if self.unstable_from.is_some_and(|from| index >= from) {
+            self.unstable_from = (index < self.last_log_index()).then_some(index + 1);
+        }
+    }
+    
+    /// highest log index known to be durable
+    /// 
+    /// with `RaftConfig::async_persist`, entries past it are unstable: the
+    /// host has them in a Ready but hasn't reported them persisted. they may
+    /// be sent to followers, but a leader doesn't count itself toward their
+    /// commit and a follower doesn't acknowledge them. without it, this is
+    /// always the last log index
+    pub fn stable_index(&self) -> u64 {
+        let last = self.last_log_index();
+        self.unstable_from.map_or(last, |from| last.min(from - 1))
+    }
+    
+    /// storage made the log durable up to `index`, whose entry is from `term`
+    /// (async_persist only)
+    /// 
+    /// storage writes in order, so everything before `index` is durable too.
+    /// a report for an entry that has since been overwritten is ignored.
+    /// a leader may commit what is now stable on its side; a follower acks
+    /// the leader for what it held back. call `ready` for the results
+    pub fn report_persisted(&mut self, index: u64, term: u64) {
+        if self.get_term_at(index) != term {
+            return;
+        }
+        self.mark_stable(index);
+        match self.state {
+            NodeState::Leader => {
+                self.try_advance_commit_index();
+            }
+            NodeState::Follower => self.send_pending_ack(),
+            _ => {}
+        }
+    }
+    
+    /// ack the leader for the held-back part of its last append that is
+    /// now stable (follower only)
+    fn send_pending_ack(&mut self) {
+        let (Some(owed), Some(leader_id)) = (self.pending_ack, self.leader_id) else {
+            return;
+        };
+        let match_index = owed.min(self.stable_index());
+        if owed <= match_index {
+            self.pending_ack = None;
+        }
+        self.send(leader_id, RaftMessage::AppendEntriesResponse {
+            term: self.current_term,
+            success: true,
+            match_index,
+            storage_health: self.storage_health,
+            backoff: self.saturated,
+            seq: 0,
+            group_id: self.config.group_id,
+        });
+    }
+    
+    // -- snapshots --
+    
+    /// encrypt/sign snapshots with `sealer` from now on
+    pub fn set_snapshot_sealer(&mut self, sealer: Box<dyn SnapshotSealer>) {
+        self.snapshot_sealer = Some(sealer);
+    }
+    
+    /// the state machine data inside `snapshot`
+    /// 
+    /// opens it with the snapshot sealer if one is installed; None means
+    /// the snapshot was tampered with or isn't ours to read
+    pub fn open_snapshot(&self, snapshot: &Snapshot) -> Option<Vec<u8>> {
+        match &self.snapshot_sealer {
+            Some(sealer) => sealer.open(&snapshot.metadata, &snapshot.data),
+            None => Some(snapshot.data.clone()),
+        }
+    }
+    
+    /// drive `machine` from a Ready: restore its snapshot, then apply its
+    /// committed entries
+    /// 
+    /// entries the machine shouldn't run (no-ops, config changes, session
+    /// duplicates and unknown sessions) are skipped; returns (index, result)
+    /// for every entry it did run. `last_applied` already covers the whole
+    /// Ready, so hosts serve `read_states` against it. for `compact_to`,
+    /// hand `machine.snapshot()` to `compact`
+    pub fn apply_ready<M: StateMachine + ?Sized>(&self, ready: &Ready, machine: &mut M) -> Vec<(u64, Vec<u8>)> {
+        // the sealer already accepted any snapshot that made it into a Ready
+        if let Some(data) = ready.snapshot.as_ref().and_then(|s| self.open_snapshot(s)) {
+            machine.restore(&data);
+        }
+        Self::apply_entries(&ready.committed_entries, machine)
+    }
+    
+    /// run the entries of `entries` that `machine` should see (see `apply_ready`)
+    fn apply_entries<M: StateMachine + ?Sized>(entries: &[LogEntry], machine: &mut M) -> Vec<(u64, Vec<u8>)> {
+        entries
+            .iter()
+            .filter(|e| e.entry_type == EntryType::Normal)
+            .filter(|e| matches!(e.dedup, None | Some(Dedup::Fresh)))
+            .map(|e| (e.index, machine.apply(e)))
+            .collect()
+    }
+    
+    /// rebuild a restarted node from what storage held, and bring a freshly
+    /// constructed `machine` up to date
+    /// 
+    /// the startup routine every host used to write by hand. `hard_state`,
+    /// `snapshot` and `log` are what was persisted; `commit` is the highest
+    /// index storage recorded as committed (0 if it keeps none; raft
+    /// itself only needs term, vote and log). the snapshot is loaded as by
+    /// `restore_snapshot` and restored into `machine`, voters come back
+    /// from the newest membership record, and every committed entry past
+    /// the snapshot is replayed into `machine` at once, whatever
+    /// `max_apply_batch` says. commit is capped at the log, so a commit
+    /// recorded past entries that never became durable is harmless. with
+    /// `verify_rejoin`, the node then starts its rejoin handshake
+    /// 
+    /// returns (index, result) for every entry `machine` ran, or None (and
+    /// changes nothing) if the snapshot sealer rejects the snapshot or,
+    /// with `hash_chain`, the log past it doesn't link up
+    pub fn recover<M: StateMachine + ?Sized>(
+        &mut self,
+        hard_state: HardState,
+        snapshot: Option<Snapshot>,
+        log: Vec<LogEntry>,
+        commit: u64,
+        machine: &mut M,
+    ) -> Option<Vec<(u64, Vec<u8>)>> {
+        let data = match &snapshot {
+            Some(snapshot) => Some(self.open_snapshot(snapshot)?),
+            None => None,
+        };
+        if self.config.hash_chain {
+            let base = snapshot.as_ref().map(|s| s.metadata).unwrap_or_default();
+            let tail = log.iter().position(|e| e.index > base.last_included_index).unwrap_or(log.len());
+            LogEntry::verify_chain(base.last_included_hash, &log[tail..]).ok()?;
+        }
+        
+        self.current_term = hard_state.term;
+        self.voted_for = hard_state.vote;
+        // already durable: the first Ready needn't write it again
+        self.reported_hard_state = hard_state;
+        self.log = log;
+        if let Some(snapshot) = snapshot {
+            self.restore_snapshot(snapshot);
+        }
+        if let Some(data) = data {
+            machine.restore(&data);
+        }
+        self.restore_membership();
+        
+        self.commit_index = self.commit_index.max(commit.min(self.last_log_index()));
+        let mut applied = Vec::new();
+        while self.has_pending_apply() {
+            let entries = self.get_entries_to_apply();
+            applied.extend(Self::apply_entries(&entries, machine));
+        }
+        if self.config.verify_rejoin {
+            self.begin_rejoin();
+        }
+        Some(applied)
+    }
+    
+    /// load a persisted snapshot (e.g. at startup)
+    /// 
+    /// entries the snapshot covers are dropped from the log, and a log that
+    /// starts right after it (as stored once a snapshot is installed) is
+    /// kept; if the log disagrees with the snapshot about its last included
+    /// entry, the whole log is dropped. commit and apply jump to the snapshot index, so the
+    /// host must restore its state machine from `open_snapshot()` as well.
+    /// returns false (and does nothing) if the snapshot sealer rejects it
+    pub fn restore_snapshot(&mut self, snapshot: Snapshot) -> bool {
+        if self.open_snapshot(&snapshot).is_none() {
+            return false;
+        }
+        
+        let index = snapshot.metadata.last_included_index;
+        let term = snapshot.metadata.last_included_term;
+        
+        if self.get_term_at(index) == term {
+            let covered = self.log_position(index).map_or(0, |pos| pos + 1);
+            self.log.drain(..covered);
+        } else if !matches!(self.log.first(), Some(e) if e.index == index + 1) {
+            self.log.clear();
+        }
+        self.commit_index = self.commit_index.max(index);
+        if index > self.last_applied {
+            self.last_applied = index;
+            self.sessions = snapshot.sessions.clone();
+        }
+        self.snapshot = Some(snapshot);
+        self.mark_stable(index);
+        true
+    }
+    
+    /// (node id, match index) of the follower furthest behind: voters,
+    /// learners and standbys alike (leader only, None without followers)
+    /// 
+    /// compacting past its match index means it can only catch up from a
+    /// snapshot; see `compact_checked`
+    pub fn slowest_follower(&self) -> Option<(u64, u64)> {
+        if self.state != NodeState::Leader {
+            return None;
+        }
+        self.progress
+            .iter()
+            .filter(|(&id, _)| id != self.id)
+            .map(|(&id, p)| (id, p.match_index))
+            .min_by_key(|&(id, match_index)| (match_index, id))
+    }
+    
+    /// `compact`, refusing to drop entries a follower still needs
+    /// 
+    /// on a leader, compacting past the slowest follower's match index is
+    /// refused unless `allow_snapshot_catch_up` is set, in which case that
+    /// follower is sent the snapshot instead. followers and candidates
+    /// track no peers, so only the range is checked
+    pub fn compact_checked(&mut self, index: u64, data: Vec<u8>, allow_snapshot_catch_up: bool) -> Result<(), CompactError> {
+        if !allow_snapshot_catch_up {
+            if let Some((node_id, match_index)) = self.slowest_follower().filter(|&(_, m)| m < index) {
+                return Err(CompactError::FollowerBehind { node_id, match_index });
+            }
+        }
+        let (snapshot_index, last_applied) = (self.snapshot_index(), self.last_applied);
+        if self.compact(index, data) {
+            Ok(())
+        } else {
+            Err(CompactError::OutOfRange { index, snapshot_index, last_applied })
+        }
+    }
+    
+    /// compact the log up to `index` into a snapshot of the state machine
+    /// 
+    /// `data` is the host's state machine as of `index`. entries up to and
+    /// including `index` are discarded; followers that still need them are
+    /// sent the snapshot instead. only applied entries can be compacted, and
+    /// only past the current snapshot. returns false (and does nothing)
+    /// otherwise, or if spilled entries it covers can't be read back
+    pub fn compact(&mut self, index: u64, data: Vec<u8>) -> bool {
+        if index <= self.snapshot_index() || index > self.last_applied {
+            return false;
+        }
+        
+        // spilled entries the snapshot covers still count toward its sessions
+        let spilled_through = self.spilled.0.min(index);
+        let spilled = if spilled_through > self.snapshot_index() {
+            let count = (spilled_through - self.snapshot_index()) as usize;
+            let Some(entries) = self.read_log(self.snapshot_index() + 1, count) else {
+                return false;
+            };
+            entries
+        } else {
+            Vec::new()
+        };
+        
+        let term = self.get_term_at(index);
+        let mut snapshot = Snapshot::new(index, term, data);
+        snapshot.metadata.last_included_hash = self.hash_at(index);
+        if let Some(sealer) = &self.snapshot_sealer {
+            snapshot.data = sealer.seal(&snapshot.metadata, &snapshot.data);
+        }
+        // sessions as of `index`, which may be behind last_applied
+        snapshot.sessions = self.snapshot.as_ref().map(|s| s.sessions.clone()).unwrap_or_default();
+        let covered = self.log_position(index).map_or(0, |pos| pos + 1);
+        for entry in spilled.iter().chain(&self.log[..covered]) {
+            snapshot.sessions.apply(entry.client);
+        }
+        snapshot.voters = self.cluster_nodes.clone();
+        self.log.drain(..covered);
+        self.snapshot = Some(snapshot);
+        self.mark_stable(index);
+        true
+    }
+    
+    /// bytes held by the in-memory log (see `LogEntry::size_bytes`)
+    pub fn log_bytes(&self) -> u64 {
+        self.log.iter().map(LogEntry::size_bytes).sum()
+    }
+    
+    /// whether the log has grown past `config.log_bytes_soft_limit`
+    pub fn over_log_budget(&self) -> bool {
+        self.config.log_bytes_soft_limit.is_some_and(|limit| self.log_bytes() > limit)
+    }
+    
+    /// index to ask the host to compact to, at most once per applied index
+    /// 
+    /// only fires while over budget and once there are applied entries
+    /// past the snapshot, since nothing else can be compacted
+    fn take_compaction_request(&mut self) -> Option<u64> {
+        let index = self.last_applied;
+        if index <= self.snapshot_index() || index <= self.compaction_requested_at {
+            return None;
+        }
+        if !self.over_log_budget() {
+            return None;
+        }
+        self.compaction_requested_at = index;
+        Some(index)
+    }
+    
+    /// whether a follower expecting `next_idx` can only be caught up by snapshot
+    fn needs_snapshot(&self, next_idx: u64) -> bool {
+        next_idx <= self.snapshot_index() && self.get_entry(next_idx).is_none()
+    }
+    
+    /// serialize our snapshot for streaming, unless it's already cached
+    fn refresh_snapshot_wire(&mut self) {
+        if self.config.snapshot_chunk_bytes.is_none() {
+            return;
+        }
+        let Some(snapshot) = &self.snapshot else {
+            self.snapshot_wire = None;
+            return;
+        };
+        if self.snapshot_wire.as_ref().is_some_and(|(metadata, _)| *metadata == snapshot.metadata) {
+            return;
+        }
+        self.snapshot_wire = Some((snapshot.metadata, snapshot.to_bytes()));
+    }
+    
+    /// the chunk of our snapshot starting at `offset` (None unless chunking is on)
+    fn snapshot_chunk(&self, offset: u64) -> Option<RaftMessage> {
+        let limit = self.config.snapshot_chunk_bytes?;
+        let snapshot = self.snapshot.as_ref()?;
+        let fresh;
+        let bytes: &[u8] = match &self.snapshot_wire {
+            Some((metadata, bytes)) if *metadata == snapshot.metadata => bytes,
+            _ => {
+                fresh = snapshot.to_bytes();
+                &fresh
+            }
+        };
+        let len = bytes.len() as u64;
+        let start = offset.min(len);
+        let end = start.saturating_add(limit).min(len);
+        Some(RaftMessage::InstallSnapshotChunk {
+            term: self.current_term,
+            leader_id: self.id,
+            last_included_index: snapshot.metadata.last_included_index,
+            last_included_term: snapshot.metadata.last_included_term,
+            offset: start,
+            data: bytes[start as usize..end as usize].to_vec(),
+            done: end == len,
+            group_id: self.config.group_id,
+        })
+    }
+    
+    /// whether `follower_id` may be sent a snapshot now: a slot is free
+    /// under max_snapshots_in_flight and the byte rate allows it
+    fn may_start_snapshot(&self, follower_id: u64) -> bool {
+        let in_flight = self
+            .progress
+            .iter()
+            .filter(|(id, p)| **id != follower_id && p.state == ProgressState::Snapshot)
+            .count();
+        in_flight < self.config.max_snapshots_in_flight && self.now_ms >= self.snapshot_send_at_ms
+    }
+    
+    /// account for `bytes` of snapshot sent under snapshot_bytes_per_sec
+    /// 
+    /// a send is never split: it goes out whole, and the next one waits
+    /// until the rate has paid for it
+    fn charge_snapshot_bytes(&mut self, bytes: u64) {
+        if let Some(rate) = self.config.snapshot_bytes_per_sec {
+            let cost_ms = bytes.saturating_mul(1000) / rate;
+            self.snapshot_send_at_ms = self.snapshot_send_at_ms.max(self.now_ms).saturating_add(cost_ms);
+        }
+    }
+    
+    /// send `follower_id` the snapshot chunk at its confirmed offset
+    /// (leader only, follower waiting on a snapshot)
+    /// 
+    /// held back while the byte rate is spent; the follower's next
+    /// heartbeat response offers it again
+    fn send_snapshot_chunk(&mut self, follower_id: u64) {
+        if self.now_ms < self.snapshot_send_at_ms {
+            return;
+        }
+        self.refresh_snapshot_wire();
+        let Some(offset) = self
+            .progress
+            .get(&follower_id)
+            .filter(|p| p.state == ProgressState::Snapshot)
+            .map(|p| p.snapshot_offset)
+        else {
+            return;
+        };
+        if let Some(chunk) = self.snapshot_chunk(offset) {
+            if let RaftMessage::InstallSnapshotChunk { data, .. } = &chunk {
+                self.charge_snapshot_bytes(data.len() as u64);
+            }
+            self.send(follower_id, chunk);
+        }
+    }
+    
+    // -- message handling --
+    
+    /// handle a vote request from a candidate
+    /// returns (response, should_reset_election_timer)
+    pub fn handle_vote_request(
+        &mut self,
+        term: u64,
+        candidate_id: u64,
+        last_log_index: u64,
+        last_log_term: u64,
+    ) -> (RaftMessage, bool) {
+        // if candidate's term is less than ours, reject
+        if term < self.current_term {
+            return (
+                RaftMessage::VoteResponse {
+                    term: self.current_term,
+                    vote_granted: false,
+                    commit_index: self.commit_index,
+                    last_log_index: self.last_log_index(),
+                    group_id: self.config.group_id,
+                },
+                false,
+            );
+        }
+        
+        // if we see a higher term, become follower
+        if term > self.current_term {
+            self.become_follower(term);
+        }
+        
+        // check if we can grant the vote:
+        // 1. we haven't voted for anyone else this term
+        // 2. candidate's log is at least as up-to-date as ours
+        let can_vote = self.voted_for.is_none() || self.voted_for == Some(candidate_id);
+        let log_ok = self.is_log_up_to_date(last_log_index, last_log_term);
+        
+        let vote_granted = can_vote && log_ok;
+        
+        if vote_granted {
+            self.voted_for = Some(candidate_id);
+        }
+        
+        (
+            RaftMessage::VoteResponse {
+                term: self.current_term,
+                vote_granted,
+                commit_index: self.commit_index,
+                last_log_index: self.last_log_index(),
+                group_id: self.config.group_id,
+            },
+            vote_granted, // reset election timer if we granted vote
+        )
+    }
+    
+    /// handle a vote response (candidate only)
+    /// returns true if we just became leader
+    pub fn handle_vote_response(&mut self, term: u64, vote_granted: bool, from: u64) -> bool {
+        // if we see a higher term, step down
+        if term > self.current_term {
+            self.become_follower(term);
+            return false;
+        }
+        
+        // ignore if we're not a candidate anymore
+        if self.state != NodeState::Candidate {
+            return false;
+        }
+        
+        // ignore if term doesn't match (stale response)
+        if term != self.current_term {
+            return false;
+        }
+        
+        // standbys and learners don't vote
+        if vote_granted && self.is_voter(from) && !self.votes_received.contains(&from) {
+            self.votes_received.push(from);
+            
             // check if we have quorum
             if self.has_quorum() {
                 self.become_leader();
                 return true;
             }
         }
-        
-        false
+        
+        false
+    }
+    
+    /// check if a candidate's log is at least as up-to-date as ours
+    /// (raft paper section 5.4.1)
+    fn is_log_up_to_date(&self, last_log_index: u64, last_log_term: u64) -> bool {
+        let our_last_term = self.last_log_term();
+        let our_last_index = self.last_log_index();
+        
+        // compare by term first, then by index
+        if last_log_term != our_last_term {
+            last_log_term > our_last_term
+        } else {
+            last_log_index >= our_last_index
+        }
+    }
+    
+    /// create an append entries message for a follower (leader only)
+    /// 
+    /// falls back to InstallSnapshot when the entries the follower needs
+    /// next only survive in our snapshot. spilled entries are read back from
+    /// the cold log; None if it can't supply them
+    pub fn create_append_entries(&self, follower_id: u64) -> Option<RaftMessage> {
+        if self.state != NodeState::Leader {
+            return None;
+        }
+        
+        let next_idx = self.progress.get(&follower_id)?.next_index;
+        if self.needs_snapshot(next_idx) && self.config.snapshot_chunk_bytes.is_some() {
+            return self.snapshot_chunk(0);
+        }
+        if self.needs_snapshot(next_idx) {
+            return Some(RaftMessage::InstallSnapshot {
+                term: self.current_term,
+                leader_id: self.id,
+                snapshot: self.snapshot.clone()?,
+                group_id: self.config.group_id,
+            });
+        }
+        let prev_log_index = next_idx.saturating_sub(1);
+        let prev_log_term = self.get_term_at(prev_log_index);
+        
+        // throttled followers only get a trickle until they catch up
+        let max_entries = if self.is_throttled(follower_id) {
+            BACKOFF_MAX_ENTRIES
+        } else {
+            self.config.max_entries_per_msg.unwrap_or(usize::MAX)
+        };
+        
+        // get entries starting from next_index
+        let entries = self.read_log(next_idx, max_entries)?;
+        
+        Some(RaftMessage::AppendEntries {
+            term: self.current_term,
+            leader_id: self.id,
+            prev_log_index,
+            prev_log_term,
+            entries,
+            leader_commit: self.commit_index,
+            seq: 0,
+            group_id: self.config.group_id,
+        })
+    }
+    
+    /// an empty AppendEntries checking that `follower_id` matches our log
+    /// just before its next_index (leader only)
+    /// 
+    /// sent on election: it announces us and its answer confirms (or
+    /// corrects) where replication starts, without sending entries that
+    /// `replicate` will send anyway. None where only a snapshot would do
+    pub fn create_probe(&self, follower_id: u64) -> Option<RaftMessage> {
+        if self.state != NodeState::Leader {
+            return None;
+        }
+        let next_idx = self.progress.get(&follower_id)?.next_index;
+        if self.needs_snapshot(next_idx) {
+            return None;
+        }
+        let prev_log_index = next_idx.saturating_sub(1);
+        Some(RaftMessage::AppendEntries {
+            term: self.current_term,
+            leader_id: self.id,
+            prev_log_index,
+            prev_log_term: self.get_term_at(prev_log_index),
+            entries: Vec::new(),
+            leader_commit: self.commit_index,
+            seq: 0,
+            group_id: self.config.group_id,
+        })
+    }
+    
+    /// create a heartbeat for `follower_id` (leader only)
+    /// 
+    /// the commit index is capped at the follower's match index: beyond it
+    /// the follower's log may still differ from ours
+    pub fn create_heartbeat(&self, follower_id: u64) -> Option<RaftMessage> {
+        if self.state != NodeState::Leader {
+            return None;
+        }
+        let matched = self.progress.get(&follower_id).map_or(0, |p| p.match_index);
+        
+        Some(RaftMessage::Heartbeat {
+            term: self.current_term,
+            leader_id: self.id,
+            commit: self.commit_index.min(matched),
+            seq: 0,
+            group_id: self.config.group_id,
+        })
+    }
+    
+    /// send `follower_id` everything past its next_index, unless its progress is paused
+    /// 
+    /// while replicating, next_index moves past the sent entries right away
+    /// (pipelining) and the message holds a slot in the follower's in-flight
+    /// window until it is acknowledged; throttled followers get one message
+    /// at a time. while probing, one message is outstanding and next_index
+    /// stays put. a snapshot moves the follower to the snapshot state.
+    /// returns false if nothing was sent: not leader, nothing new, or paused
+    pub fn send_append(&mut self, follower_id: u64) -> bool {
+        if self.state != NodeState::Leader || follower_id == self.id {
+            return false;
+        }
+        let Some(progress) = self.progress.get(&follower_id) else {
+            return false;
+        };
+        let next_idx = progress.next_index;
+        if next_idx > self.last_log_index() || progress.is_paused() {
+            return false;
+        }
+        if self.needs_snapshot(next_idx) && !self.may_start_snapshot(follower_id) {
+            return false;
+        }
+        
+        self.refresh_snapshot_wire();
+        let Some(msg) = self.create_append_entries(follower_id) else {
+            return false;
+        };
+        let (last_index, bytes) = match &msg {
+            RaftMessage::AppendEntries { entries, .. } => match entries.last() {
+                Some(last) => (last.index, entries.iter().map(LogEntry::size_bytes).sum()),
+                None => return false,
+            },
+            RaftMessage::InstallSnapshot { snapshot, .. } => {
+                (snapshot.metadata.last_included_index, snapshot.data.len() as u64)
+            }
+            RaftMessage::InstallSnapshotChunk { last_included_index, data, .. } => {
+                (*last_included_index, data.len() as u64)
+            }
+            _ => return false,
+        };
+        
+        if matches!(msg, RaftMessage::InstallSnapshot { .. } | RaftMessage::InstallSnapshotChunk { .. }) {
+            self.charge_snapshot_bytes(bytes);
+        }
+        let now_ms = self.now_ms;
+        if let Some(progress) = self.progress.get_mut(&follower_id) {
+            if let RaftMessage::AppendEntries { leader_commit, .. } = msg {
+                progress.last_append = Some((now_ms, leader_commit));
+            }
+            match (&msg, progress.state) {
+                (RaftMessage::InstallSnapshot { .. } | RaftMessage::InstallSnapshotChunk { .. }, _) => {
+                    progress.become_snapshot(last_index, bytes)
+                }
+                (_, ProgressState::Replicate) => {
+                    progress.inflights.add(next_idx, last_index, bytes);
+                    progress.next_index = last_index + 1;
+                }
+                _ => progress.inflights.add(next_idx, last_index, bytes),
+            }
+        }
+        self.send(follower_id, msg);
+        true
+    }
+    
+    /// send new entries to every follower whose window allows (leader only)
+    /// 
+    /// call after `append_entry` to replicate without waiting for the next
+    /// heartbeat
+    pub fn replicate(&mut self) -> Ready {
+        for node_id in self.replication_targets() {
+            self.send_append(node_id);
+        }
+        // a leader that is a quorum on its own: nobody will ack for it
+        if self.state == NodeState::Leader && self.is_quorum(QuorumKind::Replication, [self.id]) {
+            self.try_advance_commit_index();
+        }
+        self.ready()
+    }
+    
+    /// (messages, bytes) in flight to `follower_id` (leader only)
+    pub fn inflight(&self, follower_id: u64) -> (usize, u64) {
+        self.progress
+            .get(&follower_id)
+            .map_or((0, 0), |p| (p.inflights.count(), p.inflights.bytes()))
+    }
+    
+    /// handle a heartbeat (follower/candidate)
+    /// returns (response, should_reset_election_timer)
+    pub fn handle_heartbeat(&mut self, term: u64, leader_id: u64, commit: u64) -> (RaftMessage, bool) {
+        if term < self.current_term {
+            let response = RaftMessage::HeartbeatResponse {
+                term: self.current_term,
+                last_log_index: self.last_log_index(),
+                seq: 0,
+                group_id: self.config.group_id,
+            };
+            return (response, false);
+        }
+        
+        self.become_follower(term);
+        self.leader_id = Some(leader_id);
+        // the leader capped `commit` at what our logs share
+        if commit > self.commit_index {
+            self.commit_index = commit.min(self.last_log_index());
+        }
+        
+        let response = RaftMessage::HeartbeatResponse {
+            term: self.current_term,
+            last_log_index: self.last_log_index(),
+            seq: 0,
+            group_id: self.config.group_id,
+        };
+        (response, true)
+    }
+    
+    /// handle a heartbeat response (leader only)
+    /// 
+    /// a follower we haven't matched up to our last entry either lost what
+    /// we sent or its acks were lost. either way, probe again starting at
+    /// the entry it reports having (resending that one entry proves the
+    /// match), so an idle cluster still catches it up. returns true if we
+    /// stepped down
+    pub fn handle_heartbeat_response(&mut self, term: u64, from: u64, last_log_index: u64) -> bool {
+        if term > self.current_term {
+            self.become_follower(term);
+            return true;
+        }
+        if self.state != NodeState::Leader || term != self.current_term {
+            return false;
+        }
+        
+        let leader_last = self.last_log_index();
+        let Some(progress) = self.progress.get_mut(&from) else {
+            return false;
+        };
+        if progress.state == ProgressState::Snapshot {
+            // a chunk or its answer may have been lost: offer the one the
+            // follower last asked for again
+            if self.config.snapshot_chunk_bytes.is_some() {
+                self.send_snapshot_chunk(from);
+            }
+            return false;
+        }
+        if progress.match_index >= leader_last {
+            return false;
+        }
+        let have = last_log_index.max(progress.match_index).min(leader_last);
+        let stuck = have + 1 < progress.next_index || progress.next_index > leader_last;
+        if progress.state == ProgressState::Probe || stuck {
+            progress.become_probe();
+            progress.next_index = have.max(progress.match_index + 1);
+        }
+        self.send_append(from);
+        false
+    }
+    
+    /// handle an append entries request (follower/candidate)
+    /// returns (response, should_reset_election_timer)
+    pub fn handle_append_entries(
+        &mut self,
+        term: u64,
+        leader_id: u64,
+        prev_log_index: u64,
+        prev_log_term: u64,
+        entries: Vec<LogEntry>,
+        leader_commit: u64,
+    ) -> (RaftMessage, bool) {
+        // reject if term is less than ours
+        if term < self.current_term {
+            return (
+                RaftMessage::AppendEntriesResponse {
+                    term: self.current_term,
+                    success: false,
+                    match_index: 0,
+                    storage_health: self.storage_health,
+                    backoff: self.saturated,
+                    seq: 0,
+                    group_id: self.config.group_id,
+                },
+                false,
+            );
+        }
+        
+        // if we see higher or equal term from a leader, become follower
+        if term >= self.current_term {
+            self.become_follower(term);
+        }
+        self.leader_id = Some(leader_id);
+        
+        // log consistency check: we must have an entry at prev_log_index
+        // with term == prev_log_term (or prev_log_index == 0). anything up to
+        // our snapshot or spilled entries is committed, so it matches the
+        // leader by definition
+        let log_consistent = if prev_log_index <= self.log_base().0 {
+            true
+        } else {
+            self.get_term_at(prev_log_index) == prev_log_term
+        };
+        
+        if !log_consistent {
+            // tell the leader where our log ends, so it can skip straight
+            // back to it instead of one entry per round trip
+            return (
+                RaftMessage::AppendEntriesResponse {
+                    term: self.current_term,
+                    success: false,
+                    match_index: self.last_log_index(),
+                    storage_health: self.storage_health,
+                    backoff: self.saturated,
+                    seq: 0,
+                    group_id: self.config.group_id,
+                },
+                true, // still reset timer, we heard from a leader
+            );
+        }
+        
+        // the last index this request proves we share with the leader
+        let match_index = prev_log_index + entries.len() as u64;
+        
+        // append entries (if any); those our snapshot covers, or we spilled,
+        // are already in place
+        let base_index = self.log_base().0;
+        for entry in entries.into_iter().filter(|e| e.index > base_index) {
+            // if we have a conflicting entry, delete it and all following
+            if let Some(pos) = self.log_position(entry.index) {
+                if self.log[pos].term != entry.term {
+                    // remove conflicting entry and all after it
+                    self.log.truncate(pos);
+                    self.truncated_from = Some(
+                        self.truncated_from.map_or(entry.index, |i| i.min(entry.index)),
+                    );
+                }
+            }
+            // append if we don't have this entry
+            if self.get_entry(entry.index).is_none() {
+                // a broken chain means corruption on the way or at the
+                // leader: keep what linked, and refuse the rest
+                if self.config.hash_chain && !entry.links_to(self.hash_at(entry.index - 1)) {
+                    self.chain_break = Some(ChainBreak { index: entry.index });
+                    return (
+                        RaftMessage::AppendEntriesResponse {
+                            term: self.current_term,
+                            success: false,
+                            match_index: self.last_log_index(),
+                            storage_health: self.storage_health,
+                            backoff: self.saturated,
+                            seq: 0,
+                            group_id: self.config.group_id,
+                        },
+                        true,
+                    );
+                }
+                self.chain_break = None;
+                self.mark_unpersisted(entry.index);
+                self.log.push(entry);
+            }
+        }
+        
+        // update commit index. only entries this request proves we share
+        // may commit: anything past them can be a stale tail the leader
+        // hasn't overwritten yet
+        let commit = std::cmp::min(leader_commit, match_index);
+        if commit > self.commit_index {
+            self.commit_index = commit;
+        }
+        
+        // only acknowledge what is durable; report_persisted acks the rest
+        let stable = self.stable_index();
+        self.pending_ack = (match_index > stable).then_some(match_index);
+        let match_index = match_index.min(stable);
+        
+        (
+            RaftMessage::AppendEntriesResponse {
+                term: self.current_term,
+                success: true,
+                match_index,
+                storage_health: self.storage_health,
+                backoff: self.saturated,
+                seq: 0,
+                group_id: self.config.group_id,
+            },
+            true, // reset election timer
+        )
+    }
+    
+    /// handle an append entries response (leader only)
+    /// returns true if commit_index was updated
+    pub fn handle_append_entries_response(
+        &mut self,
+        term: u64,
+        success: bool,
+        from: u64,
+        match_index_hint: u64,
+    ) -> bool {
+        // if we see a higher term, step down
+        if term > self.current_term {
+            self.become_follower(term);
+            return false;
+        }
+        
+        // ignore if we're not the leader
+        if self.state != NodeState::Leader {
+            return false;
+        }
+        
+        // an answer to an append we sent in an earlier term: what it matched
+        // or refused was a log we may since have rewritten
+        if term < self.current_term {
+            return false;
+        }
+        
+        let Some(progress) = self.progress.get_mut(&from) else {
+            return false;
+        };
+        
+        if success {
+            // update next_index and match_index for follower; next_index may
+            // already be past this response if later appends are in flight,
+            // and a late response must not move match_index backwards
+            progress.next_index = progress.next_index.max(match_index_hint + 1);
+            progress.match_index = progress.match_index.max(match_index_hint);
+            progress.inflights.free_to(match_index_hint);
+            if progress.state == ProgressState::Probe {
+                // found where our logs match: start pipelining
+                progress.become_replicate();
+            }
+            
+            // try to advance commit_index
+            return self.try_advance_commit_index();
+        }
+        
+        // a late rejection of an append sent before the snapshot: the
+        // snapshot is still what the follower needs
+        if progress.state == ProgressState::Snapshot {
+            return false;
+        }
+        match progress.inflights.oldest_first_index() {
+            Some(oldest) if progress.state == ProgressState::Replicate => {
+                // a pipelined append was rejected, so everything after it will
+                // be too: probe again from one entry before the oldest
+                progress.next_index = oldest.saturating_sub(1).max(progress.match_index + 1).max(1);
+            }
+            _ => {
+                // decrement next_index and retry
+                if progress.next_index > 1 {
+                    progress.next_index -= 1;
+                }
+            }
+        }
+        // the follower's log ends at the hint: nothing past it can match.
+        // 0 carries no information (older followers always send it)
+        if match_index_hint > 0 {
+            progress.next_index = progress.next_index.min(match_index_hint + 1).max(progress.match_index + 1);
+        }
+        progress.become_probe();
+        
+        false
+    }
+    
+    /// handle an install snapshot request (follower/candidate)
+    /// returns (response, should_reset_election_timer)
+    pub fn handle_install_snapshot(
+        &mut self,
+        term: u64,
+        leader_id: u64,
+        snapshot: Snapshot,
+    ) -> (RaftMessage, bool) {
+        // reject if term is less than ours
+        if term < self.current_term {
+            return (
+                RaftMessage::InstallSnapshotResponse {
+                    term: self.current_term,
+                    match_index: 0,
+                    group_id: self.config.group_id,
+                },
+                false,
+            );
+        }
+        
+        self.become_follower(term);
+        self.leader_id = Some(leader_id);
+        
+        // everything the snapshot covers is already committed here, and
+        // committed entries match the leader's log
+        if snapshot.metadata.last_included_index <= self.commit_index {
+            return (
+                RaftMessage::InstallSnapshotResponse {
+                    term: self.current_term,
+                    match_index: self.commit_index,
+                    group_id: self.config.group_id,
+                },
+                true,
+            );
+        }
+        
+        // never install what we can't verify
+        if self.open_snapshot(&snapshot).is_none() {
+            return (
+                RaftMessage::InstallSnapshotResponse {
+                    term: self.current_term,
+                    match_index: 0,
+                    group_id: self.config.group_id,
+                },
+                true,
+            );
+        }
+        
+        // rewrite the persisted log: drop it all, then re-persist any
+        // suffix the snapshot lets us keep
+        if let Some(first) = self.log.first().map(|e| e.index) {
+            self.truncated_from = Some(self.truncated_from.map_or(first, |i| i.min(first)));
+        }
+        let match_index = snapshot.metadata.last_included_index;
+        self.restore_snapshot(snapshot.clone());
+        if let Some(first) = self.log.first().map(|e| e.index) {
+            self.mark_unpersisted(first);
+        }
+        self.pending_snapshot = Some(snapshot);
+        
+        (
+            RaftMessage::InstallSnapshotResponse {
+                term: self.current_term,
+                match_index,
+                group_id: self.config.group_id,
+            },
+            true,
+        )
+    }
+    
+    /// handle an install snapshot response (leader only)
+    /// returns true if commit_index was updated
+    pub fn handle_install_snapshot_response(
+        &mut self,
+        term: u64,
+        from: u64,
+        match_index_hint: u64,
+    ) -> bool {
+        // if we see a higher term, step down
+        if term > self.current_term {
+            self.become_follower(term);
+            return false;
+        }
+        
+        if self.state != NodeState::Leader {
+            return false;
+        }
+        let Some(progress) = self.progress.get_mut(&from) else {
+            return false;
+        };
+        // whatever the outcome, the snapshot is no longer in flight: probe
+        // from wherever it left the follower
+        progress.become_probe();
+        
+        // responses can arrive late: never move a follower backwards
+        if match_index_hint <= progress.match_index {
+            return false;
+        }
+        progress.match_index = progress.match_index.max(match_index_hint);
+        progress.next_index = match_index_hint + 1;
+        
+        self.try_advance_commit_index()
+    }
+    
+    /// handle one chunk of a streamed snapshot (follower/candidate)
+    /// returns (response, should_reset_election_timer)
+    /// 
+    /// chunks are collected until the one marked `done` completes the
+    /// snapshot, which is then installed like a whole InstallSnapshot and
+    /// answered with an InstallSnapshotResponse. every other chunk is
+    /// answered with the offset we need next, so the leader resumes from
+    /// there after a lost or duplicated chunk. a chunk from a newer term or
+    /// of another snapshot starts over
+    #[allow(clippy::too_many_arguments)]
+    pub fn handle_install_snapshot_chunk(
+        &mut self,
+        term: u64,
+        leader_id: u64,
+        last_included_index: u64,
+        last_included_term: u64,
+        offset: u64,
+        data: &[u8],
+        done: bool,
+    ) -> (RaftMessage, bool) {
+        if term < self.current_term {
+            return (
+                RaftMessage::InstallSnapshotResponse {
+                    term: self.current_term,
+                    match_index: 0,
+                    group_id: self.config.group_id,
+                },
+                false,
+            );
+        }
+        
+        self.become_follower(term);
+        self.leader_id = Some(leader_id);
+        
+        // already covered: stop the stream, and probe from our commit
+        if last_included_index <= self.commit_index {
+            self.snapshot_assembly = None;
+            return (
+                RaftMessage::InstallSnapshotResponse {
+                    term: self.current_term,
+                    match_index: self.commit_index,
+                    group_id: self.config.group_id,
+                },
+                true,
+            );
+        }
+        
+        let metadata = SnapshotMetadata { last_included_index, last_included_term, last_included_hash: None };
+        let current = self
+            .snapshot_assembly
+            .as_ref()
+            .is_some_and(|a| a.term == term && a.metadata == metadata);
+        if !current {
+            self.snapshot_assembly = (offset == 0).then(|| SnapshotAssembly::new(term, metadata));
+        }
+        let Some(assembly) = self.snapshot_assembly.as_mut() else {
+            // the start of this snapshot never reached us
+            let response = RaftMessage::InstallSnapshotChunkResponse {
+                term: self.current_term,
+                last_included_index,
+                next_offset: 0,
+                group_id: self.config.group_id,
+            };
+            return (response, true);
+        };
+        let next_offset = assembly.accept(offset, data);
+        if !done || next_offset != offset + data.len() as u64 {
+            let response = RaftMessage::InstallSnapshotChunkResponse {
+                term: self.current_term,
+                last_included_index,
+                next_offset,
+                group_id: self.config.group_id,
+            };
+            return (response, true);
+        }
+        
+        let bytes = self.snapshot_assembly.take().map(|a| a.bytes).unwrap_or_default();
+        let announced = |m: &SnapshotMetadata| (m.last_included_index, m.last_included_term) == (last_included_index, last_included_term);
+        match Snapshot::from_bytes(&bytes).filter(|snapshot| announced(&snapshot.metadata)) {
+            Some(snapshot) => self.handle_install_snapshot(term, leader_id, snapshot),
+            None => (
+                RaftMessage::InstallSnapshotResponse {
+                    term: self.current_term,
+                    match_index: 0,
+                    group_id: self.config.group_id,
+                },
+                true,
+            ),
+        }
+    }
+    
+    /// handle the answer to a snapshot chunk (leader only)
+    /// 
+    /// sends the chunk the follower asked for next. answers that don't move
+    /// the follower's offset (duplicates, or late ones) send nothing, so a
+    /// resent chunk never doubles the stream
+    pub fn handle_install_snapshot_chunk_response(
+        &mut self,
+        term: u64,
+        from: u64,
+        last_included_index: u64,
+        next_offset: u64,
+    ) {
+        if term > self.current_term {
+            self.become_follower(term);
+            return;
+        }
+        if self.state != NodeState::Leader || term != self.current_term {
+            return;
+        }
+        let snapshot_index = self.snapshot_index();
+        let Some(progress) = self.progress.get_mut(&from) else {
+            return;
+        };
+        if progress.state != ProgressState::Snapshot
+            || last_included_index != snapshot_index
+            || next_offset == progress.snapshot_offset
+        {
+            return;
+        }
+        progress.snapshot_offset = next_offset;
+        self.send_snapshot_chunk(from);
+    }
+    
+    /// try to advance commit_index based on match_index from followers
+    /// returns true if commit_index was advanced
+    fn try_advance_commit_index(&mut self) -> bool {
+        // the highest N such that:
+        // 1. N > commit_index
+        // 2. a replication quorum of match_index[i] >= N
+        // 3. log[N].term == current_term
+        // (2) holds for every index up to the quorum's, and terms only grow
+        // along the log, so the quorum's index is the only candidate
+        
+        let old_commit = self.commit_index;
+        
+        // what each voter holds durably, ourselves included; standbys and
+        // learners replicate but don't count toward quorum
+        let followers = self
+            .progress
+            .iter()
+            .filter(|&(&node_id, _)| node_id != self.id && self.is_voter(node_id))
+            .map(|(&node_id, progress)| (node_id, progress.match_index));
+        let quorum_index = self.config.quorum_policy.quorum_index(
+            QuorumKind::Replication,
+            &self.cluster_nodes,
+            std::iter::once((self.id, self.stable_index())).chain(followers),
+        );
+        
+        // leader can only commit entries from its own term
+        if quorum_index > self.commit_index && self.get_term_at(quorum_index) == self.current_term {
+            self.commit_index = quorum_index;
+        }
+        
+        if self.commit_index > old_commit {
+            self.propose_queued_conf_change();
+        }
+        self.commit_index > old_commit
+    }
+    
+    /// apply committed entries to state machine
+    /// returns the entries that should be applied
+    /// 
+    /// entries proposed through a client session come back with `dedup`
+    /// set, so the state machine can skip commands it already ran
+    /// 
+    /// at most `max_apply_batch` entries and `max_apply_batch_bytes` bytes
+    /// (but always one entry) come back per call; the rest wait for the
+    /// next call, see `has_pending_apply`
+    pub fn get_entries_to_apply(&mut self) -> Vec<LogEntry> {
+        let max_entries = self.config.max_apply_batch.unwrap_or(usize::MAX);
+        let max_bytes = self.config.max_apply_batch_bytes.unwrap_or(u64::MAX);
+        let mut entries = Vec::new();
+        let mut bytes = 0;
+        
+        while self.last_applied < self.commit_index && entries.len() < max_entries {
+            let Some(entry) = self.get_entry(self.last_applied + 1) else {
+                self.last_applied += 1;
+                continue;
+            };
+            if !entries.is_empty() && bytes + entry.size_bytes() > max_bytes {
+                break;
+            }
+            bytes += entry.size_bytes();
+            let mut entry = entry.clone();
+            self.last_applied += 1;
+            entry.dedup = self.sessions.apply(entry.client);
+            entries.push(entry);
+        }
+        
+        entries
+    }
+    
+    /// committed entries are still waiting to be handed out to apply
+    /// (`max_apply_batch` held them back)
+    /// 
+    /// every Ready carries the next batch, `tick`'s included, so they drain
+    /// on their own; a host that wants them sooner calls `ready()` until
+    /// this is false
+    pub fn has_pending_apply(&self) -> bool {
+        self.last_applied < self.commit_index
+    }
+    
+    // -- step api --
+    
+    /// feed one incoming message through the node
+    /// 
+    /// dispatches to the matching handler and collects every consequence
+    /// (responses, follow-up broadcasts, state to persist, entries to
+    /// apply) into a single Ready, so hosts don't need their own dispatch code
+    pub fn step(&mut self, from: u64, msg: RaftMessage) -> Ready {
+        match self.paused {
+            Some(PauseMode::Queue) => {
+                self.paused_inbox.push((from, msg));
+                return Ready::default();
+            }
+            Some(PauseMode::Drop) => return Ready::default(),
+            None => {}
+        }
+        self.handle_message(from, msg);
+        self.ready()
+    }
+    
+    /// dispatch one message to its handler, leaving the output pending
+    fn handle_message(&mut self, from: u64, msg: RaftMessage) {
+        if self.is_fenced(from) || msg.group_id() != self.config.group_id || self.quarantine.is_some() {
+            return;
+        }
+        if self.rejoining && !matches!(msg, RaftMessage::RejoinResponse { .. }) {
+            // nothing counts until a leader has vouched for our state
+            return;
+        }
+        match msg {
+            RaftMessage::PreVoteRequest { term, candidate_id, last_log_index, last_log_term, .. } => {
+                let (response, _) = self.handle_prevote_request(
+                    term, candidate_id, last_log_index, last_log_term,
+                );
+                self.send(from, response);
+            }
+            RaftMessage::PreVoteResponse { term, vote_granted, commit_index, last_log_index, .. } => {
+                if self.state == NodeState::PreCandidate {
+                    self.record_peer_log_hint(from, commit_index, last_log_index);
+                }
+                if self.handle_prevote_response(term, vote_granted, from) {
+                    self.reset_election_timer();
+                    let request = self.start_election();
+                    self.broadcast(request);
+                }
+            }
+            RaftMessage::VoteRequest { term, candidate_id, last_log_index, last_log_term, transfer, .. } => {
+                if !transfer && self.leader_recently_heard() {
+                    // sticky leader (thesis 9.6): a node that restarted or lost
+                    // touch can't unseat a leader we just heard from. ignore
+                    // its term too, so it can't depose that leader through us
+                    let response = RaftMessage::VoteResponse {
+                        term: self.current_term,
+                        vote_granted: false,
+                        commit_index: self.commit_index,
+                        last_log_index: self.last_log_index(),
+                        group_id: self.config.group_id,
+                    };
+                    self.send(from, response);
+                    return;
+                }
+                let (response, reset_timer) = self.handle_vote_request(
+                    term, candidate_id, last_log_index, last_log_term,
+                );
+                if reset_timer {
+                    self.reset_election_timer();
+                }
+                self.send(from, response);
+            }
+            RaftMessage::VoteResponse { term, vote_granted, commit_index, last_log_index, .. } => {
+                if self.state == NodeState::Candidate && term == self.current_term {
+                    self.record_peer_log_hint(from, commit_index, last_log_index);
+                }
+                self.handle_vote_response(term, vote_granted, from);
+            }
+            RaftMessage::AppendEntries { term, leader_id, prev_log_index, prev_log_term, entries, leader_commit, seq, .. } => {
+                let (mut response, reset_timer) = self.handle_append_entries(
+                    term, leader_id, prev_log_index, prev_log_term, entries, leader_commit,
+                );
+                if reset_timer {
+                    // a current leader is alive
+                    self.record_heartbeat(self.now_ms);
+                    self.reset_election_timer();
+                }
+                response.set_seq(seq);
+                self.send(from, response);
+            }
+            RaftMessage::AppendEntriesResponse { term, success, match_index, storage_health, backoff, seq, .. } => {
+                if self.is_stale_response(from, term, seq) {
+                    return;
+                }
+                if let Some(health) = storage_health {
+                    self.record_peer_storage_health(from, health);
+                }
+                if self.state == NodeState::Leader {
+                    self.record_peer_backoff(from, backoff);
+                    self.record_peer_activity(from);
+                }
+                self.handle_append_entries_response(term, success, from, match_index);
+                
+                // any response in our term confirms we were still leader
+                if self.state == NodeState::Leader && term == self.current_term && self.is_voter(from) {
+                    self.read_only.record_ack(from, seq);
+                    self.release_confirmed_reads();
+                    self.record_lease_ack(from, seq);
+                }
+                
+                // follower rejected: retry right away from the decremented next_index
+                if !success && self.state == NodeState::Leader && !self.send_append(from) {
+                    if let Some(retry) = self.create_append_entries(from) {
+                        self.send(from, retry);
+                    }
+                }
+                
+                if success && self.state == NodeState::Leader {
+                    self.maybe_yield_leadership(from);
+                }
+            }
+            RaftMessage::InstallSnapshot { term, leader_id, snapshot, .. } => {
+                let (response, reset_timer) = self.handle_install_snapshot(term, leader_id, snapshot);
+                if reset_timer {
+                    // a current leader is alive
+                    self.record_heartbeat(self.now_ms);
+                    self.reset_election_timer();
+                }
+                self.send(from, response);
+            }
+            RaftMessage::InstallSnapshotChunk {
+                term,
+                leader_id,
+                last_included_index,
+                last_included_term,
+                offset,
+                data,
+                done,
+                ..
+            } => {
+                let (response, reset_timer) = self.handle_install_snapshot_chunk(
+                    term,
+                    leader_id,
+                    last_included_index,
+                    last_included_term,
+                    offset,
+                    &data,
+                    done,
+                );
+                if reset_timer {
+                    // a current leader is alive
+                    self.record_heartbeat(self.now_ms);
+                    self.reset_election_timer();
+                }
+                self.send(from, response);
+            }
+            RaftMessage::InstallSnapshotChunkResponse { term, last_included_index, next_offset, .. } => {
+                if self.state == NodeState::Leader {
+                    self.record_peer_activity(from);
+                }
+                self.handle_install_snapshot_chunk_response(term, from, last_included_index, next_offset);
+                
+                if self.state == NodeState::Leader && term == self.current_term && self.is_voter(from) {
+                    self.read_only.record_ack(from, 0);
+                    self.release_confirmed_reads();
+                    self.record_lease_ack(from, 0);
+                }
+            }
+            RaftMessage::InstallSnapshotResponse { term, match_index, .. } => {
+                if self.state == NodeState::Leader {
+                    self.record_peer_activity(from);
+                }
+                self.handle_install_snapshot_response(term, from, match_index);
+                
+                if self.state == NodeState::Leader && term == self.current_term && self.is_voter(from) {
+                    self.read_only.record_ack(from, 0);
+                    self.release_confirmed_reads();
+                    self.record_lease_ack(from, 0);
+                }
+                
+                // the follower is caught up to the snapshot: send what follows it
+                self.send_append(from);
+            }
+            RaftMessage::RejoinRequest { cluster_id, last_log_index, last_log_term, snapshot_index, .. } => {
+                // only the leader's log speaks for the cluster. the request's
+                // state is only compared, never adopted: it may be from the future
+                if self.state == NodeState::Leader {
+                    let refusal = self.check_rejoin(cluster_id, last_log_index, last_log_term, snapshot_index);
+                    let response = RaftMessage::RejoinResponse {
+                        term: self.current_term,
+                        refusal,
+                        group_id: self.config.group_id,
+                    };
+                    self.send(from, response);
+                }
+            }
+            RaftMessage::RejoinResponse { term, refusal, .. } => {
+                self.finish_rejoin(from, term, refusal);
+            }
+            RaftMessage::Heartbeat { term, leader_id, commit, seq, .. } => {
+                let (mut response, reset_timer) = self.handle_heartbeat(term, leader_id, commit);
+                if reset_timer {
+                    // a current leader is alive
+                    self.record_heartbeat(self.now_ms);
+                    self.reset_election_timer();
+                }
+                response.set_seq(seq);
+                self.send(from, response);
+            }
+            RaftMessage::HeartbeatResponse { term, last_log_index, seq, .. } => {
+                if self.is_stale_response(from, term, seq) {
+                    return;
+                }
+                if self.state == NodeState::Leader {
+                    self.record_peer_activity(from);
+                }
+                self.handle_heartbeat_response(term, from, last_log_index);
+                
+                if self.state == NodeState::Leader && term == self.current_term && self.is_voter(from) {
+                    self.read_only.record_ack(from, seq);
+                    self.release_confirmed_reads();
+                    self.record_lease_ack(from, seq);
+                }
+            }
+            RaftMessage::TimeoutNow { term, .. } => {
+                // the leader is handing over: campaign now, skipping pre-vote
+                // (every other peer still hears from that leader and would refuse it)
+                if term == self.current_term && self.state != NodeState::Leader && self.is_voter(self.id) {
+                    self.reset_election_timer();
+                    self.clear_heartbeat();
+                    let mut request = self.start_election();
+                    if let RaftMessage::VoteRequest { transfer, .. } = &mut request {
+                        *transfer = true;
+                    }
+                    if self.has_quorum() {
+                        self.become_leader();
+                    } else {
+                        self.broadcast(request);
+                    }
+                }
+            }
+            RaftMessage::ReadQuorumRequest { read_id, .. } => {
+                let last_log_index = self.last_log_index();
+                self.send(from, RaftMessage::ReadQuorumResponse { read_id, last_log_index, group_id: self.config.group_id });
+            }
+            RaftMessage::ReadQuorumResponse { read_id, last_log_index, .. } => {
+                if self.is_voter(from) {
+                    self.record_quorum_read(read_id, from, last_log_index);
+                }
+            }
+        }
+    }
+    
+    // -- rejoin handshake --
+    
+    /// stay out of elections until a leader has checked our state against
+    /// its own: the first phase of a two-phase restart
+    /// 
+    /// sends our cluster id, last log index and term, and snapshot index
+    /// to every peer. until a leader answers, every other message is
+    /// dropped, so we neither vote nor campaign nor take entries. a leader
+    /// that finds our state ahead of the cluster's (restored from a later
+    /// backup, or a split history) or from another cluster refuses us, and
+    /// we quarantine ourselves for good: see `quarantine`. answers from
+    /// a term older than ours are ignored. if no leader answers within an
+    /// election timeout there is nothing to check against, and we take
+    /// part unverified. call it once the node's
+    /// state is loaded, when `config.verify_rejoin` is set. returns false,
+    /// doing nothing, if we hold no state or have no peers
+    pub fn begin_rejoin(&mut self) -> bool {
+        let empty = self.current_term == 0 && self.last_log_index() == 0;
+        if empty || self.quarantine.is_some() || self.cluster_nodes.iter().all(|&id| id == self.id) {
+            return false;
+        }
+        self.rejoining = true;
+        self.reset_election_timer();
+        let request = RaftMessage::RejoinRequest {
+            cluster_id: self.config.cluster_id,
+            last_log_index: self.last_log_index(),
+            last_log_term: self.last_log_term(),
+            snapshot_index: self.snapshot_index(),
+            group_id: self.config.group_id,
+        };
+        self.broadcast(request);
+        true
+    }
+    
+    /// waiting for a leader to answer our rejoin handshake
+    pub fn is_rejoining(&self) -> bool {
+        self.rejoining
+    }
+    
+    /// why a leader refused us back into the cluster, if it did
+    /// 
+    /// a quarantined node drops every message and never campaigns: its
+    /// state must be inspected, and the node wiped or restored from a good
+    /// backup, before it can rejoin
+    pub fn quarantine(&self) -> Option<RejoinError> {
+        self.quarantine
+    }
+    
+    /// why a restarted node with this state can't rejoin (leader only)
+    fn check_rejoin(
+        &self,
+        cluster_id: Option<u64>,
+        last_log_index: u64,
+        last_log_term: u64,
+        snapshot_index: u64,
+    ) -> Option<RejoinError> {
+        if let (Some(cluster), Some(local)) = (self.config.cluster_id, cluster_id) {
+            if cluster != local {
+                return Some(RejoinError::ClusterMismatch { local, cluster });
+            }
+        }
+        // our log holds every committed entry, so no snapshot reaches past it
+        let leader_last_index = self.last_log_index();
+        if snapshot_index > leader_last_index {
+            return Some(RejoinError::SnapshotAhead { snapshot_index, leader_last_index });
+        }
+        // no one but us writes entries in our term, and we never drop ours
+        let ahead = last_log_term > self.current_term
+            || (last_log_term == self.current_term && last_log_index > leader_last_index);
+        ahead.then_some(RejoinError::LogAhead { last_log_index, last_log_term })
     }
     
-    /// check if a candidate's log is at least as up-to-date as ours
-    /// (raft paper section 5.4.1)
-    fn is_log_up_to_date(&self, last_log_index: u64, last_log_term: u64) -> bool {
-        let our_last_term = self.last_log_term();
-        let our_last_index = self.last_log_index();
-        
-        // compare by term first, then by index
-        if last_log_term != our_last_term {
-            last_log_term > our_last_term
-        } else {
-            last_log_index >= our_last_index
+    /// a leader answered our rejoin handshake
+    fn finish_rejoin(&mut self, leader: u64, term: u64, refusal: Option<RejoinError>) {
+        // a leader from an older term may have been deposed, and its log
+        // no longer speaks for the cluster: neither its refusal nor its
+        // welcome counts
+        if !self.rejoining || term < self.current_term {
+            return;
+        }
+        self.rejoining = false;
+        if refusal.is_some() {
+            self.quarantine = refusal;
+            return;
         }
+        self.become_follower(term);
+        self.leader_id = Some(leader);
+        // a current leader is alive
+        self.record_heartbeat(self.now_ms);
+        self.reset_election_timer();
     }
     
-    /// create an append entries message for a follower (leader only)
-    pub fn create_append_entries(&self, follower_id: u64) -> Option<RaftMessage> {
-        if self.state != NodeState::Leader {
-            return None;
+    // -- pause --
+    
+    /// freeze the node: stopped, but not crashed
+    /// 
+    /// until `resume`, ticks are ignored (our clock stands still, so no
+    /// timer fires) and `step` returns an empty Ready, queueing or dropping
+    /// each message as `mode` says. all state, volatile included, is kept.
+    /// pausing again only changes the mode; messages already queued stay
+    pub fn pause(&mut self, mode: PauseMode) {
+        self.paused = Some(mode);
+    }
+    
+    /// unfreeze the node and deliver the messages queued while paused, in
+    /// arrival order, returning everything they produced as one Ready
+    pub fn resume(&mut self) -> Ready {
+        self.paused = None;
+        for (from, msg) in std::mem::take(&mut self.paused_inbox) {
+            self.handle_message(from, msg);
         }
-        
-        let next_idx = *self.next_index.get(&follower_id)?;
-        let prev_log_index = if next_idx > 1 { next_idx - 1 } else { 0 };
-        let prev_log_term = self.get_term_at(prev_log_index);
-        
-        // get entries starting from next_index
-        let entries: Vec<LogEntry> = self.log
-            .iter()
-            .filter(|e| e.index >= next_idx)
-            .cloned()
-            .collect();
-        
-        Some(RaftMessage::AppendEntries {
-            term: self.current_term,
-            leader_id: self.id,
-            prev_log_index,
-            prev_log_term,
-            entries,
-            leader_commit: self.commit_index,
-        })
+        self.ready()
     }
     
-    /// create a heartbeat (empty append entries) for all followers
-    pub fn create_heartbeat(&self) -> Option<RaftMessage> {
-        if self.state != NodeState::Leader {
-            return None;
+    /// how the node is paused, or None while it runs
+    pub fn pause_mode(&self) -> Option<PauseMode> {
+        self.paused
+    }
+    
+    /// messages queued while paused, waiting for `resume`
+    pub fn queued_message_count(&self) -> usize {
+        self.paused_inbox.len()
+    }
+    
+    /// send a heartbeat to every follower (leader only)
+    /// 
+    /// also starts a ReadIndex confirmation round for every read queued
+    /// since the previous heartbeat
+    /// 
+    /// this runs every heartbeat_interval even on an idle cluster, so it
+    /// stays allocation-light: a Heartbeat is three integers, built per
+    /// peer without allocating, and peers are walked in place instead of
+    /// collected
+    /// 
+    /// a peer that was sent entries within the last heartbeat_interval,
+    /// carrying our current commit index, and has acknowledged our whole
+    /// log would learn nothing new, so it is skipped, unless this
+    /// heartbeat has to confirm leadership for reads or the lease. so are
+    /// paused peers, and peers that stopped answering while their probe
+    /// backoff lasts (`RaftConfig::probe_backoff_max`)
+    pub fn broadcast_heartbeat(&mut self) -> Ready {
+        if self.state == NodeState::Leader {
+            let confirming = self.read_only.has_pending() || self.config.read_mode == ReadMode::LeaseBased;
+            // responses to anything numbered up to here predate this round
+            let round_seq = self.last_seq;
+            let peers = self.cluster_nodes
+                .iter()
+                .chain(&self.standby_nodes)
+                .chain(&self.learner_nodes)
+                .filter(|&&id| id != self.id);
+            self.msgs.reserve(peers.clone().count());
+            for &node_id in peers {
+                if self.is_replication_paused(node_id) || (!confirming && self.recently_appended(node_id)) {
+                    continue;
+                }
+                if self.probe_backed_off(node_id) {
+                    continue;
+                }
+                if let Some(mut heartbeat) = self.create_heartbeat(node_id) {
+                    // what `send` does, without borrowing all of self
+                    self.last_seq += 1;
+                    heartbeat.set_seq(self.last_seq);
+                    self.msgs.push((node_id, heartbeat));
+                    if let Some(progress) = self.progress.get_mut(&node_id) {
+                        progress.record_heartbeat(self.now_ms);
+                    }
+                }
+            }
+            self.start_lease_round(round_seq);
+            if self.has_committed_in_current_term() {
+                self.read_only.start_round(self.id, self.commit_index, round_seq);
+                self.release_confirmed_reads();
+            }
         }
-        
-        Some(RaftMessage::AppendEntries {
-            term: self.current_term,
-            leader_id: self.id,
-            prev_log_index: self.last_log_index(),
-            prev_log_term: self.last_log_term(),
-            entries: Vec::new(),
-            leader_commit: self.commit_index,
-        })
+        self.ready()
     }
     
-    /// handle an append entries request (follower/candidate)
-    /// returns (response, should_reset_election_timer)
-    pub fn handle_append_entries(
-        &mut self,
-        term: u64,
-        _leader_id: u64,
-        prev_log_index: u64,
-        prev_log_term: u64,
-        entries: Vec<LogEntry>,
-        leader_commit: u64,
-    ) -> (RaftMessage, bool) {
-        // reject if term is less than ours
-        if term < self.current_term {
-            return (
-                RaftMessage::AppendEntriesResponse {
-                    term: self.current_term,
-                    success: false,
-                },
-                false,
-            );
+    /// whether `node_id` stopped answering and its backed-off gap since
+    /// the last heartbeat hasn't passed (see `RaftConfig::probe_backoff_max`)
+    fn probe_backed_off(&self, node_id: u64) -> bool {
+        let Some(max) = self.config.probe_backoff_max else {
+            return false;
+        };
+        let Some(progress) = self.progress.get(&node_id) else {
+            return false;
+        };
+        let Some(sent) = progress.last_heartbeat_ms.filter(|_| progress.probe_failures > 0) else {
+            return false;
+        };
+        let gap = self.config.heartbeat_interval
+            .saturating_mul(1 << progress.probe_failures.min(16))
+            .min(max.max(self.config.heartbeat_interval));
+        self.now_ms.saturating_sub(sent) < gap
+    }
+    
+    /// whether `node_id` holds our whole log and was sent our current
+    /// commit index within the last heartbeat_interval
+    fn recently_appended(&self, node_id: u64) -> bool {
+        let Some(progress) = self.progress.get(&node_id) else {
+            return false;
+        };
+        progress.match_index >= self.last_log_index()
+            && progress.last_append.is_some_and(|(at, commit)| {
+                commit >= self.commit_index && self.now_ms.saturating_sub(at) < self.config.heartbeat_interval
+            })
+    }
+    
+    // -- timers --
+    
+    /// advance the node's timers by `elapsed_ms`
+    /// 
+    /// followers and candidates that hear nothing for their randomized
+    /// election timeout start a pre-vote round (the VoteRequest broadcast
+    /// follows from `step` once a pre-vote majority answers); leaders
+    /// broadcast a heartbeat every `heartbeat_interval`. hosts call this
+    /// from their timer loop and handle the returned Ready like any other.
+    pub fn tick(&mut self, elapsed_ms: u64) -> Ready {
+        if self.paused.is_some() || self.quarantine.is_some() {
+            return Ready::default();
         }
+        self.now_ms += elapsed_ms;
         
-        // if we see higher or equal term from a leader, become follower
-        if term >= self.current_term {
-            self.become_follower(term);
+        if self.state == NodeState::Leader {
+            // a handover that hasn't happened within an election timeout failed
+            if self.transfer.is_some_and(|(_, at)| self.now_ms - at >= self.config.election_timeout_max) {
+                self.transfer = None;
+                if self.stepping_down {
+                    self.step_down(None);
+                    return self.ready();
+                }
+            }
+            if self.config.check_quorum {
+                // the election timer is idle on a leader; reuse it as the check interval
+                self.election_elapsed += elapsed_ms;
+                if self.election_elapsed >= self.config.election_timeout_min {
+                    self.election_elapsed = 0;
+                    if !self.quorum_active() {
+                        // we can't reach a majority: stop taking writes that can't commit
+                        self.become_follower(self.current_term);
+                        self.reset_election_timer();
+                        return self.ready();
+                    }
+                }
+            }
+            self.heartbeat_elapsed += elapsed_ms;
+            if self.heartbeat_elapsed >= self.config.heartbeat_interval {
+                self.heartbeat_elapsed = 0;
+                return self.broadcast_heartbeat();
+            }
+            return self.ready();
         }
         
-        // log consistency check: we must have an entry at prev_log_index
-        // with term == prev_log_term (or prev_log_index == 0)
-        let log_consistent = if prev_log_index == 0 {
-            true
-        } else {
-            self.get_term_at(prev_log_index) == prev_log_term
-        };
-        
-        if !log_consistent {
-            return (
-                RaftMessage::AppendEntriesResponse {
-                    term: self.current_term,
-                    success: false,
-                },
-                true, // still reset timer, we heard from a leader
-            );
+        self.election_elapsed += elapsed_ms;
+        if self.election_elapsed >= self.randomized_election_timeout {
+            // no leader answered our rejoin handshake: there is no cluster
+            // to check against (it may be restarting too), so join unverified
+            self.rejoining = false;
+            if !self.is_voter(self.id) {
+                // standbys and learners wait to be promoted; they never campaign
+                self.reset_election_timer();
+            } else {
+                self.campaign();
+            }
+        }
+        self.ready()
+    }
+    
+    /// election timeout fired: run pre-vote, or win outright if we're alone
+    fn campaign(&mut self) {
+        if matches!(self.state, NodeState::Candidate | NodeState::PreCandidate) {
+            // our previous round timed out without a leader
+            self.failed_elections = self.failed_elections.saturating_add(1);
         }
+        self.reset_election_timer();
+        // the leader we knew about has gone quiet
+        self.clear_heartbeat();
         
-        // append entries (if any)
-        for entry in entries {
-            // if we have a conflicting entry, delete it and all following
-            if let Some(existing) = self.get_entry(entry.index) {
-                if existing.term != entry.term {
-                    // remove conflicting entry and all after it
-                    self.log.retain(|e| e.index < entry.index);
-                }
-            }
-            // append if we don't have this entry
-            if self.get_entry(entry.index).is_none() {
-                self.log.push(entry);
+        if self.config.pre_vote {
+            let prevote = self.start_prevote();
+            if !self.has_prevote_quorum() {
+                self.broadcast(prevote);
+                return;
             }
         }
         
-        // update commit index
-        if leader_commit > self.commit_index {
-            self.commit_index = std::cmp::min(leader_commit, self.last_log_index());
+        let request = self.start_election();
+        if self.has_quorum() {
+            self.become_leader();
+        } else {
+            self.broadcast(request);
         }
+    }
+    
+    /// restart the election countdown with a fresh randomized timeout
+    pub fn reset_election_timer(&mut self) {
+        self.election_elapsed = 0;
         
-        (
-            RaftMessage::AppendEntriesResponse {
-                term: self.current_term,
-                success: true,
-            },
-            true, // reset election timer
-        )
+        let min = self.config.election_timeout_min;
+        let span = self.config.election_timeout_max.saturating_sub(min) + 1;
+        // back off exponentially after lost campaigns, never past the cap
+        // (and never narrower than the configured window)
+        let widest = self.config.election_backoff_max.saturating_sub(min) + 1;
+        let span = (span << self.failed_elections.min(32)).min(widest.max(span));
+        self.randomized_election_timeout =
+            min + self.next_random() % span + self.priority_delay() + self.election_offset();
     }
     
-    /// handle an append entries response (leader only)
-    /// returns true if commit_index was updated
-    pub fn handle_append_entries_response(
-        &mut self,
-        term: u64,
-        success: bool,
-        from: u64,
-        match_index_hint: u64,
-    ) -> bool {
-        // if we see a higher term, step down
-        if term > self.current_term {
-            self.become_follower(term);
+    /// fixed part of our election timeout from `RaftConfig::election_stagger`
+    /// 
+    /// voters are ranked by id and each waits `election_stagger` ms more
+    /// than the one before, so even timeouts that draw the same jitter fire
+    /// apart and two candidacies rarely start together. deterministic: every
+    /// node derives the same ranks from the voter set. 0 for non-voters
+    pub fn election_offset(&self) -> u64 {
+        if !self.is_voter(self.id) {
+            return 0;
+        }
+        let rank = self.cluster_nodes.iter().filter(|&&id| id < self.id).count() as u64;
+        self.config.election_stagger.saturating_mul(rank)
+    }
+    
+    /// campaigns in a row that ended without a leader
+    pub fn failed_elections(&self) -> u32 {
+        self.failed_elections
+    }
+    
+    /// election timeout drawn for the current round (ms)
+    pub fn randomized_election_timeout(&self) -> u64 {
+        self.randomized_election_timeout
+    }
+    
+    /// xorshift64: cheap, dependency-free, good enough for jitter
+    fn next_random(&mut self) -> u64 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng_state = x;
+        x
+    }
+    
+    // -- linearizable reads --
+    
+    /// request a linearizable read (ReadIndex, thesis section 6.4)
+    /// 
+    /// reads are coalesced: everything requested before the next
+    /// `broadcast_heartbeat` shares one confirmation round. the confirmed
+    /// ReadState shows up in a later Ready. returns false if we're not leader.
+    pub fn read_index(&mut self, request_ctx: Vec<u8>) -> bool {
+        if self.state != NodeState::Leader {
             return false;
         }
         
-        // ignore if we're not the leader
+        // a valid lease proves leadership without another round
+        if self.config.read_mode == ReadMode::LeaseBased
+            && self.has_valid_lease()
+            && self.has_committed_in_current_term()
+        {
+            self.read_states.push(ReadState { index: self.commit_index, request_ctx });
+            return true;
+        }
+        
+        self.enqueue_read(request_ctx)
+    }
+    
+    /// queue a read for the next heartbeat round, whatever the read mode
+    /// 
+    /// the thesis' pending-reads queue: reads accumulate until the next
+    /// `broadcast_heartbeat`, one quorum of acks for that round confirms
+    /// leadership for all of them, and they are released together at the
+    /// commit index the round started with. returns false if we're not leader
+    pub fn enqueue_read(&mut self, request_ctx: Vec<u8>) -> bool {
         if self.state != NodeState::Leader {
             return false;
         }
+        self.read_only.add_request(request_ctx);
         
-        if success {
-            // update next_index and match_index for follower
-            if let Some(next) = self.next_index.get_mut(&from) {
-                *next = match_index_hint + 1;
-            }
-            if let Some(match_idx) = self.match_index.get_mut(&from) {
-                *match_idx = match_index_hint;
-            }
-            
-            // try to advance commit_index
-            return self.try_advance_commit_index();
-        } else {
-            // decrement next_index and retry
-            if let Some(next) = self.next_index.get_mut(&from) {
-                if *next > 1 {
-                    *next -= 1;
-                }
+        // a leader that is a quorum on its own confirms the read itself
+        if self.is_quorum(QuorumKind::Replication, [self.id]) && self.has_committed_in_current_term() {
+            self.read_only.start_round(self.id, self.commit_index, self.last_seq);
+            self.release_confirmed_reads();
+        }
+        true
+    }
+    
+    /// take the reads confirmed so far, in the order they were confirmed
+    /// 
+    /// reads confirmed by a heartbeat round come out of `step` in
+    /// `Ready::read_states`. some are confirmed on the spot instead (a
+    /// single-node cluster, or `read_index` under a valid lease), with no
+    /// Ready to carry them; drain those right after queueing the read
+    pub fn drain_reads(&mut self) -> Vec<ReadState> {
+        std::mem::take(&mut self.read_states)
+    }
+    
+    /// request a linearizable read by asking a majority of voters directly
+    /// 
+    /// an alternative to `read_index` for deployments that don't want to
+    /// trust a leader or its clock; the caller picks per request. works on
+    /// any node, costs one round trip per read, and the ReadState shows up
+    /// in a later Ready. its index may be past our commit index (a voter's
+    /// log can run ahead of commit), so the host waits for `last_applied` to
+    /// reach it; if that tail is later overwritten, the read waits for the
+    /// log to grow that far again. lost messages are not retried: re-issue
+    /// the read
+    pub fn quorum_read(&mut self, request_ctx: Vec<u8>) {
+        let read_id = self.quorum_reads.start(request_ctx);
+        if self.is_voter(self.id) {
+            self.record_quorum_read(read_id, self.id, self.last_log_index());
+        }
+        self.broadcast(RaftMessage::ReadQuorumRequest { read_id, group_id: self.config.group_id });
+    }
+    
+    /// count a voter's answer to one of our quorum reads
+    fn record_quorum_read(&mut self, read_id: u64, from: u64, last_log_index: u64) {
+        // the answers must overlap every replication quorum, i.e. be an
+        // election quorum, to cover every committed write
+        let policy = &self.config.quorum_policy;
+        let voters = &self.cluster_nodes;
+        let is_quorum = |acks: &HashSet<u64>| policy.is_quorum(QuorumKind::Election, voters, acks.iter().copied());
+        if let Some(read) = self.quorum_reads.record_response(read_id, from, last_log_index, is_quorum) {
+            self.read_states.push(read);
+        }
+    }
+    
+    /// number of reads waiting for heartbeat or quorum confirmation
+    pub fn pending_read_count(&self) -> usize {
+        self.read_only.len() + self.quorum_reads.len()
+    }
+    
+    // -- leader lease --
+    
+    /// whether our leader lease currently holds
+    /// 
+    /// a lease is earned when a quorum acknowledges a heartbeat round and
+    /// lasts `election_timeout_min - max_clock_drift` from when the round
+    /// was sent: followers that heard that heartbeat won't elect anyone
+    /// else before then
+    pub fn has_valid_lease(&self) -> bool {
+        self.state == NodeState::Leader
+            && self.lease_expiry.is_some_and(|expiry| self.now_ms < expiry)
+    }
+    
+    /// current logical time as reported through tick
+    pub fn now_ms(&self) -> u64 {
+        self.now_ms
+    }
+    
+    /// a heartbeat is going out: start collecting acks for a new lease
+    /// 
+    /// `seq` is last_seq before the round's heartbeats were numbered
+    fn start_lease_round(&mut self, seq: u64) {
+        if self.config.read_mode != ReadMode::LeaseBased {
+            return;
+        }
+        // reuse the previous round's set rather than allocate one per heartbeat
+        let mut acks = self.lease_round.take().map(|(_, _, acks)| acks).unwrap_or_default();
+        acks.clear();
+        acks.insert(self.id);
+        self.lease_round = Some((self.now_ms, seq, acks));
+        self.try_extend_lease();
+    }
+    
+    /// count a voter's response numbered `seq` toward the current lease round
+    /// 
+    /// a response to a request sent before the round started says nothing
+    /// about the round; 0 (unnumbered) is taken as ordered, and counts
+    fn record_lease_ack(&mut self, from: u64, seq: u64) {
+        if let Some((_, round_seq, acks)) = self.lease_round.as_mut() {
+            if seq == 0 || seq > *round_seq {
+                acks.insert(from);
             }
         }
-        
+        self.try_extend_lease();
+    }
+    
+    /// turn a quorum-acknowledged round into a lease
+    fn try_extend_lease(&mut self) {
+        let Some((started, _, acks)) = &self.lease_round else { return };
+        if !self.is_quorum(QuorumKind::Replication, acks.iter().copied()) {
+            return;
+        }
+        let lease = self.config.election_timeout_min.saturating_sub(self.config.max_clock_drift);
+        let expiry = started + lease;
+        self.lease_expiry = Some(self.lease_expiry.map_or(expiry, |e| e.max(expiry)));
+        self.lease_round = None;
+    }
+    
+    /// a leader only knows the true commit index once an entry from its
+    /// own term has committed (thesis section 6.4)
+    fn has_committed_in_current_term(&self) -> bool {
+        self.get_term_at(self.commit_index) == self.current_term
+    }
+    
+    /// move batches confirmed by a quorum into read_states
+    fn release_confirmed_reads(&mut self) {
+        let policy = &self.config.quorum_policy;
+        let voters = &self.cluster_nodes;
+        let is_quorum = |acks: &HashSet<u64>| policy.is_quorum(QuorumKind::Replication, voters, acks.iter().copied());
+        let confirmed = self.read_only.take_confirmed(is_quorum);
+        self.read_states.extend(confirmed);
+    }
+    
+    /// queue a message for a single peer, numbering AppendEntries and
+    /// heartbeats with the next `seq`
+    /// 
+    /// nothing that replicates to or hands leadership to a peer goes out
+    /// while its replication is paused
+    fn send(&mut self, to: u64, mut msg: RaftMessage) {
+        let replicating = matches!(
+            msg,
+            RaftMessage::AppendEntries { .. }
+                | RaftMessage::Heartbeat { .. }
+                | RaftMessage::InstallSnapshot { .. }
+                | RaftMessage::InstallSnapshotChunk { .. }
+                | RaftMessage::TimeoutNow { .. }
+        );
+        if replicating && self.is_replication_paused(to) {
+            return;
+        }
+        if matches!(msg, RaftMessage::AppendEntries { .. } | RaftMessage::Heartbeat { .. }) {
+            self.last_seq += 1;
+            msg.set_seq(self.last_seq);
+        }
+        self.msgs.push((to, msg));
+    }
+    
+    /// whether a response numbered `seq` from `from` answers an older
+    /// request than one we already handled from it (the transport reordered
+    /// or duplicated it); records `seq` otherwise
+    /// 
+    /// only responses to our own term are numbered by our counter, and 0 is
+    /// an unnumbered response (an older version), which is never stale
+    fn is_stale_response(&mut self, from: u64, term: u64, seq: u64) -> bool {
+        if seq == 0 || self.state != NodeState::Leader || term != self.current_term {
+            return false;
+        }
+        let Some(progress) = self.progress.get_mut(&from) else {
+            return false;
+        };
+        if seq <= progress.acked_seq {
+            return true;
+        }
+        progress.acked_seq = seq;
         false
     }
     
-    /// try to advance commit_index based on match_index from followers
-    /// returns true if commit_index was advanced
-    fn try_advance_commit_index(&mut self) -> bool {
-        // find the highest N such that:
-        // 1. N > commit_index
-        // 2. a majority of match_index[i] >= N
-        // 3. log[N].term == current_term
-        
-        let old_commit = self.commit_index;
-        
-        for n in (self.commit_index + 1)..=self.last_log_index() {
-            // check that entry at N has current term (leader can only commit own entries)
-            if self.get_term_at(n) != self.current_term {
-                continue;
-            }
-            
-            // count how many servers have this entry
-            let mut count = 1; // count ourselves
-            for (&node_id, &match_idx) in &self.match_index {
-                if node_id != self.id && match_idx >= n {
-                    count += 1;
-                }
-            }
-            
-            if count >= self.quorum_size() {
-                self.commit_index = n;
+    /// queue a message for every other voter in the cluster
+    fn broadcast(&mut self, msg: RaftMessage) {
+        for &node_id in &self.cluster_nodes {
+            if node_id != self.id {
+                self.msgs.push((node_id, msg.clone()));
             }
         }
-        
-        self.commit_index > old_commit
     }
     
-    /// apply committed entries to state machine
-    /// returns the entries that should be applied
-    pub fn get_entries_to_apply(&mut self) -> Vec<LogEntry> {
-        let mut entries = Vec::new();
-        
-        while self.last_applied < self.commit_index {
-            self.last_applied += 1;
-            if let Some(entry) = self.get_entry(self.last_applied) {
-                entries.push(entry.clone());
-            }
+    /// term and vote, as they must be persisted
+    pub fn hard_state(&self) -> HardState {
+        HardState { term: self.current_term, vote: self.voted_for }
+    }
+    
+    /// role and known leader (volatile)
+    pub fn soft_state(&self) -> SoftState {
+        SoftState { state: self.state, leader_id: self.leader_id }
+    }
+    
+    /// drain all pending output into a Ready
+    /// 
+    /// `step` and `broadcast_heartbeat` call this for you; call it directly
+    /// after driving the node through the lower-level handlers. with the
+    /// `strict-checks` feature, node invariants are asserted here first
+    pub fn ready(&mut self) -> Ready {
+        #[cfg(feature = "strict-checks")]
+        {
+            let mut seen = std::mem::take(&mut self.invariants_seen);
+            crate::invariants::check(self, &mut seen);
+            self.invariants_seen = seen;
         }
         
-        entries
+        let hard_state = self.hard_state();
+        let hard_state_changed = hard_state != self.reported_hard_state;
+        self.reported_hard_state = hard_state;
+        let soft_state = self.soft_state();
+        let soft_state_changed = soft_state != self.reported_soft_state;
+        self.reported_soft_state = soft_state;
+        
+        let entries = match self.unpersisted_from.take() {
+            Some(from) => self.log_from(from).to_vec(),
+            None => Vec::new(),
+        };
+        let committed_entries = self.get_entries_to_apply();
+        self.spill_cold_entries();
+        let proposals = self.resolve_proposals();
+        let removed = self.removed && !self.removal_reported;
+        self.removal_reported = self.removed;
+        
+        Ready {
+            messages: std::mem::take(&mut self.msgs),
+            hard_state: hard_state_changed.then_some(hard_state),
+            soft_state: soft_state_changed.then_some(soft_state),
+            snapshot: self.pending_snapshot.take(),
+            truncate_from: self.truncated_from.take(),
+            entries,
+            committed_entries,
+            read_states: std::mem::take(&mut self.read_states),
+            proposals,
+            compact_to: self.take_compaction_request(),
+            removed,
+        }
     }
 }
 
@@ -771,7 +4446,7 @@ mod tests {
         );
         
         match response {
-            RaftMessage::VoteResponse { vote_granted, term } => {
+            RaftMessage::VoteResponse { vote_granted, term, .. } => {
                 assert!(!vote_granted);
                 assert_eq!(term, 5); // should return our higher term
             }
@@ -0,0 +1,119 @@
+//! # progress
+//!
+//! why: keep everything the leader knows about one follower's replication in one place
+//! relations: one per follower in RaftNode::progress (node.rs), owns an Inflights window
+//! what: ProgressState (probe, replicate, snapshot), Progress tracker
+
+use crate::inflights::Inflights;
+use crate::RaftConfig;
+
+/// how the leader is currently replicating to a follower
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProgressState {
+    /// we don't know where the follower's log matches ours: send one
+    /// message at a time and walk next_index back on rejection
+    #[default]
+    Probe,
+    /// the follower is accepting appends: pipeline within the in-flight window
+    Replicate,
+    /// an InstallSnapshot is outstanding: send nothing else until it is answered
+    Snapshot,
+}
+
+/// the leader's view of one follower
+#[derive(Debug, Clone)]
+pub struct Progress {
+    /// highest index known to be replicated on the follower
+    pub match_index: u64,
+    /// index of the next entry to send
+    pub next_index: u64,
+    /// probe, replicate or snapshot
+    pub state: ProgressState,
+    /// the follower asked us to back off (its pipeline is saturated)
+    pub throttled: bool,
+    /// messages sent and not yet acknowledged
+    pub inflights: Inflights,
+    /// logical time (ms) of the last response from the follower
+    pub last_active_ms: Option<u64>,
+    /// (logical time in ms, leader commit it carried) of the last
+    /// AppendEntries we sent the follower
+    pub last_append: Option<(u64, u64)>,
+    /// (time, match index) when the follower was added while we lead, for ETAs
+    pub catch_up_from: Option<(u64, u64)>,
+    /// chunked snapshots: bytes of the snapshot the follower has confirmed
+    pub snapshot_offset: u64,
+    /// highest `seq` among the follower's responses we've handled; a
+    /// response echoing a lower one answers an older request and is dropped
+    pub acked_seq: u64,
+    /// an operator paused replication to the follower
+    /// (`RaftNode::pause_replication`): nothing is sent until it's resumed
+    pub replication_paused: bool,
+    /// heartbeats in a row the follower left unanswered; backs off how
+    /// often it is sent more (`RaftConfig::probe_backoff_max`)
+    pub probe_failures: u32,
+    /// logical time (ms) we last sent the follower a heartbeat
+    pub last_heartbeat_ms: Option<u64>,
+}
+
+impl Progress {
+    /// a follower we expect to need `next_index` next, replicating normally
+    pub fn new(next_index: u64, match_index: u64, config: &RaftConfig) -> Self {
+        Self {
+            match_index,
+            next_index,
+            state: ProgressState::Replicate,
+            throttled: false,
+            inflights: Inflights::new(config.max_inflight_msgs, config.max_inflight_bytes),
+            last_active_ms: None,
+            last_append: None,
+            catch_up_from: None,
+            snapshot_offset: 0,
+            acked_seq: 0,
+            replication_paused: false,
+            probe_failures: 0,
+            last_heartbeat_ms: None,
+        }
+    }
+
+    /// true if nothing more should be sent until the follower answers (or,
+    /// for `replication_paused`, until replication is resumed)
+    pub fn is_paused(&self) -> bool {
+        if self.replication_paused {
+            return true;
+        }
+        let outstanding = self.inflights.count() > 0;
+        match self.state {
+            ProgressState::Probe => outstanding,
+            ProgressState::Replicate => self.inflights.is_full() || (self.throttled && outstanding),
+            ProgressState::Snapshot => true,
+        }
+    }
+
+    /// a heartbeat goes out at `now_ms`; the previous one failed if the
+    /// follower hasn't answered anything since it was sent
+    pub fn record_heartbeat(&mut self, now_ms: u64) {
+        if self.last_heartbeat_ms.is_some_and(|sent| self.last_active_ms.is_none_or(|at| at < sent)) {
+            self.probe_failures = self.probe_failures.saturating_add(1);
+        }
+        self.last_heartbeat_ms = Some(now_ms);
+    }
+
+    /// fall back to probing (the follower rejected an append)
+    pub fn become_probe(&mut self) {
+        self.state = ProgressState::Probe;
+        self.inflights.reset();
+    }
+
+    /// start pipelining (the follower accepted an append)
+    pub fn become_replicate(&mut self) {
+        self.state = ProgressState::Replicate;
+    }
+
+    /// wait for a snapshot covering up to `snapshot_index`
+    pub fn become_snapshot(&mut self, snapshot_index: u64, bytes: u64) {
+        self.state = ProgressState::Snapshot;
+        self.snapshot_offset = 0;
+        self.inflights.reset();
+        self.inflights.add(snapshot_index, snapshot_index, bytes);
+    }
+}
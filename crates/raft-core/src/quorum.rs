@@ -0,0 +1,142 @@
+//! # quorum
+//!
+//! why: let a deployment choose which sets of voters count as a quorum instead of always a simple majority
+//! relations: chosen through RaftConfig::quorum_policy, consulted by RaftNode (node.rs) for votes, commits, reads and leases
+//! what: QuorumPolicy (majority, flexible, required members) and the QuorumKind it answers for
+
+/// what a set of acknowledgements has to prove
+///
+/// raft only needs every election quorum to overlap every other election
+/// quorum (one leader per term) and every replication quorum (a new leader
+/// holds every committed entry). replication quorums never have to overlap
+/// each other, which is what lets them shrink (flexible paxos)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuorumKind {
+    /// votes and pre-votes, quorum reads
+    Election,
+    /// commits, and confirming leadership (check_quorum, read rounds, leases)
+    Replication,
+}
+
+/// how many (and which) voters make a quorum
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum QuorumPolicy {
+    /// a simple majority of voters for everything
+    #[default]
+    Majority,
+    /// commit once `replication` voters (leader included) hold an entry;
+    /// elections then need the remaining `voters - replication + 1`
+    ///
+    /// `replication` is capped at the number of voters, and elections
+    /// never need less than a majority, so both rules keep overlapping as
+    /// membership changes. smaller replication quorums make commits faster
+    /// and elections harder: with `replication: 2` in a five node cluster
+    /// a leader commits with one follower, but a candidate needs all four
+    /// of the others
+    Flexible { replication: usize },
+    /// a majority that must include every listed voter
+    ///
+    /// ids that aren't currently voters are ignored. while a listed voter
+    /// is down the cluster can neither elect a leader nor commit
+    Required { members: Vec<u64> },
+}
+
+impl QuorumPolicy {
+    /// number of acknowledgements a `kind` quorum needs among `voters`
+    pub fn quorum_size(&self, kind: QuorumKind, voters: usize) -> usize {
+        let majority = voters / 2 + 1;
+        match (self, kind) {
+            (QuorumPolicy::Flexible { replication }, QuorumKind::Replication) => {
+                (*replication).clamp(1, voters.max(1))
+            }
+            (QuorumPolicy::Flexible { replication }, QuorumKind::Election) => {
+                let replication = (*replication).clamp(1, voters.max(1));
+                (voters + 1).saturating_sub(replication).max(majority)
+            }
+            _ => majority,
+        }
+    }
+
+    /// whether `acks` (distinct node ids) form a `kind` quorum of `voters`
+    pub fn is_quorum(&self, kind: QuorumKind, voters: &[u64], acks: impl IntoIterator<Item = u64>) -> bool {
+        let acks: Vec<u64> = acks.into_iter().collect();
+        if acks.len() < self.quorum_size(kind, voters.len()) {
+            return false;
+        }
+        match self {
+            QuorumPolicy::Required { members } => members
+                .iter()
+                .filter(|id| voters.contains(id))
+                .all(|id| acks.contains(id)),
+            _ => true,
+        }
+    }
+
+    /// highest index that a `kind` quorum of `voters` has reached, given
+    /// (node id, index) for each node that reports one
+    ///
+    /// the same answer as trying `is_quorum` on every index, from one sort:
+    /// the quorum-sized-th highest index, lowered to the slowest listed
+    /// member under `Required`
+    pub fn quorum_index(&self, kind: QuorumKind, voters: &[u64], indexes: impl IntoIterator<Item = (u64, u64)>) -> u64 {
+        let indexes: Vec<(u64, u64)> = indexes.into_iter().collect();
+        let mut sorted: Vec<u64> = indexes.iter().map(|&(_, index)| index).collect();
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+        let size = self.quorum_size(kind, voters.len());
+        let mut index = sorted.get(size - 1).copied().unwrap_or(0);
+        if let QuorumPolicy::Required { members } = self {
+            for id in members.iter().filter(|id| voters.contains(id)) {
+                let reached = indexes.iter().find(|&&(node, _)| node == *id).map_or(0, |&(_, i)| i);
+                index = index.min(reached);
+            }
+        }
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flexible_quorums_always_overlap() {
+        for voters in 1..=7 {
+            for replication in 1..=8 {
+                let policy = QuorumPolicy::Flexible { replication };
+                let election = policy.quorum_size(QuorumKind::Election, voters);
+                let commit = policy.quorum_size(QuorumKind::Replication, voters);
+                assert!(election + commit > voters, "{} voters, replication {}", voters, replication);
+                assert!(2 * election > voters, "{} voters, replication {}", voters, replication);
+            }
+        }
+    }
+
+    #[test]
+    fn required_members_must_ack() {
+        let policy = QuorumPolicy::Required { members: vec![1, 9] };
+        let voters = [1, 2, 3];
+        assert!(!policy.is_quorum(QuorumKind::Election, &voters, [2, 3]));
+        assert!(policy.is_quorum(QuorumKind::Election, &voters, [1, 3]));
+    }
+
+    #[test]
+    fn quorum_index_matches_is_quorum() {
+        let voters = [1, 2, 3, 4, 5];
+        let indexes = [(1, 9), (2, 4), (3, 7), (4, 2), (5, 7)];
+        let policies = [
+            QuorumPolicy::Majority,
+            QuorumPolicy::Flexible { replication: 2 },
+            QuorumPolicy::Required { members: vec![2] },
+        ];
+        for policy in policies {
+            let expected = (0..=9)
+                .rev()
+                .find(|&n| {
+                    let acks = indexes.iter().filter(|&&(_, i)| i >= n).map(|&(id, _)| id);
+                    policy.is_quorum(QuorumKind::Replication, &voters, acks)
+                })
+                .unwrap_or(0);
+            assert_eq!(policy.quorum_index(QuorumKind::Replication, &voters, indexes), expected, "{:?}", policy);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! # read_only
+//!
+//! why: serve linearizable reads without appending them to the log (ReadIndex, thesis 6.4)
+//! relations: owned by RaftNode in node.rs, confirmed by heartbeat responses, surfaced via ready.rs
+//! what: ReadMode option, ReadState result, ReadOnly queue that batches reads per heartbeat round, QuorumReads
+
+use std::collections::HashSet;
+
+/// how the leader confirms it is still leader before serving a read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadMode {
+    /// ReadIndex: one heartbeat quorum round per batch of reads (always safe)
+    #[default]
+    Safe,
+    /// serve reads locally while the leader lease is valid
+    ///
+    /// saves the round trip but relies on bounded clock drift between
+    /// nodes; falls back to `Safe` whenever the lease has lapsed
+    LeaseBased,
+}
+
+/// a confirmed read: safe to serve once the state machine has applied `index`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadState {
+    /// commit index at the time the read was confirmed
+    pub index: u64,
+    /// opaque caller context identifying the read request
+    pub request_ctx: Vec<u8>,
+}
+
+/// reads that went out with one heartbeat round
+#[derive(Debug)]
+struct ReadBatch {
+    /// leader commit index when the round started
+    index: u64,
+    /// leader's last `seq` before the round's heartbeats went out
+    seq: u64,
+    /// contexts of every read coalesced into this round
+    ctxs: Vec<Vec<u8>>,
+    /// nodes that acknowledged the round (including the leader)
+    acks: HashSet<u64>,
+}
+
+/// leader-side queue of ReadIndex requests
+///
+/// reads arriving within one heartbeat interval wait in `pending`; the next
+/// heartbeat broadcast turns them into a single in-flight batch, and one
+/// quorum of responses releases the whole batch. this keeps the cost of
+/// linearizable reads at one round per heartbeat regardless of read load.
+#[derive(Debug, Default)]
+pub struct ReadOnly {
+    /// reads waiting for the next heartbeat round
+    pending: Vec<Vec<u8>>,
+    /// rounds sent but not yet confirmed by a quorum (oldest first)
+    in_flight: Vec<ReadBatch>,
+}
+
+impl ReadOnly {
+    /// queue a read for the next heartbeat round
+    pub fn add_request(&mut self, ctx: Vec<u8>) {
+        self.pending.push(ctx);
+    }
+
+    /// number of reads not yet confirmed (pending or in flight)
+    pub fn len(&self) -> usize {
+        self.pending.len() + self.in_flight.iter().map(|b| b.ctxs.len()).sum::<usize>()
+    }
+
+    /// true if there are no unconfirmed reads
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// true if reads are waiting for the next heartbeat round
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// a heartbeat round is going out: move pending reads into a new batch
+    ///
+    /// `seq` is the leader's last sequence number before the round's
+    /// heartbeats were numbered
+    pub fn start_round(&mut self, leader_id: u64, commit_index: u64, seq: u64) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let mut acks = HashSet::new();
+        acks.insert(leader_id);
+        self.in_flight.push(ReadBatch {
+            index: commit_index,
+            seq,
+            ctxs: std::mem::take(&mut self.pending),
+            acks,
+        });
+    }
+
+    /// record an acknowledgement from `from` of the request numbered `seq`
+    ///
+    /// it confirms every batch whose round started before that request was
+    /// sent. an unnumbered response (`seq` 0, from an older version) falls
+    /// back to assuming per-peer ordered delivery: received after a round
+    /// started, it answers that round or a later one, so it confirms every
+    /// batch currently in flight.
+    pub fn record_ack(&mut self, from: u64, seq: u64) {
+        for batch in &mut self.in_flight {
+            if seq == 0 || seq > batch.seq {
+                batch.acks.insert(from);
+            }
+        }
+    }
+
+    /// release every in-flight batch whose acks satisfy `is_quorum`
+    pub fn take_confirmed(&mut self, is_quorum: impl Fn(&HashSet<u64>) -> bool) -> Vec<ReadState> {
+        let mut confirmed = Vec::new();
+        // batches are ordered, and a later batch's acks are a subset of an
+        // earlier one's, so confirmed batches always form a prefix
+        while self.in_flight.first().is_some_and(|b| is_quorum(&b.acks)) {
+            let batch = self.in_flight.remove(0);
+            confirmed.extend(batch.ctxs.into_iter().map(|request_ctx| ReadState {
+                index: batch.index,
+                request_ctx,
+            }));
+        }
+        confirmed
+    }
+
+    /// drop every outstanding read (leadership was lost)
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.in_flight.clear();
+    }
+}
+
+/// one quorum read waiting for voters to answer
+#[derive(Debug)]
+struct QuorumRound {
+    read_id: u64,
+    ctx: Vec<u8>,
+    /// highest last log index reported so far
+    index: u64,
+    /// voters that answered (including us, if we vote)
+    acks: HashSet<u64>,
+}
+
+/// reads confirmed by asking a majority of voters directly
+///
+/// slower than ReadIndex (one round per read, not per heartbeat) but it
+/// trusts neither a leader nor clocks, and any node can run it. every
+/// committed entry is in the log of at least one node of any election
+/// quorum (a majority, by default), so the highest last log index such a
+/// quorum reports covers every write that committed before the read
+/// started; serving at or past it is linearizable
+#[derive(Debug, Default)]
+pub struct QuorumReads {
+    next_id: u64,
+    rounds: Vec<QuorumRound>,
+}
+
+impl QuorumReads {
+    /// open a round for `ctx`, returning the id to send to the voters
+    pub fn start(&mut self, ctx: Vec<u8>) -> u64 {
+        self.next_id += 1;
+        self.rounds.push(QuorumRound { read_id: self.next_id, ctx, index: 0, acks: HashSet::new() });
+        self.next_id
+    }
+
+    /// record a voter's answer; returns the read once the answers satisfy `is_quorum`
+    pub fn record_response(
+        &mut self,
+        read_id: u64,
+        from: u64,
+        last_log_index: u64,
+        is_quorum: impl Fn(&HashSet<u64>) -> bool,
+    ) -> Option<ReadState> {
+        let pos = self.rounds.iter().position(|r| r.read_id == read_id)?;
+        let round = &mut self.rounds[pos];
+        round.acks.insert(from);
+        round.index = round.index.max(last_log_index);
+        if !is_quorum(&round.acks) {
+            return None;
+        }
+        let round = self.rounds.remove(pos);
+        Some(ReadState { index: round.index, request_ctx: round.ctx })
+    }
+
+    /// reads still waiting for a quorum
+    pub fn len(&self) -> usize {
+        self.rounds.len()
+    }
+
+    /// true if no quorum read is outstanding
+    pub fn is_empty(&self) -> bool {
+        self.rounds.is_empty()
+    }
+}
@@ -0,0 +1,100 @@
+//! # ready
+//!
+//! why: hand everything one call into the node produced back to the host in one place
+//! relations: returned by RaftNode::step in node.rs, consumed by raft-wasm and tests
+//! what: Ready struct (outgoing messages, state to persist, snapshot, entries to apply), HardState, SoftState
+
+use crate::{CommitProof, LogEntry, NodeState, ProposalOutcome, RaftMessage, ReadState, Snapshot};
+
+/// term and vote: the state raft needs back after a restart
+///
+/// a vote granted or a term seen must never be forgotten, or a restarted
+/// node could vote twice in one term
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HardState {
+    /// current term
+    pub term: u64,
+    /// who we voted for in `term`
+    pub vote: Option<u64>,
+}
+
+/// the node's role as the outside world sees it; volatile, never persisted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SoftState {
+    /// follower, (pre-)candidate or leader
+    pub state: NodeState,
+    /// who we believe leads the current term
+    pub leader_id: Option<u64>,
+}
+
+/// the output of a single step of the raft node
+///
+/// hosts should process the fields in order:
+/// 1. persist `snapshot`, `hard_state` and the log changes (`truncate_from`, `entries`)
+/// 2. send `messages`
+///
+/// the order of 1 and 2 is raft's durability contract, not a suggestion:
+/// a message may promise what step 1 writes (a vote, an acknowledged
+/// entry), so nothing may be sent until `must_sync` state is durable.
+/// with `RaftConfig::async_persist`, `entries` may be written after
+/// sending, as long as they're reported through `RaftNode::report_persisted`
+/// 3. restore the state machine from `snapshot`, then apply `committed_entries`
+/// 4. answer `read_states` once their index has been applied, and reply to
+///    the clients behind resolved `proposals`
+/// 5. if `compact_to` is set, snapshot the state machine and call `RaftNode::compact`
+#[derive(Debug, Clone, Default)]
+pub struct Ready {
+    /// messages to send, as (to_node, message)
+    pub messages: Vec<(u64, RaftMessage)>,
+    /// new term and vote if either changed during this step
+    pub hard_state: Option<HardState>,
+    /// new role or leader if either changed during this step (display only)
+    pub soft_state: Option<SoftState>,
+    /// snapshot installed from the leader; it replaces the state machine
+    /// (sealed if a snapshot sealer is installed: read it with `RaftNode::open_snapshot`)
+    pub snapshot: Option<Snapshot>,
+    /// persisted log must be truncated from this index before appending `entries`
+    pub truncate_from: Option<u64>,
+    /// log entries added during this step that must be persisted
+    pub entries: Vec<LogEntry>,
+    /// newly committed entries, ready to apply to the state machine (at most
+    /// `RaftConfig::max_apply_batch` of them; see `RaftNode::has_pending_apply`)
+    pub committed_entries: Vec<LogEntry>,
+    /// linearizable reads confirmed by a heartbeat quorum (leader only)
+    pub read_states: Vec<ReadState>,
+    /// proposals from `RaftNode::propose_tracked` that committed or were
+    /// superseded during this step
+    pub proposals: Vec<(CommitProof, ProposalOutcome)>,
+    /// the log is over `log_bytes_soft_limit`: snapshot the state machine as
+    /// of this (applied) index and hand it to `RaftNode::compact`
+    pub compact_to: Option<u64>,
+    /// this node was removed from the cluster (`RaftNode::remove_member`);
+    /// set in one Ready only. it won't campaign or take proposals again, so
+    /// the host can shut it down
+    pub removed: bool,
+}
+
+impl Ready {
+    /// true if something must be written durably before `messages` are sent
+    pub fn must_sync(&self) -> bool {
+        self.hard_state.is_some()
+            || self.snapshot.is_some()
+            || self.truncate_from.is_some()
+            || !self.entries.is_empty()
+    }
+    
+    /// true if the step produced nothing for the host to do
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+            && self.hard_state.is_none()
+            && self.soft_state.is_none()
+            && self.snapshot.is_none()
+            && self.truncate_from.is_none()
+            && self.entries.is_empty()
+            && self.committed_entries.is_empty()
+            && self.read_states.is_empty()
+            && self.proposals.is_empty()
+            && self.compact_to.is_none()
+            && !self.removed
+    }
+}
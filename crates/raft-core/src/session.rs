@@ -0,0 +1,82 @@
+//! # session
+//!
+//! why: a client that retries a command after a leader failover must not have it applied twice
+//! relations: client requests ride on LogEntry (log.rs), the table lives in RaftNode and Snapshot
+//! what: ClientRequest metadata, Dedup outcome, SessionTable of last applied sequence per client
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// who proposed an entry and which of their commands it is
+///
+/// sequence 0 registers the session (see `RaftNode::register_session`);
+/// commands then number themselves 1, 2, 3, ... and a retry reuses the
+/// sequence of the command it retries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientRequest {
+    /// the session, i.e. the index of the entry that registered it
+    pub client_id: u64,
+    /// the client's number for this command
+    pub sequence: u64,
+}
+
+/// what the session table made of a client command as it was applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dedup {
+    /// first time this command is applied: run it
+    Fresh,
+    /// already applied: skip it and answer the client from the earlier result
+    Duplicate,
+    /// the session was never registered (or was lost): reject the command
+    UnknownSession,
+}
+
+/// highest sequence applied for every registered client
+///
+/// built only from committed entries, in log order, so every node ends up
+/// with the same table; snapshots carry it past compaction
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SessionTable {
+    last_sequence: BTreeMap<u64, u64>,
+}
+
+impl SessionTable {
+    /// an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// record an applied entry's client request, if it has one
+    ///
+    /// returns None for entries without a session and for registrations
+    pub fn apply(&mut self, request: Option<ClientRequest>) -> Option<Dedup> {
+        let request = request?;
+        if request.sequence == 0 {
+            self.last_sequence.entry(request.client_id).or_insert(0);
+            return None;
+        }
+        let Some(last) = self.last_sequence.get_mut(&request.client_id) else {
+            return Some(Dedup::UnknownSession);
+        };
+        if request.sequence <= *last {
+            return Some(Dedup::Duplicate);
+        }
+        *last = request.sequence;
+        Some(Dedup::Fresh)
+    }
+
+    /// highest sequence applied for `client_id` (None if not registered)
+    pub fn last_sequence(&self, client_id: u64) -> Option<u64> {
+        self.last_sequence.get(&client_id).copied()
+    }
+
+    /// registered clients
+    pub fn len(&self) -> usize {
+        self.last_sequence.len()
+    }
+
+    /// true if no client has registered
+    pub fn is_empty(&self) -> bool {
+        self.last_sequence.is_empty()
+    }
+}
@@ -0,0 +1,111 @@
+//! # snapshot
+//!
+//! why: let a lagging follower catch up from state machine state instead of the full log
+//! relations: carried by RaftMessage::InstallSnapshot (or streamed in InstallSnapshotChunk), held by RaftNode, surfaced via Ready
+//! what: SnapshotMetadata (last included index/term/hash), Snapshot (metadata + opaque data + sessions + voters), SnapshotSealer, SnapshotAssembly
+
+use crate::session::SessionTable;
+use serde::{Deserialize, Serialize};
+
+/// Position in the log a snapshot replaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SnapshotMetadata {
+    /// Index of the last entry the snapshot covers
+    pub last_included_index: u64,
+    /// Term of that entry
+    pub last_included_term: u64,
+    /// That entry's link in the log's hash chain, so entries after the
+    /// snapshot can still be checked (None without `RaftConfig::hash_chain`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_included_hash: Option<u64>,
+}
+
+/// A state machine snapshot
+///
+/// `data` is opaque to raft: the host produces it from its state machine
+/// and restores its state machine from it. `sessions` is the client
+/// session table as of the last included index, so duplicate detection
+/// survives compaction. `voters` is the voting membership when the
+/// snapshot was taken, so membership survives compaction too (empty in
+/// snapshots written before it was recorded).
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub metadata: SnapshotMetadata,
+    pub data: Vec<u8>,
+    #[serde(default, skip_serializing_if = "SessionTable::is_empty")]
+    pub sessions: SessionTable,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub voters: Vec<u64>,
+}
+
+impl Snapshot {
+    /// Create a snapshot covering the log up to `index` (written in `term`)
+    pub fn new(index: u64, term: u64, data: Vec<u8>) -> Self {
+        Self {
+            metadata: SnapshotMetadata {
+                last_included_index: index,
+                last_included_term: term,
+                last_included_hash: None,
+            },
+            data,
+            sessions: SessionTable::default(),
+            voters: Vec::new(),
+        }
+    }
+
+    /// The whole snapshot as bytes, as streamed in InstallSnapshotChunk
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("a snapshot always serializes")
+    }
+
+    /// Read back bytes written by `to_bytes` (None if they're corrupt)
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice(bytes).ok()
+    }
+}
+
+/// A snapshot a follower is receiving in chunks
+///
+/// Chunks must arrive in order: one that doesn't start exactly where the
+/// received bytes end (a duplicate, or one past a lost chunk) is dropped,
+/// and the follower answers with the offset it needs next so the leader
+/// resumes from there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SnapshotAssembly {
+    /// Term of the leader streaming it
+    pub term: u64,
+    /// Which snapshot this is
+    pub metadata: SnapshotMetadata,
+    /// Bytes of `Snapshot::to_bytes` received so far
+    pub bytes: Vec<u8>,
+}
+
+impl SnapshotAssembly {
+    pub fn new(term: u64, metadata: SnapshotMetadata) -> Self {
+        Self { term, metadata, bytes: Vec::new() }
+    }
+
+    /// Append `data` if it starts at `offset`; returns the offset needed next
+    pub fn accept(&mut self, offset: u64, data: &[u8]) -> u64 {
+        if offset == self.bytes.len() as u64 {
+            self.bytes.extend_from_slice(data);
+        }
+        self.bytes.len() as u64
+    }
+}
+
+/// Encrypts and/or signs snapshot data before it is stored or sent
+///
+/// With a sealer installed on a node, `compact` seals the data it is
+/// given, so both storage and InstallSnapshot only ever see sealed bytes.
+/// Incoming and restored snapshots are opened first and rejected if that
+/// fails. The metadata is passed in so a signature can cover the
+/// snapshot's position in the log as well as its contents.
+pub trait SnapshotSealer: std::fmt::Debug + Send {
+    /// Seal state machine data for the snapshot described by `metadata`
+    fn seal(&self, metadata: &SnapshotMetadata, data: &[u8]) -> Vec<u8>;
+
+    /// Recover the original data, or None if it was tampered with or
+    /// can't be decrypted with our keys
+    fn open(&self, metadata: &SnapshotMetadata, sealed: &[u8]) -> Option<Vec<u8>>;
+}
@@ -0,0 +1,23 @@
+//! # state_machine
+//!
+//! why: every host hand-rolled the same restore-then-apply loop over Ready
+//! relations: driven by RaftNode::apply_ready in node.rs, fed from ready.rs, snapshots from snapshot.rs
+//! what: StateMachine trait (apply, snapshot, restore)
+
+use crate::LogEntry;
+
+/// the application raft replicates
+///
+/// raft only ever hands it committed client commands, in log order, and
+/// the same ones on every node; no-ops, membership changes and session
+/// duplicates are filtered out before `apply` sees them
+pub trait StateMachine {
+    /// run one committed command, returning its result for the client
+    fn apply(&mut self, entry: &LogEntry) -> Vec<u8>;
+
+    /// the whole state, for `RaftNode::compact` and InstallSnapshot
+    fn snapshot(&self) -> Vec<u8>;
+
+    /// replace the whole state with a snapshot's
+    fn restore(&mut self, data: &[u8]);
+}
@@ -0,0 +1,254 @@
+//! # testing
+//!
+//! why: let downstream users test their state machines against a realistic in-process cluster
+//! relations: drives RaftNode through step/tick/broadcast_heartbeat exactly like a host would
+//! what: TestCluster (deliver-until-quiet network, partitions, one-way links, paused nodes, per-node applied entries)
+
+use crate::{LogEntry, NodeState, PauseMode, RaftConfig, RaftMessage, RaftNode, Ready};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+/// upper bound on messages delivered by one `deliver_all`, to catch livelock
+const MAX_DELIVERIES: usize = 100_000;
+
+/// An in-process cluster with a perfect network that can be partitioned
+///
+/// Messages are queued and delivered in order by `deliver_all`; every
+/// helper that produces messages delivers them before returning, so the
+/// cluster is always quiet between calls. Messages to or from an
+/// isolated node are dropped, as are messages over a link cut with
+/// `cut` (one direction only, so asymmetric partitions can be built
+/// link by link). Helpers panic with a description when the
+/// cluster can't do what was asked, which is what a test wants.
+///
+/// ```
+/// use raft_core::testing::TestCluster;
+///
+/// let mut cluster = TestCluster::new(3);
+/// cluster.elect(1);
+/// cluster.propose(b"x");
+/// cluster.isolate(3);
+/// cluster.propose(b"y");
+/// cluster.heal(3);
+/// cluster.heartbeat();
+/// cluster.assert_logs_converged();
+/// ```
+pub struct TestCluster {
+    nodes: BTreeMap<u64, RaftNode>,
+    /// committed entries each node has handed to its state machine
+    applied: BTreeMap<u64, Vec<LogEntry>>,
+    isolated: HashSet<u64>,
+    /// (from, to) links that drop everything sent over them
+    cut_links: HashSet<(u64, u64)>,
+    in_flight: VecDeque<(u64, u64, RaftMessage)>,
+}
+
+impl TestCluster {
+    /// create `size` followers with ids 1..=size
+    pub fn new(size: u64) -> Self {
+        Self::with_config(size, RaftConfig::default())
+    }
+
+    /// create `size` followers that all share `config`
+    ///
+    /// e.g. set `rng_seed` to replay the same election timeouts run after run
+    pub fn with_config(size: u64, config: RaftConfig) -> Self {
+        let ids: Vec<u64> = (1..=size).collect();
+        Self {
+            nodes: ids
+                .iter()
+                .map(|&id| (id, RaftNode::with_config(id, ids.clone(), config.clone())))
+                .collect(),
+            applied: ids.iter().map(|&id| (id, Vec::new())).collect(),
+            isolated: HashSet::new(),
+            cut_links: HashSet::new(),
+            in_flight: VecDeque::new(),
+        }
+    }
+
+    /// the node with `id`
+    pub fn node(&self, id: u64) -> &RaftNode {
+        self.nodes.get(&id).unwrap_or_else(|| panic!("no node {}", id))
+    }
+
+    /// the node with `id`, for setting up unusual state
+    pub fn node_mut(&mut self, id: u64) -> &mut RaftNode {
+        self.nodes.get_mut(&id).unwrap_or_else(|| panic!("no node {}", id))
+    }
+
+    /// every node id, ascending
+    pub fn ids(&self) -> Vec<u64> {
+        self.nodes.keys().copied().collect()
+    }
+
+    /// the reachable leader with the highest term, if any
+    pub fn leader(&self) -> Option<u64> {
+        self.nodes
+            .values()
+            .filter(|n| n.state == NodeState::Leader && !self.isolated.contains(&n.id))
+            .max_by_key(|n| n.current_term)
+            .map(|n| n.id)
+    }
+
+    /// entries node `id` has applied, in order
+    pub fn applied(&self, id: u64) -> &[LogEntry] {
+        &self.applied[&id]
+    }
+
+    // -- driving the cluster --
+
+    /// make `id` leader through a real pre-vote and election
+    ///
+    /// every node forgets the current leader first (as if its heartbeats
+    /// had stopped), then `id`'s election timer fires. panics if `id`
+    /// doesn't win, e.g. because its log is behind a majority's
+    pub fn elect(&mut self, id: u64) {
+        for node in self.nodes.values_mut() {
+            node.clear_heartbeat();
+        }
+        let timeout = self.node(id).randomized_election_timeout();
+        let ready = self.node_mut(id).tick(timeout);
+        self.handle_ready(id, ready);
+        self.deliver_all();
+
+        assert_eq!(
+            self.node(id).state,
+            NodeState::Leader,
+            "node {} failed to win the election (term {})",
+            id,
+            self.node(id).current_term
+        );
+        // replicate the leader's no-op, then let everyone (including any
+        // old leader) hear about it and its commit
+        let ready = self.node_mut(id).replicate();
+        self.handle_ready(id, ready);
+        self.deliver_all();
+        self.heartbeat();
+    }
+
+    /// propose `command` on the leader and replicate it; returns its index
+    ///
+    /// finishes with a heartbeat so followers learn the new commit index
+    pub fn propose(&mut self, command: &[u8]) -> u64 {
+        let leader = self.leader().expect("propose needs a leader");
+        let node = self.node_mut(leader);
+        let index = node.propose(command.to_vec()).expect("leader accepts proposals");
+        let ready = node.replicate();
+        self.handle_ready(leader, ready);
+        self.deliver_all();
+        self.heartbeat();
+        index
+    }
+
+    /// have the leader broadcast a heartbeat and deliver the round
+    pub fn heartbeat(&mut self) {
+        if let Some(leader) = self.leader() {
+            let ready = self.node_mut(leader).broadcast_heartbeat();
+            self.handle_ready(leader, ready);
+            self.deliver_all();
+        }
+    }
+
+    /// advance every node's clock by `elapsed_ms` and deliver the results
+    pub fn tick(&mut self, elapsed_ms: u64) {
+        for id in self.ids() {
+            let ready = self.node_mut(id).tick(elapsed_ms);
+            self.handle_ready(id, ready);
+        }
+        self.deliver_all();
+    }
+
+    /// cut `id` off from every other node
+    pub fn isolate(&mut self, id: u64) {
+        self.isolated.insert(id);
+    }
+
+    /// reconnect `id`
+    pub fn heal(&mut self, id: u64) {
+        self.isolated.remove(&id);
+    }
+
+    /// drop every message `from` sends to `to`; `to` can still reach `from`
+    pub fn cut(&mut self, from: u64, to: u64) {
+        self.cut_links.insert((from, to));
+    }
+
+    /// undo `cut(from, to)`
+    pub fn restore(&mut self, from: u64, to: u64) {
+        self.cut_links.remove(&(from, to));
+    }
+
+    /// reconnect every node and restore every link
+    pub fn heal_all(&mut self) {
+        self.isolated.clear();
+        self.cut_links.clear();
+    }
+
+    /// freeze `id` (see `RaftNode::pause`); its messages still arrive
+    pub fn pause(&mut self, id: u64, mode: PauseMode) {
+        self.node_mut(id).pause(mode);
+    }
+
+    /// unfreeze `id` and deliver what its queued messages trigger
+    pub fn resume(&mut self, id: u64) {
+        let ready = self.node_mut(id).resume();
+        self.handle_ready(id, ready);
+        self.deliver_all();
+    }
+
+    /// deliver queued messages (and everything they trigger) until quiet
+    pub fn deliver_all(&mut self) {
+        let mut delivered = 0;
+        while let Some((from, to, msg)) = self.in_flight.pop_front() {
+            if self.isolated.contains(&from)
+                || self.isolated.contains(&to)
+                || self.cut_links.contains(&(from, to))
+            {
+                continue;
+            }
+            let Some(node) = self.nodes.get_mut(&to) else { continue };
+            let ready = node.step(from, msg);
+            self.handle_ready(to, ready);
+
+            delivered += 1;
+            assert!(delivered < MAX_DELIVERIES, "cluster never went quiet");
+        }
+    }
+
+    // -- assertions --
+
+    /// assert every reachable node holds the same log and commit index
+    pub fn assert_logs_converged(&self) {
+        let reachable: Vec<&RaftNode> = self
+            .nodes
+            .values()
+            .filter(|n| !self.isolated.contains(&n.id))
+            .collect();
+        let Some((first, rest)) = reachable.split_first() else { return };
+
+        let summary = |n: &RaftNode| -> Vec<(u64, u64, Vec<u8>)> {
+            n.log.iter().map(|e| (e.index, e.term, e.command.clone())).collect()
+        };
+        for node in rest {
+            assert_eq!(
+                summary(node),
+                summary(first),
+                "log of node {} differs from node {}",
+                node.id,
+                first.id
+            );
+            assert_eq!(
+                node.commit_index, first.commit_index,
+                "commit index of node {} differs from node {}",
+                node.id, first.id
+            );
+        }
+    }
+
+    /// queue a node's outgoing messages and record what it applied
+    fn handle_ready(&mut self, id: u64, ready: Ready) {
+        for (to, msg) in ready.messages {
+            self.in_flight.push_back((id, to, msg));
+        }
+        self.applied.entry(id).or_default().extend(ready.committed_entries);
+    }
+}
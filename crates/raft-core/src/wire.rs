@@ -0,0 +1,116 @@
+//! # wire
+//!
+//! why: give RaftMessage an encoding that mixed-version clusters can keep talking over during rolling upgrades
+//! relations: encodes message.rs types for transports (examples/pi_cluster.rs); captures and fixtures keep the plain serde form
+//! what: MESSAGE_VERSION, RaftMessage::to_wire / from_wire, WireError
+
+use crate::RaftMessage;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// Wire format version written by `RaftMessage::to_wire`
+///
+/// Version 0 is the bare serde form (`{"VoteRequest":{...}}`), which is
+/// still decoded. Adding a field with `#[serde(default)]`, or a new
+/// message type, needs no bump: older decoders skip unknown fields and
+/// report unknown types. Bump this only when a change can't be expressed
+/// that way; decoders refuse versions newer than their own.
+pub const MESSAGE_VERSION: u32 = 1;
+
+/// Every message type's name on the wire, next to its variant
+///
+/// The names are part of the format: never rename or reuse one, even if
+/// the variant is renamed.
+const WIRE_TYPES: [(&str, &str); 17] = [
+    ("pre_vote_request", "PreVoteRequest"),
+    ("pre_vote_response", "PreVoteResponse"),
+    ("vote_request", "VoteRequest"),
+    ("vote_response", "VoteResponse"),
+    ("append_entries", "AppendEntries"),
+    ("append_entries_response", "AppendEntriesResponse"),
+    ("install_snapshot", "InstallSnapshot"),
+    ("install_snapshot_response", "InstallSnapshotResponse"),
+    ("install_snapshot_chunk", "InstallSnapshotChunk"),
+    ("install_snapshot_chunk_response", "InstallSnapshotChunkResponse"),
+    ("heartbeat", "Heartbeat"),
+    ("heartbeat_response", "HeartbeatResponse"),
+    ("timeout_now", "TimeoutNow"),
+    ("read_quorum_request", "ReadQuorumRequest"),
+    ("read_quorum_response", "ReadQuorumResponse"),
+    ("rejoin_request", "RejoinRequest"),
+    ("rejoin_response", "RejoinResponse"),
+];
+
+/// Why bytes didn't decode into a RaftMessage
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum WireError {
+    /// Not JSON, or not shaped like any version of a message
+    #[error("malformed raft message")]
+    Malformed,
+    /// Written by a newer version whose encoding this one can't read
+    #[error("message version {version} is newer than the supported version {MESSAGE_VERSION}")]
+    UnsupportedVersion { version: u32 },
+    /// A message type this version doesn't know (from a newer peer)
+    #[error("unknown raft message type {name:?}")]
+    UnknownType { name: String },
+}
+
+/// A message as written: version and type first, then the message's fields
+#[derive(Serialize)]
+struct Outgoing<'a> {
+    message_version: u32,
+    #[serde(rename = "type")]
+    kind: &'a str,
+    #[serde(flatten)]
+    fields: Map<String, Value>,
+}
+
+/// A message as read; fields the variant doesn't know are skipped
+#[derive(Deserialize)]
+struct Incoming {
+    message_version: u32,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(flatten)]
+    fields: Map<String, Value>,
+}
+
+impl RaftMessage {
+    /// Encode as a versioned, explicitly tagged JSON object:
+    /// `{"message_version":1,"type":"vote_request","term":2,...}`
+    pub fn to_wire(&self) -> Vec<u8> {
+        let Ok(Value::Object(bare)) = serde_json::to_value(self) else {
+            unreachable!("a message always serializes to an object");
+        };
+        let (variant, fields) = bare.into_iter().next().expect("one key per variant");
+        let kind = WIRE_TYPES
+            .iter()
+            .find(|(_, name)| *name == variant)
+            .map(|(kind, _)| *kind)
+            .expect("every variant has a wire type");
+        let Value::Object(fields) = fields else {
+            unreachable!("every variant has named fields");
+        };
+        let message = Outgoing { message_version: MESSAGE_VERSION, kind, fields };
+        serde_json::to_vec(&message).expect("a message always serializes")
+    }
+
+    /// Decode bytes written by `to_wire`, or by a version 0 peer
+    pub fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        let value: Value = serde_json::from_slice(bytes).map_err(|_| WireError::Malformed)?;
+        if !value.get("message_version").is_some_and(Value::is_u64) {
+            return serde_json::from_value(value).map_err(|_| WireError::Malformed);
+        }
+        let message: Incoming = serde_json::from_value(value).map_err(|_| WireError::Malformed)?;
+        if message.message_version > MESSAGE_VERSION {
+            return Err(WireError::UnsupportedVersion { version: message.message_version });
+        }
+        let Some((_, variant)) = WIRE_TYPES.iter().find(|(kind, _)| *kind == message.kind) else {
+            return Err(WireError::UnknownType { name: message.kind });
+        };
+        let mut bare = Map::new();
+        bare.insert(variant.to_string(), Value::Object(message.fields));
+        serde_json::from_value(Value::Object(bare)).map_err(|_| WireError::Malformed)
+    }
+}
@@ -6,6 +6,90 @@
 
 use raft_core::{LogEntry, NodeState, RaftConfig, RaftMessage, RaftNode};
 
+/// node 1 of 1..=3, just elected leader of term 1 with its no-op at
+/// index 1; the election's Ready is drained
+fn leader(config: RaftConfig) -> RaftNode {
+    let mut node = RaftNode::with_config(1, vec![1, 2, 3], config);
+    node.start_election();
+    node.handle_vote_response(1, true, 2);
+    node.ready();
+    node
+}
+
+/// `leader` whose no-op node 2 has acked, so it is committed
+fn committed_leader(config: RaftConfig) -> RaftNode {
+    let mut node = leader(config);
+    node.handle_append_entries_response(1, true, 2, 1);
+    node.ready();
+    node
+}
+
+/// `leader` whose no-op nodes 2 and 3 have acked through `step`, so both
+/// are replicating
+fn caught_up(config: RaftConfig) -> RaftNode {
+    let mut node = leader(config);
+    node.replicate();
+    for peer in [2, 3] {
+        node.step(peer, append_ok(1));
+    }
+    node
+}
+
+/// `leader` with `entries` commands after its no-op, acked by nodes 2 and
+/// 3 up to `acked` (0: not at all); the Ready is drained
+fn leader_with_log(entries: u8, acked: u64) -> RaftNode {
+    let mut node = leader(RaftConfig::default());
+    for i in 0..entries {
+        node.append_entry(vec![i]);
+    }
+    if acked > 0 {
+        for peer in [2, 3] {
+            node.handle_append_entries_response(1, true, peer, acked);
+        }
+    }
+    node.ready();
+    node
+}
+
+/// term-1 AppendEntriesResponse accepting everything up to `match_index`
+fn append_ok(match_index: u64) -> RaftMessage {
+    RaftMessage::AppendEntriesResponse {
+        term: 1,
+        success: true,
+        match_index,
+        storage_health: None,
+        backoff: false,
+        seq: 0,
+        group_id: None,
+    }
+}
+
+/// `append_ok` that also asks the leader to back off
+fn append_backoff(match_index: u64) -> RaftMessage {
+    RaftMessage::AppendEntriesResponse {
+        term: 1,
+        success: true,
+        match_index,
+        storage_health: None,
+        backoff: true,
+        seq: 0,
+        group_id: None,
+    }
+}
+
+/// term-1 AppendEntriesResponse refusing an append, hinting `match_index`
+fn append_rejected(match_index: u64) -> RaftMessage {
+    RaftMessage::AppendEntriesResponse {
+        term: 1,
+        success: false,
+        match_index,
+        storage_health: None,
+        backoff: false,
+        seq: 0,
+        group_id: None,
+    }
+}
+
 // =============================================================================
 // SECTION 1: INITIALIZATION TESTS
 // =============================================================================
@@ -37,6 +121,7 @@ mod initialization {
             election_timeout_min: 200,
             election_timeout_max: 400,
             heartbeat_interval: 100,
+            ..RaftConfig::default()
         };
         let node = RaftNode::with_config(1, vec![1, 2, 3], config);
         assert_eq!(node.config.election_timeout_min, 200);
@@ -50,6 +135,31 @@ mod initialization {
         assert_eq!(config.election_timeout_min, 150);
         assert_eq!(config.election_timeout_max, 300);
         assert_eq!(config.heartbeat_interval, 50);
+        assert_eq!(config.tick_interval, 10);
+    }
+
+    #[test]
+    fn config_from_ticks() {
+        let config = RaftConfig::from_ticks(10, 5, 15, 30);
+        assert_eq!(config.heartbeat_interval, 50);
+        assert_eq!(config.election_timeout_min, 150);
+        assert_eq!(config.election_timeout_max, 300);
+        assert_eq!(config.heartbeat_ticks(), 5);
+        assert_eq!(config.election_timeout_min_ticks(), 15);
+        assert_eq!(config.election_timeout_max_ticks(), 30);
+    }
+
+    #[test]
+    fn ticks_round_up_for_coarse_timers() {
+        let config = RaftConfig {
+            tick_interval: 100,
+            ..RaftConfig::default()
+        };
+        // 50ms heartbeat with 100ms ticks still fires every tick
+        assert_eq!(config.heartbeat_ticks(), 1);
+        // 150ms rounds up to 2 ticks rather than down to 1
+        assert_eq!(config.election_timeout_min_ticks(), 2);
+        assert_eq!(config.election_timeout_max_ticks(), 3);
     }
 }
 
@@ -135,11 +245,12 @@ mod election {
         let vote_req = node.start_election();
         
         match vote_req {
-            RaftMessage::VoteRequest { term, candidate_id, last_log_index, last_log_term } => {
+            RaftMessage::VoteRequest { term, candidate_id, last_log_index, last_log_term, transfer, .. } => {
                 assert_eq!(term, 1);
                 assert_eq!(candidate_id, 1);
                 assert_eq!(last_log_index, 0);
                 assert_eq!(last_log_term, 0);
+                assert!(!transfer);
             }
             _ => panic!("expected VoteRequest"),
         }
@@ -252,7 +363,7 @@ mod vote_requests {
         let (response, should_reset) = node.handle_vote_request(1, 2, 0, 0);
         
         match response {
-            RaftMessage::VoteResponse { term, vote_granted } => {
+            RaftMessage::VoteResponse { term, vote_granted, .. } => {
                 assert_eq!(term, 1);
                 assert!(vote_granted);
             }
@@ -270,7 +381,7 @@ mod vote_requests {
         let (response, _) = node.handle_vote_request(3, 2, 0, 0);
         
         match response {
-            RaftMessage::VoteResponse { term, vote_granted } => {
+            RaftMessage::VoteResponse { term, vote_granted, .. } => {
                 assert_eq!(term, 5); // return our higher term
                 assert!(!vote_granted);
             }
@@ -421,8 +532,8 @@ mod leader_state {
         node.handle_vote_response(1, true, 2);
         
         // next_index should be last_log_index + 1 for all followers
-        assert_eq!(node.next_index.get(&2), Some(&2));
-        assert_eq!(node.next_index.get(&3), Some(&2));
+        assert_eq!(node.progress.get(&2).map(|p| p.next_index), Some(2));
+        assert_eq!(node.progress.get(&3).map(|p| p.next_index), Some(2));
     }
 
     #[test]
@@ -431,8 +542,8 @@ mod leader_state {
         node.start_election();
         node.handle_vote_response(1, true, 2);
         
-        assert_eq!(node.match_index.get(&2), Some(&0));
-        assert_eq!(node.match_index.get(&3), Some(&0));
+        assert_eq!(node.progress.get(&2).map(|p| p.match_index), Some(0));
+        assert_eq!(node.progress.get(&3).map(|p| p.match_index), Some(0));
     }
 
     #[test]
@@ -466,9 +577,9 @@ mod log_replication {
         let entry = node.append_entry(b"SET key value".to_vec());
         
         assert_eq!(entry.term, 1);
-        assert_eq!(entry.index, 1);
+        assert_eq!(entry.index, 2); // after the leader's no-op
         assert_eq!(entry.command, b"SET key value".to_vec());
-        assert_eq!(node.log.len(), 1);
+        assert_eq!(node.log.len(), 2);
     }
 
     #[test]
@@ -481,27 +592,27 @@ mod log_replication {
         node.append_entry(b"cmd2".to_vec());
         node.append_entry(b"cmd3".to_vec());
         
-        assert_eq!(node.log.len(), 3);
-        assert_eq!(node.log[0].index, 1);
+        assert_eq!(node.log.len(), 4);
         assert_eq!(node.log[1].index, 2);
         assert_eq!(node.log[2].index, 3);
+        assert_eq!(node.log[3].index, 4);
     }
 
     #[test]
-    fn create_heartbeat_returns_empty_append_entries() {
+    fn create_heartbeat_returns_heartbeat() {
         let mut node = RaftNode::new(1, vec![1, 2, 3]);
         node.start_election();
         node.handle_vote_response(1, true, 2);
         
-        let heartbeat = node.create_heartbeat().unwrap();
+        let heartbeat = node.create_heartbeat(2).unwrap();
         
         match heartbeat {
-            RaftMessage::AppendEntries { term, leader_id, entries, .. } => {
+            RaftMessage::Heartbeat { term, leader_id, commit, .. } => {
                 assert_eq!(term, 1);
                 assert_eq!(leader_id, 1);
-                assert!(entries.is_empty()); // heartbeat has no entries
+                assert_eq!(commit, 0);
             }
-            _ => panic!("expected AppendEntries"),
+            _ => panic!("expected Heartbeat"),
         }
     }
 
@@ -517,7 +628,7 @@ mod log_replication {
         
         match ae {
             RaftMessage::AppendEntries { entries, .. } => {
-                assert_eq!(entries.len(), 2);
+                assert_eq!(entries.len(), 3); // no-op + 2 commands
             }
             _ => panic!("expected AppendEntries"),
         }
@@ -526,7 +637,7 @@ mod log_replication {
     #[test]
     fn non_leader_cannot_create_heartbeat() {
         let node = RaftNode::new(1, vec![1, 2, 3]);
-        assert!(node.create_heartbeat().is_none());
+        assert!(node.create_heartbeat(2).is_none());
     }
 
     #[test]
@@ -558,7 +669,7 @@ mod append_entries_handling {
         );
         
         match response {
-            RaftMessage::AppendEntriesResponse { term, success } => {
+            RaftMessage::AppendEntriesResponse { term, success, .. } => {
                 assert_eq!(term, 5);
                 assert!(!success);
             }
@@ -678,6 +789,23 @@ mod append_entries_handling {
         
         assert_eq!(node.commit_index, 1); // capped at our log length
     }
+
+    #[test]
+    fn commit_index_capped_at_what_the_request_matched() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.log.push(LogEntry::new(1, 1, b"cmd1".to_vec()));
+        node.log.push(LogEntry::new(1, 2, b"cmd2".to_vec()));
+        node.log.push(LogEntry::new(1, 3, b"stale".to_vec()));
+        node.current_term = 1;
+
+        // the leader's entry 3 is from term 2, but this batch stops at 2:
+        // our entry 3 hasn't been checked against it yet
+        let entries = vec![LogEntry::new(1, 2, b"cmd2".to_vec())];
+        node.handle_append_entries(2, 2, 1, 1, entries, 3);
+
+        assert_eq!(node.log.len(), 3);
+        assert_eq!(node.commit_index, 2);
+    }
 }
 
 // =============================================================================
@@ -697,8 +825,8 @@ mod append_entries_response {
         let updated = node.handle_append_entries_response(1, true, 2, 1);
         
         assert!(updated || !updated); // may or may not advance commit
-        assert_eq!(node.match_index.get(&2), Some(&1));
-        assert_eq!(node.next_index.get(&2), Some(&2));
+        assert_eq!(node.progress.get(&2).map(|p| p.match_index), Some(1));
+        assert_eq!(node.progress.get(&2).map(|p| p.next_index), Some(2));
     }
 
     #[test]
@@ -709,11 +837,11 @@ mod append_entries_response {
         node.append_entry(b"cmd".to_vec());
         
         // simulate initial next_index being too high
-        node.next_index.insert(2, 5);
+        node.progress.get_mut(&2).unwrap().next_index = 5;
         
         node.handle_append_entries_response(1, false, 2, 0);
         
-        assert_eq!(node.next_index.get(&2), Some(&4)); // decremented
+        assert_eq!(node.progress.get(&2).map(|p| p.next_index), Some(4)); // decremented
     }
 
     #[test]
@@ -755,8 +883,8 @@ mod commit_advancement {
         node.append_entry(b"cmd".to_vec());
         
         // simulate node 2 replicating entry 1
-        node.next_index.insert(2, 2);
-        node.match_index.insert(2, 1);
+        node.progress.get_mut(&2).unwrap().next_index = 2;
+        node.progress.get_mut(&2).unwrap().match_index = 1;
         
         // calling handle_append_entries_response should trigger commit check
         let updated = node.handle_append_entries_response(1, true, 2, 1);
@@ -775,7 +903,7 @@ mod commit_advancement {
         node.append_entry(b"cmd".to_vec());
         
         // only node 2 has replicated (2/5 = not quorum)
-        node.match_index.insert(2, 1);
+        node.progress.get_mut(&2).unwrap().match_index = 1;
         
         let updated = node.handle_append_entries_response(1, true, 2, 1);
         
@@ -796,8 +924,8 @@ mod commit_advancement {
         node.handle_vote_response(2, true, 2);
         
         // set match_index to show entry 1 is replicated
-        node.match_index.insert(2, 1);
-        node.match_index.insert(3, 1);
+        node.progress.get_mut(&2).unwrap().match_index = 1;
+        node.progress.get_mut(&3).unwrap().match_index = 1;
         
         // manually try to advance commit (internal method test)
         // entry 1 has term 1, but current term is 2, so can't commit
@@ -1090,6 +1218,20 @@ mod prevote {
         }
     }
 
+    #[test]
+    fn prevote_refusal_expires_after_election_timeout() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.record_heartbeat(0);
+        node.tick(node.config.election_timeout_min - 1);
+        let (response, _) = node.handle_prevote_request(1, 2, 0, 0);
+        assert!(matches!(response, RaftMessage::PreVoteResponse { vote_granted: false, .. }));
+
+        // a leader heard from once, long ago, is no reason to refuse
+        node.tick(1);
+        let (response, _) = node.handle_prevote_request(1, 2, 0, 0);
+        assert!(matches!(response, RaftMessage::PreVoteResponse { vote_granted: true, .. }));
+    }
+
     #[test]
     fn prevote_does_not_reset_election_timer() {
         let mut node = RaftNode::new(1, vec![1, 2, 3]);
@@ -1140,7 +1282,7 @@ mod prevote {
         );
         
         match response {
-            RaftMessage::PreVoteResponse { vote_granted, term } => {
+            RaftMessage::PreVoteResponse { vote_granted, term, .. } => {
                 // Node 1 should REJECT because it heard from leader recently
                 assert!(!vote_granted, "healthy node should reject rogue's pre-vote");
                 // Node 1's term should NOT change
@@ -1199,5 +1341,6017 @@ mod prevote {
         node.prevotes_received = vec![1, 2]; // 2/3 = quorum
         assert!(node.has_prevote_quorum());
     }
+
+    #[test]
+    fn duplicate_prevote_counts_once() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3, 4, 5]);
+        node.start_prevote();
+        
+        assert!(!node.handle_prevote_response(1, true, 2));
+        assert!(!node.handle_prevote_response(1, true, 2));
+        assert_eq!(node.prevotes_received, vec![1, 2]);
+    }
+
+    #[test]
+    fn partitioned_node_does_not_inflate_term() {
+        // an isolated node keeps timing out but never hears back
+        let mut node = RaftNode::new(3, vec![1, 2, 3]);
+        for _ in 0..20 {
+            node.tick(node.config.election_timeout_max);
+        }
+        
+        assert_eq!(node.state, NodeState::PreCandidate);
+        assert_eq!(node.current_term, 0, "term must stay put without a pre-vote majority");
+        assert_eq!(node.voted_for, None);
+    }
+
+    #[test]
+    fn late_prevote_after_stepping_down_is_ignored() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_prevote();
+        node.become_follower(0);
+        
+        let ready = node.step(2, RaftMessage::PreVoteResponse { term: 0, vote_granted: true, commit_index: 0, last_log_index: 0, group_id: None });
+        
+        assert_eq!(node.state, NodeState::Follower);
+        assert_eq!(node.current_term, 0);
+        assert!(ready.messages.is_empty());
+    }
+}
+
+
+// =============================================================================
+// SECTION 14: STORAGE HEALTH REPORTING
+// =============================================================================
+
+mod storage_health {
+    use super::*;
+    use raft_core::StorageHealth;
+
+    #[test]
+    fn follower_includes_storage_health_in_append_response() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        let health = StorageHealth { last_fsync_ms: 12, disk_full: false };
+        node.report_storage_health(health);
+        
+        let (response, _) = node.handle_append_entries(1, 1, 0, 0, vec![], 0);
+        
+        match response {
+            RaftMessage::AppendEntriesResponse { storage_health, .. } => {
+                assert_eq!(storage_health, Some(health));
+            }
+            _ => panic!("expected AppendEntriesResponse"),
+        }
+    }
+
+    #[test]
+    fn append_response_without_report_has_no_health() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        
+        let (response, _) = node.handle_append_entries(1, 1, 0, 0, vec![], 0);
+        
+        match response {
+            RaftMessage::AppendEntriesResponse { storage_health, .. } => {
+                assert_eq!(storage_health, None);
+            }
+            _ => panic!("expected AppendEntriesResponse"),
+        }
+    }
+
+    #[test]
+    fn leader_builds_health_matrix() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.report_storage_health(StorageHealth { last_fsync_ms: 3, disk_full: false });
+        node.record_peer_storage_health(2, StorageHealth { last_fsync_ms: 40, disk_full: true });
+        
+        let matrix = node.storage_health_matrix();
+        
+        assert_eq!(matrix.len(), 3);
+        assert_eq!(matrix[0], (1, Some(StorageHealth { last_fsync_ms: 3, disk_full: false })));
+        assert_eq!(matrix[1], (2, Some(StorageHealth { last_fsync_ms: 40, disk_full: true })));
+        assert_eq!(matrix[2], (3, None));
+    }
+
+    #[test]
+    fn disk_full_node_is_unhealthy() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.record_peer_storage_health(2, StorageHealth { last_fsync_ms: 1, disk_full: true });
+        
+        assert!(!node.is_storage_healthy(2));
+        assert!(node.is_storage_healthy(3)); // never reported = assumed healthy
+    }
+}
+
+// =============================================================================
+// SECTION 15: STEP API (OUTBOX-DRIVEN)
+// =============================================================================
+
+mod step_api {
+    use super::*;
+    use raft_core::HardState;
+
+    #[test]
+    fn step_vote_request_replies_to_candidate() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        
+        let ready = node.step(1, RaftMessage::VoteRequest {
+            term: 1,
+            candidate_id: 1,
+            last_log_index: 0,
+            last_log_term: 0,
+            transfer: false,
+            group_id: None,
+        });
+        
+        assert_eq!(ready.messages.len(), 1);
+        let (to, msg) = &ready.messages[0];
+        assert_eq!(*to, 1);
+        assert!(matches!(msg, RaftMessage::VoteResponse { vote_granted: true, .. }));
+        // term and vote changed, so they must be persisted
+        assert_eq!(ready.hard_state, Some(HardState { term: 1, vote: Some(1) }));
+    }
+
+    #[test]
+    fn step_prevote_quorum_broadcasts_vote_requests() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_prevote();
+        
+        let ready = node.step(2, RaftMessage::PreVoteResponse { term: 0, vote_granted: true, commit_index: 0, last_log_index: 0, group_id: None });
+        
+        assert_eq!(node.state, NodeState::Candidate);
+        let targets: Vec<u64> = ready.messages.iter().map(|(to, _)| *to).collect();
+        assert_eq!(targets, vec![2, 3]);
+        assert!(ready.messages.iter().all(|(_, m)| matches!(m, RaftMessage::VoteRequest { term: 1, .. })));
+        assert_eq!(ready.hard_state, Some(HardState { term: 1, vote: Some(1) }));
+    }
+
+    #[test]
+    fn step_append_entries_reports_entries_and_commits() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        
+        let ready = node.step(1, RaftMessage::AppendEntries {
+            term: 1,
+            leader_id: 1,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: vec![LogEntry::new(1, 1, vec![1]), LogEntry::new(1, 2, vec![2])],
+            leader_commit: 1,
+            seq: 0,
+            group_id: None,
+        });
+        
+        assert_eq!(ready.entries.len(), 2);
+        assert_eq!(ready.committed_entries.len(), 1);
+        assert_eq!(ready.committed_entries[0].index, 1);
+        match &ready.messages[0].1 {
+            RaftMessage::AppendEntriesResponse { success, match_index, .. } => {
+                assert!(*success);
+                assert_eq!(*match_index, 2);
+            }
+            _ => panic!("expected AppendEntriesResponse"),
+        }
+    }
+
+    #[test]
+    fn step_reports_truncation_on_conflict() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.log.push(LogEntry::new(1, 1, vec![1]));
+        node.log.push(LogEntry::new(1, 2, vec![2]));
+        node.current_term = 1;
+        
+        let ready = node.step(1, RaftMessage::AppendEntries {
+            term: 2,
+            leader_id: 1,
+            prev_log_index: 1,
+            prev_log_term: 1,
+            entries: vec![LogEntry::new(2, 2, vec![9])],
+            leader_commit: 0,
+            seq: 0,
+            group_id: None,
+        });
+        
+        assert_eq!(ready.truncate_from, Some(2));
+        assert_eq!(ready.entries.len(), 1);
+        assert_eq!(ready.entries[0].term, 2);
+    }
+
+    #[test]
+    fn step_rejection_triggers_retry_from_lower_index() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        node.ready(); // election probes
+        node.append_entry(vec![1]);
+        node.append_entry(vec![2]);
+        
+        let ready = node.step(2, append_rejected(0));
+        
+        assert_eq!(ready.messages.len(), 1);
+        match &ready.messages[0] {
+            (2, RaftMessage::AppendEntries { prev_log_index, entries, .. }) => {
+                assert_eq!(*prev_log_index, 0);
+                assert_eq!(entries.len(), 3); // no-op + 2 commands
+            }
+            other => panic!("expected retry AppendEntries, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn step_success_response_advances_commit() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        node.ready(); // election probes
+        node.append_entry(b"x".to_vec());
+        
+        let ready = node.step(2, append_ok(1));
+        
+        assert_eq!(node.commit_index, 1);
+        assert_eq!(ready.committed_entries.len(), 1);
+        assert!(ready.messages.is_empty());
+    }
+
+    #[test]
+    fn empty_ready_when_nothing_happens() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        
+        // stale vote response on a follower: nothing to do
+        let ready = node.step(2, RaftMessage::VoteResponse { term: 0, vote_granted: true, commit_index: 0, last_log_index: 0, group_id: None });
+        
+        assert!(ready.is_empty());
+    }
+}
+
+// =============================================================================
+// SECTION 16: READ INDEX BATCHING
+// =============================================================================
+
+mod read_index {
+    use super::*;
+
+    #[test]
+    fn follower_rejects_read_index() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        assert!(!node.read_index(b"r1".to_vec()));
+    }
+
+    #[test]
+    fn concurrent_reads_share_one_heartbeat_round() {
+        let mut node = committed_leader(RaftConfig::default());
+        assert!(node.read_index(b"r1".to_vec()));
+        assert!(node.read_index(b"r2".to_vec()));
+        assert!(node.read_index(b"r3".to_vec()));
+        
+        // one heartbeat round for all three reads
+        let ready = node.broadcast_heartbeat();
+        assert_eq!(ready.messages.len(), 2);
+        assert!(ready.read_states.is_empty());
+        
+        // a single quorum ack releases the whole batch
+        let ready = node.step(2, append_ok(1));
+        assert_eq!(ready.read_states.len(), 3);
+        assert!(ready.read_states.iter().all(|r| r.index == 1));
+        assert_eq!(ready.read_states[0].request_ctx, b"r1".to_vec());
+        assert_eq!(node.pending_read_count(), 0);
+    }
+
+    #[test]
+    fn reads_wait_for_next_heartbeat() {
+        let mut node = committed_leader(RaftConfig::default());
+        node.read_index(b"r1".to_vec());
+        
+        // acks for an earlier round don't confirm a read that wasn't in it
+        let ready = node.step(2, append_ok(1));
+        assert!(ready.read_states.is_empty());
+        assert_eq!(node.pending_read_count(), 1);
+    }
+
+    #[test]
+    fn reads_after_round_start_join_the_next_round() {
+        let mut node = committed_leader(RaftConfig::default());
+        node.read_index(b"r1".to_vec());
+        node.broadcast_heartbeat();
+        node.read_index(b"r2".to_vec());
+        
+        let ready = node.step(2, append_ok(1));
+        assert_eq!(ready.read_states.len(), 1);
+        assert_eq!(ready.read_states[0].request_ctx, b"r1".to_vec());
+        assert_eq!(node.pending_read_count(), 1);
+    }
+
+    #[test]
+    fn stale_term_ack_does_not_confirm() {
+        let mut node = committed_leader(RaftConfig::default());
+        node.current_term = 2; // pretend a newer term, entry at commit is from term 1
+        node.read_index(b"r1".to_vec());
+        node.broadcast_heartbeat();
+        
+        let ready = node.step(2, append_ok(1));
+        assert!(ready.read_states.is_empty());
+    }
+
+    #[test]
+    fn reads_wait_until_leader_commits_in_its_term() {
+        let mut node = leader(RaftConfig::default());
+        node.read_index(b"r1".to_vec());
+        
+        node.broadcast_heartbeat();
+        let ready = node.step(2, append_ok(1));
+        
+        assert!(ready.read_states.is_empty());
+        assert_eq!(node.pending_read_count(), 1);
+    }
+
+    #[test]
+    fn stepping_down_drops_pending_reads() {
+        let mut node = committed_leader(RaftConfig::default());
+        node.read_index(b"r1".to_vec());
+        node.broadcast_heartbeat();
+        
+        node.become_follower(5);
+        
+        assert_eq!(node.pending_read_count(), 0);
+    }
+
+    #[test]
+    fn single_node_reads_resolve_immediately() {
+        let mut node = RaftNode::new(1, vec![1]);
+        node.start_election();
+        node.become_leader();
+        node.append_entry(b"x".to_vec());
+        node.commit_index = 1;
+        
+        node.read_index(b"r1".to_vec());
+        let ready = node.ready();
+        
+        assert_eq!(ready.read_states.len(), 1);
+    }
 }
 
+// =============================================================================
+// SECTION 17: TICK-DRIVEN TIMERS
+// =============================================================================
+
+mod timers {
+    use super::*;
+    use raft_core::HardState;
+
+    fn heartbeat_from(leader: u64, term: u64) -> RaftMessage {
+        RaftMessage::AppendEntries {
+            term,
+            leader_id: leader,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: vec![],
+            leader_commit: 0,
+            seq: 0,
+            group_id: None,
+        }
+    }
+
+    #[test]
+    fn randomized_timeout_within_config_range() {
+        let config = RaftConfig::default();
+        for id in 1..=20 {
+            let node = RaftNode::with_config(id, vec![1, 2, 3], config.clone());
+            let timeout = node.randomized_election_timeout();
+            assert!(timeout >= config.election_timeout_min);
+            assert!(timeout <= config.election_timeout_max);
+        }
+    }
+
+    #[test]
+    fn no_campaign_before_timeout() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        let ready = node.tick(node.config.election_timeout_min - 1);
+
+        assert!(ready.messages.is_empty());
+        assert_eq!(node.state, NodeState::Follower);
+    }
+
+    #[test]
+    fn follower_times_out_into_prevote() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        let ready = node.tick(node.config.election_timeout_max);
+
+        assert_eq!(node.state, NodeState::PreCandidate);
+        assert_eq!(node.current_term, 0, "pre-vote must not bump the term");
+        assert_eq!(ready.messages.len(), 2);
+        assert!(ready.messages.iter().all(|(_, m)| matches!(m, RaftMessage::PreVoteRequest { .. })));
+    }
+
+    #[test]
+    fn prevote_majority_broadcasts_vote_requests() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.tick(node.config.election_timeout_max);
+
+        let ready = node.step(2, RaftMessage::PreVoteResponse { term: 0, vote_granted: true, commit_index: 0, last_log_index: 0, group_id: None });
+
+        assert_eq!(node.state, NodeState::Candidate);
+        assert_eq!(ready.hard_state, Some(HardState { term: 1, vote: Some(1) }));
+        assert!(ready.messages.iter().all(|(_, m)| matches!(m, RaftMessage::VoteRequest { term: 1, .. })));
+    }
+
+    #[test]
+    fn heartbeats_keep_follower_quiet() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        for _ in 0..10 {
+            node.tick(node.config.election_timeout_min - 1);
+            node.step(2, heartbeat_from(2, 1));
+        }
+        assert_eq!(node.state, NodeState::Follower);
+    }
+
+    #[test]
+    fn uneven_ticks_accumulate() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        let timeout = node.randomized_election_timeout();
+
+        node.tick(timeout / 2);
+        assert_eq!(node.state, NodeState::Follower);
+        node.tick(timeout - timeout / 2);
+        assert_eq!(node.state, NodeState::PreCandidate);
+    }
+
+    #[test]
+    fn single_node_elects_itself() {
+        let mut node = RaftNode::new(1, vec![1]);
+        let ready = node.tick(node.config.election_timeout_max);
+
+        assert_eq!(node.state, NodeState::Leader);
+        assert_eq!(node.current_term, 1);
+        assert!(ready.messages.is_empty());
+        assert_eq!(ready.hard_state, Some(HardState { term: 1, vote: Some(1) }));
+    }
+
+    #[test]
+    fn leader_heartbeats_on_interval() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        node.ready();
+
+        let interval = node.config.heartbeat_interval;
+        assert!(node.tick(interval - 1).messages.is_empty());
+
+        let ready = node.tick(1);
+        assert_eq!(ready.messages.len(), 2);
+        assert!(ready.messages.iter().all(|(_, m)| matches!(m, RaftMessage::Heartbeat { .. })));
+
+        // the interval restarts after each heartbeat
+        assert!(node.tick(interval - 1).messages.is_empty());
+    }
+
+    #[test]
+    fn leader_never_campaigns() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+
+        node.tick(node.config.election_timeout_max * 10);
+        assert_eq!(node.state, NodeState::Leader);
+        assert_eq!(node.current_term, 1);
+    }
+}
+
+// =============================================================================
+// SECTION 18: FOLLOWER BACKOFF HINTS
+// =============================================================================
+
+mod backoff {
+    use super::*;
+
+    fn sent_entries(msg: Option<RaftMessage>) -> usize {
+        match msg {
+            Some(RaftMessage::AppendEntries { entries, .. }) => entries.len(),
+            other => panic!("expected AppendEntries, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn saturated_follower_sets_hint() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.report_backpressure(true);
+
+        let (resp, _) = node.handle_append_entries(1, 1, 0, 0, vec![], 0);
+        match resp {
+            RaftMessage::AppendEntriesResponse { backoff, .. } => assert!(backoff),
+            _ => panic!("expected AppendEntriesResponse"),
+        }
+    }
+
+    #[test]
+    fn healthy_follower_sends_no_hint() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        let (resp, _) = node.handle_append_entries(1, 1, 0, 0, vec![], 0);
+        match resp {
+            RaftMessage::AppendEntriesResponse { backoff, .. } => assert!(!backoff),
+            _ => panic!("expected AppendEntriesResponse"),
+        }
+    }
+
+    #[test]
+    fn leader_shrinks_window_for_throttled_peer() {
+        // the leader's no-op + 5 commands
+        let mut node = leader_with_log(5, 0);
+        assert_eq!(sent_entries(node.create_append_entries(2)), 6);
+
+        node.step(2, append_backoff(0));
+
+        assert!(node.is_throttled(2));
+        assert!(!node.is_throttled(3));
+        assert_eq!(sent_entries(node.create_append_entries(2)), 1);
+        assert_eq!(sent_entries(node.create_append_entries(3)), 6);
+    }
+
+    #[test]
+    fn window_reopens_when_hint_clears() {
+        let mut node = leader_with_log(5, 0);
+        node.step(2, append_backoff(1));
+        node.step(2, append_ok(1));
+
+        assert!(!node.is_throttled(2));
+        assert_eq!(sent_entries(node.create_append_entries(2)), 5);
+    }
+
+    #[test]
+    fn new_term_forgets_throttling() {
+        let mut node = leader_with_log(2, 0);
+        node.step(2, append_backoff(0));
+
+        node.become_follower(2);
+        node.start_election();
+        node.handle_vote_response(3, true, 3);
+
+        assert!(!node.is_throttled(2));
+    }
+}
+
+// =============================================================================
+// SECTION 19: WARM STANDBY
+// =============================================================================
+
+mod standby {
+    use super::*;
+
+    /// leader of voters {1,2,3} with standby 4
+    fn leader_with_standby() -> RaftNode {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.add_standby(4);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        node.ready();
+        node
+    }
+
+    #[test]
+    fn leader_replicates_to_standby() {
+        let mut node = leader_with_standby();
+        assert!(node.progress.contains_key(&4));
+
+        let ready = node.broadcast_heartbeat();
+        let targets: Vec<u64> = ready.messages.iter().map(|(to, _)| *to).collect();
+        assert_eq!(targets, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn standby_ack_does_not_commit() {
+        let mut node = leader_with_standby();
+        node.append_entry(b"x".to_vec());
+
+        node.step(4, append_ok(1));
+        assert_eq!(node.commit_index, 0, "standby alone is not a quorum");
+        assert_eq!(node.progress[&4].match_index, 1);
+
+        node.step(2, append_ok(1));
+        assert_eq!(node.commit_index, 1);
+    }
+
+    #[test]
+    fn standby_votes_are_ignored() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.add_standby(4);
+        node.start_election();
+
+        assert!(!node.handle_vote_response(1, true, 4));
+        assert_eq!(node.state, NodeState::Candidate);
+    }
+
+    #[test]
+    fn elections_skip_standby() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.add_standby(4);
+
+        let ready = node.tick(node.config.election_timeout_max);
+        assert!(ready.messages.iter().all(|(to, _)| *to != 4));
+    }
+
+    #[test]
+    fn standby_never_campaigns() {
+        let mut node = RaftNode::new(4, vec![1, 2, 3]);
+        node.add_standby(4);
+        assert!(node.is_standby());
+
+        for _ in 0..10 {
+            let ready = node.tick(node.config.election_timeout_max);
+            assert!(ready.messages.is_empty());
+        }
+        assert_eq!(node.state, NodeState::Follower);
+        assert_eq!(node.current_term, 0);
+    }
+
+    #[test]
+    fn activate_swaps_voter_and_keeps_quorum_size() {
+        let mut node = leader_with_standby();
+        node.append_entry(b"x".to_vec());
+        node.step(4, append_ok(1));
+
+        assert!(node.activate_standby(4, 3));
+
+        assert_eq!(node.cluster_nodes, vec![1, 2, 4]);
+        assert!(node.standby_nodes.is_empty());
+        assert_eq!(node.quorum_size(), 2);
+        assert!(!node.progress.contains_key(&3));
+        // the standby's replication progress carries over
+        assert_eq!(node.progress[&4].match_index, 1);
+    }
+
+    #[test]
+    fn activated_standby_counts_toward_commit() {
+        let mut node = leader_with_standby();
+        node.activate_standby(4, 3);
+        node.append_entry(b"x".to_vec());
+
+        node.step(4, append_ok(1));
+        assert_eq!(node.commit_index, 1);
+    }
+
+    #[test]
+    fn activate_rejects_unknown_nodes() {
+        let mut node = leader_with_standby();
+        assert!(!node.activate_standby(5, 3), "5 is not a standby");
+        assert!(!node.activate_standby(4, 9), "9 is not a voter");
+        assert_eq!(node.cluster_nodes, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn swapped_out_leader_steps_down() {
+        let mut node = leader_with_standby();
+        assert!(node.activate_standby(4, 1));
+        assert_eq!(node.state, NodeState::Follower);
+    }
+}
+
+// =============================================================================
+// SECTION 20: LEARNERS
+// =============================================================================
+
+mod learners {
+    use super::*;
+    use raft_core::PromoteError;
+
+    #[test]
+    fn learner_added_to_running_leader_gets_entries() {
+        let mut node = leader(RaftConfig::default());
+        node.append_entry(b"x".to_vec());
+        node.add_learner(4);
+
+        assert_eq!(node.learner_nodes, vec![4]);
+        assert_eq!(node.quorum_size(), 2, "learners don't change quorum");
+        match node.create_append_entries(4) {
+            Some(RaftMessage::AppendEntries { entries, .. }) => assert!(entries.is_empty()),
+            other => panic!("expected AppendEntries, got {:?}", other),
+        }
+
+        // catching up: a rejection walks next_index back to the start
+        let ready = node.step(4, append_rejected(0));
+        match &ready.messages[..] {
+            [(4, RaftMessage::AppendEntries { entries, .. })] => assert_eq!(entries.len(), 1),
+            other => panic!("expected retry to learner, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn learner_does_not_count_toward_commit() {
+        let mut node = leader(RaftConfig::default());
+        node.add_learner(4);
+        node.append_entry(b"x".to_vec());
+
+        node.step(4, append_ok(1));
+        assert_eq!(node.commit_index, 0);
+        node.step(3, append_ok(1));
+        assert_eq!(node.commit_index, 1);
+    }
+
+    #[test]
+    fn learner_receives_heartbeats() {
+        let mut node = leader(RaftConfig::default());
+        node.add_learner(4);
+
+        let ready = node.broadcast_heartbeat();
+        assert!(ready.messages.iter().any(|(to, _)| *to == 4));
+    }
+
+    #[test]
+    fn learner_never_campaigns() {
+        let mut node = RaftNode::new(4, vec![1, 2, 3]);
+        node.add_learner(4);
+        assert!(node.is_learner());
+
+        let ready = node.tick(node.config.election_timeout_max * 5);
+        assert!(ready.messages.is_empty());
+        assert_eq!(node.state, NodeState::Follower);
+    }
+
+    #[test]
+    fn learner_follows_leader_log() {
+        let mut node = RaftNode::new(4, vec![1, 2, 3]);
+        node.add_learner(4);
+
+        let ready = node.step(1, RaftMessage::AppendEntries {
+            term: 1,
+            leader_id: 1,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: vec![LogEntry::new(1, 1, b"x".to_vec())],
+            leader_commit: 1,
+            seq: 0,
+            group_id: None,
+        });
+
+        assert_eq!(node.commit_index, 1);
+        assert_eq!(ready.committed_entries.len(), 1);
+    }
+
+    #[test]
+    fn leader_refuses_to_promote_a_lagging_learner() {
+        let mut node = leader(RaftConfig::default());
+        node.config.max_promotion_lag = 1;
+        node.add_learner(4);
+        for _ in 0..3 {
+            node.append_entry(b"x".to_vec());
+        }
+        let last_index = node.last_log_index();
+
+        assert_eq!(
+            node.promote_learner(4),
+            Err(PromoteError::Lagging { match_index: 0, last_index })
+        );
+        assert!(!node.is_voter(4));
+
+        node.step(4, append_ok(last_index - 1));
+        assert_eq!(node.promote_learner(4), Ok(()));
+        assert!(node.is_voter(4));
+        assert!(node.learner_nodes.is_empty());
+        assert_eq!(node.quorum_size(), 3);
+    }
+
+    #[test]
+    fn only_learners_are_promoted() {
+        let mut node = leader(RaftConfig::default());
+        assert_eq!(node.promote_learner(2), Err(PromoteError::NotLearner { node_id: 2 }));
+        assert_eq!(node.promote_learner(9), Err(PromoteError::NotLearner { node_id: 9 }));
+    }
+
+    #[test]
+    fn follower_promotes_when_told() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.add_learner(4);
+        assert_eq!(node.promote_learner(4), Ok(()));
+        assert_eq!(node.cluster_nodes, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn duplicate_membership_is_ignored() {
+        let mut node = leader(RaftConfig::default());
+        node.add_learner(2);
+        node.add_standby(5);
+        node.add_learner(5);
+
+        assert!(node.learner_nodes.is_empty());
+        assert_eq!(node.standby_nodes, vec![5]);
+    }
+}
+
+// =============================================================================
+// SECTION 21: LOG HINTS ON VOTE RESPONSES
+// =============================================================================
+
+mod vote_hints {
+    use super::*;
+
+    fn vote(commit_index: u64, last_log_index: u64) -> RaftMessage {
+        RaftMessage::VoteResponse { term: 1, vote_granted: true, commit_index, last_log_index, group_id: None }
+    }
+
+    /// candidate for term 1 whose log holds `entries` older entries
+    fn candidate_with_log(entries: u64) -> RaftNode {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        for i in 1..=entries {
+            node.log.push(LogEntry::new(0, i, vec![]));
+        }
+        node.start_election();
+        node
+    }
+
+    #[test]
+    fn responses_carry_responder_log_position() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.log.push(LogEntry::new(1, 1, vec![]));
+        node.log.push(LogEntry::new(1, 2, vec![]));
+        node.commit_index = 1;
+
+        let (resp, _) = node.handle_vote_request(2, 1, 2, 1);
+        match resp {
+            RaftMessage::VoteResponse { commit_index, last_log_index, .. } => {
+                assert_eq!(commit_index, 1);
+                assert_eq!(last_log_index, 2);
+            }
+            other => panic!("expected VoteResponse, got {:?}", other),
+        }
+
+        let (resp, _) = node.handle_prevote_request(3, 1, 2, 1);
+        match resp {
+            RaftMessage::PreVoteResponse { commit_index, last_log_index, .. } => {
+                assert_eq!(commit_index, 1);
+                assert_eq!(last_log_index, 2);
+            }
+            other => panic!("expected PreVoteResponse, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn new_leader_seeds_progress_from_hints() {
+        let mut node = candidate_with_log(10);
+        node.step(2, vote(4, 6));
+
+        assert_eq!(node.state, NodeState::Leader);
+        assert_eq!(node.progress[&2].next_index, 7);
+        assert_eq!(node.progress[&2].match_index, 4);
+        // no hint from node 3: classic initialization
+        assert_eq!(node.progress[&3].next_index, 11);
+        assert_eq!(node.progress[&3].match_index, 0);
+    }
+
+    #[test]
+    fn hints_are_capped_at_leader_log() {
+        let mut node = candidate_with_log(3);
+        node.step(2, vote(5, 8));
+
+        assert_eq!(node.progress[&2].next_index, 4);
+        assert_eq!(node.progress[&2].match_index, 3);
+    }
+
+    #[test]
+    fn first_append_starts_at_hinted_index() {
+        let mut node = candidate_with_log(10);
+        node.step(2, vote(4, 6));
+
+        match node.create_append_entries(2) {
+            Some(RaftMessage::AppendEntries { prev_log_index, entries, .. }) => {
+                assert_eq!(prev_log_index, 6);
+                assert_eq!(entries.len(), 5); // 7..=10 plus the leader's no-op
+            }
+            other => panic!("expected AppendEntries, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn prevote_hints_survive_into_leadership() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        for i in 1..=5 {
+            node.log.push(LogEntry::new(0, i, vec![]));
+        }
+        node.start_prevote();
+        node.step(3, RaftMessage::PreVoteResponse {
+            term: 0,
+            vote_granted: true,
+            commit_index: 2,
+            last_log_index: 2,
+            group_id: None,
+        });
+        assert_eq!(node.state, NodeState::Candidate);
+
+        node.step(3, RaftMessage::VoteResponse {
+            term: 1,
+            vote_granted: true,
+            commit_index: 2,
+            last_log_index: 3,
+            group_id: None,
+        });
+        assert_eq!(node.state, NodeState::Leader);
+        assert_eq!(node.progress[&3].next_index, 4, "the newer vote hint wins");
+        assert_eq!(node.progress[&3].match_index, 2);
+    }
+}
+
+// =============================================================================
+// SECTION 22: LEADER LEASE READS
+// =============================================================================
+
+mod lease_reads {
+    use super::*;
+    use raft_core::ReadMode;
+
+    /// leader with one committed entry in its term, using `mode`
+    fn reader(mode: ReadMode) -> RaftNode {
+        let mut node = committed_leader(RaftConfig { read_mode: mode, ..RaftConfig::default() });
+        node.append_entry(b"x".to_vec());
+        node.ready();
+        node
+    }
+
+    #[test]
+    fn default_mode_is_safe() {
+        assert_eq!(RaftConfig::default().read_mode, ReadMode::Safe);
+    }
+
+    #[test]
+    fn quorum_ack_grants_lease() {
+        let mut node = reader(ReadMode::LeaseBased);
+        assert!(!node.has_valid_lease());
+
+        node.broadcast_heartbeat();
+        node.step(2, append_ok(1));
+        assert!(node.has_valid_lease());
+    }
+
+    #[test]
+    fn lease_read_skips_the_round() {
+        let mut node = reader(ReadMode::LeaseBased);
+        node.broadcast_heartbeat();
+        node.step(2, append_ok(1));
+
+        assert!(node.read_index(b"r".to_vec()));
+        let ready = node.ready();
+        assert_eq!(ready.read_states.len(), 1);
+        assert_eq!(ready.read_states[0].index, 1);
+        assert_eq!(node.pending_read_count(), 0);
+    }
+
+    #[test]
+    fn lease_expires_before_election_timeout() {
+        let mut node = reader(ReadMode::LeaseBased);
+        node.broadcast_heartbeat();
+        node.step(2, append_ok(1));
+
+        let lease = node.config.election_timeout_min - node.config.max_clock_drift;
+        // advance the clock without triggering another heartbeat
+        node.config.heartbeat_interval = u64::MAX;
+        node.tick(lease);
+
+        assert!(!node.has_valid_lease());
+        assert!(node.read_index(b"r".to_vec()));
+        assert!(node.ready().read_states.is_empty(), "falls back to ReadIndex");
+        assert_eq!(node.pending_read_count(), 1);
+    }
+
+    #[test]
+    fn safe_mode_never_uses_lease() {
+        let mut node = reader(ReadMode::Safe);
+        node.broadcast_heartbeat();
+        node.step(2, append_ok(1));
+
+        assert!(!node.has_valid_lease());
+        node.read_index(b"r".to_vec());
+        assert!(node.ready().read_states.is_empty());
+    }
+
+    #[test]
+    fn stepping_down_drops_lease() {
+        let mut node = reader(ReadMode::LeaseBased);
+        node.broadcast_heartbeat();
+        node.step(2, append_ok(1));
+
+        node.become_follower(2);
+        assert!(!node.has_valid_lease());
+    }
+
+    #[test]
+    fn lease_needs_committed_entry_in_term() {
+        let config = RaftConfig { read_mode: ReadMode::LeaseBased, ..RaftConfig::default() };
+        let mut node = RaftNode::with_config(1, vec![1, 2, 3], config);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        node.broadcast_heartbeat();
+        // acked, but the leader's no-op hasn't reached the follower yet
+        node.step(2, append_ok(0));
+
+        assert!(node.has_valid_lease());
+        node.read_index(b"r".to_vec());
+        assert!(node.ready().read_states.is_empty());
+    }
+}
+
+// =============================================================================
+// SECTION 23: INSTALL SNAPSHOT
+// =============================================================================
+
+mod install_snapshot {
+    use super::*;
+    use raft_core::Snapshot;
+
+    /// leader whose log before index 6 only survives in a snapshot
+    fn leader_with_snapshot() -> RaftNode {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.restore_snapshot(Snapshot::new(5, 1, b"state@5".to_vec()));
+        node.current_term = 1;
+        node.log.push(LogEntry::new(1, 6, b"six".to_vec()));
+        node.start_election();
+        node.handle_vote_response(2, true, 2);
+        assert_eq!(node.state, NodeState::Leader);
+        node.ready();
+        node
+    }
+
+    #[test]
+    fn restore_sets_log_offset() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.restore_snapshot(Snapshot::new(5, 2, vec![]));
+
+        assert_eq!(node.last_log_index(), 5);
+        assert_eq!(node.last_log_term(), 2);
+        assert_eq!(node.get_term_at(5), 2);
+        assert_eq!(node.commit_index, 5);
+        assert_eq!(node.last_applied, 5);
+    }
+
+    #[test]
+    fn restore_keeps_matching_suffix() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        for i in 1..=4 {
+            node.log.push(LogEntry::new(1, i, vec![]));
+        }
+        node.restore_snapshot(Snapshot::new(2, 1, vec![]));
+
+        let indices: Vec<u64> = node.log.iter().map(|e| e.index).collect();
+        assert_eq!(indices, vec![3, 4]);
+        assert_eq!(node.last_log_index(), 4);
+    }
+
+    #[test]
+    fn restore_keeps_a_log_that_starts_after_it() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.log.push(LogEntry::new(2, 3, vec![]));
+        node.restore_snapshot(Snapshot::new(2, 1, vec![]));
+
+        assert_eq!(node.first_log_index(), 3);
+        assert_eq!(node.last_log_index(), 3);
+    }
+
+    #[test]
+    fn leader_sends_snapshot_when_entries_are_gone() {
+        let mut node = leader_with_snapshot();
+        node.progress.get_mut(&2).unwrap().next_index = 3;
+
+        match node.create_append_entries(2) {
+            Some(RaftMessage::InstallSnapshot { term, leader_id, snapshot, .. }) => {
+                assert_eq!(term, 2);
+                assert_eq!(leader_id, 1);
+                assert_eq!(snapshot.metadata.last_included_index, 5);
+                assert_eq!(snapshot.data, b"state@5".to_vec());
+            }
+            other => panic!("expected InstallSnapshot, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn leader_appends_right_after_snapshot() {
+        let mut node = leader_with_snapshot();
+        node.progress.get_mut(&2).unwrap().next_index = 6;
+
+        match node.create_append_entries(2) {
+            Some(RaftMessage::AppendEntries { prev_log_index, prev_log_term, entries, .. }) => {
+                assert_eq!(prev_log_index, 5);
+                assert_eq!(prev_log_term, 1, "term of the snapshot's last entry");
+                assert_eq!(entries.len(), 2); // entry 6 and the leader's no-op
+            }
+            other => panic!("expected AppendEntries, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn follower_installs_snapshot() {
+        let mut follower = RaftNode::new(2, vec![1, 2, 3]);
+        follower.current_term = 1;
+        follower.log.push(LogEntry::new(1, 1, vec![]));
+        follower.ready();
+
+        let ready = follower.step(1, RaftMessage::InstallSnapshot {
+            term: 2,
+            leader_id: 1,
+            snapshot: Snapshot::new(5, 1, b"state@5".to_vec()),
+            group_id: None,
+        });
+
+        assert!(follower.log.is_empty());
+        assert_eq!(follower.commit_index, 5);
+        assert_eq!(follower.last_applied, 5);
+        assert_eq!(follower.last_log_index(), 5);
+        assert_eq!(ready.snapshot.as_ref().map(|s| s.data.clone()), Some(b"state@5".to_vec()));
+        assert_eq!(ready.truncate_from, Some(1));
+        assert!(ready.committed_entries.is_empty(), "the snapshot replaces applying 1..=5");
+        assert!(matches!(
+            ready.messages.as_slice(),
+            [(1, RaftMessage::InstallSnapshotResponse { term: 2, match_index: 5, .. })]
+        ));
+    }
+
+    #[test]
+    fn follower_keeps_entries_past_snapshot() {
+        let mut follower = RaftNode::new(2, vec![1, 2, 3]);
+        follower.current_term = 1;
+        for i in 1..=7 {
+            follower.log.push(LogEntry::new(1, i, vec![]));
+        }
+        follower.ready();
+
+        let ready = follower.step(1, RaftMessage::InstallSnapshot {
+            term: 1,
+            leader_id: 1,
+            snapshot: Snapshot::new(5, 1, vec![]),
+            group_id: None,
+        });
+
+        assert_eq!(follower.log.len(), 2);
+        assert_eq!(follower.last_log_index(), 7);
+        // storage is rewritten to match: truncate, then re-persist 6..=7
+        assert_eq!(ready.truncate_from, Some(1));
+        let persisted: Vec<u64> = ready.entries.iter().map(|e| e.index).collect();
+        assert_eq!(persisted, vec![6, 7]);
+    }
+
+    #[test]
+    fn stale_snapshot_is_not_installed() {
+        let mut follower = RaftNode::new(2, vec![1, 2, 3]);
+        follower.current_term = 1;
+        for i in 1..=6 {
+            follower.log.push(LogEntry::new(1, i, vec![]));
+        }
+        follower.commit_index = 6;
+        follower.ready();
+
+        let ready = follower.step(1, RaftMessage::InstallSnapshot {
+            term: 1,
+            leader_id: 1,
+            snapshot: Snapshot::new(4, 1, vec![]),
+            group_id: None,
+        });
+
+        assert!(ready.snapshot.is_none());
+        assert_eq!(follower.log.len(), 6);
+        assert!(matches!(
+            ready.messages.as_slice(),
+            [(1, RaftMessage::InstallSnapshotResponse { match_index: 6, .. })]
+        ));
+    }
+
+    #[test]
+    fn lower_term_snapshot_is_rejected() {
+        let mut follower = RaftNode::new(2, vec![1, 2, 3]);
+        follower.current_term = 3;
+
+        let ready = follower.step(1, RaftMessage::InstallSnapshot {
+            term: 2,
+            leader_id: 1,
+            snapshot: Snapshot::new(5, 1, vec![]),
+            group_id: None,
+        });
+
+        assert!(follower.snapshot.is_none());
+        assert!(matches!(
+            ready.messages.as_slice(),
+            [(1, RaftMessage::InstallSnapshotResponse { term: 3, match_index: 0, .. })]
+        ));
+    }
+
+    #[test]
+    fn response_advances_progress_and_continues_replication() {
+        let mut node = leader_with_snapshot();
+        node.progress.get_mut(&2).unwrap().next_index = 3;
+
+        let ready = node.step(2, RaftMessage::InstallSnapshotResponse { term: 2, match_index: 5, group_id: None });
+
+        assert_eq!(node.progress[&2].match_index, 5);
+        assert_eq!(node.progress[&2].next_index, 6);
+        assert!(matches!(
+            ready.messages.as_slice(),
+            [(2, RaftMessage::AppendEntries { prev_log_index: 5, .. })]
+        ));
+    }
+
+    #[test]
+    fn late_response_never_moves_progress_back() {
+        let mut node = leader_with_snapshot();
+        node.progress.get_mut(&2).unwrap().match_index = 6;
+        node.progress.get_mut(&2).unwrap().next_index = 7;
+
+        node.step(2, RaftMessage::InstallSnapshotResponse { term: 2, match_index: 5, group_id: None });
+
+        assert_eq!(node.progress[&2].match_index, 6);
+        assert_eq!(node.progress[&2].next_index, 7);
+    }
+
+    #[test]
+    fn snapshot_round_trip_between_nodes() {
+        let mut leader = leader_with_snapshot();
+        let mut follower = RaftNode::new(2, vec![1, 2, 3]);
+        leader.progress.get_mut(&2).unwrap().next_index = 1;
+
+        let install = leader.create_append_entries(2).unwrap();
+        let mut inbound = follower.step(1, install).messages;
+        // deliver until both sides go quiet
+        while let Some((_, msg)) = inbound.pop() {
+            for (_, reply) in leader.step(2, msg).messages {
+                inbound.extend(follower.step(1, reply).messages);
+            }
+        }
+
+        assert_eq!(follower.last_log_index(), 7);
+        assert_eq!(follower.get_term_at(6), 1);
+        assert_eq!(leader.progress[&2].match_index, 7);
+    }
+}
+
+// =============================================================================
+// SECTION 24: LOG COMPACTION
+// =============================================================================
+
+mod log_compaction {
+    use super::*;
+    use raft_core::CompactError;
+
+    /// leader with its no-op at 1 and entries 2..=5 committed and applied
+    fn applied_leader() -> RaftNode {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        for i in 0..4 {
+            node.append_entry(vec![i]);
+        }
+        node.handle_append_entries_response(1, true, 2, 5);
+        node.ready();
+        assert_eq!(node.last_applied, 5);
+        node
+    }
+
+    #[test]
+    fn compact_discards_prefix() {
+        let mut node = applied_leader();
+        assert!(node.compact(3, b"state@3".to_vec()));
+
+        assert_eq!(node.first_log_index(), 4);
+        assert_eq!(node.last_log_index(), 5);
+        assert_eq!(node.snapshot_index(), 3);
+        assert_eq!(node.snapshot_term(), 1);
+        assert!(node.get_entry(2).is_none());
+        assert_eq!(node.get_term_at(3), 1);
+        assert_eq!(node.get_term_at(2), 0, "compacted entries have no term");
+    }
+
+    #[test]
+    fn compact_everything_keeps_last_position() {
+        let mut node = applied_leader();
+        assert!(node.compact(5, vec![]));
+
+        assert!(node.log.is_empty());
+        assert_eq!(node.first_log_index(), 6);
+        assert_eq!(node.last_log_index(), 5);
+        assert_eq!(node.last_log_term(), 1);
+
+        let entry = node.append_entry(b"next".to_vec());
+        assert_eq!(entry.index, 6);
+    }
+
+    #[test]
+    fn cannot_compact_unapplied_entries() {
+        let mut node = applied_leader();
+        node.append_entry(b"pending".to_vec());
+
+        assert!(!node.compact(6, vec![]));
+        assert_eq!(node.first_log_index(), 1);
+    }
+
+    #[test]
+    fn cannot_compact_behind_snapshot() {
+        let mut node = applied_leader();
+        assert!(node.compact(4, vec![]));
+        assert!(!node.compact(4, vec![]));
+        assert!(!node.compact(2, vec![]));
+        assert_eq!(node.snapshot_index(), 4);
+    }
+
+    #[test]
+    fn uncompacted_node_starts_at_one() {
+        let node = RaftNode::new(1, vec![1, 2, 3]);
+        assert_eq!(node.first_log_index(), 1);
+        assert_eq!(node.snapshot_index(), 0);
+    }
+
+    #[test]
+    fn lagging_follower_gets_snapshot_after_compaction() {
+        let mut node = applied_leader();
+        node.compact(4, b"state@4".to_vec());
+        node.progress.get_mut(&3).unwrap().next_index = 2;
+
+        assert!(matches!(
+            node.create_append_entries(3),
+            Some(RaftMessage::InstallSnapshot { .. })
+        ));
+    }
+
+    #[test]
+    fn lookups_are_offset_by_the_compacted_prefix() {
+        let mut node = applied_leader();
+        node.append_entry(b"six".to_vec());
+        assert!(node.compact(3, vec![]));
+
+        assert!(node.get_entry(3).is_none());
+        assert_eq!(node.get_entry(4).map(|e| e.command.clone()), Some(vec![2]));
+        assert_eq!(node.get_entry(6).map(|e| e.command.clone()), Some(b"six".to_vec()));
+        assert!(node.get_entry(7).is_none());
+    }
+
+    #[test]
+    fn slowest_follower_is_reported() {
+        let node = applied_leader();
+        assert_eq!(node.slowest_follower(), Some((3, 0)));
+        assert_eq!(RaftNode::new(2, vec![1, 2, 3]).slowest_follower(), None, "followers track no peers");
+    }
+
+    #[test]
+    fn checked_compaction_keeps_what_followers_need() {
+        let mut node = applied_leader();
+        node.handle_append_entries_response(1, true, 3, 3);
+
+        assert_eq!(
+            node.compact_checked(4, vec![], false),
+            Err(CompactError::FollowerBehind { node_id: 3, match_index: 3 })
+        );
+        assert_eq!(node.snapshot_index(), 0);
+        assert_eq!(node.compact_checked(3, vec![], false), Ok(()));
+        assert_eq!(node.snapshot_index(), 3);
+    }
+
+    #[test]
+    fn checked_compaction_can_opt_into_snapshot_catch_up() {
+        let mut node = applied_leader();
+        assert_eq!(node.compact_checked(4, vec![], true), Ok(()));
+        assert_eq!(
+            node.compact_checked(9, vec![], true),
+            Err(CompactError::OutOfRange { index: 9, snapshot_index: 4, last_applied: 5 })
+        );
+    }
+
+    #[test]
+    fn follower_accepts_append_overlapping_its_snapshot() {
+        let mut follower = RaftNode::new(2, vec![1, 2, 3]);
+        follower.restore_snapshot(raft_core::Snapshot::new(3, 1, vec![]));
+
+        // the leader resends 2..=5 from before it learned about our snapshot
+        let entries = (2..=5).map(|i| LogEntry::new(1, i, vec![])).collect();
+        let (response, _) = follower.handle_append_entries(1, 1, 1, 1, entries, 5);
+
+        match response {
+            RaftMessage::AppendEntriesResponse { success, match_index, .. } => {
+                assert!(success);
+                assert_eq!(match_index, 5);
+            }
+            other => panic!("expected AppendEntriesResponse, got {:?}", other),
+        }
+        let indices: Vec<u64> = follower.log.iter().map(|e| e.index).collect();
+        assert_eq!(indices, vec![4, 5]);
+        assert_eq!(follower.commit_index, 5);
+    }
+
+    #[test]
+    fn follower_still_rejects_mismatch_after_snapshot() {
+        let mut follower = RaftNode::new(2, vec![1, 2, 3]);
+        follower.restore_snapshot(raft_core::Snapshot::new(3, 1, vec![]));
+
+        let (response, _) = follower.handle_append_entries(1, 1, 4, 1, vec![], 4);
+        assert!(matches!(response, RaftMessage::AppendEntriesResponse { success: false, .. }));
+    }
+}
+
+// =============================================================================
+// SECTION 25: SNAPSHOT SEALING
+// =============================================================================
+
+mod snapshot_sealing {
+    use super::*;
+    use raft_core::{Snapshot, SnapshotMetadata, SnapshotSealer};
+
+    /// toy sealer: xor "encryption" plus a checksum over key, position and data
+    #[derive(Debug)]
+    struct XorSealer(u8);
+
+    impl XorSealer {
+        fn tag(&self, metadata: &SnapshotMetadata, data: &[u8]) -> u8 {
+            data.iter().fold(
+                self.0 ^ metadata.last_included_index as u8 ^ metadata.last_included_term as u8,
+                |acc, b| acc.rotate_left(1) ^ b,
+            )
+        }
+    }
+
+    impl SnapshotSealer for XorSealer {
+        fn seal(&self, metadata: &SnapshotMetadata, data: &[u8]) -> Vec<u8> {
+            let mut sealed: Vec<u8> = data.iter().map(|b| b ^ self.0).collect();
+            sealed.push(self.tag(metadata, data));
+            sealed
+        }
+
+        fn open(&self, metadata: &SnapshotMetadata, sealed: &[u8]) -> Option<Vec<u8>> {
+            let (tag, body) = sealed.split_last()?;
+            let data: Vec<u8> = body.iter().map(|b| b ^ self.0).collect();
+            (self.tag(metadata, &data) == *tag).then_some(data)
+        }
+    }
+
+    fn sealed_leader() -> RaftNode {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.set_snapshot_sealer(Box::new(XorSealer(0x5a)));
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        for i in 0..3 {
+            node.append_entry(vec![i]);
+        }
+        node.handle_append_entries_response(1, true, 2, 3);
+        node.ready();
+        node
+    }
+
+    fn sealed_follower() -> RaftNode {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.set_snapshot_sealer(Box::new(XorSealer(0x5a)));
+        node
+    }
+
+    #[test]
+    fn compact_stores_sealed_data() {
+        let mut node = sealed_leader();
+        node.compact(3, b"state".to_vec());
+
+        let snapshot = node.snapshot.clone().unwrap();
+        assert_ne!(snapshot.data, b"state".to_vec(), "plaintext never leaves the node");
+        assert_eq!(node.open_snapshot(&snapshot), Some(b"state".to_vec()));
+    }
+
+    #[test]
+    fn sealed_snapshot_installs_on_peer_with_same_key() {
+        let mut leader = sealed_leader();
+        leader.compact(3, b"state".to_vec());
+        leader.progress.get_mut(&2).unwrap().next_index = 1;
+        let install = leader.create_append_entries(2).unwrap();
+
+        let mut follower = sealed_follower();
+        let ready = follower.step(1, install);
+
+        let snapshot = ready.snapshot.expect("installed");
+        assert_eq!(follower.open_snapshot(&snapshot), Some(b"state".to_vec()));
+        assert_eq!(follower.commit_index, 3);
+    }
+
+    #[test]
+    fn tampered_snapshot_is_rejected() {
+        let mut leader = sealed_leader();
+        leader.compact(3, b"state".to_vec());
+        let mut snapshot = leader.snapshot.clone().unwrap();
+        snapshot.data[0] ^= 0xff;
+
+        let mut follower = sealed_follower();
+        let ready = follower.step(1, RaftMessage::InstallSnapshot { term: 1, leader_id: 1, snapshot, group_id: None });
+
+        assert!(ready.snapshot.is_none());
+        assert!(follower.snapshot.is_none());
+        assert_eq!(follower.commit_index, 0);
+        assert!(matches!(
+            ready.messages.as_slice(),
+            [(1, RaftMessage::InstallSnapshotResponse { match_index: 0, .. })]
+        ));
+    }
+
+    #[test]
+    fn signature_covers_log_position() {
+        let mut leader = sealed_leader();
+        leader.compact(3, b"state".to_vec());
+        let mut snapshot = leader.snapshot.clone().unwrap();
+        // same bytes, replayed as if they covered more of the log
+        snapshot.metadata.last_included_index = 9;
+
+        let mut follower = sealed_follower();
+        assert!(!follower.restore_snapshot(snapshot));
+        assert_eq!(follower.last_log_index(), 0);
+    }
+
+    #[test]
+    fn wrong_key_cannot_restore() {
+        let mut leader = sealed_leader();
+        leader.compact(3, b"state".to_vec());
+
+        let mut stranger = RaftNode::new(2, vec![1, 2, 3]);
+        stranger.set_snapshot_sealer(Box::new(XorSealer(0x11)));
+        assert!(!stranger.restore_snapshot(leader.snapshot.clone().unwrap()));
+    }
+
+    #[test]
+    fn without_sealer_data_is_plain() {
+        let node = RaftNode::new(1, vec![1, 2, 3]);
+        let snapshot = Snapshot::new(1, 1, b"plain".to_vec());
+        assert_eq!(node.open_snapshot(&snapshot), Some(b"plain".to_vec()));
+    }
+}
+
+// =============================================================================
+// SECTION 26: SEEDING NEW MEMBERS FROM SNAPSHOTS
+// =============================================================================
+
+mod snapshot_seeding {
+    use super::*;
+
+    /// leader with 10 applied entries, compacted through index 8
+    fn compacted_leader() -> RaftNode {
+        // the leader's no-op is entry 1
+        let mut node = leader_with_log(9, 10);
+        node.compact(8, b"state@8".to_vec());
+        node
+    }
+
+    #[test]
+    fn new_learner_gets_snapshot_immediately() {
+        let mut node = compacted_leader();
+        node.add_learner(4);
+
+        let ready = node.ready();
+        assert!(matches!(
+            ready.messages.as_slice(),
+            [(4, RaftMessage::InstallSnapshot { .. })]
+        ));
+    }
+
+    #[test]
+    fn new_standby_gets_snapshot_immediately() {
+        let mut node = compacted_leader();
+        node.add_standby(5);
+
+        assert!(matches!(
+            node.ready().messages.as_slice(),
+            [(5, RaftMessage::InstallSnapshot { .. })]
+        ));
+    }
+
+    #[test]
+    fn without_snapshot_nothing_is_sent_up_front() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        node.ready();
+
+        node.add_learner(4);
+        assert!(node.ready().messages.is_empty());
+        assert_eq!(node.progress[&4].next_index, node.last_log_index() + 1);
+    }
+
+    #[test]
+    fn progress_reports_snapshot_in_flight() {
+        let mut node = compacted_leader();
+        node.add_learner(4);
+
+        let progress = node.catch_up_progress(4).unwrap();
+        assert!(progress.installing_snapshot);
+        assert_eq!(progress.match_index, 0);
+        assert_eq!(progress.target_index, 10);
+        assert_eq!(progress.percent, 0);
+        assert_eq!(progress.eta_ms, None, "no progress yet, no rate");
+    }
+
+    #[test]
+    fn progress_estimates_eta_from_rate() {
+        let mut node = compacted_leader();
+        node.config.heartbeat_interval = u64::MAX;
+        node.add_learner(4);
+        node.ready();
+
+        // the snapshot (8 of 10 entries) lands 400ms later
+        node.tick(400);
+        node.step(4, RaftMessage::InstallSnapshotResponse { term: 1, match_index: 8, group_id: None });
+
+        let progress = node.catch_up_progress(4).unwrap();
+        assert!(!progress.installing_snapshot);
+        assert_eq!(progress.percent, 80);
+        // 8 entries in 400ms -> 2 more in 100ms
+        assert_eq!(progress.eta_ms, Some(100));
+    }
+
+    #[test]
+    fn caught_up_member_is_done() {
+        let mut node = compacted_leader();
+        node.add_learner(4);
+        node.step(4, RaftMessage::InstallSnapshotResponse { term: 1, match_index: 8, group_id: None });
+        node.handle_append_entries_response(1, true, 4, 10);
+
+        let progress = node.catch_up_progress(4).unwrap();
+        assert_eq!(progress.percent, 100);
+        assert_eq!(progress.eta_ms, Some(0));
+    }
+
+    #[test]
+    fn progress_is_leader_only() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.add_learner(4);
+        assert!(node.catch_up_progress(4).is_none());
+    }
+}
+
+// =============================================================================
+// SECTION 27: MESSAGE CAPTURE AND REPLAY
+// =============================================================================
+
+mod capture_replay {
+    use super::*;
+    use raft_core::capture::{read_capture, replay, Recorder};
+    use raft_core::Ready;
+    use std::collections::VecDeque;
+
+    /// run a 3-node cluster with node 1 recorded until it has elected a
+    /// leader and committed its no-op and one entry; returns (node 1, capture bytes)
+    fn recorded_run() -> (RaftNode, Vec<u8>) {
+        let mut nodes: Vec<RaftNode> = (1..=3).map(|id| RaftNode::new(id, vec![1, 2, 3])).collect();
+        let mut recorder = Recorder::new(Vec::new());
+        let mut wire: VecDeque<(u64, u64, RaftMessage)> = VecDeque::new();
+        let mut proposed = false;
+
+        let push = |wire: &mut VecDeque<_>, from: u64, ready: Ready| {
+            for (to, msg) in ready.messages {
+                wire.push_back((from, to, msg));
+            }
+        };
+
+        for _ in 0..200 {
+            // only node 1's timers run, so it is the one that campaigns
+            let ready = recorder.tick(&mut nodes[0], 10).unwrap();
+            push(&mut wire, 1, ready);
+            while let Some((from, to, msg)) = wire.pop_front() {
+                let ready = if to == 1 {
+                    recorder.step(&mut nodes[0], from, msg).unwrap()
+                } else {
+                    nodes[to as usize - 1].step(from, msg)
+                };
+                push(&mut wire, to, ready);
+            }
+            if nodes[0].state == NodeState::Leader && !proposed {
+                nodes[0].append_entry(b"x".to_vec());
+                proposed = true;
+            }
+            if nodes[0].commit_index >= 2 {
+                break;
+            }
+        }
+        assert_eq!(nodes[0].commit_index, 2, "cluster should commit");
+        let node = nodes.remove(0);
+        (node, recorder.into_inner())
+    }
+
+    #[test]
+    fn capture_is_one_json_line_per_message() {
+        let (_, bytes) = recorded_run();
+        let capture = read_capture(bytes.as_slice()).unwrap();
+
+        assert_eq!(capture.len(), bytes.iter().filter(|&&b| b == b'\n').count());
+        assert!(capture.iter().any(|c| c.from == 1 && matches!(c.msg, RaftMessage::PreVoteRequest { .. })));
+        assert!(capture.iter().any(|c| c.to == 1 && matches!(c.msg, RaftMessage::VoteResponse { .. })));
+        assert!(capture.windows(2).all(|w| w[0].at_ms <= w[1].at_ms), "timestamps are monotonic");
+    }
+
+    #[test]
+    fn replay_reproduces_the_election() {
+        let (live, bytes) = recorded_run();
+        let capture = read_capture(bytes.as_slice()).unwrap();
+
+        let mut fresh = RaftNode::new(1, vec![1, 2, 3]);
+        replay(&mut fresh, &capture);
+
+        assert_eq!(fresh.state, NodeState::Leader);
+        assert_eq!(fresh.current_term, live.current_term);
+    }
+
+    #[test]
+    fn replay_skips_messages_for_other_nodes() {
+        let (_, bytes) = recorded_run();
+        let capture = read_capture(bytes.as_slice()).unwrap();
+
+        // nothing in a node-1 capture is addressed to node 4
+        let mut bystander = RaftNode::new(4, vec![1, 2, 3, 4]);
+        assert!(replay(&mut bystander, &capture).is_empty());
+        assert_eq!(bystander.current_term, 0);
+    }
+
+    #[test]
+    fn malformed_capture_is_an_error() {
+        let err = read_capture(&b"{not json}\n"[..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
+
+// =============================================================================
+// SECTION 28: TEST CLUSTER HARNESS
+// =============================================================================
+
+mod test_cluster {
+    use super::*;
+    use raft_core::testing::TestCluster;
+
+    #[test]
+    fn elect_makes_the_chosen_node_leader() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(2);
+
+        assert_eq!(cluster.leader(), Some(2));
+        for id in [1, 3] {
+            assert_eq!(cluster.node(id).state, NodeState::Follower);
+            assert_eq!(cluster.node(id).current_term, cluster.node(2).current_term);
+        }
+    }
+
+    #[test]
+    fn elect_moves_leadership_to_another_node() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.propose(b"x");
+        cluster.elect(3);
+
+        assert_eq!(cluster.leader(), Some(3));
+        assert_eq!(cluster.node(1).state, NodeState::Follower);
+    }
+
+    #[test]
+    fn propose_commits_and_applies_everywhere() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let index = cluster.propose(b"x");
+
+        cluster.assert_logs_converged();
+        for id in cluster.ids() {
+            assert_eq!(cluster.node(id).commit_index, index);
+            let applied = cluster.applied(id);
+            assert_eq!(applied.last().map(|e| e.command.as_slice()), Some(&b"x"[..]));
+        }
+    }
+
+    #[test]
+    fn isolated_node_catches_up_after_heal() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.isolate(2);
+        let index = cluster.propose(b"y");
+
+        assert_eq!(cluster.node(1).commit_index, index, "majority of two commits");
+        assert!(cluster.node(2).last_log_index() < index);
+        cluster.assert_logs_converged();
+
+        cluster.heal(2);
+        cluster.heartbeat();
+        cluster.assert_logs_converged();
+        assert_eq!(cluster.node(2).commit_index, index);
+    }
+
+    #[test]
+    fn isolated_leader_is_replaced() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.isolate(1);
+        cluster.elect(2);
+        cluster.propose(b"z");
+
+        cluster.heal(1);
+        cluster.heartbeat();
+        assert_eq!(cluster.node(1).state, NodeState::Follower);
+        cluster.assert_logs_converged();
+    }
+
+    #[test]
+    #[should_panic(expected = "differs")]
+    fn diverged_logs_fail_the_convergence_check() {
+        let mut cluster = TestCluster::new(3);
+        cluster.node_mut(3).log.push(LogEntry::new(1, 1, b"rogue".to_vec()));
+        cluster.assert_logs_converged();
+    }
+
+    #[test]
+    #[should_panic(expected = "failed to win")]
+    fn elect_panics_when_the_node_cannot_win() {
+        let mut cluster = TestCluster::new(3);
+        cluster.isolate(1);
+        cluster.elect(1);
+    }
+}
+
+// =============================================================================
+// SECTION 29: LOG BYTE BUDGET
+// =============================================================================
+
+mod log_byte_budget {
+    use super::*;
+
+    /// a follower holding `count` applied 10-byte entries
+    fn follower_with_applied(count: u64, limit: Option<u64>) -> RaftNode {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.config.log_bytes_soft_limit = limit;
+        node.current_term = 1;
+        for i in 1..=count {
+            node.log.push(LogEntry::new(1, i, vec![0; 10]));
+        }
+        node.commit_index = count;
+        node
+    }
+
+    #[test]
+    fn log_bytes_counts_commands_and_headers() {
+        let node = follower_with_applied(3, None);
+        assert_eq!(node.log_bytes(), 3 * (10 + 16));
+        assert_eq!(RaftNode::new(1, vec![1]).log_bytes(), 0);
+    }
+
+    #[test]
+    fn no_limit_never_asks_to_compact() {
+        let mut node = follower_with_applied(100, None);
+        assert!(!node.over_log_budget());
+        assert_eq!(node.ready().compact_to, None);
+    }
+
+    #[test]
+    fn over_budget_asks_to_compact_to_last_applied() {
+        let mut node = follower_with_applied(4, Some(50));
+        assert!(node.over_log_budget());
+
+        let ready = node.ready();
+        assert_eq!(ready.committed_entries.len(), 4);
+        assert_eq!(ready.compact_to, Some(4));
+    }
+
+    #[test]
+    fn request_is_not_repeated_for_the_same_index() {
+        let mut node = follower_with_applied(4, Some(50));
+        assert_eq!(node.ready().compact_to, Some(4));
+        assert!(node.ready().is_empty(), "ignored request is not re-sent every step");
+
+        node.log.push(LogEntry::new(1, 5, vec![0; 10]));
+        node.commit_index = 5;
+        assert_eq!(node.ready().compact_to, Some(5));
+    }
+
+    #[test]
+    fn compacting_brings_the_log_back_under_budget() {
+        let mut node = follower_with_applied(4, Some(50));
+        let index = node.ready().compact_to.unwrap();
+        assert!(node.compact(index, b"state".to_vec()));
+
+        assert_eq!(node.log_bytes(), 0);
+        assert!(!node.over_log_budget());
+    }
+
+    #[test]
+    fn unapplied_entries_do_not_trigger_a_request() {
+        let mut node = follower_with_applied(4, Some(50));
+        node.commit_index = 0;
+        assert!(node.over_log_budget());
+        assert_eq!(node.ready().compact_to, None);
+    }
+}
+
+// =============================================================================
+// SECTION 30: PIPELINED REPLICATION
+// =============================================================================
+
+mod pipelined_replication {
+    use super::*;
+    use raft_core::ProgressState;
+
+    /// (prev_log_index, entry indices) of the appends sent to `peer`
+    fn appends_to(ready: &raft_core::Ready, peer: u64) -> Vec<(u64, Vec<u64>)> {
+        ready
+            .messages
+            .iter()
+            .filter(|(to, _)| *to == peer)
+            .filter_map(|(_, msg)| match msg {
+                RaftMessage::AppendEntries { prev_log_index, entries, .. } => {
+                    Some((*prev_log_index, entries.iter().map(|e| e.index).collect()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn propose(node: &mut RaftNode, command: &[u8]) -> raft_core::Ready {
+        node.append_entry(command.to_vec());
+        node.replicate()
+    }
+
+    #[test]
+    fn proposals_stream_without_waiting_for_acks() {
+        let mut node = caught_up(RaftConfig::default());
+        node.append_entry(b"a".to_vec());
+        let first = propose(&mut node, b"b");
+        let second = propose(&mut node, b"c");
+
+        assert_eq!(appends_to(&first, 2), vec![(1, vec![2, 3])]);
+        assert_eq!(appends_to(&second, 2), vec![(3, vec![4])]);
+        assert_eq!(node.progress[&2].next_index, 5);
+        assert_eq!(node.inflight(2).0, 2);
+    }
+
+    #[test]
+    fn nothing_new_sends_nothing() {
+        let mut node = caught_up(RaftConfig::default());
+        propose(&mut node, b"a");
+        assert!(node.replicate().messages.is_empty());
+    }
+
+    #[test]
+    fn full_window_holds_back_until_acked() {
+        let mut node = caught_up(RaftConfig { max_inflight_msgs: 2, ..RaftConfig::default() });
+        propose(&mut node, b"a");
+        propose(&mut node, b"b");
+        let held = propose(&mut node, b"c");
+        assert!(appends_to(&held, 2).is_empty(), "window of 2 is full");
+        assert_eq!(node.progress[&2].next_index, 4);
+
+        node.step(2, append_ok(2));
+        assert_eq!(node.inflight(2).0, 1);
+        assert_eq!(appends_to(&node.replicate(), 2), vec![(3, vec![4])]);
+    }
+
+    #[test]
+    fn byte_window_limits_outstanding_data() {
+        let config = RaftConfig { max_inflight_bytes: Some(40), ..RaftConfig::default() };
+        let mut node = caught_up(config);
+        propose(&mut node, &[0; 30]);
+        assert_eq!(node.inflight(2), (1, 46));
+
+        let held = propose(&mut node, b"x");
+        assert!(appends_to(&held, 2).is_empty());
+    }
+
+    #[test]
+    fn late_ack_does_not_rewind_next_index() {
+        let mut node = caught_up(RaftConfig::default());
+        propose(&mut node, b"a");
+        propose(&mut node, b"b");
+
+        node.step(2, append_ok(2));
+        assert_eq!(node.progress[&2].match_index, 2);
+        assert_eq!(node.progress[&2].next_index, 4);
+
+        node.step(2, append_ok(3));
+        assert_eq!(node.inflight(2), (0, 0));
+    }
+
+    #[test]
+    fn rejection_drops_the_window_and_probes_before_the_oldest() {
+        let mut node = caught_up(RaftConfig::default());
+        for i in 0..3u8 {
+            node.append_entry(vec![i]);
+        }
+        node.progress.get_mut(&2).unwrap().next_index = 4;
+        propose(&mut node, b"d");
+        propose(&mut node, b"e");
+        assert_eq!(node.inflight(2).0, 2);
+
+        let ready = node.step(2, append_rejected(0));
+
+        assert_eq!(node.progress[&2].state, ProgressState::Probe);
+        assert_eq!(node.inflight(2).0, 1, "only the retry probe is outstanding");
+        assert_eq!(node.progress[&2].next_index, 3);
+        assert_eq!(appends_to(&ready, 2), vec![(2, vec![3, 4, 5, 6])]);
+    }
+
+    #[test]
+    fn throttled_follower_gets_one_message_at_a_time() {
+        let mut node = caught_up(RaftConfig::default());
+        node.step(2, append_backoff(1));
+
+        propose(&mut node, b"a");
+        let held = propose(&mut node, b"b");
+        assert!(appends_to(&held, 2).is_empty());
+        assert_eq!(appends_to(&held, 3), vec![(2, vec![3])]);
+    }
+
+    #[test]
+    fn new_term_clears_the_windows() {
+        let mut node = caught_up(RaftConfig::default());
+        propose(&mut node, b"a");
+        node.become_follower(2);
+        node.start_election();
+        node.handle_vote_response(3, true, 2);
+
+        assert_eq!(node.inflight(2), (0, 0));
+    }
+}
+
+// =============================================================================
+// SECTION 31: FOLLOWER PROGRESS STATES
+// =============================================================================
+
+mod progress_states {
+    use super::*;
+    use raft_core::{ProgressState, Snapshot};
+
+    fn sent_to(ready: &raft_core::Ready, peer: u64) -> usize {
+        ready.messages.iter().filter(|(to, _)| *to == peer).count()
+    }
+
+    #[test]
+    fn new_leader_probes_every_peer() {
+        let node = leader(RaftConfig::default());
+        for peer in [2, 3] {
+            let progress = &node.progress[&peer];
+            assert_eq!(progress.state, ProgressState::Probe);
+            assert_eq!(progress.next_index, 1);
+            assert_eq!(progress.match_index, 0);
+            assert!(!progress.is_paused());
+        }
+    }
+
+    #[test]
+    fn first_accepted_append_ends_the_probe() {
+        let mut node = leader(RaftConfig::default());
+        node.step(2, append_ok(1));
+        assert_eq!(node.progress[&2].state, ProgressState::Replicate);
+        assert_eq!(node.progress[&3].state, ProgressState::Probe);
+    }
+
+    #[test]
+    fn rejection_skips_back_to_the_followers_last_index() {
+        let mut node = leader(RaftConfig::default());
+        for _ in 0..10 {
+            node.append_entry(b"x".to_vec());
+        }
+        node.progress.get_mut(&2).unwrap().next_index = 12;
+        node.step(2, append_rejected(3));
+        assert_eq!(node.progress[&2].next_index, 4);
+
+        // older followers send no hint: back off one entry
+        node.step(2, append_rejected(0));
+        assert_eq!(node.progress[&2].next_index, 3);
+    }
+
+    #[test]
+    fn follower_reports_where_its_log_ends_on_mismatch() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.handle_append_entries(1, 1, 0, 0, vec![LogEntry::new(1, 1, vec![]), LogEntry::new(1, 2, vec![])], 0);
+        let (resp, _) = node.handle_append_entries(2, 1, 7, 2, vec![], 0);
+        match resp {
+            RaftMessage::AppendEntriesResponse { success, match_index, .. } => {
+                assert!(!success);
+                assert_eq!(match_index, 2);
+            }
+            other => panic!("expected AppendEntriesResponse, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lagging_follower_is_repaired_by_the_election_alone() {
+        let mut cluster = raft_core::testing::TestCluster::new(3);
+        cluster.elect(1);
+        cluster.isolate(3);
+        for i in 0..5u8 {
+            cluster.propose(&[i]);
+        }
+        cluster.heal(3);
+        cluster.elect(2);
+        cluster.assert_logs_converged();
+        assert_eq!(cluster.node(3).last_log_index(), cluster.node(2).last_log_index());
+    }
+
+    #[test]
+    fn rejection_probes_one_message_at_a_time() {
+        let mut node = leader(RaftConfig::default());
+        node.append_entry(b"a".to_vec());
+        node.append_entry(b"b".to_vec());
+        node.step(2, append_rejected(0));
+        assert_eq!(node.progress[&2].state, ProgressState::Probe);
+        assert!(node.progress[&2].is_paused(), "the retry probe is outstanding");
+
+        node.append_entry(b"c".to_vec());
+        assert_eq!(sent_to(&node.replicate(), 2), 0);
+        assert_eq!(node.progress[&2].next_index, 1, "probing does not move next_index");
+    }
+
+    #[test]
+    fn acceptance_resumes_pipelining() {
+        let mut node = leader(RaftConfig::default());
+        node.append_entry(b"a".to_vec());
+        node.step(2, append_rejected(0));
+        node.step(2, append_ok(1));
+
+        let progress = &node.progress[&2];
+        assert_eq!(progress.state, ProgressState::Replicate);
+        assert_eq!(progress.match_index, 1);
+        assert!(!progress.is_paused());
+    }
+
+    #[test]
+    fn late_acceptance_never_moves_match_back() {
+        let mut node = leader(RaftConfig::default());
+        node.append_entry(b"a".to_vec());
+        node.append_entry(b"b".to_vec());
+        node.step(2, append_ok(3));
+        node.step(2, append_ok(1));
+
+        let progress = &node.progress[&2];
+        assert_eq!(progress.match_index, 3);
+        assert_eq!(progress.next_index, 4);
+    }
+
+    #[test]
+    fn snapshot_pauses_until_answered() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.restore_snapshot(Snapshot::new(5, 1, b"state@5".to_vec()));
+        node.current_term = 1;
+        node.log.push(LogEntry::new(1, 6, b"six".to_vec()));
+        node.start_election();
+        node.handle_vote_response(2, true, 2);
+        node.progress.get_mut(&2).unwrap().next_index = 3;
+
+        assert!(node.send_append(2));
+        assert_eq!(node.progress[&2].state, ProgressState::Snapshot);
+        assert!(!node.send_append(2), "one snapshot at a time");
+
+        node.step(2, RaftMessage::InstallSnapshotResponse { term: 2, match_index: 5, group_id: None });
+        assert_ne!(node.progress[&2].state, ProgressState::Snapshot);
+        assert_eq!(node.progress[&2].match_index, 5);
+    }
+
+    #[test]
+    fn responses_record_activity_and_backoff() {
+        let mut node = leader(RaftConfig::default());
+        node.tick(20);
+        assert_eq!(node.progress[&2].last_active_ms, None);
+
+        node.step(2, append_backoff(0));
+
+        assert_eq!(node.progress[&2].last_active_ms, Some(20));
+        assert!(node.progress[&2].throttled);
+        assert_eq!(node.progress[&3].last_active_ms, None);
+    }
+
+    #[test]
+    fn stepping_down_and_winning_again_starts_fresh() {
+        let mut node = leader(RaftConfig::default());
+        node.step(2, append_rejected(0));
+        node.become_follower(2);
+        node.start_election();
+        node.handle_vote_response(3, true, 2);
+
+        assert_eq!(node.progress[&2].state, ProgressState::Probe);
+        assert_eq!(node.progress[&2].inflights.count(), 0);
+        assert_eq!(node.progress[&2].last_active_ms, None);
+    }
+}
+
+// =============================================================================
+// SECTION 32: REJOIN HANDSHAKE
+// =============================================================================
+
+mod rejoin_handshake {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::{RejoinError, Snapshot};
+
+    /// put `id` back into the cluster as if it restarted holding `term`,
+    /// `snapshot` and `log`, and deliver its handshake
+    fn restart(cluster: &mut TestCluster, id: u64, config: RaftConfig, term: u64, snapshot: Option<Snapshot>, log: Vec<LogEntry>) {
+        let mut node = RaftNode::with_config(id, cluster.ids(), config);
+        node.current_term = term;
+        node.log = log;
+        if let Some(snapshot) = snapshot {
+            node.restore_snapshot(snapshot);
+        }
+        assert!(node.begin_rejoin());
+        *cluster.node_mut(id) = node;
+        cluster.tick(0);
+    }
+
+    #[test]
+    fn a_verified_node_follows_the_leader() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.propose(b"x");
+        let (term, log) = (cluster.node(3).current_term, cluster.node(3).log.clone());
+
+        restart(&mut cluster, 3, RaftConfig::default(), term, None, log);
+        assert!(!cluster.node(3).is_rejoining());
+        assert_eq!(cluster.node(3).quarantine(), None);
+        assert_eq!(cluster.node(3).leader_id, Some(1));
+
+        cluster.propose(b"y");
+        cluster.assert_logs_converged();
+    }
+
+    #[test]
+    fn a_rejoining_node_takes_no_part_in_elections() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let term = cluster.node(3).current_term;
+        let mut node = RaftNode::new(3, cluster.ids());
+        node.current_term = term;
+        node.log = cluster.node(3).log.clone();
+        node.begin_rejoin();
+
+        let request = RaftMessage::VoteRequest {
+            term: term + 1,
+            candidate_id: 2,
+            last_log_index: 10,
+            last_log_term: term,
+            transfer: false,
+            group_id: None,
+        };
+        let ready = node.step(2, request);
+        assert!(ready.messages.iter().all(|(_, msg)| matches!(msg, RaftMessage::RejoinRequest { .. })));
+        assert_eq!(node.current_term, term);
+        assert_eq!(node.voted_for, None);
+    }
+
+    #[test]
+    fn a_log_from_a_later_term_is_refused() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.propose(b"x");
+        let leader_term = cluster.node(1).current_term;
+        let last = cluster.node(3).last_log_index();
+
+        let request = RaftMessage::RejoinRequest {
+            cluster_id: None,
+            last_log_index: last + 1,
+            last_log_term: leader_term + 4,
+            snapshot_index: 0,
+            group_id: None,
+        };
+        let ready = cluster.node_mut(1).step(3, request);
+        let refusal = RejoinError::LogAhead { last_log_index: last + 1, last_log_term: leader_term + 4 };
+        assert!(ready.messages.iter().any(|(to, msg)| {
+            *to == 3 && matches!(msg, RaftMessage::RejoinResponse { refusal: Some(r), .. } if *r == refusal)
+        }));
+        assert!(refusal.to_string().contains("split history"));
+        assert_eq!(cluster.node(1).current_term, leader_term);
+    }
+
+    #[test]
+    fn a_deposed_leaders_answer_is_ignored() {
+        let mut node = RaftNode::new(3, vec![1, 2, 3]);
+        node.current_term = 5;
+        node.log.push(LogEntry::new(5, 1, b"x".to_vec()));
+        assert!(node.begin_rejoin());
+
+        // a leader from term 4 hasn't seen entry 1, and would refuse us
+        let stale = RaftMessage::RejoinResponse {
+            term: 4,
+            refusal: Some(RejoinError::LogAhead { last_log_index: 1, last_log_term: 5 }),
+            group_id: None,
+        };
+        node.step(1, stale);
+        assert!(node.is_rejoining());
+        assert_eq!(node.quarantine(), None);
+        assert_eq!(node.leader_id, None);
+
+        let current = RaftMessage::RejoinResponse { term: 5, refusal: None, group_id: None };
+        node.step(2, current);
+        assert!(!node.is_rejoining());
+        assert_eq!(node.leader_id, Some(2));
+        assert_eq!(node.current_term, 5);
+    }
+
+    #[test]
+    fn entries_past_the_leaders_own_in_its_term_are_ahead() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.propose(b"x");
+        let term = cluster.node(1).current_term;
+        let mut log = cluster.node(3).log.clone();
+        let last = log.last().unwrap().index;
+        log.push(LogEntry::new(term, last + 1, b"forked".to_vec()));
+
+        restart(&mut cluster, 3, RaftConfig::default(), term, None, log);
+        assert_eq!(cluster.node(3).quarantine(), Some(RejoinError::LogAhead { last_log_index: last + 1, last_log_term: term }));
+    }
+
+    #[test]
+    fn a_snapshot_past_the_leaders_log_is_quarantined() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let term = cluster.node(1).current_term;
+        let leader_last_index = cluster.node(1).last_log_index();
+
+        restart(&mut cluster, 3, RaftConfig::default(), term, Some(Snapshot::new(50, term, Vec::new())), Vec::new());
+        assert_eq!(
+            cluster.node(3).quarantine(),
+            Some(RejoinError::SnapshotAhead { snapshot_index: 50, leader_last_index })
+        );
+    }
+
+    #[test]
+    fn data_from_another_cluster_is_quarantined() {
+        let mut cluster = TestCluster::new(3);
+        for id in cluster.ids() {
+            cluster.node_mut(id).config.cluster_id = Some(7);
+        }
+        cluster.elect(1);
+        let term = cluster.node(3).current_term;
+
+        let other = RaftConfig::builder().cluster_id(8).build().unwrap();
+        restart(&mut cluster, 3, other, term, None, Vec::new());
+        assert_eq!(cluster.node(3).quarantine(), Some(RejoinError::ClusterMismatch { local: 8, cluster: 7 }));
+    }
+
+    #[test]
+    fn without_a_leader_the_node_joins_after_an_election_timeout() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.isolate(1);
+        let (term, log) = (cluster.node(3).current_term, cluster.node(3).log.clone());
+
+        restart(&mut cluster, 3, RaftConfig::default(), term, None, log);
+        assert!(cluster.node(3).is_rejoining());
+        let timeout = cluster.node(3).config.election_timeout_max;
+        cluster.tick(timeout);
+        assert!(!cluster.node(3).is_rejoining());
+        assert_eq!(cluster.node(3).quarantine(), None);
+    }
+
+    #[test]
+    fn a_node_with_nothing_stored_skips_the_handshake() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        assert!(!node.begin_rejoin());
+        assert!(!node.is_rejoining());
+    }
+}
+
+// =============================================================================
+// SECTION 33: LEADER NO-OP
+// =============================================================================
+
+mod leader_noop {
+    use super::*;
+    use raft_core::testing::TestCluster;
+
+    #[test]
+    fn new_leader_appends_an_empty_entry_in_its_term() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+
+        assert_eq!(node.last_log_index(), 1);
+        let noop = node.get_entry(1).unwrap();
+        assert_eq!(noop.term, node.current_term);
+        assert!(noop.command.is_empty());
+
+        let ready = node.ready();
+        assert_eq!(ready.entries.len(), 1, "the no-op must be persisted");
+    }
+
+    #[test]
+    fn noop_is_replicated_to_followers() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+
+        match node.create_append_entries(2) {
+            Some(RaftMessage::AppendEntries { entries, .. }) => {
+                assert_eq!(entries.len(), 1);
+                assert!(entries[0].command.is_empty());
+            }
+            other => panic!("expected AppendEntries, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn noop_commits_entries_from_earlier_terms() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.current_term = 1;
+        node.log.push(LogEntry::new(1, 1, b"old".to_vec()));
+        node.log.push(LogEntry::new(1, 2, b"older".to_vec()));
+        node.start_election();
+        node.handle_vote_response(2, true, 2);
+        assert_eq!(node.last_log_index(), 3);
+
+        // a majority holding only the old entries is not enough
+        node.handle_append_entries_response(2, true, 2, 2);
+        assert_eq!(node.commit_index, 0);
+
+        node.handle_append_entries_response(2, true, 2, 3);
+        assert_eq!(node.commit_index, 3);
+    }
+
+    #[test]
+    fn election_alone_commits_the_noop_everywhere() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+
+        for id in cluster.ids() {
+            assert_eq!(cluster.node(id).commit_index, 1);
+        }
+        cluster.assert_logs_converged();
+    }
+}
+
+// =============================================================================
+// SECTION 34: ASYMMETRIC PARTITIONS
+// =============================================================================
+
+mod asymmetric_partitions {
+    use super::*;
+    use raft_core::testing::TestCluster;
+
+    #[test]
+    fn leader_that_never_hears_back_cannot_commit() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let committed = cluster.node(1).commit_index;
+        cluster.cut(2, 1);
+        cluster.cut(3, 1);
+
+        let index = cluster.propose(b"x");
+
+        // followers got the entry, but no acknowledgement reached the leader
+        assert_eq!(cluster.node(2).last_log_index(), index);
+        assert_eq!(cluster.node(1).commit_index, committed);
+
+        cluster.restore(2, 1);
+        cluster.heartbeat();
+        assert_eq!(cluster.node(1).commit_index, index);
+    }
+
+    #[test]
+    fn heartbeats_still_hold_off_elections_without_responses() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.cut(2, 1);
+        cluster.cut(3, 1);
+
+        for _ in 0..100 {
+            cluster.tick(10);
+        }
+        assert_eq!(cluster.leader(), Some(1));
+        assert_eq!(cluster.node(2).state, NodeState::Follower);
+    }
+
+    #[test]
+    fn follower_cut_off_from_the_leader_cannot_disrupt_it() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let term = cluster.node(1).current_term;
+        // node 3 hears nothing from the leader but can still talk to everyone
+        cluster.cut(1, 3);
+
+        for _ in 0..100 {
+            cluster.tick(10);
+        }
+
+        // its pre-votes are refused by node 2, which still hears the leader
+        assert_eq!(cluster.leader(), Some(1));
+        assert_eq!(cluster.node(1).current_term, term);
+        assert_ne!(cluster.node(3).state, NodeState::Leader);
+    }
+
+    #[test]
+    fn one_way_cut_only_drops_one_direction() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.cut(1, 2);
+
+        let index = cluster.propose(b"x");
+        assert!(cluster.node(2).last_log_index() < index);
+        assert_eq!(cluster.node(1).commit_index, index, "node 3 completes the quorum");
+
+        cluster.heal_all();
+        cluster.heartbeat();
+        cluster.assert_logs_converged();
+    }
+}
+
+// =============================================================================
+// SECTION 35: TYPED LOG ENTRIES
+// =============================================================================
+
+mod entry_types {
+    use super::*;
+    use raft_core::EntryType;
+
+    #[test]
+    fn new_entries_are_normal() {
+        let entry = LogEntry::new(1, 1, b"cmd".to_vec());
+        assert_eq!(entry.entry_type, EntryType::Normal);
+    }
+
+    #[test]
+    fn leader_noop_is_typed() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+
+        assert_eq!(node.get_entry(1).unwrap().entry_type, EntryType::NoOp);
+        assert_eq!(node.append_entry(b"x".to_vec()).entry_type, EntryType::Normal);
+    }
+
+    #[test]
+    fn config_entries_share_the_log() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        node.append_entry(b"x".to_vec());
+        let config = node.append_entry_of_type(EntryType::Config, b"add 4".to_vec());
+
+        assert_eq!(config.index, 3);
+        assert_eq!(config.entry_type, EntryType::Config);
+    }
+
+    #[test]
+    fn normal_entries_serialize_as_before() {
+        let json = serde_json::to_string(&LogEntry::new(1, 2, vec![7])).unwrap();
+        assert_eq!(json, r#"{"term":1,"index":2,"command":[7]}"#);
+
+        let old: LogEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(old.entry_type, EntryType::Normal);
+    }
+
+    #[test]
+    fn typed_entries_round_trip() {
+        let noop = LogEntry::with_type(2, 5, EntryType::NoOp, Vec::new());
+        let json = serde_json::to_string(&noop).unwrap();
+        let back: LogEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.entry_type, EntryType::NoOp);
+        assert_eq!((back.term, back.index), (2, 5));
+    }
+
+    #[test]
+    fn entry_types_survive_replication() {
+        let mut leader = RaftNode::new(1, vec![1, 2, 3]);
+        leader.start_election();
+        leader.handle_vote_response(1, true, 2);
+        leader.append_entry_of_type(EntryType::Config, b"add 4".to_vec());
+
+        let mut follower = RaftNode::new(2, vec![1, 2, 3]);
+        follower.step(1, leader.create_append_entries(2).unwrap());
+
+        assert_eq!(follower.get_entry(1).unwrap().entry_type, EntryType::NoOp);
+        assert_eq!(follower.get_entry(2).unwrap().entry_type, EntryType::Config);
+    }
+}
+
+// =============================================================================
+// SECTION 36: CLIENT SESSIONS
+// =============================================================================
+
+mod client_sessions {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::Dedup;
+
+    fn commit_all(node: &mut RaftNode) -> Vec<LogEntry> {
+        node.commit_index = node.last_log_index();
+        node.get_entries_to_apply()
+    }
+
+    #[test]
+    fn register_session_returns_entry_index() {
+        let mut node = leader(RaftConfig::default());
+        let client = node.register_session();
+
+        assert_eq!(client, node.last_log_index());
+        commit_all(&mut node);
+        assert_eq!(node.sessions.last_sequence(client), Some(0));
+    }
+
+    #[test]
+    fn retried_command_is_marked_duplicate() {
+        let mut node = leader(RaftConfig::default());
+        let client = node.register_session();
+        node.append_client_entry(client, 1, b"set x 1".to_vec());
+        node.append_client_entry(client, 1, b"set x 1".to_vec());
+        node.append_client_entry(client, 2, b"set x 2".to_vec());
+
+        let dedup: Vec<_> = commit_all(&mut node).iter().filter_map(|e| e.dedup).collect();
+        assert_eq!(dedup, vec![Dedup::Fresh, Dedup::Duplicate, Dedup::Fresh]);
+        assert_eq!(node.sessions.last_sequence(client), Some(2));
+    }
+
+    #[test]
+    fn unregistered_client_is_flagged() {
+        let mut node = leader(RaftConfig::default());
+        node.append_client_entry(42, 1, b"set x 1".to_vec());
+
+        let applied = commit_all(&mut node);
+        assert_eq!(applied.last().unwrap().dedup, Some(Dedup::UnknownSession));
+    }
+
+    #[test]
+    fn plain_entries_carry_no_dedup() {
+        let mut node = leader(RaftConfig::default());
+        node.append_entry(b"set x 1".to_vec());
+
+        assert!(commit_all(&mut node).iter().all(|e| e.dedup.is_none()));
+        assert!(node.sessions.is_empty());
+    }
+
+    #[test]
+    fn sessions_survive_compaction_and_restore() {
+        let mut node = leader(RaftConfig::default());
+        let client = node.register_session();
+        node.append_client_entry(client, 1, b"a".to_vec());
+        commit_all(&mut node);
+        node.append_client_entry(client, 2, b"b".to_vec());
+        commit_all(&mut node);
+
+        // compacting behind last_applied snapshots the table as of that index
+        assert!(node.compact(3, Vec::new()));
+        let snapshot = node.snapshot.clone().unwrap();
+        assert_eq!(snapshot.sessions.last_sequence(client), Some(1));
+
+        let mut restored = RaftNode::new(2, vec![1, 2]);
+        assert!(restored.restore_snapshot(snapshot));
+        assert_eq!(restored.sessions.last_sequence(client), Some(1));
+    }
+
+    #[test]
+    fn retry_after_failover_is_not_applied_twice() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let client = cluster.node_mut(1).register_session();
+        cluster.node_mut(1).append_client_entry(client, 1, b"set x 1".to_vec());
+        cluster.propose(b"set y 1");
+
+        // the client never heard back and retries with the new leader
+        cluster.isolate(1);
+        cluster.elect(2);
+        cluster.node_mut(2).append_client_entry(client, 1, b"set x 1".to_vec());
+        cluster.propose(b"set y 2");
+
+        let retries: Vec<_> = cluster
+            .applied(3)
+            .iter()
+            .filter(|e| e.command == b"set x 1")
+            .map(|e| e.dedup)
+            .collect();
+        assert_eq!(retries, vec![Some(Dedup::Fresh), Some(Dedup::Duplicate)]);
+    }
+
+    #[test]
+    fn session_metadata_round_trips_through_serde() {
+        let mut node = leader(RaftConfig::default());
+        node.append_client_entry(7, 3, b"cmd".to_vec());
+        let json = serde_json::to_string(node.log.last().unwrap()).unwrap();
+        let back: LogEntry = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(back.client.map(|c| (c.client_id, c.sequence)), Some((7, 3)));
+        assert!(!json.contains("dedup"));
+    }
+}
+
+// =============================================================================
+// SECTION 37: HEARTBEAT FAST PATH
+// =============================================================================
+
+mod heartbeat_fast_path {
+    use super::*;
+    use raft_core::ReadMode;
+
+    #[test]
+    fn one_heartbeat_per_peer() {
+        let mut node = leader(RaftConfig::default());
+        node.add_standby(4);
+        node.add_learner(5);
+
+        let ready = node.broadcast_heartbeat();
+        let mut targets: Vec<u64> = ready.messages.iter().map(|(to, _)| *to).collect();
+        targets.sort();
+        assert_eq!(targets, vec![2, 3, 4, 5]);
+        assert!(ready.messages.iter().all(|(_, msg)| matches!(msg, RaftMessage::Heartbeat { .. })));
+    }
+
+    #[test]
+    fn lease_round_forgets_previous_acks() {
+        let config = RaftConfig { read_mode: ReadMode::LeaseBased, ..RaftConfig::default() };
+        let mut node = leader(config);
+        node.handle_append_entries_response(1, true, 1, 2);
+        node.broadcast_heartbeat();
+        node.step(2, append_ok(1));
+        assert!(node.has_valid_lease());
+
+        // let the lease lapse, then start a round nobody has answered yet
+        node.config.heartbeat_interval = u64::MAX;
+        node.tick(node.config.election_timeout_min);
+        node.broadcast_heartbeat();
+        assert!(!node.has_valid_lease());
+    }
+}
+
+// =============================================================================
+// SECTION 38: PROPOSALS
+// =============================================================================
+
+mod proposals {
+    use super::*;
+    use raft_core::ProposeError;
+
+    #[test]
+    fn leader_accepts_and_returns_index() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+
+        assert_eq!(node.propose(b"set x 1".to_vec()), Ok(2));
+        assert_eq!(node.get_entry(2).unwrap().command, b"set x 1");
+    }
+
+    #[test]
+    fn follower_refuses_without_touching_its_log() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+
+        let err = node.propose(b"set x 1".to_vec()).unwrap_err();
+        assert!(matches!(err, ProposeError::NotLeader { .. }));
+        assert!(node.log.is_empty());
+    }
+
+    #[test]
+    fn candidate_refuses() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+
+        assert!(node.propose(b"x".to_vec()).is_err());
+    }
+
+    #[test]
+    fn error_describes_itself() {
+        let err = ProposeError::NotLeader { leader_hint: Some(3) };
+        assert_eq!(err.to_string(), "not the leader (try node 3)");
+        let unknown = ProposeError::NotLeader { leader_hint: None };
+        assert_eq!(unknown.to_string(), "not the leader (leader unknown)");
+    }
+}
+
+// =============================================================================
+// SECTION 39: COMMIT PROOFS
+// =============================================================================
+
+mod commit_proofs {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::{CommitProof, CommitStatus};
+
+    #[test]
+    fn proof_only_for_committed_entries() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let index = cluster.propose(b"set x 1");
+        let leader = cluster.node_mut(1);
+        let pending = leader.propose(b"set x 2".to_vec()).unwrap();
+
+        assert_eq!(leader.commit_proof(index), Some(CommitProof { term: leader.current_term, index }));
+        assert_eq!(leader.commit_proof(pending), None);
+        assert_eq!(leader.commit_proof(0), None);
+    }
+
+    #[test]
+    fn committed_proof_verifies_everywhere() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let index = cluster.propose(b"set x 1");
+        let proof = cluster.node(1).commit_proof(index).unwrap();
+
+        for id in cluster.ids() {
+            assert_eq!(cluster.node(id).verify_commit(proof), CommitStatus::Committed);
+        }
+    }
+
+    #[test]
+    fn truncated_suffix_reads_lost() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.propose(b"a");
+
+        // the old leader appends an entry nobody else will ever see
+        cluster.isolate(1);
+        let orphan = cluster.node_mut(1).propose(b"orphan".to_vec()).unwrap();
+        let orphan_term = cluster.node(1).current_term;
+        assert_eq!(
+            cluster.node(1).verify_commit(CommitProof { term: orphan_term, index: orphan }),
+            CommitStatus::Uncommitted
+        );
+
+        // a new leader commits something else at that index
+        cluster.elect(2);
+        cluster.propose(b"b");
+        cluster.heal(1);
+        cluster.heartbeat();
+
+        let proof = CommitProof { term: orphan_term, index: orphan };
+        assert_eq!(cluster.node(2).verify_commit(proof), CommitStatus::Lost);
+        assert_eq!(cluster.node(1).verify_commit(proof), CommitStatus::Lost);
+    }
+
+    #[test]
+    fn beyond_our_log_is_unknown() {
+        let node = RaftNode::new(2, vec![1, 2, 3]);
+        assert_eq!(node.verify_commit(CommitProof { term: 1, index: 5 }), CommitStatus::Unknown);
+    }
+
+    #[test]
+    fn compacted_positions_cannot_be_checked() {
+        let mut node = RaftNode::new(1, vec![1]);
+        node.start_election();
+        node.become_leader();
+        node.append_entry(b"a".to_vec());
+        node.append_entry(b"b".to_vec());
+        node.commit_index = 3;
+        node.get_entries_to_apply();
+        assert!(node.compact(2, Vec::new()));
+
+        assert_eq!(node.verify_commit(CommitProof { term: 1, index: 1 }), CommitStatus::Compacted);
+        assert_eq!(node.commit_proof(1), None);
+        // the snapshot's own position still has a known term
+        assert_eq!(node.verify_commit(CommitProof { term: 1, index: 2 }), CommitStatus::Committed);
+    }
+}
+
+// =============================================================================
+// SECTION 40: LEADER TRACKING
+// =============================================================================
+
+mod leader_tracking {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::ProposeError;
+
+    #[test]
+    fn everyone_learns_the_leader() {
+        let mut cluster = TestCluster::new(3);
+        assert!(cluster.ids().iter().all(|&id| cluster.node(id).leader_id.is_none()));
+
+        cluster.elect(2);
+        for id in cluster.ids() {
+            assert_eq!(cluster.node(id).leader_id, Some(2));
+        }
+    }
+
+    #[test]
+    fn followers_hint_at_the_leader() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+
+        let err = cluster.node_mut(3).propose(b"x".to_vec()).unwrap_err();
+        assert_eq!(err, ProposeError::NotLeader { leader_hint: Some(1) });
+    }
+
+    #[test]
+    fn elections_forget_the_leader() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+
+        let node = cluster.node_mut(2);
+        node.start_prevote();
+        assert_eq!(node.leader_id, None);
+    }
+
+    #[test]
+    fn higher_term_clears_then_relearns() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.handle_append_entries(1, 1, 0, 0, vec![], 0);
+        assert_eq!(node.leader_id, Some(1));
+
+        node.handle_vote_request(2, 3, 0, 0);
+        assert_eq!(node.current_term, 2);
+        assert_eq!(node.leader_id, None);
+
+        node.handle_append_entries(2, 3, 0, 0, vec![], 0);
+        assert_eq!(node.leader_id, Some(3));
+    }
+
+    #[test]
+    fn stale_leader_is_ignored() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.handle_append_entries(3, 3, 0, 0, vec![], 0);
+        node.handle_append_entries(2, 1, 0, 0, vec![], 0);
+        assert_eq!(node.leader_id, Some(3));
+    }
+
+    #[test]
+    fn leader_steps_down_on_new_term() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.isolate(1);
+        cluster.elect(2);
+        assert_eq!(cluster.node(1).leader_id, Some(1), "isolated, it still thinks it leads");
+
+        cluster.heal(1);
+        cluster.heartbeat();
+        assert_eq!(cluster.node(1).leader_id, Some(2));
+    }
+}
+
+// =============================================================================
+// SECTION 41: HARD AND SOFT STATE
+// =============================================================================
+
+mod hard_soft_state {
+    use super::*;
+    use raft_core::{HardState, SoftState};
+
+    #[test]
+    fn hard_state_only_reported_when_changed() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.handle_vote_request(1, 1, 0, 0);
+
+        let ready = node.ready();
+        assert_eq!(ready.hard_state, Some(HardState { term: 1, vote: Some(1) }));
+        assert!(ready.must_sync());
+
+        node.handle_append_entries(1, 1, 0, 0, vec![], 0);
+        let ready = node.ready();
+        assert_eq!(ready.hard_state, None);
+        assert!(!ready.must_sync(), "a heartbeat ack promises nothing new");
+    }
+
+    #[test]
+    fn soft_state_tracks_leader_changes() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        assert_eq!(node.ready().soft_state, None);
+
+        node.handle_append_entries(1, 1, 0, 0, vec![], 0);
+        let ready = node.ready();
+        assert_eq!(ready.soft_state, Some(SoftState { state: NodeState::Follower, leader_id: Some(1) }));
+        assert!(node.ready().soft_state.is_none());
+    }
+
+    #[test]
+    fn soft_state_alone_needs_no_sync() {
+        let mut node = RaftNode::new(1, vec![1]);
+        node.become_leader();
+        let _ = node.ready();
+
+        let term = node.current_term;
+        node.become_follower(term);
+        let ready = node.ready();
+        assert_eq!(ready.soft_state.map(|s| s.state), Some(NodeState::Follower));
+        assert!(!ready.must_sync());
+        assert!(!ready.is_empty());
+    }
+
+    #[test]
+    fn appended_entries_must_sync() {
+        let mut node = RaftNode::new(1, vec![1]);
+        node.become_leader();
+        let _ = node.ready();
+
+        node.propose(b"x".to_vec()).unwrap();
+        let ready = node.ready();
+        assert_eq!(ready.entries.len(), 1);
+        assert!(ready.must_sync());
+    }
+
+    #[test]
+    fn accessors_match_node_fields() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.handle_vote_request(3, 3, 0, 0);
+        assert_eq!(node.hard_state(), HardState { term: 3, vote: Some(3) });
+        assert_eq!(node.soft_state(), SoftState { state: NodeState::Follower, leader_id: None });
+    }
+}
+
+// =============================================================================
+// SECTION 42: CHANGELOG
+// =============================================================================
+
+mod changelog {
+    use super::*;
+    use raft_core::{ChangelogError, CommitProof};
+
+    fn indexes(entries: &[LogEntry]) -> Vec<u64> {
+        entries.iter().map(|e| e.index).collect()
+    }
+
+    #[test]
+    fn reads_only_committed_entries() {
+        let node = leader_with_log(5, 5);
+        let changes = node.committed_since(None, 100).unwrap();
+        assert_eq!(indexes(&changes), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn resumes_from_last_position() {
+        let node = leader_with_log(5, 5);
+        let first = node.committed_since(None, 2).unwrap();
+        assert_eq!(indexes(&first), vec![1, 2]);
+
+        let position = node.commit_proof(first.last().unwrap().index);
+        let rest = node.committed_since(position, 100).unwrap();
+        assert_eq!(indexes(&rest), vec![3, 4, 5]);
+
+        let position = node.commit_proof(5);
+        assert!(node.committed_since(position, 100).unwrap().is_empty());
+    }
+
+    #[test]
+    fn position_ahead_waits() {
+        let node = leader_with_log(5, 5);
+        let ahead = CommitProof { term: 1, index: 9 };
+        assert!(node.committed_since(Some(ahead), 100).unwrap().is_empty());
+    }
+
+    #[test]
+    fn foreign_position_is_rejected() {
+        let node = leader_with_log(5, 5);
+        let foreign = CommitProof { term: 7, index: 3 };
+        assert_eq!(node.committed_since(Some(foreign), 100).unwrap_err(), ChangelogError::Diverged);
+    }
+
+    #[test]
+    fn compacted_position_needs_snapshot() {
+        let mut node = leader_with_log(5, 5);
+        node.ready();
+        assert!(node.compact(3, vec![]));
+
+        let compacted = CommitProof { term: 1, index: 2 };
+        assert_eq!(node.committed_since(Some(compacted), 100).unwrap_err(), ChangelogError::Compacted { snapshot_index: 3 });
+        assert_eq!(node.committed_since(None, 100).unwrap_err(), ChangelogError::Compacted { snapshot_index: 3 });
+
+        // resuming exactly at the snapshot works
+        let at_snapshot = CommitProof { term: node.snapshot_term(), index: 3 };
+        assert_eq!(indexes(&node.committed_since(Some(at_snapshot), 100).unwrap()), vec![4, 5]);
+    }
+}
+
+// =============================================================================
+// SECTION 43: QUORUM READS
+// =============================================================================
+
+mod quorum_reads {
+    use super::*;
+
+    /// answer every ReadQuorumRequest in `ready` from the node with that id
+    fn answer(from: &mut RaftNode, ready: &raft_core::Ready) -> Vec<RaftMessage> {
+        let id = from.id;
+        ready
+            .messages
+            .iter()
+            .filter(|(to, _)| *to == id)
+            .flat_map(|(_, msg)| from.step(0, msg.clone()).messages)
+            .map(|(_, msg)| msg)
+            .collect()
+    }
+
+    #[test]
+    fn follower_reads_at_highest_reported_index() {
+        let mut reader = RaftNode::new(1, vec![1, 2, 3]);
+        let mut ahead = RaftNode::new(2, vec![1, 2, 3]);
+        ahead.current_term = 1;
+        ahead.log.push(LogEntry::new(1, 1, b"a".to_vec()));
+        ahead.log.push(LogEntry::new(1, 2, b"b".to_vec()));
+
+        reader.quorum_read(b"r1".to_vec());
+        let ready = reader.ready();
+        assert_eq!(ready.messages.len(), 2, "asks every other voter");
+        assert!(ready.read_states.is_empty());
+
+        for response in answer(&mut ahead, &ready) {
+            let ready = reader.step(2, response);
+            assert_eq!(ready.read_states.len(), 1);
+            assert_eq!(ready.read_states[0].index, 2);
+            assert_eq!(ready.read_states[0].request_ctx, b"r1".to_vec());
+        }
+        assert_eq!(reader.pending_read_count(), 0);
+    }
+
+    #[test]
+    fn waits_for_a_majority() {
+        let mut reader = RaftNode::new(1, vec![1, 2, 3, 4, 5]);
+        let mut peer = RaftNode::new(2, vec![1, 2, 3, 4, 5]);
+        reader.quorum_read(b"r".to_vec());
+        let ready = reader.ready();
+
+        for response in answer(&mut peer, &ready) {
+            assert!(reader.step(2, response).read_states.is_empty());
+        }
+        assert_eq!(reader.pending_read_count(), 1);
+    }
+
+    #[test]
+    fn answering_leaves_the_responder_alone() {
+        let mut cluster_node = RaftNode::new(2, vec![1, 2, 3]);
+        cluster_node.handle_append_entries(3, 1, 0, 0, vec![], 0);
+        cluster_node.ready();
+        let ready = cluster_node.step(1, RaftMessage::ReadQuorumRequest { read_id: 1, group_id: None });
+
+        assert_eq!(cluster_node.current_term, 3);
+        assert_eq!(cluster_node.leader_id, Some(1));
+        assert!(ready.hard_state.is_none());
+        assert!(matches!(ready.messages[..], [(1, RaftMessage::ReadQuorumResponse { read_id: 1, .. })]));
+    }
+
+    #[test]
+    fn non_voters_do_not_count() {
+        let mut reader = RaftNode::new(1, vec![1, 2, 3]);
+        reader.quorum_read(b"r".to_vec());
+        reader.ready();
+
+        let ready = reader.step(9, RaftMessage::ReadQuorumResponse { read_id: 1, last_log_index: 50, group_id: None });
+        assert!(ready.read_states.is_empty());
+    }
+
+    #[test]
+    fn single_node_confirms_immediately() {
+        let mut node = RaftNode::new(1, vec![1]);
+        node.quorum_read(b"r".to_vec());
+        let ready = node.ready();
+        assert_eq!(ready.read_states.len(), 1);
+        assert!(ready.messages.is_empty());
+    }
+}
+
+// =============================================================================
+// SECTION 44: STATE MACHINE TRAIT
+// =============================================================================
+
+mod state_machine_trait {
+    use super::*;
+    use raft_core::{EntryType, Snapshot, StateMachine};
+
+    /// appends every command it runs; its snapshot is the whole history
+    #[derive(Default)]
+    struct Journal {
+        history: Vec<u8>,
+    }
+
+    impl StateMachine for Journal {
+        fn apply(&mut self, entry: &LogEntry) -> Vec<u8> {
+            self.history.extend(&entry.command);
+            self.history.len().to_be_bytes().to_vec()
+        }
+
+        fn snapshot(&self) -> Vec<u8> {
+            self.history.clone()
+        }
+
+        fn restore(&mut self, data: &[u8]) {
+            self.history = data.to_vec();
+        }
+    }
+
+    #[test]
+    fn applies_committed_commands_in_order() {
+        let mut node = RaftNode::new(1, vec![1]);
+        node.become_leader();
+        node.propose(b"a".to_vec()).unwrap();
+        node.propose(b"b".to_vec()).unwrap();
+        node.commit_index = node.last_log_index();
+        let ready = node.ready();
+
+        let mut journal = Journal::default();
+        let results = node.apply_ready(&ready, &mut journal);
+        assert_eq!(results.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![2, 3], "the no-op at 1 is skipped");
+        assert_eq!(journal.history, b"ab".to_vec());
+        assert_eq!(results[1].1, 2usize.to_be_bytes().to_vec());
+    }
+
+    #[test]
+    fn skips_config_entries_and_duplicates() {
+        let mut node = RaftNode::new(1, vec![1]);
+        node.become_leader();
+        let client = node.register_session();
+        node.append_client_entry(client, 1, b"x".to_vec());
+        node.append_client_entry(client, 1, b"x".to_vec());
+        node.append_entry_of_type(EntryType::Config, b"conf".to_vec());
+        node.commit_index = node.last_log_index();
+        let ready = node.ready();
+
+        let mut journal = Journal::default();
+        node.apply_ready(&ready, &mut journal);
+        assert_eq!(journal.history, b"x".to_vec());
+    }
+
+    #[test]
+    fn installed_snapshot_restores_before_entries() {
+        let mut follower = RaftNode::new(3, vec![1, 2, 3]);
+        let install = RaftMessage::InstallSnapshot {
+            term: 1,
+            leader_id: 1,
+            snapshot: Snapshot::new(3, 1, b"ab".to_vec()),
+            group_id: None,
+        };
+        let ready = follower.step(1, install);
+
+        let mut journal = Journal::default();
+        follower.apply_ready(&ready, &mut journal);
+        assert_eq!(journal.history, b"ab".to_vec());
+    }
+}
+
+// =============================================================================
+// SECTION 45: SEEDED RANDOMNESS
+// =============================================================================
+
+mod seeded_randomness {
+    use super::*;
+    use raft_core::testing::TestCluster;
+
+    fn seeded(seed: u64) -> RaftConfig {
+        RaftConfig { rng_seed: Some(seed), ..RaftConfig::default() }
+    }
+
+    /// the first few election timeouts node `id` draws
+    fn timeouts(id: u64, config: RaftConfig) -> Vec<u64> {
+        let mut node = RaftNode::with_config(id, vec![1, 2, 3], config);
+        (0..5)
+            .map(|_| {
+                let timeout = node.randomized_election_timeout();
+                node.reset_election_timer();
+                timeout
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_timeouts() {
+        assert_eq!(timeouts(1, seeded(42)), timeouts(1, seeded(42)));
+    }
+
+    #[test]
+    fn different_seeds_differ() {
+        assert_ne!(timeouts(1, seeded(42)), timeouts(1, seeded(43)));
+    }
+
+    #[test]
+    fn shared_seed_still_spreads_nodes() {
+        assert_ne!(timeouts(1, seeded(7)), timeouts(2, seeded(7)));
+    }
+
+    #[test]
+    fn timeouts_stay_in_range() {
+        let config = seeded(99);
+        let (min, max) = (config.election_timeout_min, config.election_timeout_max);
+        assert!(timeouts(3, config).iter().all(|t| (min..=max).contains(t)));
+    }
+
+    #[test]
+    fn no_seed_matches_plain_constructor() {
+        let plain = RaftNode::new(2, vec![1, 2, 3]);
+        assert_eq!(timeouts(2, RaftConfig::default())[0], plain.randomized_election_timeout());
+    }
+
+    #[test]
+    fn seeded_cluster_is_reproducible() {
+        let run = || {
+            let cluster = TestCluster::with_config(3, seeded(5));
+            cluster.ids().iter().map(|&id| cluster.node(id).randomized_election_timeout()).collect::<Vec<_>>()
+        };
+        assert_eq!(run(), run());
+    }
+}
+
+// =============================================================================
+// SECTION 46: FORCE NEW CLUSTER
+// =============================================================================
+
+mod force_new_cluster {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::{EntryType, Membership};
+
+    /// node 3 of a cluster that committed two entries, restarted from its
+    /// persisted state after nodes 1 and 2 were lost
+    fn survivor() -> RaftNode {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.propose(b"a");
+        cluster.propose(b"b");
+
+        let old = cluster.node(3);
+        let mut node = RaftNode::new(3, old.cluster_nodes.clone());
+        node.current_term = old.current_term;
+        node.voted_for = old.voted_for;
+        node.log = old.log.clone();
+        node
+    }
+
+    #[test]
+    fn survivor_becomes_sole_leader() {
+        let mut node = survivor();
+        let term = node.current_term;
+        let fenced = node.force_new_cluster();
+
+        assert_eq!(fenced, vec![1, 2]);
+        assert_eq!(node.cluster_nodes, vec![3]);
+        assert_eq!(node.state, NodeState::Leader);
+        assert!(node.current_term > term);
+        assert_eq!(node.commit_index, node.last_log_index());
+        let last = node.get_entry(node.last_log_index()).unwrap();
+        assert_eq!(last.entry_type, EntryType::Config);
+    }
+
+    #[test]
+    fn keeps_committed_history() {
+        let mut node = survivor();
+        node.force_new_cluster();
+        let commands: Vec<_> = node.log.iter().map(|e| e.command.clone()).collect();
+        assert!(commands.contains(&b"a".to_vec()));
+        assert!(commands.contains(&b"b".to_vec()));
+    }
+
+    #[test]
+    fn old_peers_are_ignored() {
+        let mut node = survivor();
+        node.force_new_cluster();
+        node.ready();
+
+        let term = node.current_term;
+        let ready = node.step(1, RaftMessage::VoteRequest {
+            term: term + 5,
+            candidate_id: 1,
+            last_log_index: 100,
+            last_log_term: term + 4,
+            transfer: false,
+            group_id: None,
+        });
+        assert!(ready.messages.is_empty());
+        assert_eq!(node.current_term, term);
+        assert_eq!(node.state, NodeState::Leader);
+    }
+
+    #[test]
+    fn lone_leader_commits_new_proposals() {
+        let mut node = survivor();
+        node.force_new_cluster();
+        let index = node.propose(b"c".to_vec()).unwrap();
+        let ready = node.replicate();
+        assert_eq!(node.commit_index, index);
+        assert!(ready.committed_entries.iter().any(|e| e.index == index));
+    }
+
+    #[test]
+    fn restart_reads_the_new_membership() {
+        let mut node = survivor();
+        let fenced = node.force_new_cluster();
+        let last = node.get_entry(node.last_log_index()).unwrap();
+        assert_eq!(Membership::decode(last), Some(Membership { voters: vec![3] }));
+
+        // the host only knows the old members; the log has the new ones
+        let mut restarted = RaftNode::new(3, vec![1, 2, 3]);
+        restarted.current_term = node.current_term;
+        restarted.voted_for = node.voted_for;
+        restarted.log = node.log.clone();
+        assert!(restarted.restore_membership());
+        for id in fenced {
+            restarted.fence_peer(id);
+        }
+
+        assert_eq!(restarted.cluster_nodes, vec![3]);
+        let ready = restarted.tick(restarted.config.election_timeout_max);
+        assert_eq!(restarted.state, NodeState::Leader);
+        assert!(ready.messages.is_empty());
+    }
+
+    #[test]
+    fn fencing_survives_restart_via_host() {
+        let mut restarted = RaftNode::new(3, vec![3]);
+        restarted.fence_peer(1);
+        restarted.fence_peer(3);
+        assert!(restarted.is_fenced(1));
+        assert!(!restarted.is_fenced(3), "a node never fences itself");
+    }
+}
+
+// =============================================================================
+// SECTION 47: ELECTION PRIORITY
+// =============================================================================
+
+mod election_priority {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::ProposeError;
+    use std::collections::HashMap;
+
+    fn ranked(priorities: &[(u64, u64)]) -> RaftConfig {
+        RaftConfig {
+            priorities: priorities.iter().copied().collect::<HashMap<_, _>>(),
+            ..RaftConfig::default()
+        }
+    }
+
+    #[test]
+    fn low_priority_waits_longer() {
+        let config = ranked(&[(1, 10), (2, 5)]);
+        let min = config.election_timeout_min;
+        let max = config.election_timeout_max;
+
+        let top = RaftNode::with_config(1, vec![1, 2, 3], config.clone());
+        let half = RaftNode::with_config(2, vec![1, 2, 3], config.clone());
+        let bottom = RaftNode::with_config(3, vec![1, 2, 3], config);
+
+        assert!(top.randomized_election_timeout() <= max);
+        assert!(half.randomized_election_timeout() >= min + min / 2);
+        assert!(bottom.randomized_election_timeout() >= 2 * min);
+    }
+
+    #[test]
+    fn no_priorities_means_no_delay() {
+        let node = RaftNode::new(1, vec![1, 2, 3]);
+        assert_eq!(node.priority(1), 0);
+        assert!(node.randomized_election_timeout() <= node.config.election_timeout_max);
+    }
+
+    #[test]
+    fn leader_yields_to_higher_priority_peer() {
+        let mut cluster = TestCluster::with_config(3, ranked(&[(2, 10)]));
+        cluster.elect(1);
+        cluster.heartbeat();
+
+        assert_eq!(cluster.leader(), Some(2));
+        assert_eq!(cluster.node(1).state, NodeState::Follower);
+        cluster.propose(b"after");
+        cluster.assert_logs_converged();
+    }
+
+    #[test]
+    fn equal_priority_does_not_yield() {
+        let mut cluster = TestCluster::with_config(3, ranked(&[(1, 5), (2, 5), (3, 5)]));
+        cluster.elect(1);
+        cluster.heartbeat();
+        assert_eq!(cluster.leader(), Some(1));
+        assert_eq!(cluster.node(1).transfer_target(), None);
+    }
+
+    #[test]
+    fn manual_transfer() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let term = cluster.node(1).current_term;
+
+        assert!(cluster.node_mut(1).transfer_leadership(3));
+        // the TimeoutNow goes out with the next heartbeat round
+        cluster.heartbeat();
+
+        assert_eq!(cluster.leader(), Some(3));
+        assert_eq!(cluster.node(3).current_term, term + 1);
+        assert_eq!(cluster.node(1).transfer_target(), None);
+        cluster.propose(b"after");
+        cluster.assert_logs_converged();
+    }
+
+    #[test]
+    fn transfer_refuses_proposals_until_done() {
+        let mut node = RaftNode::new(1, vec![1, 2]);
+        node.start_election();
+        node.votes_received.push(2);
+        node.become_leader();
+        node.commit_index = 0;
+        node.progress.get_mut(&2).unwrap().match_index = node.last_log_index();
+
+        assert!(node.transfer_leadership(2));
+        assert_eq!(node.transfer_target(), Some(2));
+        assert!(matches!(
+            node.propose(b"x".to_vec()),
+            Err(ProposeError::NotLeader { leader_hint: Some(2) })
+        ));
+
+        // the target never took over: give up after an election timeout
+        node.tick(node.config.election_timeout_max);
+        assert_eq!(node.transfer_target(), None);
+        assert!(node.propose(b"x".to_vec()).is_ok());
+    }
+
+    #[test]
+    fn transfer_needs_caught_up_voter() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.votes_received.push(2);
+        node.become_leader();
+
+        assert!(!node.transfer_leadership(2), "peer hasn't acked the no-op yet");
+        assert!(!node.transfer_leadership(1));
+        assert!(!node.transfer_leadership(9));
+    }
+
+    #[test]
+    fn stale_timeout_now_is_ignored() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.current_term = 4;
+        node.step(1, RaftMessage::TimeoutNow { term: 3, group_id: None });
+        assert_eq!(node.state, NodeState::Follower);
+        assert_eq!(node.current_term, 4);
+    }
+}
+
+// =============================================================================
+// SECTION 48: STICKY LEADER
+// =============================================================================
+
+mod sticky_leader {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::Ready;
+
+    fn vote_request(term: u64, transfer: bool) -> RaftMessage {
+        RaftMessage::VoteRequest { term, candidate_id: 3, last_log_index: 10, last_log_term: term, transfer, group_id: None }
+    }
+
+    /// follower 2 of term 1 that has just heard from leader 1
+    fn follower() -> RaftNode {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.step(1, RaftMessage::AppendEntries {
+            term: 1,
+            leader_id: 1,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: vec![],
+            leader_commit: 0,
+            seq: 0,
+            group_id: None,
+        });
+        node.ready();
+        node
+    }
+
+    fn granted(ready: &Ready) -> bool {
+        ready.messages.iter().any(|(_, m)| matches!(m, RaftMessage::VoteResponse { vote_granted: true, .. }))
+    }
+
+    #[test]
+    fn vote_withheld_right_after_leader_contact() {
+        let mut node = follower();
+        assert!(node.leader_recently_heard());
+
+        let ready = node.step(3, vote_request(5, false));
+        assert!(!granted(&ready));
+        assert_eq!(node.current_term, 1, "the candidate's term is ignored too");
+        assert_eq!(node.leader_id, Some(1));
+        assert_eq!(node.voted_for, None);
+    }
+
+    #[test]
+    fn vote_granted_once_leader_goes_quiet() {
+        let mut node = follower();
+        node.tick(node.config.election_timeout_min);
+        assert!(!node.leader_recently_heard());
+
+        let ready = node.step(3, vote_request(5, false));
+        assert!(granted(&ready));
+        assert_eq!(node.current_term, 5);
+    }
+
+    #[test]
+    fn transfer_elections_bypass_stickiness() {
+        let mut node = follower();
+        let ready = node.step(3, vote_request(2, true));
+        assert!(granted(&ready));
+        assert_eq!(node.voted_for, Some(3));
+    }
+
+    #[test]
+    fn restarted_node_cannot_steal_leadership() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.propose(b"a");
+        let term = cluster.node(1).current_term;
+
+        // node 3 comes back and campaigns at once, skipping pre-vote
+        let request = cluster.node_mut(3).start_election();
+        let ready = cluster.node_mut(2).step(3, request);
+        assert!(!granted(&ready));
+        assert_eq!(cluster.node(2).current_term, term);
+        assert_eq!(cluster.node(2).leader_id, Some(1));
+        assert_eq!(cluster.node(3).state, NodeState::Candidate, "one vote is not a majority");
+    }
+
+    fn prevote_granted(ready: &Ready) -> bool {
+        ready.messages.iter().any(|(_, m)| matches!(m, RaftMessage::PreVoteResponse { vote_granted: true, .. }))
+    }
+
+    fn prevote_request(term: u64) -> RaftMessage {
+        RaftMessage::PreVoteRequest { term, candidate_id: 3, last_log_index: 10, last_log_term: term, group_id: None }
+    }
+
+    #[test]
+    fn prevote_withheld_right_after_leader_contact() {
+        let mut node = follower();
+        let ready = node.step(3, prevote_request(2));
+        assert!(!prevote_granted(&ready));
+    }
+
+    #[test]
+    fn prevote_granted_once_leader_goes_quiet() {
+        let mut node = follower();
+        node.tick(node.config.election_timeout_min);
+        assert!(!node.leader_recently_heard());
+
+        let ready = node.step(3, prevote_request(2));
+        assert!(prevote_granted(&ready));
+        assert_eq!(node.current_term, 1, "a pre-vote changes nothing");
+    }
+
+    #[test]
+    fn leader_refuses_prevotes() {
+        let mut node = leader(RaftConfig::default());
+        let ready = node.step(3, prevote_request(2));
+        assert!(!prevote_granted(&ready));
+        assert_eq!(node.state, NodeState::Leader);
+    }
+
+    #[test]
+    fn transfer_wins_in_five_node_cluster() {
+        let mut cluster = TestCluster::new(5);
+        cluster.elect(1);
+        cluster.propose(b"a");
+
+        // every follower has just heard from node 1, yet node 4 needs two of them
+        assert!(cluster.node(2).leader_recently_heard());
+        assert!(cluster.node_mut(1).transfer_leadership(4));
+        cluster.heartbeat();
+        assert_eq!(cluster.leader(), Some(4));
+    }
+}
+
+// =============================================================================
+// SECTION 49: HEARTBEAT MESSAGES
+// =============================================================================
+
+mod heartbeat_messages {
+    use super::*;
+    use raft_core::testing::TestCluster;
+
+    #[test]
+    fn commit_is_capped_at_follower_match() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.isolate(3);
+        let index = cluster.propose(b"a");
+
+        let leader = cluster.node(1);
+        assert_eq!(leader.commit_index, index);
+        let matched = leader.progress[&3].match_index;
+        assert!(matched < index);
+        assert!(matches!(
+            leader.create_heartbeat(3),
+            Some(RaftMessage::Heartbeat { leader_id: 1, commit, .. }) if commit == matched
+        ));
+        assert!(matches!(
+            leader.create_heartbeat(2),
+            Some(RaftMessage::Heartbeat { leader_id: 1, commit, .. }) if commit == index
+        ));
+    }
+
+    #[test]
+    fn follower_commits_and_resets_timer() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.current_term = 1;
+        node.log.push(LogEntry::new(1, 1, b"a".to_vec()));
+        node.tick(10);
+
+        let ready = node.step(1, RaftMessage::Heartbeat { term: 1, leader_id: 1, commit: 1, seq: 0, group_id: None });
+        assert_eq!(node.commit_index, 1);
+        assert_eq!(node.leader_id, Some(1));
+        assert!(node.leader_recently_heard());
+        assert_eq!(ready.committed_entries.len(), 1);
+        assert!(matches!(
+            ready.messages.as_slice(),
+            [(1, RaftMessage::HeartbeatResponse { term: 1, last_log_index: 1, .. })]
+        ));
+    }
+
+    #[test]
+    fn stale_heartbeat_is_answered_with_our_term() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.current_term = 4;
+        let ready = node.step(1, RaftMessage::Heartbeat { term: 3, leader_id: 1, commit: 0, seq: 0, group_id: None });
+        assert_eq!(node.leader_id, None);
+        assert!(matches!(
+            ready.messages.as_slice(),
+            [(1, RaftMessage::HeartbeatResponse { term: 4, last_log_index: 0, .. })]
+        ));
+    }
+
+    #[test]
+    fn higher_term_response_deposes_leader() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let term = cluster.node(1).current_term;
+        cluster.node_mut(1).step(2, RaftMessage::HeartbeatResponse { term: term + 1, last_log_index: 0, seq: 0, group_id: None });
+        assert_eq!(cluster.node(1).state, NodeState::Follower);
+        assert_eq!(cluster.node(1).current_term, term + 1);
+    }
+
+    #[test]
+    fn responses_confirm_reads() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let term = cluster.node(1).current_term;
+        let last = cluster.node(1).last_log_index();
+
+        let leader = cluster.node_mut(1);
+        assert!(leader.read_index(b"r".to_vec()));
+        leader.broadcast_heartbeat();
+        let ready = leader.step(2, RaftMessage::HeartbeatResponse { term, last_log_index: last, seq: 0, group_id: None });
+        assert_eq!(ready.read_states.len(), 1);
+    }
+
+    #[test]
+    fn lagging_follower_catches_up_on_idle_cluster() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.isolate(3);
+        cluster.propose(b"a");
+        cluster.propose(b"b");
+        cluster.heal(3);
+
+        // no new proposals: heartbeats alone bring node 3 up to date
+        cluster.heartbeat();
+        cluster.heartbeat();
+        cluster.assert_logs_converged();
+    }
+}
+
+// =============================================================================
+// SECTION 50: ELECTION BACKOFF
+// =============================================================================
+
+mod election_backoff {
+    use super::*;
+
+    /// let `node` time out `rounds` times with nobody answering
+    fn lose_campaigns(node: &mut RaftNode, rounds: u32) {
+        for _ in 0..rounds {
+            let timeout = node.randomized_election_timeout();
+            node.tick(timeout);
+        }
+    }
+
+    #[test]
+    fn lost_campaigns_are_counted() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        lose_campaigns(&mut node, 1);
+        assert_eq!(node.state, NodeState::PreCandidate);
+        assert_eq!(node.failed_elections(), 0, "the first campaign hasn't failed yet");
+
+        lose_campaigns(&mut node, 3);
+        assert_eq!(node.failed_elections(), 3);
+    }
+
+    #[test]
+    fn window_widens_up_to_the_cap() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        let cap = node.config.election_backoff_max;
+        let mut longest = 0;
+        for _ in 0..40 {
+            lose_campaigns(&mut node, 1);
+            let timeout = node.randomized_election_timeout();
+            assert!(timeout >= node.config.election_timeout_min);
+            assert!(timeout <= cap);
+            longest = longest.max(timeout);
+        }
+        assert!(longest > node.config.election_timeout_max, "backoff never widened the window");
+    }
+
+    #[test]
+    fn hearing_a_leader_resets_backoff() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        lose_campaigns(&mut node, 5);
+        assert!(node.failed_elections() > 0);
+
+        let term = node.current_term + 1;
+        node.step(1, RaftMessage::Heartbeat { term, leader_id: 1, commit: 0, seq: 0, group_id: None });
+        assert_eq!(node.failed_elections(), 0);
+        assert!(node.randomized_election_timeout() <= node.config.election_timeout_max);
+    }
+
+    #[test]
+    fn winning_resets_backoff() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        lose_campaigns(&mut node, 3);
+        node.start_election();
+        node.votes_received.push(2);
+        node.become_leader();
+        assert_eq!(node.failed_elections(), 0);
+    }
+
+    #[test]
+    fn cap_below_the_window_keeps_the_window() {
+        let config = RaftConfig { election_backoff_max: 0, ..RaftConfig::default() };
+        let mut node = RaftNode::with_config(1, vec![1, 2, 3], config);
+        lose_campaigns(&mut node, 6);
+        assert!(node.randomized_election_timeout() <= node.config.election_timeout_max);
+    }
+}
+
+// =============================================================================
+// SECTION 51: TRANSPORT REPORTS
+// =============================================================================
+
+mod transport_reports {
+    use super::*;
+    use raft_core::{ProgressState, Snapshot};
+
+    /// leader whose log before index 6 only survives in a snapshot, with
+    /// one in flight to node 2
+    fn leader_sending_snapshot() -> RaftNode {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.restore_snapshot(Snapshot::new(5, 1, b"state@5".to_vec()));
+        node.current_term = 1;
+        node.log.push(LogEntry::new(1, 6, b"six".to_vec()));
+        node.start_election();
+        node.handle_vote_response(2, true, 2);
+        node.progress.get_mut(&2).unwrap().next_index = 3;
+        assert!(node.send_append(2));
+        assert_eq!(node.progress[&2].state, ProgressState::Snapshot);
+        node.ready();
+        node
+    }
+
+    #[test]
+    fn unreachable_peer_falls_back_to_probing() {
+        let mut node = leader_with_log(4, 2);
+        node.replicate();
+        assert!(node.progress[&2].next_index > 3);
+
+        node.report_unreachable(2);
+        let progress = &node.progress[&2];
+        assert_eq!(progress.state, ProgressState::Probe);
+        assert_eq!(progress.next_index, 3);
+        assert_eq!(progress.inflights.count(), 0);
+
+        // one probe, then nothing until the peer answers
+        node.ready();
+        assert!(node.send_append(2));
+        assert!(!node.send_append(2));
+    }
+
+    #[test]
+    fn unreachable_is_ignored_off_leader() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.report_unreachable(1);
+        node.report_snapshot(1, true);
+        assert!(node.progress.is_empty());
+    }
+
+    #[test]
+    fn snapshot_success_resumes_past_the_snapshot() {
+        let mut node = leader_sending_snapshot();
+        node.report_snapshot(2, true);
+
+        let progress = &node.progress[&2];
+        assert_eq!(progress.state, ProgressState::Probe);
+        assert_eq!(progress.next_index, 6);
+        assert!(matches!(node.create_append_entries(2), Some(RaftMessage::AppendEntries { prev_log_index: 5, .. })));
+    }
+
+    #[test]
+    fn snapshot_failure_retries_the_snapshot() {
+        let mut node = leader_sending_snapshot();
+        node.report_snapshot(2, false);
+
+        assert_eq!(node.progress[&2].state, ProgressState::Probe);
+        assert!(node.send_append(2));
+        assert_eq!(node.progress[&2].state, ProgressState::Snapshot);
+        let ready = node.ready();
+        assert!(matches!(ready.messages.as_slice(), [(2, RaftMessage::InstallSnapshot { .. })]));
+    }
+
+    #[test]
+    fn snapshot_report_without_snapshot_in_flight_is_ignored() {
+        let mut node = leader_with_log(4, 2);
+        let before = node.progress[&3].next_index;
+        node.report_snapshot(3, true);
+        assert_eq!(node.progress[&3].state, ProgressState::Replicate);
+        assert_eq!(node.progress[&3].next_index, before);
+    }
+}
+
+// =============================================================================
+// SECTION 52: PROPOSAL HANDLES
+// =============================================================================
+
+mod proposal_handles {
+    use super::*;
+    use raft_core::{CommitProof, ProposalOutcome, ProposeError};
+
+    #[test]
+    fn handle_resolves_once_committed() {
+        let mut node = committed_leader(RaftConfig::default());
+        let proof = node.propose_tracked(b"x".to_vec()).unwrap();
+        assert_eq!(proof, CommitProof { term: 1, index: 2 });
+        assert!(node.ready().proposals.is_empty());
+        assert_eq!(node.pending_proposals(), &[proof]);
+
+        node.handle_append_entries_response(1, true, 2, 2);
+        assert_eq!(node.ready().proposals, vec![(proof, ProposalOutcome::Committed)]);
+        assert!(node.pending_proposals().is_empty());
+        assert!(node.ready().proposals.is_empty());
+    }
+
+    #[test]
+    fn handle_is_superseded_when_overwritten() {
+        let mut node = committed_leader(RaftConfig::default());
+        let proof = node.propose_tracked(b"x".to_vec()).unwrap();
+        node.ready();
+
+        // a new leader never saw index 2 and puts its own entry there
+        let ready = node.step(2, RaftMessage::AppendEntries {
+            term: 2,
+            leader_id: 2,
+            prev_log_index: 1,
+            prev_log_term: 1,
+            entries: vec![LogEntry::new(2, 2, b"y".to_vec())],
+            leader_commit: 1,
+            seq: 0,
+            group_id: None,
+        });
+        assert_eq!(ready.proposals, vec![(proof, ProposalOutcome::Superseded)]);
+        assert!(node.pending_proposals().is_empty());
+    }
+
+    #[test]
+    fn handle_is_superseded_when_truncated_past() {
+        let mut node = committed_leader(RaftConfig::default());
+        let first = node.propose_tracked(b"x".to_vec()).unwrap();
+        let second = node.propose_tracked(b"y".to_vec()).unwrap();
+        node.ready();
+
+        // the new leader's log ends before either proposal
+        let ready = node.step(2, RaftMessage::AppendEntries {
+            term: 2,
+            leader_id: 2,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: vec![LogEntry::new(2, 1, Vec::new())],
+            leader_commit: 0,
+            seq: 0,
+            group_id: None,
+        });
+        assert_eq!(
+            ready.proposals,
+            vec![(first, ProposalOutcome::Superseded), (second, ProposalOutcome::Superseded)]
+        );
+    }
+
+    #[test]
+    fn refused_proposal_is_not_tracked() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        assert!(matches!(node.propose_tracked(b"x".to_vec()), Err(ProposeError::NotLeader { .. })));
+        assert!(node.pending_proposals().is_empty());
+    }
+}
+
+// =============================================================================
+// SECTION 53: BATCHED PROPOSALS
+// =============================================================================
+
+mod batched_proposals {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::{EntryType, ProposeError};
+
+    #[test]
+    fn batch_takes_one_contiguous_range() {
+        let mut node = leader(RaftConfig::default());
+        let range = node.propose_many(vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]).unwrap();
+        assert_eq!(range, 2..5);
+
+        let ready = node.ready();
+        assert!(ready.must_sync());
+        let written: Vec<(u64, u64, Vec<u8>)> =
+            ready.entries.iter().map(|e| (e.term, e.index, e.command.clone())).collect();
+        assert_eq!(
+            written,
+            vec![(1, 2, b"a".to_vec()), (1, 3, b"b".to_vec()), (1, 4, b"c".to_vec())]
+        );
+        assert!(ready.entries.iter().all(|e| e.entry_type == EntryType::Normal));
+    }
+
+    #[test]
+    fn batch_replicates_in_one_append() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let range = cluster.node_mut(1).propose_many(vec![b"a".to_vec(), b"b".to_vec()]).unwrap();
+
+        let ready = cluster.node_mut(1).replicate();
+        let to_2: Vec<_> = ready.messages.iter().filter(|(to, _)| *to == 2).collect();
+        assert_eq!(to_2.len(), 1);
+        assert!(matches!(&to_2[0].1, RaftMessage::AppendEntries { entries, .. } if entries.len() == 2));
+        assert_eq!(range.end, cluster.node(1).last_log_index() + 1);
+    }
+
+    #[test]
+    fn empty_batch_appends_nothing() {
+        let mut node = leader(RaftConfig::default());
+        assert_eq!(node.propose_many(Vec::new()).unwrap(), 2..2);
+        assert_eq!(node.last_log_index(), 1);
+        assert!(node.ready().entries.is_empty());
+    }
+
+    #[test]
+    fn batch_is_refused_off_leader() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        let result = node.propose_many(vec![b"a".to_vec(), b"b".to_vec()]);
+        assert!(matches!(result, Err(ProposeError::NotLeader { .. })));
+        assert_eq!(node.last_log_index(), 0);
+    }
+}
+
+// =============================================================================
+// SECTION 54: CONFIG CHANGE GUARD
+// =============================================================================
+
+mod conf_change_guard {
+    use super::*;
+    use raft_core::{ConfChangeError, EntryType};
+
+    #[test]
+    fn second_change_is_refused_while_first_is_pending() {
+        let mut node = committed_leader(RaftConfig::default());
+        assert_eq!(node.propose_conf_change(b"add 4".to_vec()), Ok(2));
+        assert_eq!(node.pending_conf_index(), Some(2));
+        assert_eq!(
+            node.propose_conf_change(b"add 5".to_vec()),
+            Err(ConfChangeError::Pending { index: 2 })
+        );
+        assert_eq!(node.last_log_index(), 2);
+
+        // ordinary commands are not held back
+        assert_eq!(node.propose(b"x".to_vec()), Ok(3));
+    }
+
+    #[test]
+    fn next_change_is_allowed_once_first_commits() {
+        let mut node = committed_leader(RaftConfig::default());
+        node.propose_conf_change(b"add 4".to_vec()).unwrap();
+        node.handle_append_entries_response(1, true, 2, 2);
+        assert_eq!(node.pending_conf_index(), None);
+        assert_eq!(node.propose_conf_change(b"add 5".to_vec()), Ok(3));
+        assert_eq!(node.get_entry(3).unwrap().entry_type, EntryType::Config);
+    }
+
+    #[test]
+    fn queued_change_is_proposed_after_commit() {
+        let mut node = committed_leader(RaftConfig::default());
+        assert_eq!(node.queue_conf_change(b"add 4".to_vec()), Ok(Some(2)));
+        assert_eq!(node.queue_conf_change(b"add 5".to_vec()), Ok(None));
+        assert_eq!(node.queued_conf_changes(), &[b"add 5".to_vec()]);
+        assert_eq!(node.last_log_index(), 2);
+
+        node.handle_append_entries_response(1, true, 2, 2);
+        assert!(node.queued_conf_changes().is_empty());
+        assert_eq!(node.pending_conf_index(), Some(3));
+        let entry = node.get_entry(3).unwrap();
+        assert_eq!((entry.entry_type, entry.command.as_slice()), (EntryType::Config, &b"add 5"[..]));
+    }
+
+    #[test]
+    fn queue_is_dropped_on_step_down() {
+        let mut node = committed_leader(RaftConfig::default());
+        node.queue_conf_change(b"add 4".to_vec()).unwrap();
+        node.queue_conf_change(b"add 5".to_vec()).unwrap();
+        node.become_follower(2);
+        assert!(node.queued_conf_changes().is_empty());
+    }
+
+    #[test]
+    fn follower_refuses_conf_changes() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        assert_eq!(
+            node.queue_conf_change(b"add 4".to_vec()),
+            Err(ConfChangeError::NotLeader { leader_hint: None })
+        );
+        assert!(node.queued_conf_changes().is_empty());
+    }
+}
+
+// =============================================================================
+// SECTION 55: READ QUEUE
+// =============================================================================
+
+mod read_queue {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::ReadMode;
+
+    /// a follower at index 1 answering a heartbeat round
+    fn heartbeat_ack() -> RaftMessage {
+        RaftMessage::HeartbeatResponse { term: 1, last_log_index: 1, seq: 0, group_id: None }
+    }
+
+    #[test]
+    fn queued_reads_release_together_on_one_round() {
+        let mut node = committed_leader(RaftConfig::default());
+        for ctx in [b"r1", b"r2", b"r3"] {
+            assert!(node.enqueue_read(ctx.to_vec()));
+        }
+        assert!(node.broadcast_heartbeat().read_states.is_empty());
+
+        let ready = node.step(2, heartbeat_ack());
+        let ctxs: Vec<&[u8]> = ready.read_states.iter().map(|r| r.request_ctx.as_slice()).collect();
+        assert_eq!(ctxs, vec![&b"r1"[..], b"r2", b"r3"]);
+        assert!(ready.read_states.iter().all(|r| r.index == 1));
+        assert_eq!(node.pending_read_count(), 0);
+    }
+
+    #[test]
+    fn reads_confirmed_on_the_spot_are_drained() {
+        let mut cluster = TestCluster::new(1);
+        cluster.elect(1);
+        let node = cluster.node_mut(1);
+
+        assert!(node.enqueue_read(b"r1".to_vec()));
+        let reads = node.drain_reads();
+        assert_eq!(reads.len(), 1);
+        assert_eq!(reads[0].request_ctx, b"r1".to_vec());
+        assert!(node.drain_reads().is_empty());
+        assert!(node.ready().read_states.is_empty());
+    }
+
+    #[test]
+    fn queued_reads_skip_the_lease() {
+        let config = RaftConfig { read_mode: ReadMode::LeaseBased, ..RaftConfig::default() };
+        let mut node = committed_leader(config);
+        node.broadcast_heartbeat();
+        node.step(2, heartbeat_ack());
+        assert!(node.has_valid_lease());
+
+        // read_index would answer from the lease; the queue waits for a round
+        assert!(node.enqueue_read(b"r1".to_vec()));
+        assert!(node.drain_reads().is_empty());
+        node.broadcast_heartbeat();
+        assert_eq!(node.step(3, heartbeat_ack()).read_states.len(), 1);
+    }
+
+    #[test]
+    fn follower_refuses_to_queue_reads() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        assert!(!node.enqueue_read(b"r1".to_vec()));
+        assert_eq!(node.pending_read_count(), 0);
+    }
+}
+
+// =============================================================================
+// SECTION 56: INITIAL PROBE
+// =============================================================================
+
+mod initial_probe {
+    use super::*;
+
+    #[test]
+    fn winning_election_probes_every_follower() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.log.push(LogEntry::new(1, 1, b"x".to_vec()));
+        node.current_term = 1;
+        node.start_election();
+        node.ready();
+
+        let ready = node.step(2, RaftMessage::VoteResponse { term: 2, vote_granted: true, commit_index: 0, last_log_index: 1, group_id: None });
+        assert_eq!(node.state, NodeState::Leader);
+        let mut probed: Vec<u64> = ready.messages.iter().map(|(to, _)| *to).collect();
+        probed.sort_unstable();
+        assert_eq!(probed, vec![2, 3]);
+        for (_, msg) in &ready.messages {
+            assert!(matches!(
+                msg,
+                RaftMessage::AppendEntries { term: 2, leader_id: 1, prev_log_index: 1, prev_log_term: 1, entries, .. }
+                    if entries.is_empty()
+            ));
+        }
+        // the probe doesn't move replication along: the no-op still goes out
+        assert_eq!(node.progress[&2].next_index, 2);
+    }
+
+    #[test]
+    fn probe_tells_follower_who_leads() {
+        let mut leader = RaftNode::new(1, vec![1, 2, 3]);
+        leader.start_election();
+        leader.handle_vote_response(1, true, 2);
+        let ready = leader.ready();
+        let (_, probe) = ready.messages.into_iter().find(|(to, _)| *to == 3).unwrap();
+
+        let mut follower = RaftNode::new(3, vec![1, 2, 3]);
+        let ready = follower.step(1, probe);
+        assert_eq!(follower.leader_id, Some(1));
+        assert_eq!(follower.current_term, 1);
+        assert!(matches!(
+            ready.messages.as_slice(),
+            [(1, RaftMessage::AppendEntriesResponse { success: true, match_index: 0, .. })]
+        ));
+    }
+
+    #[test]
+    fn learners_are_probed_too() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.add_learner(4);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        let ready = node.ready();
+        assert!(ready.messages.iter().any(|(to, _)| *to == 4));
+    }
+}
+
+// =============================================================================
+// SECTION 57: CONFIG BUILDER
+// =============================================================================
+
+mod config_builder {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::ConfigError;
+
+    #[test]
+    fn defaults_are_valid() {
+        assert_eq!(RaftConfig::default().validate(), Ok(()));
+        let config = RaftConfig::builder().build().unwrap();
+        assert!(config.pre_vote);
+        assert!(!config.check_quorum);
+        assert_eq!(config.max_entries_per_msg, None);
+    }
+
+    #[test]
+    fn builder_sets_every_knob() {
+        let config = RaftConfig::builder()
+            .election_timeout(400, 800)
+            .heartbeat_interval(100)
+            .tick_interval(20)
+            .max_entries_per_msg(32)
+            .priority(2, 5)
+            .pre_vote(false)
+            .check_quorum(true)
+            .build()
+            .unwrap();
+        assert_eq!((config.election_timeout_min, config.election_timeout_max), (400, 800));
+        assert_eq!((config.heartbeat_interval, config.tick_interval), (100, 20));
+        assert_eq!(config.max_entries_per_msg, Some(32));
+        assert_eq!(config.priorities.get(&2), Some(&5));
+        assert!(!config.pre_vote && config.check_quorum);
+    }
+
+    #[test]
+    fn builder_rejects_settings_that_do_not_fit() {
+        assert_eq!(
+            RaftConfig::builder().heartbeat_interval(150).build().unwrap_err(),
+            ConfigError::HeartbeatTooSlow { heartbeat_interval: 150, election_timeout_min: 150 }
+        );
+        assert_eq!(
+            RaftConfig::builder().election_timeout(300, 300).build().unwrap_err(),
+            ConfigError::ElectionWindowEmpty { min: 300, max: 300 }
+        );
+        assert_eq!(
+            RaftConfig::builder().tick_interval(0).build().unwrap_err(),
+            ConfigError::Zero { field: "tick_interval" }
+        );
+        assert_eq!(
+            RaftConfig::builder().election_backoff_max(200).build().unwrap_err(),
+            ConfigError::BackoffBelowTimeout { election_backoff_max: 200, election_timeout_max: 300 }
+        );
+    }
+
+    #[test]
+    fn without_pre_vote_timeout_starts_a_real_election() {
+        let config = RaftConfig::builder().pre_vote(false).build().unwrap();
+        let mut node = RaftNode::with_config(1, vec![1, 2, 3], config);
+        let timeout = node.randomized_election_timeout();
+        let ready = node.tick(timeout);
+
+        assert_eq!(node.state, NodeState::Candidate);
+        assert_eq!(node.current_term, 1);
+        assert!(ready.messages.iter().all(|(_, m)| matches!(m, RaftMessage::VoteRequest { .. })));
+        assert_eq!(ready.messages.len(), 2);
+    }
+
+    #[test]
+    fn check_quorum_deposes_a_cut_off_leader() {
+        let config = RaftConfig::builder().check_quorum(true).build().unwrap();
+        let mut cluster = TestCluster::with_config(3, config);
+        cluster.elect(1);
+        cluster.isolate(1);
+
+        // heartbeats go unanswered for a whole election_timeout_min
+        for _ in 0..4 {
+            cluster.node_mut(1).tick(50);
+        }
+        assert_eq!(cluster.node(1).state, NodeState::Follower);
+        assert_eq!(cluster.node(1).current_term, 1);
+    }
+
+    #[test]
+    fn check_quorum_keeps_a_healthy_leader() {
+        let config = RaftConfig::builder().check_quorum(true).build().unwrap();
+        let mut cluster = TestCluster::with_config(3, config);
+        cluster.elect(1);
+        for _ in 0..10 {
+            cluster.tick(50);
+        }
+        assert_eq!(cluster.leader(), Some(1));
+    }
+
+    #[test]
+    fn appends_are_capped_at_max_entries_per_msg() {
+        let config = RaftConfig::builder().max_entries_per_msg(2).build().unwrap();
+        let mut node = RaftNode::with_config(1, vec![1, 2, 3], config);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        node.propose_many(vec![vec![1], vec![2], vec![3]]).unwrap();
+        let Some(RaftMessage::AppendEntries { entries, .. }) = node.create_append_entries(2) else {
+            panic!("expected AppendEntries");
+        };
+        assert_eq!(entries.len(), 2);
+    }
+}
+
+// =============================================================================
+// SECTION 58: PROPOSAL LIMITS
+// =============================================================================
+
+mod proposal_limits {
+    use super::*;
+    use raft_core::ProposeError;
+
+    #[test]
+    fn oversized_command_is_refused() {
+        let mut node = leader(RaftConfig::builder().max_proposal_bytes(8).build().unwrap());
+        assert_eq!(node.propose(vec![0; 8]), Ok(2));
+        assert_eq!(node.propose(vec![0; 9]), Err(ProposeError::TooLarge { size: 9, limit: 8 }));
+        assert_eq!(
+            node.propose_many(vec![vec![0; 1], vec![0; 20]]),
+            Err(ProposeError::TooLarge { size: 20, limit: 8 })
+        );
+        assert_eq!(node.last_log_index(), 2);
+    }
+
+    #[test]
+    fn uncommitted_tail_is_capped() {
+        let mut node = leader(RaftConfig::builder().max_uncommitted_bytes(100).build().unwrap());
+        // the no-op counts 16 bytes, each 20-byte command 36
+        assert_eq!(node.uncommitted_bytes(), 16);
+        node.propose(vec![0; 20]).unwrap();
+        node.propose(vec![0; 20]).unwrap();
+        assert_eq!(
+            node.propose(vec![0; 20]),
+            Err(ProposeError::UncommittedFull { uncommitted: 88, limit: 100 })
+        );
+
+        // once a follower catches up the tail commits and proposals resume
+        node.handle_append_entries_response(1, true, 2, 3);
+        assert_eq!(node.uncommitted_bytes(), 0);
+        assert_eq!(node.propose(vec![0; 20]), Ok(4));
+    }
+
+    #[test]
+    fn batch_counts_as_a_whole() {
+        let mut node = leader(RaftConfig::builder().max_uncommitted_bytes(100).build().unwrap());
+        let result = node.propose_many(vec![vec![0; 20]; 3]);
+        assert_eq!(result, Err(ProposeError::UncommittedFull { uncommitted: 16, limit: 100 }));
+        assert_eq!(node.last_log_index(), 1);
+    }
+
+    #[test]
+    fn large_proposal_passes_when_nothing_is_waiting() {
+        let mut node = leader(RaftConfig::builder().max_uncommitted_bytes(100).build().unwrap());
+        node.handle_append_entries_response(1, true, 2, 1);
+        assert_eq!(node.propose(vec![0; 500]), Ok(2));
+        assert!(matches!(node.propose(vec![0; 1]), Err(ProposeError::UncommittedFull { .. })));
+    }
+
+    #[test]
+    fn conf_changes_ignore_the_cap() {
+        let mut node = leader(RaftConfig::builder().max_uncommitted_bytes(50).build().unwrap());
+        node.propose(vec![0; 10]).unwrap();
+        assert!(node.propose(vec![0; 10]).is_err());
+        assert_eq!(node.propose_conf_change(b"remove 3".to_vec()), Ok(3));
+    }
+}
+
+// =============================================================================
+// SECTION 59: STEP DOWN
+// =============================================================================
+
+mod step_down {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::ProposeError;
+
+    #[test]
+    fn step_down_keeps_term_and_vote() {
+        let mut node = committed_leader(RaftConfig::default());
+        assert!(node.step_down(None));
+        assert_eq!(node.state, NodeState::Follower);
+        assert_eq!((node.current_term, node.voted_for), (1, Some(1)));
+        assert_eq!(node.leader_id, None);
+        assert!(matches!(node.propose(b"x".to_vec()), Err(ProposeError::NotLeader { leader_hint: None })));
+    }
+
+    #[test]
+    fn only_a_leader_steps_down() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        assert!(!node.step_down(None));
+        assert!(!node.step_down(Some(2)));
+    }
+
+    #[test]
+    fn step_down_with_transfer_hands_over() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        assert!(cluster.node_mut(1).step_down(Some(2)));
+        cluster.heartbeat();
+
+        assert_eq!(cluster.leader(), Some(2));
+        assert_eq!(cluster.node(1).state, NodeState::Follower);
+        assert_eq!(cluster.node(2).current_term, 2);
+    }
+
+    #[test]
+    fn failed_handover_still_steps_down() {
+        let mut node = committed_leader(RaftConfig::default());
+        assert!(node.step_down(Some(2)));
+        assert_eq!(node.state, NodeState::Leader);
+
+        // the TimeoutNow was lost: after an election timeout we go anyway
+        node.tick(node.config.election_timeout_max);
+        assert_eq!(node.state, NodeState::Follower);
+        assert_eq!(node.current_term, 1);
+    }
+
+    #[test]
+    fn refused_handover_leaves_leader_in_place() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        // node 3 hasn't acked anything yet
+        assert!(!node.step_down(Some(3)));
+        assert_eq!(node.state, NodeState::Leader);
+        assert_eq!(node.transfer_target(), None);
+    }
+}
+
+// =============================================================================
+// SECTION 60: PAUSE AND RESUME
+// =============================================================================
+
+mod pause_resume {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::PauseMode;
+
+    #[test]
+    fn paused_node_ignores_ticks() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.pause(PauseMode::Drop);
+        let ready = node.tick(10_000);
+        assert!(ready.is_empty());
+        assert_eq!(node.state, NodeState::Follower);
+        assert_eq!(node.now_ms(), 0);
+    }
+
+    #[test]
+    fn queued_messages_are_delivered_on_resume() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.pause(PauseMode::Queue);
+        let append = |index: u64| RaftMessage::AppendEntries {
+            term: 1,
+            leader_id: 1,
+            prev_log_index: index - 1,
+            prev_log_term: if index == 1 { 0 } else { 1 },
+            entries: vec![LogEntry::new(1, index, vec![index as u8])],
+            leader_commit: 0,
+            seq: 0,
+            group_id: None,
+        };
+        assert!(node.step(1, append(1)).is_empty());
+        assert!(node.step(1, append(2)).is_empty());
+        assert_eq!(node.queued_message_count(), 2);
+        assert_eq!(node.last_log_index(), 0);
+
+        let ready = node.resume();
+        assert_eq!(node.pause_mode(), None);
+        assert_eq!(ready.entries.len(), 2);
+        assert_eq!(ready.messages.len(), 2);
+        assert_eq!(node.leader_id, Some(1));
+        assert_eq!(node.queued_message_count(), 0);
+    }
+
+    #[test]
+    fn dropped_messages_are_gone() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.pause(PauseMode::Drop);
+        node.step(1, RaftMessage::Heartbeat { term: 3, leader_id: 1, commit: 0, seq: 0, group_id: None });
+        assert_eq!(node.queued_message_count(), 0);
+        assert!(node.resume().is_empty());
+        assert_eq!(node.current_term, 0);
+    }
+
+    #[test]
+    fn paused_leader_is_replaced_and_catches_up() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.pause(1, PauseMode::Queue);
+        cluster.elect(2);
+        cluster.propose(b"while frozen");
+        assert_eq!(cluster.node(1).state, NodeState::Leader, "a frozen node notices nothing");
+
+        cluster.resume(1);
+        cluster.heartbeat();
+        assert_eq!(cluster.leader(), Some(2));
+        cluster.assert_logs_converged();
+    }
+}
+
+// =============================================================================
+// SECTION 61: QUORUM POLICY
+// =============================================================================
+
+mod quorum_policy {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::{ConfigError, QuorumPolicy};
+
+    fn flexible(replication: usize) -> RaftConfig {
+        RaftConfig { quorum_policy: QuorumPolicy::Flexible { replication }, ..RaftConfig::default() }
+    }
+
+    #[test]
+    fn majority_is_the_default() {
+        let node = RaftNode::new(1, vec![1, 2, 3, 4, 5]);
+        assert_eq!(node.quorum_size(), 3);
+        assert_eq!(node.election_quorum_size(), 3);
+    }
+
+    #[test]
+    fn flexible_trades_election_size_for_commit_size() {
+        let node = RaftNode::with_config(1, vec![1, 2, 3, 4, 5], flexible(2));
+        assert_eq!(node.quorum_size(), 2);
+        assert_eq!(node.election_quorum_size(), 4);
+    }
+
+    #[test]
+    fn flexible_candidate_needs_the_larger_quorum() {
+        let config = RaftConfig { pre_vote: false, ..flexible(2) };
+        let mut node = RaftNode::with_config(1, vec![1, 2, 3, 4, 5], config);
+        let timeout = node.randomized_election_timeout();
+        node.tick(timeout);
+        assert_eq!(node.state, NodeState::Candidate);
+
+        let granted = RaftMessage::VoteResponse { term: 1, vote_granted: true, commit_index: 0, last_log_index: 0, group_id: None };
+        node.step(2, granted.clone());
+        node.step(3, granted.clone());
+        assert_eq!(node.state, NodeState::Candidate, "a majority isn't enough");
+        node.step(4, granted);
+        assert_eq!(node.state, NodeState::Leader);
+    }
+
+    #[test]
+    fn flexible_leader_commits_with_one_follower() {
+        let mut cluster = TestCluster::with_config(5, flexible(2));
+        cluster.elect(1);
+        for id in [3, 4, 5] {
+            cluster.isolate(id);
+        }
+        let index = cluster.propose(b"fast");
+        assert_eq!(cluster.node(1).commit_index, index);
+        assert_eq!(cluster.node(2).commit_index, index);
+    }
+
+    #[test]
+    fn required_member_must_hold_the_entry() {
+        let config = RaftConfig { quorum_policy: QuorumPolicy::Required { members: vec![3] }, ..RaftConfig::default() };
+        let mut cluster = TestCluster::with_config(3, config);
+        cluster.elect(1);
+        cluster.isolate(3);
+        let index = cluster.propose(b"needs 3");
+        assert!(cluster.node(1).commit_index < index, "1 and 2 are a majority but lack node 3");
+
+        cluster.heal(3);
+        cluster.heartbeat();
+        cluster.heartbeat();
+        assert_eq!(cluster.node(1).commit_index, index);
+    }
+
+    #[test]
+    fn builder_rejects_an_empty_replication_quorum() {
+        let err = RaftConfig::builder().quorum_policy(QuorumPolicy::Flexible { replication: 0 }).build().unwrap_err();
+        assert_eq!(err, ConfigError::Zero { field: "quorum_policy.replication" });
+    }
+}
+
+// =============================================================================
+// SECTION 62: BOOTSTRAP
+// =============================================================================
+
+mod bootstrap {
+    use super::*;
+    use raft_core::{EntryType, Membership};
+
+    #[test]
+    fn bootstrap_writes_a_committed_membership_entry() {
+        let mut node = RaftNode::new(1, Vec::new());
+        assert!(node.bootstrap(vec![1, 2, 3]));
+        assert_eq!(node.cluster_nodes, vec![1, 2, 3]);
+        assert_eq!(node.current_term, 1);
+        assert_eq!(node.commit_index, 1);
+
+        let ready = node.ready();
+        assert_eq!(ready.entries.len(), 1);
+        assert_eq!(ready.entries[0].entry_type, EntryType::Config);
+        assert_eq!(Membership::decode(&ready.entries[0]), Some(Membership { voters: vec![1, 2, 3] }));
+        assert_eq!(ready.hard_state.map(|hs| hs.term), Some(1));
+    }
+
+    #[test]
+    fn bootstrap_only_on_a_new_node() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        assert!(node.bootstrap(vec![1, 2, 3]));
+        assert!(!node.bootstrap(vec![1, 2]));
+        assert_eq!(node.cluster_nodes, vec![1, 2, 3]);
+
+        let mut empty = RaftNode::new(1, Vec::new());
+        assert!(!empty.bootstrap(Vec::new()));
+    }
+
+    #[test]
+    fn membership_is_restored_from_the_log() {
+        let mut founder = RaftNode::new(1, Vec::new());
+        founder.bootstrap(vec![1, 2, 3]);
+
+        let mut restarted = RaftNode::new(1, Vec::new());
+        restarted.log = founder.log.clone();
+        assert!(restarted.restore_membership());
+        assert_eq!(restarted.cluster_nodes, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn membership_survives_compaction() {
+        let mut node = RaftNode::new(1, Vec::new());
+        node.bootstrap(vec![1, 2, 3]);
+        node.ready();
+        node.get_entries_to_apply();
+        assert!(node.compact(1, b"state".to_vec()));
+        assert_eq!(node.snapshot.as_ref().map(|s| s.voters.clone()), Some(vec![1, 2, 3]));
+
+        let mut restarted = RaftNode::new(1, Vec::new());
+        restarted.restore_snapshot(node.snapshot.clone().unwrap());
+        assert!(restarted.restore_membership());
+        assert_eq!(restarted.cluster_nodes, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn host_config_entries_are_not_membership() {
+        let entry = LogEntry::with_type(1, 1, EntryType::Config, b"{\"voters\":[1]}".to_vec());
+        assert_eq!(Membership::decode(&entry), None);
+        assert!(!RaftNode::new(1, vec![1]).restore_membership());
+    }
+}
+
+// =============================================================================
+// SECTION 63: MEMBER REMOVAL
+// =============================================================================
+
+mod member_removal {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::ProposeError;
+
+    #[test]
+    fn removed_node_never_campaigns() {
+        let mut node = RaftNode::new(3, vec![1, 2, 3]);
+        assert!(node.remove_member(3));
+        assert!(node.is_removed());
+
+        let ready = node.tick(node.config.election_timeout_max * 5);
+        assert!(ready.messages.is_empty());
+        assert_eq!(node.state, NodeState::Follower);
+    }
+
+    #[test]
+    fn removed_event_is_reported_once() {
+        let mut node = RaftNode::new(3, vec![1, 2, 3]);
+        node.remove_member(3);
+        assert!(node.ready().removed);
+        assert!(!node.ready().removed);
+    }
+
+    #[test]
+    fn removed_leader_steps_down_and_refuses_proposals() {
+        let mut node = RaftNode::new(1, vec![1]);
+        node.become_leader();
+        assert!(node.remove_member(1));
+        assert_eq!(node.state, NodeState::Follower);
+        assert_eq!(node.propose(b"x".to_vec()), Err(ProposeError::Removed));
+    }
+
+    #[test]
+    fn removing_a_peer_shrinks_the_quorum() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.isolate(3);
+        cluster.isolate(2);
+        let index = cluster.propose(b"stuck");
+        assert!(cluster.node(1).commit_index < index);
+
+        for id in cluster.ids() {
+            cluster.node_mut(id).remove_member(3);
+            cluster.node_mut(id).remove_member(2);
+        }
+        assert_eq!(cluster.node(1).commit_index, index);
+        assert!(cluster.node(3).is_removed());
+    }
+
+    #[test]
+    fn unknown_members_are_not_removed() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        assert!(!node.remove_member(9));
+        assert!(!node.ready().removed);
+    }
+}
+
+// =============================================================================
+// SECTION 64: ELECTION STAGGER
+// =============================================================================
+
+mod election_stagger {
+    use super::*;
+
+    fn staggered(id: u64) -> RaftNode {
+        let config = RaftConfig::builder().election_stagger(100).rng_seed(7).build().unwrap();
+        RaftNode::with_config(id, vec![30, 10, 20], config)
+    }
+
+    #[test]
+    fn off_by_default() {
+        let node = RaftNode::new(3, vec![1, 2, 3]);
+        assert_eq!(node.election_offset(), 0);
+    }
+
+    #[test]
+    fn offsets_follow_rank_by_id() {
+        assert_eq!(staggered(10).election_offset(), 0);
+        assert_eq!(staggered(20).election_offset(), 100);
+        assert_eq!(staggered(30).election_offset(), 200);
+    }
+
+    #[test]
+    fn offset_is_added_to_every_timeout() {
+        let mut node = staggered(30);
+        for _ in 0..20 {
+            node.reset_election_timer();
+            let timeout = node.randomized_election_timeout();
+            assert!(timeout >= node.config.election_timeout_min + 200);
+            assert!(timeout <= node.config.election_timeout_max + 200);
+        }
+    }
+
+    #[test]
+    fn non_voters_have_no_offset() {
+        let mut node = staggered(40);
+        node.add_learner(40);
+        assert_eq!(node.election_offset(), 0);
+    }
+}
+
+// =============================================================================
+// SECTION 65: CHUNKED SNAPSHOTS
+// =============================================================================
+
+mod chunked_snapshots {
+    use super::*;
+    use raft_core::{ConfigError, Snapshot};
+
+    fn chunked(id: u64) -> RaftNode {
+        let config = RaftConfig::builder().snapshot_chunk_bytes(16).build().unwrap();
+        RaftNode::with_config(id, vec![1, 2, 3], config)
+    }
+
+    /// leader streaming its snapshot at index 5 to node 2, first chunk out
+    fn streaming_leader() -> (RaftNode, RaftMessage) {
+        let mut node = chunked(1);
+        node.restore_snapshot(Snapshot::new(5, 1, b"a state machine snapshot".to_vec()));
+        node.current_term = 1;
+        node.start_election();
+        node.handle_vote_response(2, true, 2);
+        node.ready();
+        node.progress.get_mut(&2).unwrap().next_index = 1;
+        assert!(node.send_append(2));
+        let mut messages = node.ready().messages;
+        assert_eq!(messages.len(), 1);
+        (node, messages.remove(0).1)
+    }
+
+    fn next_offset(msg: &RaftMessage) -> u64 {
+        match msg {
+            RaftMessage::InstallSnapshotChunkResponse { next_offset, .. } => *next_offset,
+            other => panic!("expected InstallSnapshotChunkResponse, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn zero_chunk_size_is_rejected() {
+        let err = RaftConfig::builder().snapshot_chunk_bytes(0).build().unwrap_err();
+        assert_eq!(err, ConfigError::Zero { field: "snapshot_chunk_bytes" });
+    }
+
+    #[test]
+    fn snapshot_is_streamed_and_installed() {
+        let (mut leader, first) = streaming_leader();
+        assert!(matches!(first, RaftMessage::InstallSnapshotChunk { offset: 0, done: false, .. }));
+        let mut follower = chunked(2);
+
+        let mut chunks = 1;
+        let mut inbound = follower.step(1, first).messages;
+        let mut installed = None;
+        while let Some((_, msg)) = inbound.pop() {
+            for (_, reply) in leader.step(2, msg).messages {
+                if matches!(reply, RaftMessage::InstallSnapshotChunk { .. }) {
+                    chunks += 1;
+                }
+                let ready = follower.step(1, reply);
+                installed = installed.or(ready.snapshot);
+                inbound.extend(ready.messages);
+            }
+        }
+
+        assert!(chunks > 2, "{} chunks", chunks);
+        assert_eq!(installed.map(|s| s.data), Some(b"a state machine snapshot".to_vec()));
+        assert_eq!(follower.commit_index, 5);
+        assert_eq!(leader.progress[&2].match_index, 6, "snapshot plus the leader's no-op");
+    }
+
+    #[test]
+    fn out_of_order_chunk_asks_for_the_missing_offset() {
+        let (_, first) = streaming_leader();
+        let mut follower = chunked(2);
+        let reply = follower.step(1, first).messages.remove(0).1;
+        assert_eq!(next_offset(&reply), 16);
+
+        let gap = RaftMessage::InstallSnapshotChunk {
+            term: 2,
+            leader_id: 1,
+            last_included_index: 5,
+            last_included_term: 1,
+            offset: 32,
+            data: vec![0; 16],
+            done: false,
+            group_id: None,
+        };
+        let reply = follower.step(1, gap).messages.remove(0).1;
+        assert_eq!(next_offset(&reply), 16);
+    }
+
+    #[test]
+    fn lost_chunk_is_resent_from_the_confirmed_offset() {
+        let (mut leader, first) = streaming_leader();
+        let mut follower = chunked(2);
+        let reply = follower.step(1, first).messages.remove(0).1;
+        // the chunk at 16 is lost in transit
+        assert_eq!(leader.step(2, reply).messages.len(), 1);
+
+        leader.handle_heartbeat_response(2, 2, 0);
+        assert!(matches!(
+            leader.ready().messages.as_slice(),
+            [(2, RaftMessage::InstallSnapshotChunk { offset: 16, .. })]
+        ));
+    }
+
+    #[test]
+    fn duplicate_answer_sends_nothing() {
+        let (mut leader, first) = streaming_leader();
+        let mut follower = chunked(2);
+        let reply = follower.step(1, first).messages.remove(0).1;
+        assert_eq!(leader.step(2, reply.clone()).messages.len(), 1);
+        assert!(leader.step(2, reply).messages.is_empty());
+    }
+
+    #[test]
+    fn new_term_discards_a_partial_snapshot() {
+        let (_, first) = streaming_leader();
+        let mut follower = chunked(2);
+        follower.step(1, first);
+
+        let from_new_leader = RaftMessage::InstallSnapshotChunk {
+            term: 3,
+            leader_id: 3,
+            last_included_index: 5,
+            last_included_term: 1,
+            offset: 16,
+            data: vec![0; 16],
+            done: false,
+            group_id: None,
+        };
+        let reply = follower.step(3, from_new_leader).messages.remove(0).1;
+        assert_eq!(next_offset(&reply), 0, "the stream starts over");
+    }
+
+    #[test]
+    fn stale_chunk_is_rejected_with_our_term() {
+        let mut follower = chunked(2);
+        follower.current_term = 4;
+        let (_, first) = streaming_leader();
+        assert!(matches!(
+            follower.step(1, first).messages.as_slice(),
+            [(1, RaftMessage::InstallSnapshotResponse { term: 4, match_index: 0, .. })]
+        ));
+    }
+}
+
+// =============================================================================
+// SECTION 66: SNAPSHOT THROTTLING
+// =============================================================================
+
+mod snapshot_throttling {
+    use super::*;
+    use raft_core::{ConfigError, ProgressState, Snapshot};
+
+    /// leader of 1..=3 whose followers both need its 500 byte snapshot
+    fn snapshotting(config: RaftConfig) -> RaftNode {
+        let mut node = leader(config);
+        node.restore_snapshot(Snapshot::new(5, 1, vec![7; 500]));
+        for id in [2, 3] {
+            let progress = node.progress.get_mut(&id).unwrap();
+            progress.become_probe();
+            progress.next_index = 1;
+        }
+        node
+    }
+
+    #[test]
+    fn zero_limits_are_rejected() {
+        let err = RaftConfig::builder().max_snapshots_in_flight(0).build().unwrap_err();
+        assert_eq!(err, ConfigError::Zero { field: "max_snapshots_in_flight" });
+        let err = RaftConfig::builder().snapshot_bytes_per_sec(0).build().unwrap_err();
+        assert_eq!(err, ConfigError::Zero { field: "snapshot_bytes_per_sec" });
+    }
+
+    #[test]
+    fn one_snapshot_at_a_time_by_default() {
+        let mut node = snapshotting(RaftConfig::default());
+        assert!(node.send_append(2));
+        assert!(!node.send_append(3), "node 2 holds the only slot");
+
+        node.report_snapshot(2, true);
+        assert!(node.send_append(3));
+        assert_eq!(node.progress[&3].state, ProgressState::Snapshot);
+    }
+
+    #[test]
+    fn more_slots_send_concurrently() {
+        let config = RaftConfig::builder().max_snapshots_in_flight(2).build().unwrap();
+        let mut node = snapshotting(config);
+        assert!(node.send_append(2));
+        assert!(node.send_append(3));
+    }
+
+    #[test]
+    fn byte_rate_spaces_out_sends() {
+        let config = RaftConfig::builder().snapshot_bytes_per_sec(1000).build().unwrap();
+        let mut node = snapshotting(config);
+        assert!(node.send_append(2));
+        node.report_snapshot(2, false);
+
+        assert!(!node.send_append(2), "500 bytes at 1000/s pays off in 500ms");
+        node.tick(250);
+        assert!(!node.send_append(2));
+        node.tick(250);
+        assert!(node.send_append(2));
+    }
+
+    #[test]
+    fn late_rejection_keeps_the_snapshot_in_flight() {
+        let mut node = snapshotting(RaftConfig::default());
+        assert!(node.send_append(2));
+
+        node.handle_append_entries_response(1, false, 2, 0);
+        assert_eq!(node.progress[&2].state, ProgressState::Snapshot);
+        assert!(!node.send_append(2), "no second copy of the snapshot");
+    }
+}
+
+// =============================================================================
+// SECTION 67: UNSTABLE ENTRIES
+// =============================================================================
+
+mod unstable_entries {
+    use super::*;
+
+    fn async_node(id: u64) -> RaftNode {
+        let config = RaftConfig::builder().async_persist(true).build().unwrap();
+        RaftNode::with_config(id, vec![1, 2, 3], config)
+    }
+
+    /// async leader of term 1 with its no-op at 1 and `x` at 2, neither durable
+    fn async_leader() -> RaftNode {
+        let mut node = async_node(1);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        node.append_entry(b"x".to_vec());
+        let ready = node.ready();
+        assert_eq!(ready.entries.len(), 2);
+        node
+    }
+
+    fn append(term: u64, prev_log_index: u64, entries: Vec<LogEntry>) -> RaftMessage {
+        RaftMessage::AppendEntries { term, leader_id: 1, prev_log_index, prev_log_term: 1, entries, leader_commit: 0, seq: 0, group_id: None }
+    }
+
+    fn acked(messages: &[(u64, RaftMessage)]) -> Vec<u64> {
+        messages
+            .iter()
+            .filter_map(|(_, msg)| match msg {
+                RaftMessage::AppendEntriesResponse { success: true, match_index, .. } => Some(*match_index),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn everything_is_stable_by_default() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.start_election();
+        node.handle_vote_response(1, true, 2);
+        node.append_entry(b"x".to_vec());
+        assert_eq!(node.stable_index(), 2);
+    }
+
+    #[test]
+    fn leader_counts_itself_once_persisted() {
+        let mut node = async_leader();
+        assert_eq!(node.stable_index(), 0);
+
+        node.handle_append_entries_response(1, true, 2, 2);
+        assert_eq!(node.commit_index, 0, "only node 2 holds index 2 durably");
+
+        node.report_persisted(2, 1);
+        assert_eq!(node.stable_index(), 2);
+        assert_eq!(node.commit_index, 2);
+    }
+
+    #[test]
+    fn unstable_entries_are_still_replicated() {
+        let mut node = async_leader();
+        node.progress.get_mut(&2).unwrap().next_index = 1;
+        match node.create_append_entries(2) {
+            Some(RaftMessage::AppendEntries { entries, .. }) => assert_eq!(entries.len(), 2),
+            other => panic!("expected AppendEntries, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn follower_acks_only_what_is_durable() {
+        let mut follower = async_node(2);
+        let entries = vec![LogEntry::new(1, 1, vec![]), LogEntry::new(1, 2, vec![])];
+        let ready = follower.step(1, append(1, 0, entries));
+        assert_eq!(acked(&ready.messages), vec![0]);
+
+        follower.report_persisted(1, 1);
+        assert_eq!(acked(&follower.ready().messages), vec![1]);
+        follower.report_persisted(2, 1);
+        assert_eq!(acked(&follower.ready().messages), vec![2]);
+        follower.report_persisted(2, 1);
+        assert!(follower.ready().messages.is_empty(), "nothing more is owed");
+    }
+
+    #[test]
+    fn report_for_an_overwritten_entry_is_ignored() {
+        let mut follower = async_node(2);
+        follower.step(1, append(1, 0, vec![LogEntry::new(1, 1, vec![]), LogEntry::new(1, 2, vec![])]));
+        follower.report_persisted(1, 1);
+        // a new leader replaces index 2 before storage gets to it
+        follower.step(1, append(2, 1, vec![LogEntry::new(2, 2, vec![])]));
+
+        follower.report_persisted(2, 1);
+        assert_eq!(follower.stable_index(), 1);
+        follower.report_persisted(2, 2);
+        assert_eq!(follower.stable_index(), 2);
+    }
+}
+
+// =============================================================================
+// SECTION 68: HEARTBEAT SUPPRESSION
+// =============================================================================
+
+mod heartbeat_suppression {
+    use super::*;
+    use raft_core::Ready;
+
+    /// `caught_up` leader whose entry 2 node 3 acked first, then sent to and
+    /// acked by node 2 with the commit index that ack produced
+    fn commit_unseen_by_3() -> RaftNode {
+        let mut node = caught_up(RaftConfig::default());
+        node.append_entry(b"x".to_vec());
+        assert!(node.send_append(3));
+        node.step(3, append_ok(2));
+        assert_eq!(node.commit_index, 2);
+        assert!(node.send_append(2));
+        node.step(2, append_ok(2));
+        node.ready();
+        node
+    }
+
+    fn heartbeat_targets(ready: &Ready) -> Vec<u64> {
+        let mut targets: Vec<u64> = ready
+            .messages
+            .iter()
+            .filter(|(_, msg)| matches!(msg, RaftMessage::Heartbeat { .. }))
+            .map(|&(to, _)| to)
+            .collect();
+        targets.sort_unstable();
+        targets
+    }
+
+    #[test]
+    fn caught_up_follower_that_knows_the_commit_is_skipped() {
+        let mut node = commit_unseen_by_3();
+        let ready = node.broadcast_heartbeat();
+        assert_eq!(heartbeat_targets(&ready), vec![3], "node 3 hasn't heard about commit 2");
+    }
+
+    #[test]
+    fn skip_lasts_one_heartbeat_interval() {
+        let mut node = commit_unseen_by_3();
+        node.tick(node.config.heartbeat_interval);
+        assert_eq!(node.broadcast_heartbeat().messages.len(), 2);
+    }
+
+    #[test]
+    fn follower_short_of_our_log_still_gets_heartbeats() {
+        let mut node = commit_unseen_by_3();
+        node.append_entry(b"y".to_vec());
+        assert!(node.send_append(2));
+        node.ready();
+        assert_eq!(heartbeat_targets(&node.broadcast_heartbeat()), vec![2, 3]);
+    }
+
+    #[test]
+    fn pending_reads_are_confirmed_by_everyone() {
+        let mut node = commit_unseen_by_3();
+        assert!(node.read_index(b"r".to_vec()));
+        assert_eq!(heartbeat_targets(&node.broadcast_heartbeat()), vec![2, 3]);
+    }
+}
+
+// =============================================================================
+// SECTION 69: MESSAGE SEQUENCING
+// =============================================================================
+
+mod message_sequencing {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::ProgressState;
+
+    /// hand the leader's messages to their followers, returning what each
+    /// answered as (from, response), without delivering the answers
+    fn answers(cluster: &mut TestCluster, leader: u64, messages: Vec<(u64, RaftMessage)>) -> Vec<(u64, RaftMessage)> {
+        let mut answers = Vec::new();
+        for (to, msg) in messages {
+            let ready = cluster.node_mut(to).step(leader, msg);
+            answers.extend(ready.messages.into_iter().filter(|&(dest, _)| dest == leader).map(|(_, msg)| (to, msg)));
+        }
+        answers
+    }
+
+    fn appends_to(cluster: &mut TestCluster, follower: u64, command: &[u8]) -> Vec<(u64, RaftMessage)> {
+        let leader = cluster.node_mut(1);
+        leader.propose(command.to_vec()).unwrap();
+        leader.replicate().messages.into_iter().filter(|&(to, _)| to == follower).collect()
+    }
+
+    #[test]
+    fn requests_are_numbered_and_answers_echo_them() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let first = appends_to(&mut cluster, 2, b"a");
+        let second = appends_to(&mut cluster, 2, b"b");
+        let (first_seq, second_seq) = (first[0].1.seq(), second[0].1.seq());
+        assert!(first_seq > 0 && second_seq > first_seq);
+
+        let answered = answers(&mut cluster, 1, first.into_iter().chain(second).collect());
+        let echoed: Vec<u64> = answered.iter().map(|(_, msg)| msg.seq()).collect();
+        assert_eq!(echoed, vec![first_seq, second_seq]);
+    }
+
+    #[test]
+    fn reordered_answer_is_ignored() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        let first = appends_to(&mut cluster, 2, b"a");
+        let second = appends_to(&mut cluster, 2, b"b");
+        let mut answered = answers(&mut cluster, 1, first.into_iter().chain(second).collect());
+        let (_, newer) = answered.pop().unwrap();
+        let (_, older) = answered.pop().unwrap();
+        let last = cluster.node(1).last_log_index();
+
+        cluster.node_mut(1).step(2, newer);
+        assert_eq!(cluster.node(1).progress[&2].match_index, last);
+
+        // an old rejection arriving late must not knock the follower back to probing
+        let term = cluster.node(1).current_term;
+        let stale = RaftMessage::AppendEntriesResponse {
+            term,
+            success: false,
+            match_index: 0,
+            storage_health: None,
+            backoff: false,
+            seq: older.seq(),
+            group_id: None,
+        };
+        cluster.node_mut(1).step(2, stale);
+        cluster.node_mut(1).step(2, older);
+        let progress = &cluster.node(1).progress[&2];
+        assert_eq!(progress.state, ProgressState::Replicate);
+        assert_eq!(progress.match_index, last);
+        assert_eq!(progress.next_index, last + 1);
+    }
+
+    #[test]
+    fn answer_to_an_earlier_heartbeat_does_not_confirm_a_later_read() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        assert!(cluster.node_mut(1).read_index(b"first".to_vec()));
+        let round = cluster.node_mut(1).broadcast_heartbeat().messages;
+        let early = answers(&mut cluster, 1, round);
+
+        assert!(cluster.node_mut(1).read_index(b"second".to_vec()));
+        let round = cluster.node_mut(1).broadcast_heartbeat().messages;
+        let late = answers(&mut cluster, 1, round);
+
+        // the early answers were sent before the second read started
+        let mut confirmed = Vec::new();
+        for (from, msg) in early {
+            confirmed.extend(cluster.node_mut(1).step(from, msg).read_states);
+        }
+        assert_eq!(confirmed.len(), 1);
+        assert_eq!(confirmed[0].request_ctx, b"first".to_vec());
+
+        let (from, msg) = late.into_iter().next().unwrap();
+        let ready = cluster.node_mut(1).step(from, msg);
+        assert_eq!(ready.read_states.len(), 1);
+        assert_eq!(ready.read_states[0].request_ctx, b"second".to_vec());
+    }
+
+    #[test]
+    fn unnumbered_answers_keep_the_ordered_delivery_assumption() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        assert!(cluster.node_mut(1).read_index(b"r".to_vec()));
+        cluster.node_mut(1).broadcast_heartbeat();
+        let term = cluster.node(1).current_term;
+        let last_log_index = cluster.node(1).last_log_index();
+        let ready = cluster.node_mut(1).step(2, RaftMessage::HeartbeatResponse { term, last_log_index, seq: 0, group_id: None });
+        assert_eq!(ready.read_states.len(), 1);
+    }
+
+    #[test]
+    fn acceptance_from_an_earlier_term_is_ignored() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        node.current_term = 1;
+        node.start_election();
+        node.handle_vote_response(2, true, 2);
+        assert_eq!(node.log[0].term, 2);
+
+        // node 2 accepting index 1 back when it held something from term 1
+        node.step(2, append_ok(1));
+        assert_eq!(node.progress[&2].match_index, 0);
+        assert_eq!(node.commit_index, 0, "the term-2 no-op reached nobody");
+    }
+}
+
+// =============================================================================
+// SECTION 70: BOUNDED APPLY
+// =============================================================================
+
+mod bounded_apply {
+    use super::*;
+    use raft_core::ConfigError;
+
+    /// a follower of 1..=3 that has just been sent ten committed entries
+    fn caught_up_follower(config: RaftConfig) -> (RaftNode, Vec<LogEntry>) {
+        let mut node = RaftNode::with_config(2, vec![1, 2, 3], config);
+        let entries: Vec<LogEntry> = (1..=10).map(|i| LogEntry::new(1, i, vec![i as u8; 100])).collect();
+        let ready = node.step(1, RaftMessage::AppendEntries {
+            term: 1,
+            leader_id: 1,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: entries.clone(),
+            leader_commit: 10,
+            seq: 0,
+            group_id: None,
+        });
+        (node, ready.committed_entries)
+    }
+
+    fn indexes(entries: &[LogEntry]) -> Vec<u64> {
+        entries.iter().map(|e| e.index).collect()
+    }
+
+    #[test]
+    fn zero_batch_is_rejected() {
+        let err = RaftConfig::builder().max_apply_batch(0).build().unwrap_err();
+        assert_eq!(err, ConfigError::Zero { field: "max_apply_batch" });
+        let err = RaftConfig::builder().max_apply_batch_bytes(0).build().unwrap_err();
+        assert_eq!(err, ConfigError::Zero { field: "max_apply_batch_bytes" });
+    }
+
+    #[test]
+    fn unbounded_by_default() {
+        let (node, first) = caught_up_follower(RaftConfig::default());
+        assert_eq!(first.len(), 10);
+        assert!(!node.has_pending_apply());
+    }
+
+    #[test]
+    fn entries_drain_in_batches_across_ticks() {
+        let config = RaftConfig::builder().max_apply_batch(4).build().unwrap();
+        let (mut node, first) = caught_up_follower(config);
+        assert_eq!(indexes(&first), vec![1, 2, 3, 4]);
+        assert_eq!(node.last_applied, 4);
+        assert!(node.has_pending_apply());
+
+        assert_eq!(indexes(&node.tick(1).committed_entries), vec![5, 6, 7, 8]);
+        assert_eq!(indexes(&node.tick(1).committed_entries), vec![9, 10]);
+        assert!(!node.has_pending_apply());
+        assert!(node.tick(1).committed_entries.is_empty());
+    }
+
+    #[test]
+    fn byte_limit_bounds_a_batch() {
+        let size = LogEntry::new(1, 1, vec![0; 100]).size_bytes();
+        let config = RaftConfig::builder().max_apply_batch_bytes(3 * size + 1).build().unwrap();
+        let (mut node, first) = caught_up_follower(config);
+        assert_eq!(indexes(&first), vec![1, 2, 3]);
+        assert_eq!(indexes(&node.ready().committed_entries), vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn entry_over_the_byte_limit_still_goes_out_alone() {
+        let config = RaftConfig::builder().max_apply_batch_bytes(1).build().unwrap();
+        let (mut node, first) = caught_up_follower(config);
+        assert_eq!(indexes(&first), vec![1]);
+        assert_eq!(indexes(&node.ready().committed_entries), vec![2]);
+    }
+}
+
+// =============================================================================
+// SECTION 71: OPERATOR REPLICATION PAUSE
+// =============================================================================
+
+mod replication_pause {
+    use super::*;
+    use raft_core::testing::TestCluster;
+
+    fn cluster() -> TestCluster {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster
+    }
+
+    #[test]
+    fn paused_peer_is_sent_nothing_and_the_rest_commit() {
+        let mut cluster = cluster();
+        let before = cluster.node(3).last_log_index();
+        assert!(cluster.node_mut(1).pause_replication(3));
+        assert!(cluster.node(1).is_replication_paused(3));
+
+        let index = cluster.propose(b"x");
+        assert_eq!(cluster.node(1).commit_index, index);
+        assert_eq!(cluster.node(2).last_log_index(), index);
+        assert_eq!(cluster.node(3).last_log_index(), before);
+
+        assert!(cluster.node_mut(1).read_index(b"r".to_vec()));
+        let ready = cluster.node_mut(1).broadcast_heartbeat();
+        assert!(ready.messages.iter().all(|&(to, _)| to != 3));
+    }
+
+    #[test]
+    fn resumed_peer_catches_up() {
+        let mut cluster = cluster();
+        cluster.node_mut(1).pause_replication(3);
+        let index = cluster.propose(b"x");
+
+        assert!(cluster.node_mut(1).resume_replication(3));
+        assert!(!cluster.node(1).is_replication_paused(3));
+        cluster.tick(1);
+        assert_eq!(cluster.node(3).last_log_index(), index);
+        cluster.assert_logs_converged();
+    }
+
+    #[test]
+    fn caught_up_peer_gets_a_heartbeat_on_resume() {
+        let mut cluster = cluster();
+        let node = cluster.node_mut(1);
+        node.ready();
+        assert!(node.pause_replication(2));
+        assert!(node.resume_replication(2));
+        let ready = node.ready();
+        assert!(matches!(ready.messages.as_slice(), [(2, RaftMessage::Heartbeat { .. })]));
+    }
+
+    #[test]
+    fn paused_peer_is_no_transfer_target() {
+        let mut cluster = cluster();
+        cluster.node_mut(1).pause_replication(2);
+        assert!(!cluster.node_mut(1).transfer_leadership(2));
+        assert!(cluster.node_mut(1).transfer_leadership(3));
+    }
+
+    #[test]
+    fn only_a_leader_pauses_its_followers() {
+        let mut cluster = cluster();
+        assert!(!cluster.node_mut(2).pause_replication(3));
+        assert!(!cluster.node_mut(1).pause_replication(9));
+        assert!(!cluster.node_mut(1).resume_replication(3), "wasn't paused");
+    }
+
+    #[test]
+    fn new_leadership_forgets_the_pause() {
+        let mut cluster = cluster();
+        cluster.node_mut(1).pause_replication(3);
+        cluster.elect(2);
+        cluster.elect(1);
+        assert!(!cluster.node(1).is_replication_paused(3));
+    }
+}
+
+// =============================================================================
+// SECTION 72: PROBE BACKOFF
+// =============================================================================
+
+mod probe_backoff {
+    use super::*;
+    use raft_core::ConfigError;
+
+    /// tick for `ms` in steps of 10, with node 2 answering every heartbeat
+    /// and node 3 down; returns how many heartbeats each was sent
+    fn run(node: &mut RaftNode, ms: u64) -> (usize, usize) {
+        let (mut to_2, mut to_3) = (0, 0);
+        for _ in 0..ms / 10 {
+            for (to, msg) in node.tick(10).messages {
+                let RaftMessage::Heartbeat { term, seq, .. } = msg else { continue };
+                if to == 2 {
+                    to_2 += 1;
+                    let last_log_index = node.last_log_index();
+                    node.step(2, RaftMessage::HeartbeatResponse { term, last_log_index, seq, group_id: None });
+                } else {
+                    to_3 += 1;
+                }
+            }
+        }
+        (to_2, to_3)
+    }
+
+    #[test]
+    fn backoff_must_stay_below_the_election_timeout() {
+        let err = RaftConfig::builder().probe_backoff_max(150).build().unwrap_err();
+        assert_eq!(err, ConfigError::ProbeBackoffTooLong { probe_backoff_max: 150, election_timeout_min: 150 });
+        let err = RaftConfig::builder().probe_backoff_max(0).build().unwrap_err();
+        assert_eq!(err, ConfigError::Zero { field: "probe_backoff_max" });
+    }
+
+    #[test]
+    fn off_by_default() {
+        let mut node = leader(RaftConfig::default());
+        let (to_2, to_3) = run(&mut node, 1000);
+        assert_eq!(to_2, 20);
+        assert_eq!(to_3, 20);
+    }
+
+    #[test]
+    fn silent_follower_is_heartbeaten_less_often() {
+        let mut node = leader(RaftConfig::builder().probe_backoff_max(140).build().unwrap());
+        let (to_2, to_3) = run(&mut node, 1000);
+        assert_eq!(to_2, 20, "a follower that answers keeps the full rate");
+        // 50, 100, 200, then capped: every 150ms (the first tick past 140)
+        assert_eq!(to_3, 8);
+        assert!(node.progress[&3].probe_failures > 0);
+    }
+
+    #[test]
+    fn any_response_restores_the_full_rate() {
+        let mut node = leader(RaftConfig::builder().probe_backoff_max(140).build().unwrap());
+        run(&mut node, 1000);
+        let term = node.current_term;
+        node.step(3, RaftMessage::HeartbeatResponse { term, last_log_index: 0, seq: 0, group_id: None });
+        assert_eq!(node.progress[&3].probe_failures, 0);
+        let ready = node.tick(node.config.heartbeat_interval);
+        assert!(ready.messages.iter().any(|(to, msg)| *to == 3 && matches!(msg, RaftMessage::Heartbeat { .. })));
+    }
+}
+
+// =============================================================================
+// SECTION 73: CRASH RECOVERY
+// =============================================================================
+
+mod crash_recovery {
+    use super::*;
+    use raft_core::{HardState, Snapshot, StateMachine};
+
+    #[derive(Default)]
+    struct Journal(Vec<u8>);
+
+    impl StateMachine for Journal {
+        fn apply(&mut self, entry: &LogEntry) -> Vec<u8> {
+            self.0.extend(&entry.command);
+            Vec::new()
+        }
+
+        fn snapshot(&self) -> Vec<u8> {
+            self.0.clone()
+        }
+
+        fn restore(&mut self, data: &[u8]) {
+            self.0 = data.to_vec();
+        }
+    }
+
+    fn log(terms: &[u64]) -> Vec<LogEntry> {
+        terms.iter().enumerate().map(|(i, &t)| LogEntry::new(t, i as u64 + 1, vec![b'a' + i as u8])).collect()
+    }
+
+    #[test]
+    fn replays_the_committed_tail_after_the_snapshot() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        let mut machine = Journal::default();
+        let snapshot = Snapshot::new(2, 1, b"ab".to_vec());
+        let applied = node
+            .recover(HardState { term: 2, vote: Some(2) }, Some(snapshot), log(&[1, 1, 2, 2, 2]), 4, &mut machine)
+            .unwrap();
+
+        assert_eq!(applied.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(machine.0, b"abcd");
+        assert_eq!(node.current_term, 2);
+        assert_eq!(node.voted_for, Some(2));
+        assert_eq!(node.commit_index, 4);
+        assert_eq!(node.last_applied, 4);
+        assert!(node.ready().hard_state.is_none(), "the recovered hard state is already durable");
+    }
+
+    #[test]
+    fn commit_is_capped_at_the_log() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        let mut machine = Journal::default();
+        let applied = node.recover(HardState { term: 1, vote: None }, None, log(&[1, 1]), 9, &mut machine).unwrap();
+
+        assert_eq!(applied.len(), 2);
+        assert_eq!(node.commit_index, 2);
+        assert_eq!(machine.0, b"ab");
+    }
+
+    #[test]
+    fn recovering_with_verify_rejoin_starts_the_handshake() {
+        let config = RaftConfig::builder().verify_rejoin(true).build().unwrap();
+        let mut node = RaftNode::with_config(1, vec![1, 2, 3], config);
+        let mut machine = Journal::default();
+        node.recover(HardState { term: 2, vote: None }, None, log(&[1, 2]), 2, &mut machine).unwrap();
+
+        assert!(node.is_rejoining());
+        let ready = node.ready();
+        assert_eq!(ready.messages.len(), 2);
+        assert!(ready.messages.iter().all(|(_, msg)| matches!(
+            msg,
+            RaftMessage::RejoinRequest { last_log_index: 2, last_log_term: 2, .. }
+        )));
+    }
+}
+
+// =============================================================================
+// SECTION 74: RAFT GROUPS
+// =============================================================================
+
+mod raft_groups {
+    use super::*;
+
+    fn grouped(id: u64, group: u64) -> RaftNode {
+        RaftNode::with_config(id, vec![1, 2, 3], RaftConfig::builder().group_id(group).build().unwrap())
+    }
+
+    fn vote_request(group_id: Option<u64>) -> RaftMessage {
+        RaftMessage::VoteRequest {
+            term: 1,
+            candidate_id: 2,
+            last_log_index: 0,
+            last_log_term: 0,
+            transfer: false,
+            group_id,
+        }
+    }
+
+    #[test]
+    fn every_message_carries_the_group() {
+        let mut node = grouped(1, 7);
+        let ready = node.tick(node.config.election_timeout_max * 2);
+        assert!(!ready.messages.is_empty());
+        assert!(ready.messages.iter().all(|(_, msg)| msg.group_id() == Some(7)));
+
+        let mut plain = RaftNode::new(1, vec![1, 2, 3]);
+        let ready = plain.tick(plain.config.election_timeout_max * 2);
+        assert!(ready.messages.iter().all(|(_, msg)| msg.group_id().is_none()));
+    }
+
+    #[test]
+    fn messages_for_another_group_are_dropped() {
+        let mut node = grouped(1, 7);
+        for group in [Some(8), None] {
+            let ready = node.step(2, vote_request(group));
+            assert!(ready.messages.is_empty(), "{:?}", group);
+            assert_eq!(node.current_term, 0);
+        }
+
+        let ready = node.step(2, vote_request(Some(7)));
+        assert!(matches!(
+            ready.messages.as_slice(),
+            [(2, RaftMessage::VoteResponse { vote_granted: true, group_id: Some(7), .. })]
+        ));
+    }
+
+    #[test]
+    fn ungrouped_nodes_ignore_tagged_messages() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        assert!(node.step(2, vote_request(Some(7))).messages.is_empty());
+        assert_eq!(node.current_term, 0);
+    }
+
+    #[test]
+    fn untagged_messages_keep_the_old_encoding() {
+        let json = serde_json::to_string(&vote_request(None)).unwrap();
+        assert!(!json.contains("group_id"));
+        let tagged: RaftMessage = serde_json::from_str(&serde_json::to_string(&vote_request(Some(7))).unwrap()).unwrap();
+        assert_eq!(tagged.group_id(), Some(7));
+    }
+}
+
+// =============================================================================
+// SECTION 75: WIRE ENCODING
+// =============================================================================
+
+mod wire_encoding {
+    use super::*;
+    use raft_core::{Snapshot, WireError, MESSAGE_VERSION};
+
+    fn one_of_each() -> Vec<RaftMessage> {
+        let group_id = Some(3);
+        vec![
+            RaftMessage::PreVoteRequest { term: 2, candidate_id: 1, last_log_index: 4, last_log_term: 1, group_id },
+            RaftMessage::PreVoteResponse { term: 2, vote_granted: true, commit_index: 3, last_log_index: 4, group_id },
+            RaftMessage::VoteRequest { term: 2, candidate_id: 1, last_log_index: 4, last_log_term: 1, transfer: true, group_id },
+            RaftMessage::VoteResponse { term: 2, vote_granted: true, commit_index: 3, last_log_index: 4, group_id },
+            RaftMessage::AppendEntries {
+                term: 2,
+                leader_id: 1,
+                prev_log_index: 3,
+                prev_log_term: 1,
+                entries: vec![LogEntry::new(2, 4, b"set".to_vec())],
+                leader_commit: 3,
+                seq: 5,
+                group_id,
+            },
+            RaftMessage::AppendEntriesResponse {
+                term: 2,
+                success: true,
+                match_index: 4,
+                storage_health: None,
+                backoff: true,
+                seq: 5,
+                group_id,
+            },
+            RaftMessage::InstallSnapshot { term: 2, leader_id: 1, snapshot: Snapshot::new(3, 1, b"s".to_vec()), group_id },
+            RaftMessage::InstallSnapshotResponse { term: 2, match_index: 3, group_id },
+            RaftMessage::InstallSnapshotChunk {
+                term: 2,
+                leader_id: 1,
+                last_included_index: 3,
+                last_included_term: 1,
+                offset: 8,
+                data: b"chunk".to_vec(),
+                done: false,
+                group_id,
+            },
+            RaftMessage::InstallSnapshotChunkResponse { term: 2, last_included_index: 3, next_offset: 13, group_id },
+            RaftMessage::Heartbeat { term: 2, leader_id: 1, commit: 3, seq: 6, group_id },
+            RaftMessage::HeartbeatResponse { term: 2, last_log_index: 4, seq: 6, group_id },
+            RaftMessage::TimeoutNow { term: 2, group_id },
+            RaftMessage::ReadQuorumRequest { read_id: 7, group_id },
+            RaftMessage::ReadQuorumResponse { read_id: 7, last_log_index: 4, group_id },
+            RaftMessage::RejoinRequest { cluster_id: Some(9), last_log_index: 4, last_log_term: 2, snapshot_index: 3, group_id },
+            RaftMessage::RejoinResponse {
+                term: 2,
+                refusal: Some(raft_core::RejoinError::LogAhead { last_log_index: 4, last_log_term: 5 }),
+                group_id,
+            },
+        ]
+    }
+
+    #[test]
+    fn every_message_round_trips() {
+        for msg in one_of_each() {
+            let decoded = RaftMessage::from_wire(&msg.to_wire()).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", msg));
+        }
+    }
+
+    #[test]
+    fn version_and_type_come_first() {
+        let msg = RaftMessage::TimeoutNow { term: 2, group_id: None };
+        let wire = String::from_utf8(msg.to_wire()).unwrap();
+        assert_eq!(wire, format!(r#"{{"message_version":{},"type":"timeout_now","term":2}}"#, MESSAGE_VERSION));
+    }
+
+    #[test]
+    fn fields_from_newer_versions_are_skipped() {
+        let wire = br#"{"message_version":1,"type":"heartbeat","term":2,"leader_id":1,"commit":3,"trace":"abc"}"#;
+        let msg = RaftMessage::from_wire(wire).unwrap();
+        assert!(matches!(msg, RaftMessage::Heartbeat { term: 2, leader_id: 1, commit: 3, seq: 0, .. }));
+    }
+
+    #[test]
+    fn unknown_types_and_newer_versions_are_reported() {
+        let unknown = br#"{"message_version":1,"type":"gossip","term":2}"#;
+        assert_eq!(RaftMessage::from_wire(unknown).unwrap_err(), WireError::UnknownType { name: "gossip".into() });
+
+        let newer = format!(r#"{{"message_version":{},"type":"timeout_now","term":2}}"#, MESSAGE_VERSION + 1);
+        assert_eq!(
+            RaftMessage::from_wire(newer.as_bytes()).unwrap_err(),
+            WireError::UnsupportedVersion { version: MESSAGE_VERSION + 1 }
+        );
+        assert_eq!(RaftMessage::from_wire(b"not json").unwrap_err(), WireError::Malformed);
+    }
+
+    #[test]
+    fn bare_serde_messages_still_decode() {
+        for msg in one_of_each() {
+            let decoded = RaftMessage::from_wire(&serde_json::to_vec(&msg).unwrap()).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", msg));
+        }
+    }
+}
+
+// =============================================================================
+// SECTION 76: COLD LOG SPILLING
+// =============================================================================
+
+mod cold_log {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::{ChangelogError, ColdLog, ConfigError};
+    use std::ops::Range;
+
+    /// a copy of the log standing in for the host's storage
+    #[derive(Debug)]
+    struct Cold(Vec<LogEntry>);
+
+    impl ColdLog for Cold {
+        fn read_entries(&self, range: Range<u64>) -> Option<Vec<LogEntry>> {
+            Some(self.0.iter().filter(|e| range.contains(&e.index)).cloned().collect())
+        }
+    }
+
+    /// node 1 leads with eight entries applied; node 3 missed all but the first
+    fn cluster() -> TestCluster {
+        let config = RaftConfig::builder().max_hot_entries(2).build().unwrap();
+        let mut cluster = TestCluster::with_config(3, config);
+        cluster.elect(1);
+        cluster.isolate(3);
+        for i in 0..7u8 {
+            cluster.propose(&[i]);
+        }
+        cluster
+    }
+
+    /// hand node 1 a cold log holding its whole log, then let it spill
+    fn spill(cluster: &mut TestCluster, cold: Vec<LogEntry>) {
+        cluster.node_mut(1).set_cold_log(Box::new(Cold(cold)));
+        cluster.node_mut(1).ready();
+    }
+
+    #[test]
+    fn zero_hot_entries_is_rejected() {
+        let err = RaftConfig::builder().max_hot_entries(0).build().unwrap_err();
+        assert_eq!(err, ConfigError::Zero { field: "max_hot_entries" });
+    }
+
+    #[test]
+    fn nothing_spills_without_a_cold_log() {
+        let mut cluster = cluster();
+        cluster.node_mut(1).ready();
+        assert_eq!(cluster.node(1).log.len(), 8);
+        assert_eq!(cluster.node(1).spilled_index(), 0);
+    }
+
+    #[test]
+    fn applied_entries_past_the_hot_tail_are_spilled() {
+        let mut cluster = cluster();
+        let log = cluster.node(1).log.clone();
+        spill(&mut cluster, log.clone());
+
+        let node = cluster.node(1);
+        assert_eq!(node.spilled_index(), 6);
+        assert_eq!(node.first_log_index(), 7);
+        assert_eq!(node.log.len(), 2);
+        assert_eq!(node.last_log_index(), 8);
+        assert_eq!(node.get_term_at(3), log[2].term, "read back from the cold log");
+        let changes = node.committed_since(None, 100).unwrap();
+        assert_eq!(changes.iter().map(|e| e.index).collect::<Vec<_>>(), (1..=8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn lagging_follower_catches_up_from_the_cold_log() {
+        let mut cluster = cluster();
+        let log = cluster.node(1).log.clone();
+        spill(&mut cluster, log);
+
+        cluster.heal(3);
+        cluster.heartbeat();
+        cluster.heartbeat();
+        assert_eq!(cluster.node(3).last_log_index(), 8);
+        assert_eq!(cluster.node(3).commit_index, 8);
+        let commands = |id: u64| cluster.applied(id).iter().map(|e| e.command.clone()).collect::<Vec<_>>();
+        assert_eq!(commands(3), commands(2));
+    }
+
+    #[test]
+    fn unreadable_entries_are_reported() {
+        let mut cluster = cluster();
+        spill(&mut cluster, Vec::new());
+
+        assert_eq!(cluster.node(1).spilled_index(), 6);
+        assert_eq!(cluster.node(1).committed_since(None, 100).unwrap_err(), ChangelogError::Unavailable);
+
+        // nothing can be sent, so node 3 stays behind instead of taking a gap
+        cluster.heal(3);
+        cluster.heartbeat();
+        cluster.heartbeat();
+        assert_eq!(cluster.node(3).last_log_index(), 1);
+    }
+}
+
+// =============================================================================
+// SECTION 77: LOG HASH CHAIN
+// =============================================================================
+
+mod hash_chain {
+    use super::*;
+    use raft_core::testing::TestCluster;
+    use raft_core::{ChainBreak, HardState, StateMachine};
+
+    struct Discard;
+
+    impl StateMachine for Discard {
+        fn apply(&mut self, _entry: &LogEntry) -> Vec<u8> {
+            Vec::new()
+        }
+
+        fn snapshot(&self) -> Vec<u8> {
+            Vec::new()
+        }
+
+        fn restore(&mut self, _data: &[u8]) {}
+    }
+
+    fn chained() -> RaftConfig {
+        RaftConfig::builder().hash_chain(true).build().unwrap()
+    }
+
+    /// `commands` as a chained log in term 1
+    fn chain(commands: &[&[u8]]) -> Vec<LogEntry> {
+        let mut prev = None;
+        let mut log = Vec::new();
+        for (i, command) in commands.iter().enumerate() {
+            let mut entry = LogEntry::new(1, i as u64 + 1, command.to_vec());
+            entry.hash = Some(entry.chain_hash(prev));
+            prev = entry.hash;
+            log.push(entry);
+        }
+        log
+    }
+
+    fn append(entries: Vec<LogEntry>) -> RaftMessage {
+        RaftMessage::AppendEntries {
+            term: 1,
+            leader_id: 1,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries,
+            leader_commit: 0,
+            seq: 0,
+            group_id: None,
+        }
+    }
+
+    #[test]
+    fn off_by_default() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.propose(b"x");
+        assert!(cluster.node(1).log.iter().all(|e| e.hash.is_none()));
+    }
+
+    #[test]
+    fn every_replica_holds_the_leaders_chain() {
+        let mut cluster = TestCluster::with_config(3, chained());
+        cluster.elect(1);
+        cluster.propose(b"x");
+        cluster.propose(b"y");
+
+        let hashes = |id: u64| cluster.node(id).log.iter().map(|e| e.hash).collect::<Vec<_>>();
+        assert!(hashes(1).iter().all(Option::is_some));
+        assert_eq!(hashes(2), hashes(1));
+        assert_eq!(hashes(3), hashes(1));
+        for id in 1..=3 {
+            assert_eq!(cluster.node(id).verify_log(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn follower_refuses_an_entry_that_does_not_link() {
+        let mut node = RaftNode::with_config(2, vec![1, 2, 3], chained());
+        let mut entries = chain(&[b"a", b"b", b"c"]);
+        entries[1].command = b"B".to_vec();
+
+        let ready = node.step(1, append(entries));
+        assert!(matches!(ready.messages[0].1, RaftMessage::AppendEntriesResponse { success: false, match_index: 1, .. }));
+        assert_eq!(node.last_log_index(), 1, "what linked is kept");
+        assert_eq!(node.chain_break(), Some(ChainBreak { index: 2 }));
+
+        node.step(1, append(chain(&[b"a", b"b", b"c"])));
+        assert_eq!(node.last_log_index(), 3);
+        assert_eq!(node.chain_break(), None);
+    }
+
+    #[test]
+    fn a_dropped_hash_breaks_the_chain() {
+        let mut log = chain(&[b"a", b"b", b"c"]);
+        assert_eq!(LogEntry::verify_chain(None, &log), Ok(()));
+        log[2].hash = None;
+        assert_eq!(LogEntry::verify_chain(None, &log), Err(ChainBreak { index: 3 }));
+    }
+
+    #[test]
+    fn recovery_refuses_a_tampered_log() {
+        let mut log = chain(&[b"a", b"b"]);
+        log[0].term = 2;
+        let mut node = RaftNode::with_config(1, vec![1, 2, 3], chained());
+        let mut machine = Discard;
+        assert!(node.recover(HardState { term: 2, vote: None }, None, log, 2, &mut machine).is_none());
+        assert_eq!(node.current_term, 0, "nothing changed");
+        assert!(node.log.is_empty());
+    }
+
+    #[test]
+    fn the_chain_continues_past_a_snapshot() {
+        let mut cluster = TestCluster::with_config(3, chained());
+        cluster.elect(1);
+        cluster.propose(b"x");
+        let applied = cluster.node(1).last_applied;
+        assert!(cluster.node_mut(1).compact(applied, Vec::new()));
+        let snapshot = cluster.node(1).snapshot.clone().unwrap();
+        assert!(snapshot.metadata.last_included_hash.is_some());
+
+        cluster.propose(b"y");
+        assert_eq!(cluster.node(1).verify_log(), Ok(()));
+        assert_eq!(cluster.node(2).log.last().unwrap().hash, cluster.node(1).log.last().unwrap().hash);
+    }
+}
+
+// =============================================================================
+// SECTION 78: TRANSFER TARGET SELECTION
+// =============================================================================
+
+mod transfer_target {
+    use super::*;
+    use raft_core::testing::TestCluster;
+
+    #[test]
+    fn only_a_leader_picks_one() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        assert_eq!(cluster.node(2).best_transfer_target(), None);
+        assert!(cluster.node(1).best_transfer_target().is_some());
+    }
+
+    #[test]
+    fn the_most_caught_up_follower_wins() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.isolate(3);
+        cluster.propose(b"x");
+        assert_eq!(cluster.node(1).best_transfer_target(), Some(2));
+        assert!(cluster.node_mut(1).transfer_leadership(2));
+    }
+
+    #[test]
+    fn silent_and_paused_followers_are_skipped() {
+        let mut cluster = TestCluster::new(3);
+        cluster.elect(1);
+        cluster.isolate(2);
+        let window = cluster.node(1).config.election_timeout_min;
+        cluster.tick(window);
+        assert_eq!(cluster.node(1).best_transfer_target(), Some(3));
+
+        cluster.node_mut(1).pause_replication(3);
+        assert_eq!(cluster.node(1).best_transfer_target(), None);
+    }
+
+    #[test]
+    fn ties_go_to_the_higher_priority() {
+        let config = RaftConfig::builder().priority(1, 10).priority(2, 1).priority(3, 2).build().unwrap();
+        let mut cluster = TestCluster::with_config(3, config);
+        cluster.elect(1);
+        assert_eq!(cluster.node(1).best_transfer_target(), Some(3));
+    }
+}
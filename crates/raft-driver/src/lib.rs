@@ -0,0 +1,439 @@
+//! # raft-driver
+//!
+//! why: run the ready loop (persist, send, apply) once instead of in every embedder
+//! relations: owns a raft-storage DurableNode and a StateMachine, ticks and steps raft-core's RaftNode on tokio
+//! what: Driver (the event loop), Handle (proposals, reads, transfers, config changes as futures), Transport, DriverError
+
+use raft_core::{
+    ConfChangeError, EntryType, LogEntry, NodeState, ProposalOutcome, ProposeError, RaftMessage, RaftNode, Ready,
+    SoftState, StateMachine,
+};
+use raft_storage::{DurableNode, Storage};
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{Instant, MissedTickBehavior};
+
+/// how the driver hands messages to other nodes
+///
+/// delivery may be lossy, late or out of order: raft retries on its own.
+/// `send` must not block, so transports queue or spawn
+pub trait Transport: Send + 'static {
+    /// deliver `msg` to node `to`
+    fn send(&mut self, to: u64, msg: RaftMessage);
+}
+
+impl<F: FnMut(u64, RaftMessage) + Send + 'static> Transport for F {
+    fn send(&mut self, to: u64, msg: RaftMessage) {
+        self(to, msg)
+    }
+}
+
+/// why a request through a Handle failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum DriverError {
+    /// the node refused the proposal
+    #[error(transparent)]
+    Propose(ProposeError),
+    /// the node refused the config change
+    #[error(transparent)]
+    ConfChange(ConfChangeError),
+    /// reads and transfers are served by the leader; retry on `leader_hint`
+    #[error("{}", ProposeError::NotLeader { leader_hint: *.leader_hint })]
+    NotLeader { leader_hint: Option<u64> },
+    /// a newer leader overwrote the entry, so it will never commit; retry
+    #[error("the entry was overwritten by a newer leader")]
+    Superseded,
+    /// a snapshot replaced the entry before this node saw it commit, so
+    /// whether it did is unknown (see `RaftNode::verify_commit`)
+    #[error("a snapshot replaced the entry before it was seen to commit")]
+    Compacted,
+    /// `target` can't take over, or another node won the election first
+    #[error("leadership did not move to node {target}")]
+    TransferFailed { target: u64 },
+    /// no follower can take over (see `RaftNode::best_transfer_target`)
+    #[error("no follower can take over leadership")]
+    NoTransferTarget,
+    /// the driver stopped (storage failed, or the node was removed)
+    #[error("the raft driver has stopped")]
+    Stopped,
+}
+
+/// where the answer to a request goes
+type Reply<T> = oneshot::Sender<Result<T, DriverError>>;
+
+/// runs a confirmed read against the state machine, or fails it
+type ReadFn<M> = Box<dyn FnOnce(Result<&M, DriverError>) + Send>;
+
+/// sees each committed config entry (see `Driver::on_conf_change`)
+type ConfChangeHook = Box<dyn FnMut(&mut RaftNode, &LogEntry) + Send>;
+
+/// a request from a Handle
+enum Command<M> {
+    Step { from: u64, msg: RaftMessage },
+    Propose { command: Vec<u8>, reply: Reply<Vec<u8>> },
+    ConfChange { change: Vec<u8>, reply: Reply<u64> },
+    Read { read: ReadFn<M> },
+    /// to `target`, or to the best follower if None
+    Transfer { target: Option<u64>, reply: Reply<u64> },
+    Status { reply: oneshot::Sender<SoftState> },
+}
+
+/// the event loop around one node
+///
+/// owns the node, its storage and its state machine, and keeps the Ready
+/// contract for every step: entries and hard state are durable before a
+/// message goes out (DurableNode), messages go out before anything is
+/// applied, and reads run only once the machine has applied their index.
+/// ticks come from a timer every `RaftConfig::tick_interval`; everything
+/// else arrives through a `Handle`
+///
+/// `Ready::compact_to` compacts the in-memory log only, so the stored log
+/// stays whole. an installed snapshot is stored before the log it
+/// replaces is dropped, and a restart restores it and replays the log
+/// past it (see `DurableNode::recover`). the storage must keep snapshots
+/// (see `Storage::save_snapshot`), or the first install stops the driver
+pub struct Driver<S: Storage, M: StateMachine, T: Transport> {
+    node: DurableNode<S>,
+    machine: M,
+    transport: T,
+    commands: mpsc::UnboundedReceiver<Command<M>>,
+    /// open proposals and config changes by index, with the term they went in at
+    proposals: HashMap<u64, (u64, Reply<Vec<u8>>)>,
+    conf_changes: HashMap<u64, (u64, Reply<u64>)>,
+    /// reads waiting for a quorum to confirm them, by request context
+    unconfirmed_reads: HashMap<Vec<u8>, ReadFn<M>>,
+    /// confirmed reads waiting for the machine to reach their index
+    confirmed_reads: Vec<(u64, ReadFn<M>)>,
+    next_read: u64,
+    transfer: Option<(u64, Reply<u64>)>,
+    on_conf_change: Option<ConfChangeHook>,
+}
+
+impl<S, M, T> Driver<S, M, T>
+where
+    S: Storage + Send + 'static,
+    M: StateMachine + Send + 'static,
+    T: Transport,
+{
+    /// a driver for `node`, applying to `machine`, and the handle that feeds it
+    ///
+    /// `machine` must already match the node (see `DurableNode::recover`).
+    /// nothing happens until `run` is polled
+    pub fn new(node: DurableNode<S>, machine: M, transport: T) -> (Self, Handle<M>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let driver = Self {
+            node,
+            machine,
+            transport,
+            commands: rx,
+            proposals: HashMap::new(),
+            conf_changes: HashMap::new(),
+            unconfirmed_reads: HashMap::new(),
+            confirmed_reads: Vec::new(),
+            next_read: 0,
+            transfer: None,
+            on_conf_change: None,
+        };
+        (driver, Handle { commands: tx })
+    }
+
+    /// run `hook` on every committed `Config` entry, before the config
+    /// change that proposed it resolves
+    ///
+    /// config entries never reach the state machine, and raft-core leaves
+    /// their encoding to the host: this is where the host decodes one and
+    /// applies it to the node (`add_learner`, `promote_learner`,
+    /// `remove_member`). it runs on every member, leader or not
+    pub fn on_conf_change(mut self, hook: impl FnMut(&mut RaftNode, &LogEntry) + Send + 'static) -> Self {
+        self.on_conf_change = Some(Box::new(hook));
+        self
+    }
+
+    /// the node being driven
+    pub fn node(&self) -> &RaftNode {
+        self.node.node()
+    }
+
+    /// the state machine being driven
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// drive the node until every Handle is dropped or the node is removed
+    ///
+    /// a storage error stops the loop and is returned: the Ready that
+    /// failed to persist is dropped unsent. so does a leader refusing the
+    /// node back in after a restart, as InvalidData wrapping the
+    /// `RejoinError` (see `RaftNode::quarantine`). requests still open then
+    /// fail with `DriverError::Stopped`
+    pub async fn run(mut self) -> io::Result<()> {
+        let tick = Duration::from_millis(self.node.node().config.tick_interval.max(1));
+        let mut ticker = tokio::time::interval(tick);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut last_tick = Instant::now();
+
+        loop {
+            let ready = tokio::select! {
+                _ = ticker.tick() => {
+                    // whole milliseconds only; the remainder carries over
+                    let elapsed = last_tick.elapsed().as_millis() as u64;
+                    last_tick += Duration::from_millis(elapsed);
+                    self.node.tick(elapsed)?
+                }
+                command = self.commands.recv() => match command {
+                    Some(command) => self.handle(command)?,
+                    None => return Ok(()),
+                },
+            };
+            self.process(ready)?;
+            if self.node.node().is_removed() {
+                return Ok(());
+            }
+            if let Some(refusal) = self.node.node().quarantine() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, refusal));
+            }
+        }
+    }
+
+    /// carry out one request, returning the Ready it produced
+    fn handle(&mut self, command: Command<M>) -> io::Result<Ready> {
+        match command {
+            Command::Step { from, msg } => return self.node.step(from, msg),
+            Command::Propose { command, reply } => match self.node.node_mut().propose_tracked(command) {
+                Ok(proof) => {
+                    self.proposals.insert(proof.index, (proof.term, reply));
+                    return self.node.replicate();
+                }
+                Err(e) => {
+                    let _ = reply.send(Err(DriverError::Propose(e)));
+                }
+            },
+            Command::ConfChange { change, reply } => match self.node.node_mut().propose_conf_change(change) {
+                Ok(index) => {
+                    self.conf_changes.insert(index, (self.node.node().current_term, reply));
+                    return self.node.replicate();
+                }
+                Err(e) => {
+                    let _ = reply.send(Err(DriverError::ConfChange(e)));
+                }
+            },
+            Command::Read { read } => {
+                self.next_read += 1;
+                let ctx = self.next_read.to_be_bytes().to_vec();
+                if self.node.node_mut().read_index(ctx.clone()) {
+                    self.unconfirmed_reads.insert(ctx, read);
+                } else {
+                    read(Err(self.not_leader()));
+                }
+            }
+            Command::Transfer { target, reply } => {
+                let target = target.or_else(|| self.node.node().best_transfer_target());
+                match target {
+                    _ if self.node.node().state != NodeState::Leader => {
+                        let _ = reply.send(Err(self.not_leader()));
+                    }
+                    None => {
+                        let _ = reply.send(Err(DriverError::NoTransferTarget));
+                    }
+                    Some(target) if self.node.node_mut().transfer_leadership(target) => {
+                        self.transfer = Some((target, reply));
+                    }
+                    Some(target) => {
+                        let _ = reply.send(Err(DriverError::TransferFailed { target }));
+                    }
+                }
+            }
+            Command::Status { reply } => {
+                let node = self.node.node();
+                let _ = reply.send(SoftState { state: node.state, leader_id: node.leader_id });
+            }
+        }
+        self.node.ready()
+    }
+
+    /// send, apply and answer everything in `ready` (already persisted),
+    /// then drain any committed entries it held back
+    fn process(&mut self, mut ready: Ready) -> io::Result<()> {
+        loop {
+            for (to, msg) in ready.messages.drain(..) {
+                self.transport.send(to, msg);
+            }
+
+            let results: HashMap<u64, Vec<u8>> = self.node.node().apply_ready(&ready, &mut self.machine).into_iter().collect();
+            for entry in &ready.committed_entries {
+                self.settle(entry, results.get(&entry.index));
+            }
+            for (proof, outcome) in &ready.proposals {
+                let error = match outcome {
+                    ProposalOutcome::Committed => continue,
+                    ProposalOutcome::Superseded => DriverError::Superseded,
+                    ProposalOutcome::Compacted => DriverError::Compacted,
+                };
+                if self.proposals.get(&proof.index).is_some_and(|(term, _)| *term == proof.term) {
+                    let (_, reply) = self.proposals.remove(&proof.index).expect("just checked");
+                    let _ = reply.send(Err(error));
+                }
+            }
+
+            for read in ready.read_states.drain(..) {
+                if let Some(run) = self.unconfirmed_reads.remove(&read.request_ctx) {
+                    self.confirmed_reads.push((read.index, run));
+                }
+            }
+            if ready.soft_state.is_some_and(|soft| soft.state != NodeState::Leader) {
+                // stepping down dropped the reads still waiting for a quorum
+                let error = self.not_leader();
+                for (_, run) in self.unconfirmed_reads.drain() {
+                    run(Err(error));
+                }
+            }
+            self.serve_reads();
+            self.check_transfer();
+
+            if let Some(index) = ready.compact_to {
+                let data = self.machine.snapshot();
+                self.node.node_mut().compact(index, data);
+            }
+
+            if !self.node.node().has_pending_apply() {
+                return Ok(());
+            }
+            ready = self.node.ready()?;
+        }
+    }
+
+    /// resolve whatever was waiting for `entry` to commit
+    fn settle(&mut self, entry: &LogEntry, result: Option<&Vec<u8>>) {
+        if entry.entry_type == EntryType::Config {
+            if let Some(hook) = &mut self.on_conf_change {
+                hook(self.node.node_mut(), entry);
+            }
+            if let Some((term, reply)) = self.conf_changes.remove(&entry.index) {
+                let outcome = if term == entry.term { Ok(entry.index) } else { Err(DriverError::Superseded) };
+                let _ = reply.send(outcome);
+            }
+        }
+        if let Some((term, reply)) = self.proposals.remove(&entry.index) {
+            // a session duplicate commits without running: it has no result
+            let outcome = if term == entry.term {
+                Ok(result.cloned().unwrap_or_default())
+            } else {
+                Err(DriverError::Superseded)
+            };
+            let _ = reply.send(outcome);
+        }
+    }
+
+    /// run the confirmed reads the machine has caught up with
+    fn serve_reads(&mut self) {
+        let applied = self.node.node().last_applied;
+        let (ready, waiting) = std::mem::take(&mut self.confirmed_reads)
+            .into_iter()
+            .partition(|(index, _)| *index <= applied);
+        self.confirmed_reads = waiting;
+        for (_, run) in ready {
+            run(Ok(&self.machine));
+        }
+    }
+
+    /// resolve the transfer in progress once its outcome is known
+    fn check_transfer(&mut self) {
+        let Some((target, _)) = self.transfer else {
+            return;
+        };
+        let node = self.node.node();
+        let outcome = match (node.state, node.leader_id) {
+            // still leading and no longer handing over: it timed out
+            (NodeState::Leader, _) if node.transfer_target() != Some(target) => Err(DriverError::TransferFailed { target }),
+            (NodeState::Leader, _) | (_, None) => return,
+            (_, Some(leader)) if leader == target => Ok(target),
+            (_, Some(_)) => Err(DriverError::TransferFailed { target }),
+        };
+        if let Some((_, reply)) = self.transfer.take() {
+            let _ = reply.send(outcome);
+        }
+    }
+
+    fn not_leader(&self) -> DriverError {
+        DriverError::NotLeader { leader_hint: self.node.node().leader_id }
+    }
+}
+
+/// the way into a running Driver; cheap to clone and share between tasks
+///
+/// every request is a future that resolves once raft has decided it. if
+/// the driver stops first, it fails with `DriverError::Stopped`
+pub struct Handle<M> {
+    commands: mpsc::UnboundedSender<Command<M>>,
+}
+
+impl<M> Clone for Handle<M> {
+    fn clone(&self) -> Self {
+        Self { commands: self.commands.clone() }
+    }
+}
+
+impl<M: StateMachine + 'static> Handle<M> {
+    /// hand the driver a message from node `from` (what the transport calls
+    /// on receipt); dropped if the driver has stopped
+    pub fn step(&self, from: u64, msg: RaftMessage) {
+        let _ = self.commands.send(Command::Step { from, msg });
+    }
+
+    /// propose `command` and wait for it to be applied, returning what the
+    /// state machine returned for it
+    pub async fn propose(&self, command: Vec<u8>) -> Result<Vec<u8>, DriverError> {
+        let (reply, rx) = oneshot::channel();
+        self.request(Command::Propose { command, reply }, rx).await?
+    }
+
+    /// linearizable read: `f` runs against the state machine once this
+    /// node has confirmed it still leads and has applied everything
+    /// committed before the read (ReadIndex)
+    pub async fn read<R, F>(&self, f: F) -> Result<R, DriverError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&M) -> R + Send + 'static,
+    {
+        let (reply, rx) = oneshot::channel();
+        let read: ReadFn<M> = Box::new(move |machine| {
+            let _ = reply.send(machine.map(f));
+        });
+        self.request(Command::Read { read }, rx).await?
+    }
+
+    /// hand leadership to `target` and wait until it has taken over
+    /// (see `RaftNode::transfer_leadership`)
+    pub async fn transfer_leadership(&self, target: u64) -> Result<(), DriverError> {
+        let (reply, rx) = oneshot::channel();
+        self.request(Command::Transfer { target: Some(target), reply }, rx).await?.map(|_| ())
+    }
+
+    /// hand leadership to the best follower and wait until it has taken
+    /// over, returning its id (see `RaftNode::best_transfer_target`)
+    pub async fn hand_off(&self) -> Result<u64, DriverError> {
+        let (reply, rx) = oneshot::channel();
+        self.request(Command::Transfer { target: None, reply }, rx).await?
+    }
+
+    /// propose a config change and wait for it to commit, returning its
+    /// index (see `RaftNode::propose_conf_change` and `Driver::on_conf_change`)
+    pub async fn propose_conf_change(&self, change: Vec<u8>) -> Result<u64, DriverError> {
+        let (reply, rx) = oneshot::channel();
+        self.request(Command::ConfChange { change, reply }, rx).await?
+    }
+
+    /// the node's role and the leader it knows of
+    pub async fn status(&self) -> Result<SoftState, DriverError> {
+        let (reply, rx) = oneshot::channel();
+        self.request(Command::Status { reply }, rx).await
+    }
+
+    async fn request<R>(&self, command: Command<M>, rx: oneshot::Receiver<R>) -> Result<R, DriverError> {
+        self.commands.send(command).map_err(|_| DriverError::Stopped)?;
+        rx.await.map_err(|_| DriverError::Stopped)
+    }
+}
@@ -0,0 +1,282 @@
+//! # comprehensive driver tests
+//!
+//! why: verify the event loop keeps raft's contract and resolves every request
+//! relations: tests raft-driver crate over raft-storage's InMemoryStorage
+//! what: proposals, linearizable reads, leadership transfer, config changes, shutdown
+
+use raft_core::{LogEntry, NodeState, RaftConfig, RaftMessage, RaftNode, StateMachine};
+use raft_driver::{Driver, DriverError, Handle};
+use raft_storage::{DurableNode, InMemoryStorage};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// appends every command; tests keep a clone to look inside
+#[derive(Clone, Default)]
+struct Journal(Arc<Mutex<Vec<u8>>>);
+
+impl Journal {
+    fn contents(&self) -> Vec<u8> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl StateMachine for Journal {
+    fn apply(&mut self, entry: &LogEntry) -> Vec<u8> {
+        let mut data = self.0.lock().unwrap();
+        data.extend(&entry.command);
+        data.len().to_be_bytes().to_vec()
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        self.contents()
+    }
+
+    fn restore(&mut self, data: &[u8]) {
+        *self.0.lock().unwrap() = data.to_vec();
+    }
+}
+
+type Handles = Arc<Mutex<HashMap<u64, Handle<Journal>>>>;
+type TestDriver = Driver<InMemoryStorage, Journal, Box<dyn FnMut(u64, RaftMessage) + Send>>;
+
+/// `ids.len()` drivers wired to each other through their handles
+struct Cluster {
+    handles: Handles,
+    machines: HashMap<u64, Journal>,
+}
+
+impl Cluster {
+    fn start(ids: &[u64]) -> Self {
+        Self::start_with(ids, |_, driver| driver)
+    }
+
+    /// like `start`, with `setup` applied to each driver before it runs
+    fn start_with(ids: &[u64], setup: impl Fn(u64, TestDriver) -> TestDriver) -> Self {
+        let handles: Handles = Arc::default();
+        let mut machines = HashMap::new();
+        for &id in ids {
+            let node = DurableNode::open(id, ids.to_vec(), RaftConfig::default(), InMemoryStorage::new()).unwrap();
+            let peers = handles.clone();
+            let transport: Box<dyn FnMut(u64, RaftMessage) + Send> = Box::new(move |to, msg| {
+                if let Some(peer) = peers.lock().unwrap().get(&to) {
+                    peer.step(id, msg);
+                }
+            });
+            let machine = Journal::default();
+            let (driver, handle) = Driver::new(node, machine.clone(), transport);
+            tokio::spawn(setup(id, driver).run());
+            handles.lock().unwrap().insert(id, handle);
+            machines.insert(id, machine);
+        }
+        Self { handles, machines }
+    }
+
+    fn handle(&self, id: u64) -> Handle<Journal> {
+        self.handles.lock().unwrap()[&id].clone()
+    }
+
+    /// wait until some node leads, and return it
+    async fn leader(&self) -> u64 {
+        loop {
+            let ids: Vec<u64> = self.handles.lock().unwrap().keys().copied().collect();
+            for id in ids {
+                if self.handle(id).status().await.unwrap().state == NodeState::Leader {
+                    return id;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    fn follower_of(&self, leader: u64) -> u64 {
+        *self.machines.keys().find(|&&id| id != leader).unwrap()
+    }
+}
+
+// =============================================================================
+// SECTION 1: PROPOSALS
+// =============================================================================
+
+mod proposals {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn a_single_node_applies_its_own_proposals() {
+        let cluster = Cluster::start(&[1]);
+        cluster.leader().await;
+        let handle = cluster.handle(1);
+
+        assert_eq!(handle.propose(b"ab".to_vec()).await, Ok(2usize.to_be_bytes().to_vec()));
+        assert_eq!(handle.propose(b"c".to_vec()).await, Ok(3usize.to_be_bytes().to_vec()));
+        assert_eq!(cluster.machines[&1].contents(), b"abc");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn committed_proposals_reach_every_machine() {
+        let cluster = Cluster::start(&[1, 2, 3]);
+        let leader = cluster.leader().await;
+        cluster.handle(leader).propose(b"x".to_vec()).await.unwrap();
+
+        // followers learn the commit from the next append or heartbeat
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        for machine in cluster.machines.values() {
+            assert_eq!(machine.contents(), b"x");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn followers_refuse_with_a_leader_hint() {
+        let cluster = Cluster::start(&[1, 2, 3]);
+        let leader = cluster.leader().await;
+        let follower = cluster.follower_of(leader);
+        // the follower knows the leader once it has heard from it
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let refused = cluster.handle(follower).propose(b"x".to_vec()).await;
+        assert_eq!(
+            refused,
+            Err(DriverError::Propose(raft_core::ProposeError::NotLeader { leader_hint: Some(leader) }))
+        );
+    }
+}
+
+// =============================================================================
+// SECTION 2: LINEARIZABLE READS
+// =============================================================================
+
+mod reads {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn a_read_sees_every_acknowledged_write() {
+        let cluster = Cluster::start(&[1, 2, 3]);
+        let leader = cluster.handle(cluster.leader().await);
+        leader.propose(b"ab".to_vec()).await.unwrap();
+
+        let seen = leader.read(|machine: &Journal| machine.contents()).await;
+        assert_eq!(seen, Ok(b"ab".to_vec()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn followers_refuse_reads() {
+        let cluster = Cluster::start(&[1, 2, 3]);
+        let leader = cluster.leader().await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let refused = cluster.handle(cluster.follower_of(leader)).read(|m: &Journal| m.contents()).await;
+        assert_eq!(refused, Err(DriverError::NotLeader { leader_hint: Some(leader) }));
+    }
+}
+
+// =============================================================================
+// SECTION 3: LEADERSHIP TRANSFER
+// =============================================================================
+
+mod transfer {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn resolves_once_the_target_leads() {
+        let cluster = Cluster::start(&[1, 2, 3]);
+        let leader = cluster.leader().await;
+        let target = cluster.follower_of(leader);
+        // the target must hold the whole log: let it catch up first
+        cluster.handle(leader).propose(b"x".to_vec()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(cluster.handle(leader).transfer_leadership(target).await, Ok(()));
+        assert_eq!(cluster.handle(target).status().await.unwrap().state, NodeState::Leader);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fails_for_a_node_outside_the_cluster() {
+        let cluster = Cluster::start(&[1, 2, 3]);
+        let leader = cluster.leader().await;
+
+        let refused = cluster.handle(leader).transfer_leadership(9).await;
+        assert_eq!(refused, Err(DriverError::TransferFailed { target: 9 }));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hand_off_picks_a_caught_up_follower() {
+        let cluster = Cluster::start(&[1, 2, 3]);
+        let leader = cluster.leader().await;
+        cluster.handle(leader).propose(b"x".to_vec()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let target = cluster.handle(leader).hand_off().await.unwrap();
+        assert_ne!(target, leader);
+        assert_eq!(cluster.handle(target).status().await.unwrap().state, NodeState::Leader);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hand_off_fails_without_followers() {
+        let cluster = Cluster::start(&[1]);
+        cluster.leader().await;
+        assert_eq!(cluster.handle(1).hand_off().await, Err(DriverError::NoTransferTarget));
+    }
+}
+
+// =============================================================================
+// SECTION 4: CONFIG CHANGES
+// =============================================================================
+
+mod conf_changes {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn every_member_applies_the_change_through_the_hook() {
+        let learners: Arc<Mutex<Vec<(u64, u64)>>> = Arc::default();
+        let seen = learners.clone();
+        let cluster = Cluster::start_with(&[1, 2, 3], move |id, driver| {
+            let seen = seen.clone();
+            driver.on_conf_change(move |node: &mut RaftNode, entry: &LogEntry| {
+                let learner = u64::from_be_bytes(entry.command[..8].try_into().unwrap());
+                node.add_learner(learner);
+                seen.lock().unwrap().push((id, learner));
+            })
+        });
+        let leader = cluster.leader().await;
+
+        let index = cluster.handle(leader).propose_conf_change(4u64.to_be_bytes().to_vec()).await.unwrap();
+        assert!(index > 0);
+        assert!(learners.lock().unwrap().contains(&(leader, 4)), "applied before the change resolves");
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let mut members: Vec<u64> = learners.lock().unwrap().iter().map(|&(id, _)| id).collect();
+        members.sort_unstable();
+        assert_eq!(members, vec![1, 2, 3]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_second_change_waits_for_the_first() {
+        let cluster = Cluster::start(&[1, 2, 3]);
+        let leader = cluster.handle(cluster.leader().await);
+
+        let (first, second) = tokio::join!(
+            leader.propose_conf_change(b"a".to_vec()),
+            leader.propose_conf_change(b"b".to_vec()),
+        );
+        let index = first.unwrap();
+        assert_eq!(second, Err(DriverError::ConfChange(raft_core::ConfChangeError::Pending { index })));
+    }
+}
+
+// =============================================================================
+// SECTION 5: SHUTDOWN
+// =============================================================================
+
+mod shutdown {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn dropping_every_handle_stops_the_driver() {
+        let node = DurableNode::open(1, vec![1], RaftConfig::default(), InMemoryStorage::new()).unwrap();
+        let (driver, handle) = Driver::new(node, Journal::default(), |_, _| {});
+        let running = tokio::spawn(driver.run());
+
+        drop(handle);
+        assert!(running.await.unwrap().is_ok());
+    }
+}
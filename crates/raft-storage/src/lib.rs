@@ -2,12 +2,20 @@
 //!
 //! why: provide durable persistence for raft state using standard rust fs apis
 //! relations: used by raft-core for state persistence, mapped to indexeddb via wasi
-//! what: Storage trait, FileStorage implementation, InMemoryStorage for testing
+//! what: Storage trait, FileStorage implementation (also a ColdLog), InMemoryStorage for testing, CoalescingStorage batching, DurableNode (and its crash recovery)
 
-use raft_core::LogEntry;
+use raft_core::{ColdLog, HardState, LogEntry, RaftConfig, RaftMessage, RaftNode, Ready, Snapshot, StateMachine};
 use std::io::{self, Read, Write};
-use std::fs::{self, File, OpenOptions};
-use std::path::PathBuf;
+use std::ops::Range;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// on-disk format version written to meta.json and log.json
+/// 
+/// version 0 is the original unversioned layout (bare meta object, bare
+/// entry array); it is still readable. bump this when a change can't be
+/// expressed as a new `#[serde(default)]` field.
+pub const FORMAT_VERSION: u32 = 1;
 
 /// trait for durable storage of raft state
 /// 
@@ -22,6 +30,17 @@ pub trait Storage {
     /// load the persisted term and voted_for
     fn load_term_and_vote(&self) -> io::Result<(u64, Option<u64>)>;
     
+    /// persist a Ready's hard state
+    fn save_hard_state(&mut self, hard_state: &HardState) -> io::Result<()> {
+        self.save_term_and_vote(hard_state.term, hard_state.vote)
+    }
+    
+    /// load the persisted hard state
+    fn load_hard_state(&self) -> io::Result<HardState> {
+        let (term, vote) = self.load_term_and_vote()?;
+        Ok(HardState { term, vote })
+    }
+    
     /// append entries to the log
     fn append_entries(&mut self, entries: &[LogEntry]) -> io::Result<()>;
     
@@ -31,17 +50,90 @@ pub trait Storage {
     /// truncate log from given index (for conflict resolution)
     fn truncate_log_from(&mut self, from_index: u64) -> io::Result<()>;
     
+    /// record that everything up to `index` is committed
+    /// 
+    /// optional: raft never needs it back, but `DurableNode::recover` can
+    /// then replay committed entries into the state machine at startup
+    /// instead of waiting for a leader to repeat the commit index
+    fn save_commit(&mut self, _index: u64) -> io::Result<()> {
+        Ok(())
+    }
+    
+    /// the last index passed to `save_commit` (0 if it keeps none)
+    fn load_commit(&self) -> io::Result<u64> {
+        Ok(0)
+    }
+    
+    /// persist a Ready's hard state and commit index, either of which may
+    /// be absent
+    /// 
+    /// storage that keeps both in one record should override this to write
+    /// it once; the default saves them one after the other
+    fn save_hard_state_and_commit(&mut self, hard_state: Option<&HardState>, commit: Option<u64>) -> io::Result<()> {
+        if let Some(hard_state) = hard_state {
+            self.save_hard_state(hard_state)?;
+        }
+        if let Some(index) = commit {
+            self.save_commit(index)?;
+        }
+        Ok(())
+    }
+    
+    /// persist a snapshot installed from the leader, replacing the last one
+    /// 
+    /// `persist_ready` writes it before dropping the log it replaces.
+    /// storage that keeps no snapshots refuses, rather than lose the state
+    /// the dropped entries held
+    fn save_snapshot(&mut self, _snapshot: &Snapshot) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "this storage keeps no snapshots"))
+    }
+    
+    /// the last snapshot passed to `save_snapshot` (None if it keeps none)
+    fn load_snapshot(&self) -> io::Result<Option<Snapshot>> {
+        Ok(None)
+    }
+    
     /// clear all persisted state (for testing)
     fn clear(&mut self) -> io::Result<()>;
 }
 
+/// write everything in `ready` that must be durable before its messages go out
+/// 
+/// this is step 1 of the Ready contract: save an installed snapshot,
+/// truncate, append, then save the hard state and commit index together.
+/// only send `ready.messages` once this returns Ok. the snapshot goes
+/// first: the truncation may drop entries only it now holds
+pub fn persist_ready<S: Storage + ?Sized>(storage: &mut S, ready: &Ready) -> io::Result<()> {
+    if let Some(snapshot) = &ready.snapshot {
+        storage.save_snapshot(snapshot)?;
+    }
+    if let Some(from) = ready.truncate_from {
+        storage.truncate_log_from(from)?;
+    }
+    if let Some(first) = ready.entries.first() {
+        // entries may overlap what's stored; rewrite from the first one
+        storage.truncate_log_from(first.index)?;
+        storage.append_entries(&ready.entries)?;
+    }
+    let commit = ready.committed_entries.last().map(|e| e.index);
+    if ready.hard_state.is_some() || commit.is_some() {
+        storage.save_hard_state_and_commit(ready.hard_state.as_ref(), commit)?;
+    }
+    Ok(())
+}
+
 // -- file storage implementation --
 
 /// file-based storage implementation using std::fs
 /// 
 /// stores raft state in a directory with:
-/// - meta.json: term and voted_for
-/// - log.json: array of log entries
+/// - meta.json: format version, term, voted_for and commit
+/// - log.json: format version and array of log entries
+/// - snapshot.json: format version and the last installed snapshot
+/// 
+/// clones share the directory, so a clone can be installed as a node's
+/// cold log (see `RaftNode::set_cold_log`)
+#[derive(Debug, Clone)]
 pub struct FileStorage {
     /// directory path for storing state files
     dir: PathBuf,
@@ -65,47 +157,145 @@ impl FileStorage {
     fn log_path(&self) -> PathBuf {
         self.dir.join("log.json")
     }
+    
+    /// get the path to the snapshot file
+    fn snapshot_path(&self) -> PathBuf {
+        self.dir.join("snapshot.json")
+    }
+    
+    /// the metadata file's contents (defaults for a new node)
+    fn read_meta(&self) -> io::Result<MetaData> {
+        let path = self.meta_path();
+        if !path.exists() {
+            return Ok(MetaData::default()); // default for new nodes
+        }
+        
+        let mut file = File::open(&path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        
+        let meta: MetaData = serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        check_version(meta.version, &path)?;
+        Ok(meta)
+    }
+    
+    /// atomically replace the metadata file with `meta` in the current format
+    fn write_meta(&self, mut meta: MetaData) -> io::Result<()> {
+        meta.version = FORMAT_VERSION;
+        let json = serde_json::to_string_pretty(&meta)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.replace_file(&self.meta_path(), &json)
+    }
+    
+    /// atomically replace the log file with `log` in the current format
+    fn write_log(&self, log: Vec<LogEntry>) -> io::Result<()> {
+        let file = LogFile { version: FORMAT_VERSION, entries: log };
+        let json = serde_json::to_string_pretty(&file)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.replace_file(&self.log_path(), &json)
+    }
+    
+    /// atomically replace the file at `path` with `json`: write a temp
+    /// file next to it, sync, then rename over it
+    fn replace_file(&self, path: &Path, json: &str) -> io::Result<()> {
+        let temp_path = path.with_extension("tmp");
+        let mut file = File::create(&temp_path)?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    }
 }
 
 /// metadata structure for term and vote
+/// 
+/// unknown fields are ignored and missing ones default, so newer writers
+/// can add fields without breaking older data directories
 #[derive(serde::Serialize, serde::Deserialize, Default)]
 struct MetaData {
+    /// format version (absent in version 0 files)
+    #[serde(default)]
+    version: u32,
     term: u64,
     voted_for: Option<u64>,
+    /// last index recorded by `save_commit` (absent before it existed)
+    #[serde(default)]
+    commit: u64,
+}
+
+/// versioned log file layout
+#[derive(serde::Serialize, serde::Deserialize)]
+struct LogFile {
+    version: u32,
+    entries: Vec<LogEntry>,
+}
+
+/// versioned snapshot file layout
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SnapshotFile {
+    version: u32,
+    snapshot: Snapshot,
+}
+
+/// every log layout we know how to read
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum LogFileOnDisk {
+    /// version 1+: `{ "version": n, "entries": [...] }`
+    Versioned(LogFile),
+    /// version 0: a bare array of entries
+    Legacy(Vec<LogEntry>),
+}
+
+/// refuse files written by a newer, unknown format
+fn check_version(version: u32, path: &Path) -> io::Result<()> {
+    if version > FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} uses format version {} but this build only understands up to {}",
+                path.display(),
+                version,
+                FORMAT_VERSION
+            ),
+        ));
+    }
+    Ok(())
 }
 
 impl Storage for FileStorage {
     fn save_term_and_vote(&mut self, term: u64, voted_for: Option<u64>) -> io::Result<()> {
-        let meta = MetaData { term, voted_for };
-        let json = serde_json::to_string_pretty(&meta)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        
-        // atomic write: write to temp file then rename
-        let temp_path = self.dir.join("meta.tmp");
-        let mut file = File::create(&temp_path)?;
-        file.write_all(json.as_bytes())?;
-        file.sync_all()?;
-        fs::rename(&temp_path, self.meta_path())?;
-        
-        Ok(())
+        let meta = self.read_meta()?;
+        self.write_meta(MetaData { term, voted_for, ..meta })
     }
     
     fn load_term_and_vote(&self) -> io::Result<(u64, Option<u64>)> {
-        let path = self.meta_path();
-        if !path.exists() {
-            return Ok((0, None)); // default for new nodes
-        }
-        
-        let mut file = File::open(&path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        
-        let meta: MetaData = serde_json::from_str(&contents)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        
+        let meta = self.read_meta()?;
         Ok((meta.term, meta.voted_for))
     }
     
+    fn save_commit(&mut self, index: u64) -> io::Result<()> {
+        let meta = self.read_meta()?;
+        self.write_meta(MetaData { commit: index, ..meta })
+    }
+    
+    fn load_commit(&self) -> io::Result<u64> {
+        Ok(self.read_meta()?.commit)
+    }
+    
+    fn save_hard_state_and_commit(&mut self, hard_state: Option<&HardState>, commit: Option<u64>) -> io::Result<()> {
+        // both live in meta.json: one rewrite and fsync for the pair
+        let mut meta = self.read_meta()?;
+        if let Some(hard_state) = hard_state {
+            meta.term = hard_state.term;
+            meta.voted_for = hard_state.vote;
+        }
+        if let Some(index) = commit {
+            meta.commit = index;
+        }
+        self.write_meta(meta)
+    }
+    
     fn append_entries(&mut self, entries: &[LogEntry]) -> io::Result<()> {
         if entries.is_empty() {
             return Ok(());
@@ -118,16 +308,7 @@ impl Storage for FileStorage {
         log.extend(entries.iter().cloned());
         
         // write entire log (simple approach - could optimize with append-only file)
-        let json = serde_json::to_string_pretty(&log)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        
-        let temp_path = self.dir.join("log.tmp");
-        let mut file = File::create(&temp_path)?;
-        file.write_all(json.as_bytes())?;
-        file.sync_all()?;
-        fs::rename(&temp_path, self.log_path())?;
-        
-        Ok(())
+        self.write_log(log)
     }
     
     fn load_log(&self) -> io::Result<Vec<LogEntry>> {
@@ -140,35 +321,59 @@ impl Storage for FileStorage {
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
         
-        let log: Vec<LogEntry> = serde_json::from_str(&contents)
+        let on_disk: LogFileOnDisk = serde_json::from_str(&contents)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
         
-        Ok(log)
+        match on_disk {
+            LogFileOnDisk::Versioned(file) => {
+                check_version(file.version, &path)?;
+                Ok(file.entries)
+            }
+            LogFileOnDisk::Legacy(entries) => Ok(entries),
+        }
     }
     
     fn truncate_log_from(&mut self, from_index: u64) -> io::Result<()> {
         let mut log = self.load_log()?;
         log.retain(|e| e.index < from_index);
         
-        let json = serde_json::to_string_pretty(&log)
+        self.write_log(log)
+    }
+    
+    fn save_snapshot(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        let file = SnapshotFile { version: FORMAT_VERSION, snapshot: snapshot.clone() };
+        let json = serde_json::to_string(&file)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        
-        let temp_path = self.dir.join("log.tmp");
-        let mut file = File::create(&temp_path)?;
-        file.write_all(json.as_bytes())?;
-        file.sync_all()?;
-        fs::rename(&temp_path, self.log_path())?;
-        
-        Ok(())
+        self.replace_file(&self.snapshot_path(), &json)
+    }
+    
+    fn load_snapshot(&self) -> io::Result<Option<Snapshot>> {
+        let path = self.snapshot_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)?;
+        let file: SnapshotFile = serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        check_version(file.version, &path)?;
+        Ok(Some(file.snapshot))
     }
     
     fn clear(&mut self) -> io::Result<()> {
         let _ = fs::remove_file(self.meta_path());
         let _ = fs::remove_file(self.log_path());
+        let _ = fs::remove_file(self.snapshot_path());
         Ok(())
     }
 }
 
+impl ColdLog for FileStorage {
+    fn read_entries(&self, range: Range<u64>) -> Option<Vec<LogEntry>> {
+        let log = self.load_log().ok()?;
+        Some(log.into_iter().filter(|e| range.contains(&e.index)).collect())
+    }
+}
+
 // -- in-memory storage implementation --
 
 /// in-memory storage for testing
@@ -179,6 +384,8 @@ pub struct InMemoryStorage {
     term: u64,
     voted_for: Option<u64>,
     log: Vec<LogEntry>,
+    commit: u64,
+    snapshot: Option<Snapshot>,
 }
 
 impl InMemoryStorage {
@@ -213,14 +420,298 @@ impl Storage for InMemoryStorage {
         Ok(())
     }
     
+    fn save_commit(&mut self, index: u64) -> io::Result<()> {
+        self.commit = index;
+        Ok(())
+    }
+    
+    fn load_commit(&self) -> io::Result<u64> {
+        Ok(self.commit)
+    }
+    
+    fn save_snapshot(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        self.snapshot = Some(snapshot.clone());
+        Ok(())
+    }
+    
+    fn load_snapshot(&self) -> io::Result<Option<Snapshot>> {
+        Ok(self.snapshot.clone())
+    }
+    
     fn clear(&mut self) -> io::Result<()> {
         self.term = 0;
         self.voted_for = None;
         self.log.clear();
+        self.commit = 0;
+        self.snapshot = None;
         Ok(())
     }
 }
 
+// -- coalescing wrapper --
+
+/// merges term/vote writes and holds messages until they are durable
+/// 
+/// during an election storm a node can change term and vote several times
+/// between two chances to fsync. saving each change costs one meta fsync;
+/// this wrapper keeps only the latest (term, voted_for) and writes it once
+/// on `flush`. that is safe because terms only grow and a vote only goes
+/// from None to Some within a term, so the latest hard state implies every
+/// earlier one: a restarted node can't vote in an older term at all.
+/// 
+/// what makes it safe is that nothing leaves before the flush: messages
+/// from the same Readys are handed to `hold` and only released by a
+/// successful `flush`. log writes go straight to the inner storage.
+pub struct CoalescingStorage<S: Storage> {
+    inner: S,
+    /// latest (term, voted_for) not yet written
+    pending: Option<(u64, Option<u64>)>,
+    /// messages waiting for the pending hard state to be durable
+    held: Vec<(u64, RaftMessage)>,
+}
+
+impl<S: Storage> CoalescingStorage<S> {
+    /// wrap `inner`
+    pub fn new(inner: S) -> Self {
+        Self { inner, pending: None, held: Vec::new() }
+    }
+    
+    /// hold outgoing messages until the next successful flush
+    pub fn hold(&mut self, messages: impl IntoIterator<Item = (u64, RaftMessage)>) {
+        self.held.extend(messages);
+    }
+    
+    /// write the pending hard state (one write however many saves were
+    /// merged), then release every held message, in order
+    /// 
+    /// on error nothing is released and the hard state stays pending
+    pub fn flush(&mut self) -> io::Result<Vec<(u64, RaftMessage)>> {
+        if let Some((term, voted_for)) = self.pending {
+            self.inner.save_term_and_vote(term, voted_for)?;
+            self.pending = None;
+        }
+        Ok(std::mem::take(&mut self.held))
+    }
+    
+    /// (term, voted_for) saved but not yet written
+    pub fn pending_hard_state(&self) -> Option<(u64, Option<u64>)> {
+        self.pending
+    }
+    
+    /// messages waiting for the next flush
+    pub fn held_count(&self) -> usize {
+        self.held.len()
+    }
+    
+    /// the wrapped storage (pending state is not visible through it)
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+    
+    /// unwrap, dropping anything pending or held (as a crash would)
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Storage> Storage for CoalescingStorage<S> {
+    fn save_term_and_vote(&mut self, term: u64, voted_for: Option<u64>) -> io::Result<()> {
+        self.pending = Some((term, voted_for));
+        Ok(())
+    }
+    
+    fn load_term_and_vote(&self) -> io::Result<(u64, Option<u64>)> {
+        match self.pending {
+            Some(hard_state) => Ok(hard_state),
+            None => self.inner.load_term_and_vote(),
+        }
+    }
+    
+    fn append_entries(&mut self, entries: &[LogEntry]) -> io::Result<()> {
+        self.inner.append_entries(entries)
+    }
+    
+    fn load_log(&self) -> io::Result<Vec<LogEntry>> {
+        self.inner.load_log()
+    }
+    
+    fn truncate_log_from(&mut self, from_index: u64) -> io::Result<()> {
+        self.inner.truncate_log_from(from_index)
+    }
+    
+    fn save_commit(&mut self, index: u64) -> io::Result<()> {
+        self.inner.save_commit(index)
+    }
+    
+    fn load_commit(&self) -> io::Result<u64> {
+        self.inner.load_commit()
+    }
+    
+    fn save_snapshot(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        self.inner.save_snapshot(snapshot)
+    }
+    
+    fn load_snapshot(&self) -> io::Result<Option<Snapshot>> {
+        self.inner.load_snapshot()
+    }
+    
+    fn clear(&mut self) -> io::Result<()> {
+        self.pending = None;
+        self.held.clear();
+        self.inner.clear()
+    }
+}
+
+// -- durable node --
+
+/// a RaftNode that writes its own hard state and log
+/// 
+/// raft-core can't depend on this crate, so instead of making RaftNode
+/// generic over Storage this owns both and runs `persist_ready` inside
+/// every call that can produce a Ready. by the time one is returned, its
+/// snapshot, term, vote and entries are durable and `messages` are safe
+/// to send; the host still applies `snapshot` and `committed_entries`.
+/// 
+/// changes made through `node_mut` are picked up by the next call.
+/// a failed write poisons the node: see `persist`
+pub struct DurableNode<S: Storage> {
+    node: RaftNode,
+    storage: S,
+    /// (kind, message) of the persist that failed, if one did
+    poisoned: Option<(io::ErrorKind, String)>,
+}
+
+impl<S: Storage> DurableNode<S> {
+    /// start a node from whatever `storage` holds (nothing for a new node)
+    /// 
+    /// a stored snapshot is loaded as by `RaftNode::restore_snapshot`, and
+    /// the host restores its machine from it; `recover` does both. if the
+    /// stored log holds a membership record (see
+    /// `RaftNode::bootstrap`), its voters replace `cluster_nodes`. with
+    /// `config.hash_chain`, a log whose chain is broken fails with InvalidData.
+    /// with `config.verify_rejoin`, a node that held state starts its rejoin
+    /// handshake (see `RaftNode::begin_rejoin`)
+    pub fn open(id: u64, cluster_nodes: Vec<u64>, config: RaftConfig, storage: S) -> io::Result<Self> {
+        let mut node = RaftNode::with_config(id, cluster_nodes, config);
+        let hard_state = storage.load_hard_state()?;
+        node.current_term = hard_state.term;
+        node.voted_for = hard_state.vote;
+        node.log = storage.load_log()?;
+        if let Some(snapshot) = storage.load_snapshot()? {
+            node.restore_snapshot(snapshot);
+        }
+        if node.config.hash_chain {
+            node.verify_log().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+        node.restore_membership();
+        if node.config.verify_rejoin {
+            node.begin_rejoin();
+        }
+        Ok(Self { node, storage, poisoned: None })
+    }
+    
+    /// `open`, then bring a freshly constructed `machine` up to date
+    /// 
+    /// `snapshot` is the latest one the host took itself, if any; without
+    /// one, the snapshot storage holds is used. it is restored into
+    /// `machine`, then every entry past it that
+    /// storage recorded as committed is replayed (see `RaftNode::recover`).
+    /// returns the node and (index, result) for every entry `machine` ran.
+    /// a sealed snapshot can't be opened before the sealer is installed:
+    /// such hosts call `RaftNode::recover` themselves
+    #[allow(clippy::type_complexity)]
+    pub fn recover<M: StateMachine + ?Sized>(
+        id: u64,
+        cluster_nodes: Vec<u64>,
+        config: RaftConfig,
+        storage: S,
+        snapshot: Option<Snapshot>,
+        machine: &mut M,
+    ) -> io::Result<(Self, Vec<(u64, Vec<u8>)>)> {
+        let mut node = RaftNode::with_config(id, cluster_nodes, config);
+        let snapshot = match snapshot {
+            Some(snapshot) => Some(snapshot),
+            None => storage.load_snapshot()?,
+        };
+        let applied = node
+            .recover(storage.load_hard_state()?, snapshot, storage.load_log()?, storage.load_commit()?, machine)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "snapshot rejected by the sealer, or log hash chain broken"))?;
+        Ok((Self { node, storage, poisoned: None }, applied))
+    }
+    
+    /// handle a message, persisting before returning
+    pub fn step(&mut self, from: u64, msg: RaftMessage) -> io::Result<Ready> {
+        self.check_poisoned()?;
+        let ready = self.node.step(from, msg);
+        self.persist(ready)
+    }
+    
+    /// advance time, persisting before returning
+    pub fn tick(&mut self, elapsed_ms: u64) -> io::Result<Ready> {
+        self.check_poisoned()?;
+        let ready = self.node.tick(elapsed_ms);
+        self.persist(ready)
+    }
+    
+    /// send new entries to followers (see `RaftNode::replicate`), persisting before returning
+    pub fn replicate(&mut self) -> io::Result<Ready> {
+        self.check_poisoned()?;
+        let ready = self.node.replicate();
+        self.persist(ready)
+    }
+    
+    /// collect (and persist) anything produced through `node_mut`
+    pub fn ready(&mut self) -> io::Result<Ready> {
+        self.check_poisoned()?;
+        let ready = self.node.ready();
+        self.persist(ready)
+    }
+    
+    /// on error the Ready is dropped: none of its messages may be sent.
+    /// the node already counts its hard state and entries as handed off
+    /// and won't produce them again, so it is poisoned: every later call
+    /// fails with the same error. reopen it from storage to go on
+    fn persist(&mut self, ready: Ready) -> io::Result<Ready> {
+        if let Err(e) = persist_ready(&mut self.storage, &ready) {
+            self.poisoned = Some((e.kind(), e.to_string()));
+            return Err(e);
+        }
+        Ok(ready)
+    }
+    
+    fn check_poisoned(&self) -> io::Result<()> {
+        match &self.poisoned {
+            Some((kind, message)) => Err(io::Error::new(*kind, format!("an earlier persist failed: {}", message))),
+            None => Ok(()),
+        }
+    }
+    
+    /// a persist failed, and the node must be reopened from storage
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.is_some()
+    }
+    
+    /// the raft node
+    pub fn node(&self) -> &RaftNode {
+        &self.node
+    }
+    
+    /// the raft node, for proposals, reads and membership changes
+    pub fn node_mut(&mut self) -> &mut RaftNode {
+        &mut self.node
+    }
+    
+    /// the storage behind the node
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+    
+    /// split back into the node and its storage
+    pub fn into_parts(self) -> (RaftNode, S) {
+        (self.node, self.storage)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
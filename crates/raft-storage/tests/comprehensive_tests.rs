@@ -567,3 +567,879 @@ mod trait_polymorphism {
         test_storage_impl(&mut storage);
     }
 }
+
+// ============================================================================
+// SECTION 7: ON-DISK FORMAT VERSIONING
+// ============================================================================
+
+mod format_versioning {
+    use super::*;
+    use raft_storage::FORMAT_VERSION;
+    use std::path::Path;
+
+    /// copy a checked-in data directory into a fresh temp dir
+    fn load_fixture(name: &str) -> tempfile::TempDir {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+        let dir = tempdir().unwrap();
+        for file in ["meta.json", "log.json"] {
+            fs::copy(src.join(file), dir.path().join(file)).unwrap();
+        }
+        dir
+    }
+
+    fn assert_fixture_contents(storage: &FileStorage) {
+        let (term, voted_for) = storage.load_term_and_vote().unwrap();
+        assert_eq!(term, 3);
+        assert_eq!(voted_for, Some(2));
+
+        let log = storage.load_log().unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].command, b"set".to_vec());
+        assert_eq!(log[1].term, 3);
+        assert_eq!(log[1].index, 2);
+    }
+
+    #[test]
+    fn reads_unversioned_v0_fixture() {
+        let dir = load_fixture("v0");
+        let storage = FileStorage::new(dir.path()).unwrap();
+        assert_fixture_contents(&storage);
+    }
+
+    #[test]
+    fn reads_v1_fixture() {
+        let dir = load_fixture("v1");
+        let storage = FileStorage::new(dir.path()).unwrap();
+        assert_fixture_contents(&storage);
+    }
+
+    #[test]
+    fn v0_log_is_upgraded_on_next_write() {
+        let dir = load_fixture("v0");
+        let mut storage = FileStorage::new(dir.path()).unwrap();
+
+        storage.append_entries(&[LogEntry::new(3, 3, vec![])]).unwrap();
+
+        let contents = fs::read_to_string(dir.path().join("log.json")).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(value["version"], FORMAT_VERSION);
+        assert_eq!(storage.load_log().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn writes_current_version() {
+        let dir = tempdir().unwrap();
+        let mut storage = FileStorage::new(dir.path()).unwrap();
+        storage.save_term_and_vote(1, None).unwrap();
+
+        let contents = fs::read_to_string(dir.path().join("meta.json")).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(value["version"], FORMAT_VERSION);
+    }
+
+    #[test]
+    fn ignores_unknown_fields() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("meta.json"),
+            r#"{"version": 1, "term": 4, "voted_for": null, "added_later": true}"#,
+        ).unwrap();
+        fs::write(
+            dir.path().join("log.json"),
+            r#"{"version": 1, "entries": [{"term": 4, "index": 1, "command": [], "kind": "noop"}]}"#,
+        ).unwrap();
+
+        let storage = FileStorage::new(dir.path()).unwrap();
+        assert_eq!(storage.load_term_and_vote().unwrap(), (4, None));
+        assert_eq!(storage.load_log().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let dir = tempdir().unwrap();
+        let newer = FORMAT_VERSION + 1;
+        fs::write(
+            dir.path().join("meta.json"),
+            format!(r#"{{"version": {}, "term": 1, "voted_for": null}}"#, newer),
+        ).unwrap();
+        fs::write(
+            dir.path().join("log.json"),
+            format!(r#"{{"version": {}, "entries": []}}"#, newer),
+        ).unwrap();
+
+        let storage = FileStorage::new(dir.path()).unwrap();
+        let err = storage.load_term_and_vote().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(storage.load_log().is_err());
+    }
+}
+
+// ============================================================================
+// SECTION 8: COALESCED HARD STATE WRITES
+// ============================================================================
+
+mod coalescing {
+    use super::*;
+    use raft_core::{RaftMessage, RaftNode};
+    use raft_storage::CoalescingStorage;
+    use std::cell::Cell;
+    use std::io;
+    use std::rc::Rc;
+
+    /// in-memory storage that counts hard state writes (stand-in for fsyncs)
+    #[derive(Default)]
+    struct CountingStorage {
+        inner: InMemoryStorage,
+        meta_writes: usize,
+        /// shared so a test can fail the next write through the wrapper
+        fail_next: Rc<Cell<bool>>,
+    }
+
+    impl Storage for CountingStorage {
+        fn save_term_and_vote(&mut self, term: u64, voted_for: Option<u64>) -> io::Result<()> {
+            if self.fail_next.take() {
+                return Err(io::Error::other("disk full"));
+            }
+            self.meta_writes += 1;
+            self.inner.save_term_and_vote(term, voted_for)
+        }
+        fn load_term_and_vote(&self) -> io::Result<(u64, Option<u64>)> {
+            self.inner.load_term_and_vote()
+        }
+        fn append_entries(&mut self, entries: &[LogEntry]) -> io::Result<()> {
+            self.inner.append_entries(entries)
+        }
+        fn load_log(&self) -> io::Result<Vec<LogEntry>> {
+            self.inner.load_log()
+        }
+        fn truncate_log_from(&mut self, from_index: u64) -> io::Result<()> {
+            self.inner.truncate_log_from(from_index)
+        }
+        fn clear(&mut self) -> io::Result<()> {
+            self.inner.clear()
+        }
+    }
+
+    fn vote_request(term: u64, candidate_id: u64) -> RaftMessage {
+        RaftMessage::VoteRequest { term, candidate_id, last_log_index: 0, last_log_term: 0, transfer: false, group_id: None }
+    }
+
+    /// step the node through `requests`, persisting and holding every Ready
+    fn storm(
+        node: &mut RaftNode,
+        storage: &mut CoalescingStorage<CountingStorage>,
+        requests: &[(u64, u64)],
+    ) {
+        for &(term, candidate) in requests {
+            let ready = node.step(candidate, vote_request(term, candidate));
+            if let Some(hard_state) = ready.hard_state {
+                storage.save_hard_state(&hard_state).unwrap();
+            }
+            storage.hold(ready.messages);
+        }
+    }
+
+    #[test]
+    fn storm_costs_one_write() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3, 4, 5]);
+        let mut storage = CoalescingStorage::new(CountingStorage::default());
+        storm(&mut node, &mut storage, &[(2, 2), (3, 3), (4, 4), (5, 5)]);
+
+        assert_eq!(storage.inner().meta_writes, 0);
+        assert_eq!(storage.held_count(), 4);
+
+        let released = storage.flush().unwrap();
+        assert_eq!(released.len(), 4);
+        assert_eq!(storage.inner().meta_writes, 1);
+        assert_eq!(storage.inner().load_term_and_vote().unwrap(), (5, Some(5)));
+    }
+
+    #[test]
+    fn nothing_leaves_before_the_write_lands() {
+        let fail_next = Rc::new(Cell::new(false));
+        let counting = CountingStorage { fail_next: fail_next.clone(), ..Default::default() };
+        let mut node = RaftNode::new(1, vec![1, 2, 3]);
+        let mut storage = CoalescingStorage::new(counting);
+        storm(&mut node, &mut storage, &[(2, 2)]);
+
+        fail_next.set(true);
+        assert!(storage.flush().is_err());
+        assert_eq!(storage.held_count(), 1, "grant must not be released");
+        assert_eq!(storage.pending_hard_state(), Some((2, Some(2))));
+
+        assert_eq!(storage.flush().unwrap().len(), 1);
+        assert_eq!(storage.pending_hard_state(), None);
+    }
+
+    #[test]
+    fn released_grants_are_covered_after_a_crash() {
+        let mut node = RaftNode::new(1, vec![1, 2, 3, 4, 5]);
+        let mut storage = CoalescingStorage::new(CountingStorage::default());
+        storm(&mut node, &mut storage, &[(2, 2), (2, 3), (3, 3), (3, 2)]);
+        let released = storage.flush().unwrap();
+
+        // crash: restart from what reached the disk
+        let disk = storage.into_inner();
+        let (term, voted_for) = disk.load_term_and_vote().unwrap();
+        let mut restarted = RaftNode::new(1, vec![1, 2, 3, 4, 5]);
+        restarted.current_term = term;
+        restarted.voted_for = voted_for;
+
+        // no granted vote may be contradicted by a grant after restart
+        for (to, msg) in released {
+            let RaftMessage::VoteResponse { term: granted_term, vote_granted: true, .. } = msg else {
+                continue;
+            };
+            for other in [2, 3, 4, 5].into_iter().filter(|&c| c != to) {
+                let (response, _) = restarted.handle_vote_request(granted_term, other, 0, 0);
+                assert!(
+                    !matches!(response, RaftMessage::VoteResponse { vote_granted: true, .. }),
+                    "node {} got a second vote in term {}",
+                    other,
+                    granted_term
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pending_state_reads_back() {
+        let mut storage = CoalescingStorage::new(InMemoryStorage::new());
+        storage.save_term_and_vote(3, Some(1)).unwrap();
+        storage.save_term_and_vote(4, None).unwrap();
+
+        assert_eq!(storage.load_term_and_vote().unwrap(), (4, None));
+        assert_eq!(storage.inner().load_term_and_vote().unwrap(), (0, None));
+    }
+}
+
+// ============================================================================
+// SECTION 9: PERSISTING A READY
+// ============================================================================
+
+mod persist_ready {
+    use super::*;
+    use raft_core::{HardState, RaftNode};
+    use std::io;
+
+    /// file storage that counts meta.json rewrites (stand-in for fsyncs)
+    struct MetaWrites {
+        inner: FileStorage,
+        writes: usize,
+    }
+
+    impl Storage for MetaWrites {
+        fn save_term_and_vote(&mut self, term: u64, voted_for: Option<u64>) -> io::Result<()> {
+            self.writes += 1;
+            self.inner.save_term_and_vote(term, voted_for)
+        }
+        fn load_term_and_vote(&self) -> io::Result<(u64, Option<u64>)> {
+            self.inner.load_term_and_vote()
+        }
+        fn save_commit(&mut self, index: u64) -> io::Result<()> {
+            self.writes += 1;
+            self.inner.save_commit(index)
+        }
+        fn save_hard_state_and_commit(&mut self, hard_state: Option<&HardState>, commit: Option<u64>) -> io::Result<()> {
+            self.writes += 1;
+            self.inner.save_hard_state_and_commit(hard_state, commit)
+        }
+        fn append_entries(&mut self, entries: &[LogEntry]) -> io::Result<()> {
+            self.inner.append_entries(entries)
+        }
+        fn load_log(&self) -> io::Result<Vec<LogEntry>> {
+            self.inner.load_log()
+        }
+        fn truncate_log_from(&mut self, from_index: u64) -> io::Result<()> {
+            self.inner.truncate_log_from(from_index)
+        }
+        fn clear(&mut self) -> io::Result<()> {
+            self.inner.clear()
+        }
+    }
+
+    #[test]
+    fn vote_is_durable_before_reply() {
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.handle_vote_request(1, 1, 0, 0);
+        let ready = node.ready();
+        assert!(ready.must_sync());
+
+        let mut storage = InMemoryStorage::new();
+        raft_storage::persist_ready(&mut storage, &ready).unwrap();
+        assert_eq!(storage.load_hard_state().unwrap(), HardState { term: 1, vote: Some(1) });
+    }
+
+    #[test]
+    fn overlapping_entries_are_rewritten() {
+        let mut storage = InMemoryStorage::new();
+        storage.append_entries(&[LogEntry::new(1, 1, vec![1]), LogEntry::new(1, 2, vec![2])]).unwrap();
+
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.current_term = 1;
+        node.log = storage.load_log().unwrap();
+        let _ = node.ready();
+        node.handle_append_entries(2, 1, 1, 1, vec![LogEntry::new(2, 2, vec![9])], 0);
+
+        raft_storage::persist_ready(&mut storage, &node.ready()).unwrap();
+        let log = storage.load_log().unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!((log[1].term, log[1].command.clone()), (2, vec![9]));
+        assert_eq!(storage.load_term_and_vote().unwrap(), (2, None));
+    }
+
+    #[test]
+    fn hard_state_and_commit_share_one_meta_write() {
+        let dir = tempdir().unwrap();
+        let mut storage = MetaWrites { inner: FileStorage::new(dir.path()).unwrap(), writes: 0 };
+
+        // a follower's first append from a new leader moves its term and
+        // commits what it carries
+        let mut node = RaftNode::new(2, vec![1, 2, 3]);
+        node.handle_append_entries(1, 1, 0, 0, vec![LogEntry::new(1, 1, vec![1])], 1);
+        let ready = node.ready();
+        assert!(ready.hard_state.is_some() && !ready.committed_entries.is_empty());
+
+        raft_storage::persist_ready(&mut storage, &ready).unwrap();
+        assert_eq!(storage.writes, 1);
+
+        let reopened = FileStorage::new(dir.path()).unwrap();
+        assert_eq!(reopened.load_hard_state().unwrap(), HardState { term: 1, vote: None });
+        assert_eq!(reopened.load_commit().unwrap(), 1);
+    }
+}
+
+// ============================================================================
+// SECTION 10: DURABLE NODE
+// ============================================================================
+
+mod durable_node {
+    use super::*;
+    use raft_core::{RaftConfig, RaftMessage};
+    use raft_storage::DurableNode;
+    use std::cell::Cell;
+    use std::io;
+    use std::rc::Rc;
+
+    fn vote_request(term: u64, candidate_id: u64) -> RaftMessage {
+        RaftMessage::VoteRequest { term, candidate_id, last_log_index: 0, last_log_term: 0, transfer: false, group_id: None }
+    }
+
+    /// in-memory storage whose next hard state write fails when asked
+    #[derive(Default)]
+    struct FlakyStorage {
+        inner: InMemoryStorage,
+        fail_next: Rc<Cell<bool>>,
+    }
+
+    impl Storage for FlakyStorage {
+        fn save_term_and_vote(&mut self, term: u64, voted_for: Option<u64>) -> io::Result<()> {
+            if self.fail_next.take() {
+                return Err(io::Error::other("disk full"));
+            }
+            self.inner.save_term_and_vote(term, voted_for)
+        }
+        fn load_term_and_vote(&self) -> io::Result<(u64, Option<u64>)> {
+            self.inner.load_term_and_vote()
+        }
+        fn append_entries(&mut self, entries: &[LogEntry]) -> io::Result<()> {
+            self.inner.append_entries(entries)
+        }
+        fn load_log(&self) -> io::Result<Vec<LogEntry>> {
+            self.inner.load_log()
+        }
+        fn truncate_log_from(&mut self, from_index: u64) -> io::Result<()> {
+            self.inner.truncate_log_from(from_index)
+        }
+        fn clear(&mut self) -> io::Result<()> {
+            self.inner.clear()
+        }
+    }
+
+    #[test]
+    fn vote_is_written_before_reply_is_returned() {
+        let mut durable = DurableNode::open(2, vec![1, 2, 3], RaftConfig::default(), InMemoryStorage::new()).unwrap();
+        let ready = durable.step(1, vote_request(1, 1)).unwrap();
+
+        assert!(!ready.messages.is_empty());
+        assert_eq!(durable.storage().load_term_and_vote().unwrap(), (1, Some(1)));
+    }
+
+    #[test]
+    fn failed_write_poisons_the_node() {
+        let fail_next = Rc::new(Cell::new(true));
+        let storage = FlakyStorage { fail_next: fail_next.clone(), ..Default::default() };
+        let mut durable = DurableNode::open(2, vec![1, 2, 3], RaftConfig::default(), storage).unwrap();
+        assert!(durable.step(1, vote_request(1, 1)).is_err());
+        assert_eq!(durable.storage().load_term_and_vote().unwrap(), (0, None));
+
+        // the node believes the vote is written and won't write it again:
+        // nothing may go out until it is reopened from what storage holds
+        assert!(!fail_next.get());
+        assert!(durable.is_poisoned());
+        assert!(durable.step(1, vote_request(1, 1)).is_err());
+        assert!(durable.tick(1000).is_err());
+
+        let (_, storage) = durable.into_parts();
+        let mut reopened = DurableNode::open(2, vec![1, 2, 3], RaftConfig::default(), storage).unwrap();
+        let ready = reopened.step(1, vote_request(1, 1)).unwrap();
+        assert!(ready.messages.iter().any(|(_, msg)| matches!(msg, RaftMessage::VoteResponse { vote_granted: true, .. })));
+        assert_eq!(reopened.storage().load_term_and_vote().unwrap(), (1, Some(1)));
+    }
+
+    #[test]
+    fn restart_remembers_the_vote() {
+        let mut durable = DurableNode::open(2, vec![1, 2, 3], RaftConfig::default(), InMemoryStorage::new()).unwrap();
+        durable.step(1, vote_request(1, 1)).unwrap();
+        let (_, storage) = durable.into_parts();
+
+        let mut restarted = DurableNode::open(2, vec![1, 2, 3], RaftConfig::default(), storage).unwrap();
+        assert_eq!(restarted.node().voted_for, Some(1));
+        let ready = restarted.step(3, vote_request(1, 3)).unwrap();
+        assert!(ready.messages.iter().all(|(_, msg)| !matches!(msg, RaftMessage::VoteResponse { vote_granted: true, .. })));
+    }
+
+    #[test]
+    fn appended_entries_are_written() {
+        let mut durable = DurableNode::open(1, vec![1], RaftConfig::default(), InMemoryStorage::new()).unwrap();
+        durable.node_mut().become_leader();
+        durable.node_mut().propose(b"x".to_vec()).unwrap();
+        durable.ready().unwrap();
+
+        let log = durable.storage().load_log().unwrap();
+        assert_eq!(log.last().map(|e| e.command.clone()), Some(b"x".to_vec()));
+    }
+
+    #[test]
+    fn restart_reloads_the_log() {
+        let mut storage = InMemoryStorage::new();
+        storage.save_term_and_vote(4, Some(1)).unwrap();
+        storage.append_entries(&[LogEntry::new(4, 1, vec![7])]).unwrap();
+
+        let durable = DurableNode::open(1, vec![1, 2, 3], RaftConfig::default(), storage).unwrap();
+        assert_eq!(durable.node().current_term, 4);
+        assert_eq!(durable.node().last_log_index(), 1);
+    }
+
+    #[test]
+    fn reopening_stored_state_starts_the_handshake() {
+        let mut storage = InMemoryStorage::new();
+        storage.save_term_and_vote(2, Some(1)).unwrap();
+        storage.append_entries(&[LogEntry::new(2, 1, b"x".to_vec())]).unwrap();
+
+        let config = RaftConfig { verify_rejoin: true, ..RaftConfig::default() };
+        let mut durable = DurableNode::open(3, vec![1, 2, 3], config, storage).unwrap();
+        assert!(durable.node().is_rejoining());
+        let ready = durable.ready().unwrap();
+        assert_eq!(ready.messages.len(), 2);
+        assert!(ready.messages.iter().all(|(_, msg)| matches!(
+            msg,
+            RaftMessage::RejoinRequest { last_log_index: 1, last_log_term: 2, snapshot_index: 0, .. }
+        )));
+    }
+
+    #[test]
+    fn a_new_node_has_nothing_to_verify() {
+        let config = RaftConfig { verify_rejoin: true, ..RaftConfig::default() };
+        let durable = DurableNode::open(3, vec![1, 2, 3], config, InMemoryStorage::new()).unwrap();
+        assert!(!durable.node().is_rejoining());
+    }
+
+    #[test]
+    fn restart_reads_membership_from_the_log() {
+        let mut durable = DurableNode::open(1, Vec::new(), RaftConfig::default(), InMemoryStorage::new()).unwrap();
+        assert!(durable.node_mut().bootstrap(vec![1, 2, 3]));
+        durable.ready().unwrap();
+        let (_, storage) = durable.into_parts();
+
+        let restarted = DurableNode::open(1, Vec::new(), RaftConfig::default(), storage).unwrap();
+        assert_eq!(restarted.node().cluster_nodes, vec![1, 2, 3]);
+        assert_eq!(restarted.node().current_term, 1);
+    }
+}
+
+// ============================================================================
+// SECTION 11: UPGRADE FIXTURES
+// ============================================================================
+
+mod upgrade_fixtures {
+    use super::*;
+    use raft_core::{RaftMessage, Snapshot};
+    use raft_storage::FORMAT_VERSION;
+    use std::path::{Path, PathBuf};
+
+    /// every checked-in fixture directory (v0, v1, ...)
+    fn fixture_dirs() -> Vec<PathBuf> {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let mut dirs: Vec<PathBuf> = fs::read_dir(root)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_dir())
+            .collect();
+        dirs.sort();
+        dirs
+    }
+
+    fn read_messages(dir: &Path) -> Option<Vec<RaftMessage>> {
+        let contents = fs::read_to_string(dir.join("messages.json")).ok()?;
+        Some(serde_json::from_str(&contents).unwrap_or_else(|e| panic!("{}: {}", dir.display(), e)))
+    }
+
+    #[test]
+    fn current_version_has_fixtures() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("tests/fixtures/v{}", FORMAT_VERSION));
+        for file in ["meta.json", "log.json", "messages.json", "snapshot.json"] {
+            assert!(
+                dir.join(file).exists(),
+                "{} is missing: run the fixtures example for v{}",
+                file,
+                FORMAT_VERSION
+            );
+        }
+    }
+
+    #[test]
+    fn every_storage_fixture_loads() {
+        for src in fixture_dirs() {
+            let dir = tempdir().unwrap();
+            for file in ["meta.json", "log.json"] {
+                fs::copy(src.join(file), dir.path().join(file)).unwrap();
+            }
+            let storage = FileStorage::new(dir.path()).unwrap();
+            assert_eq!(storage.load_term_and_vote().unwrap(), (3, Some(2)), "{}", src.display());
+            assert_eq!(storage.load_log().unwrap().len(), 2, "{}", src.display());
+        }
+    }
+
+    #[test]
+    fn every_message_fixture_decodes_and_round_trips() {
+        for dir in fixture_dirs() {
+            let Some(messages) = read_messages(&dir) else { continue };
+            assert!(!messages.is_empty());
+            for msg in messages {
+                let encoded = serde_json::to_string(&msg).unwrap();
+                let decoded: RaftMessage = serde_json::from_str(&encoded).unwrap();
+                assert_eq!(format!("{:?}", decoded), format!("{:?}", msg), "{}", dir.display());
+            }
+        }
+    }
+
+    #[test]
+    fn every_message_fixture_decodes_from_the_wire() {
+        for dir in fixture_dirs() {
+            let Some(messages) = read_messages(&dir) else { continue };
+            for msg in messages {
+                let decoded = RaftMessage::from_wire(&msg.to_wire()).unwrap();
+                assert_eq!(format!("{:?}", decoded), format!("{:?}", msg), "{}", dir.display());
+            }
+        }
+    }
+
+    #[test]
+    fn every_snapshot_fixture_decodes() {
+        for dir in fixture_dirs() {
+            let Ok(contents) = fs::read_to_string(dir.join("snapshot.json")) else { continue };
+            let snapshot: Snapshot = serde_json::from_str(&contents).unwrap();
+            assert_eq!(snapshot.metadata.last_included_index, 2, "{}", dir.display());
+            assert_eq!(snapshot.metadata.last_included_term, 3);
+            assert_eq!(snapshot.data, b"state@2".to_vec());
+        }
+    }
+
+    #[test]
+    fn v1_messages_keep_their_values() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/v1");
+        let messages = read_messages(&dir).unwrap();
+        assert!(matches!(
+            messages[0],
+            RaftMessage::PreVoteRequest { term: 4, candidate_id: 2, last_log_index: 2, last_log_term: 3, .. }
+        ));
+        assert!(messages.iter().any(|m| matches!(m, RaftMessage::VoteRequest { transfer: true, .. })));
+        assert!(messages.iter().any(|m| matches!(
+            m,
+            RaftMessage::AppendEntriesResponse { match_index: 4, backoff: true, storage_health: Some(h), .. } if h.last_fsync_ms == 7
+        )));
+        assert!(messages.iter().any(|m| matches!(m, RaftMessage::Heartbeat { commit: 2, .. })));
+    }
+}
+
+// ============================================================================
+// SECTION 12: RECOVERING THE STATE MACHINE
+// ============================================================================
+
+mod recovery {
+    use super::*;
+    use raft_core::{RaftConfig, RaftMessage, Snapshot, StateMachine};
+    use raft_storage::DurableNode;
+    use std::io;
+
+    /// one-byte commands, in the order they were applied
+    #[derive(Default)]
+    struct Journal(Vec<u8>);
+
+    impl StateMachine for Journal {
+        fn apply(&mut self, entry: &LogEntry) -> Vec<u8> {
+            self.0.extend_from_slice(&entry.command);
+            Vec::new()
+        }
+
+        fn snapshot(&self) -> Vec<u8> {
+            self.0.clone()
+        }
+
+        fn restore(&mut self, data: &[u8]) {
+            self.0 = data.to_vec();
+        }
+    }
+
+    /// in-memory storage left with `Storage`'s default snapshot methods
+    #[derive(Default)]
+    struct NoSnapshots(InMemoryStorage);
+
+    impl Storage for NoSnapshots {
+        fn save_term_and_vote(&mut self, term: u64, voted_for: Option<u64>) -> io::Result<()> {
+            self.0.save_term_and_vote(term, voted_for)
+        }
+        fn load_term_and_vote(&self) -> io::Result<(u64, Option<u64>)> {
+            self.0.load_term_and_vote()
+        }
+        fn append_entries(&mut self, entries: &[LogEntry]) -> io::Result<()> {
+            self.0.append_entries(entries)
+        }
+        fn load_log(&self) -> io::Result<Vec<LogEntry>> {
+            self.0.load_log()
+        }
+        fn truncate_log_from(&mut self, from_index: u64) -> io::Result<()> {
+            self.0.truncate_log_from(from_index)
+        }
+        fn clear(&mut self) -> io::Result<()> {
+            self.0.clear()
+        }
+    }
+
+    /// a single-node leader that has committed and applied `commands`
+    fn leader_with(commands: &[u8], machine: &mut Journal) -> DurableNode<InMemoryStorage> {
+        let mut durable = DurableNode::open(1, vec![1], RaftConfig::default(), InMemoryStorage::new()).unwrap();
+        durable.node_mut().become_leader();
+        propose(&mut durable, commands, machine);
+        durable
+    }
+
+    fn propose(durable: &mut DurableNode<InMemoryStorage>, commands: &[u8], machine: &mut Journal) {
+        for &command in commands {
+            durable.node_mut().propose(vec![command]).unwrap();
+            let ready = durable.replicate().unwrap();
+            durable.node().apply_ready(&ready, machine);
+        }
+    }
+
+    #[test]
+    fn file_storage_keeps_the_commit_across_restarts() {
+        let dir = tempdir().unwrap();
+        let mut storage = FileStorage::new(dir.path()).unwrap();
+        storage.save_commit(7).unwrap();
+        storage.save_term_and_vote(2, Some(1)).unwrap();
+
+        let reopened = FileStorage::new(dir.path()).unwrap();
+        assert_eq!(reopened.load_commit().unwrap(), 7);
+        assert_eq!(reopened.load_term_and_vote().unwrap(), (2, Some(1)));
+    }
+
+    #[test]
+    fn file_storage_keeps_the_snapshot_across_restarts() {
+        let dir = tempdir().unwrap();
+        let mut storage = FileStorage::new(dir.path()).unwrap();
+        assert_eq!(storage.load_snapshot().unwrap(), None);
+        storage.save_snapshot(&Snapshot::new(5, 2, b"state".to_vec())).unwrap();
+
+        let reopened = FileStorage::new(dir.path()).unwrap();
+        assert_eq!(reopened.load_snapshot().unwrap(), Some(Snapshot::new(5, 2, b"state".to_vec())));
+        assert!(!dir.path().join("snapshot.tmp").exists());
+    }
+
+    #[test]
+    fn persisting_a_ready_records_its_commit() {
+        let mut machine = Journal::default();
+        let durable = leader_with(b"ab", &mut machine);
+        assert_eq!(durable.storage().load_commit().unwrap(), durable.node().commit_index);
+    }
+
+    #[test]
+    fn committed_entries_are_replayed_into_a_fresh_machine() {
+        let mut before = Journal::default();
+        let durable = leader_with(b"abc", &mut before);
+        let commit = durable.node().commit_index;
+        let (_, storage) = durable.into_parts();
+
+        let mut machine = Journal::default();
+        let (recovered, applied) =
+            DurableNode::recover(1, vec![1], RaftConfig::default(), storage, None, &mut machine).unwrap();
+        assert_eq!(machine.0, b"abc".to_vec());
+        assert_eq!(applied.len(), 3);
+        assert_eq!(recovered.node().commit_index, commit);
+        assert_eq!(recovered.node().last_applied, commit);
+    }
+
+    #[test]
+    fn replay_starts_after_the_snapshot() {
+        let mut before = Journal::default();
+        let mut durable = leader_with(b"ab", &mut before);
+        let applied = durable.node().last_applied;
+        assert!(durable.node_mut().compact(applied, before.snapshot()));
+        let snapshot = durable.node().snapshot.clone();
+        propose(&mut durable, b"cd", &mut before);
+        let (_, storage) = durable.into_parts();
+
+        let mut machine = Journal::default();
+        let (_, applied) = DurableNode::recover(1, vec![1], RaftConfig::default(), storage, snapshot, &mut machine).unwrap();
+        assert_eq!(machine.0, b"abcd".to_vec());
+        assert_eq!(applied.len(), 2, "only the entries past the snapshot are run");
+    }
+
+    #[test]
+    fn an_installed_snapshot_survives_a_restart() {
+        let mut storage = InMemoryStorage::new();
+        storage.append_entries(&[LogEntry::new(1, 1, vec![b'x'])]).unwrap();
+        let mut durable = DurableNode::open(2, vec![1, 2], RaftConfig::default(), storage).unwrap();
+
+        // the snapshot replaces the stale entry 1, and entry 4 follows it
+        let snapshot = Snapshot::new(3, 2, b"abc".to_vec());
+        durable.step(1, RaftMessage::InstallSnapshot { term: 2, leader_id: 1, snapshot, group_id: None }).unwrap();
+        let append = RaftMessage::AppendEntries {
+            term: 2,
+            leader_id: 1,
+            prev_log_index: 3,
+            prev_log_term: 2,
+            entries: vec![LogEntry::new(2, 4, vec![b'd'])],
+            leader_commit: 4,
+            seq: 0,
+            group_id: None,
+        };
+        durable.step(1, append).unwrap();
+        // entry 4 is handed out to apply once it is durable
+        durable.ready().unwrap();
+        let (_, storage) = durable.into_parts();
+        assert_eq!(storage.load_snapshot().unwrap().map(|s| s.data), Some(b"abc".to_vec()));
+
+        let mut machine = Journal::default();
+        let (recovered, _) = DurableNode::recover(2, vec![1, 2], RaftConfig::default(), storage, None, &mut machine).unwrap();
+        assert_eq!(machine.0, b"abcd".to_vec());
+        assert_eq!(recovered.node().last_applied, 4);
+    }
+
+    #[test]
+    fn storage_without_snapshots_refuses_an_install() {
+        let mut durable = DurableNode::open(2, vec![1, 2], RaftConfig::default(), NoSnapshots::default()).unwrap();
+        let snapshot = Snapshot::new(3, 2, b"abc".to_vec());
+        let err = durable.step(1, RaftMessage::InstallSnapshot { term: 2, leader_id: 1, snapshot, group_id: None }).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn storage_without_a_commit_replays_nothing_past_the_snapshot() {
+        let mut storage = InMemoryStorage::new();
+        storage.append_entries(&[LogEntry::new(1, 1, vec![b'a'])]).unwrap();
+
+        let mut machine = Journal::default();
+        let (recovered, applied) =
+            DurableNode::recover(1, vec![1, 2, 3], RaftConfig::default(), storage, None, &mut machine).unwrap();
+        assert!(applied.is_empty());
+        assert_eq!(recovered.node().commit_index, 0);
+        assert_eq!(recovered.node().last_log_index(), 1);
+    }
+}
+
+// ============================================================================
+// SECTION 13: COLD LOG
+// ============================================================================
+
+mod cold_log {
+    use super::*;
+    use raft_core::{ColdLog, RaftConfig};
+    use raft_storage::DurableNode;
+
+    #[test]
+    fn file_storage_reads_back_a_range() {
+        let dir = tempdir().unwrap();
+        let mut storage = FileStorage::new(dir.path()).unwrap();
+        let entries: Vec<LogEntry> = (1..=5).map(|i| LogEntry::new(1, i, vec![i as u8])).collect();
+        storage.append_entries(&entries).unwrap();
+
+        let read = storage.read_entries(2..4).unwrap();
+        assert_eq!(read.iter().map(|e| e.index).collect::<Vec<_>>(), vec![2, 3]);
+        assert!(storage.read_entries(6..9).unwrap().is_empty());
+    }
+
+    #[test]
+    fn spilled_entries_are_read_back_from_file_storage() {
+        let dir = tempdir().unwrap();
+        let config = RaftConfig::builder().max_hot_entries(2).build().unwrap();
+        let mut durable = DurableNode::open(1, vec![1], config, FileStorage::new(dir.path()).unwrap()).unwrap();
+        let cold = durable.storage().clone();
+        durable.node_mut().set_cold_log(Box::new(cold));
+        durable.node_mut().become_leader();
+        for command in b"abcdef" {
+            durable.node_mut().propose(vec![*command]).unwrap();
+            durable.replicate().unwrap();
+        }
+
+        let node = durable.node();
+        assert!(node.spilled_index() > 0);
+        assert!(node.log.len() < 4);
+        let commands: Vec<u8> = node
+            .committed_since(None, 100)
+            .unwrap()
+            .iter()
+            .flat_map(|e| e.command.clone())
+            .collect();
+        assert_eq!(commands, b"abcdef");
+    }
+}
+
+// ============================================================================
+// SECTION 14: HASH CHAIN
+// ============================================================================
+
+mod hash_chain {
+    use super::*;
+    use raft_core::RaftConfig;
+    use raft_storage::DurableNode;
+
+    fn chained() -> RaftConfig {
+        RaftConfig::builder().hash_chain(true).build().unwrap()
+    }
+
+    /// a single-node leader's storage after it appended `commands`
+    fn written(commands: &[u8], dir: &std::path::Path) -> FileStorage {
+        let mut durable = DurableNode::open(1, vec![1], chained(), FileStorage::new(dir).unwrap()).unwrap();
+        durable.node_mut().become_leader();
+        for &command in commands {
+            durable.node_mut().propose(vec![command]).unwrap();
+            durable.replicate().unwrap();
+        }
+        durable.into_parts().1
+    }
+
+    #[test]
+    fn an_intact_log_reopens() {
+        let dir = tempdir().unwrap();
+        let storage = written(b"abc", dir.path());
+        let reopened = DurableNode::open(1, vec![1], chained(), storage).unwrap();
+        assert_eq!(reopened.node().verify_log(), Ok(()));
+    }
+
+    #[test]
+    fn an_edited_entry_is_refused() {
+        let dir = tempdir().unwrap();
+        let mut storage = written(b"abc", dir.path());
+        let mut log = storage.load_log().unwrap();
+        log[2].command = b"x".to_vec();
+        storage.truncate_log_from(1).unwrap();
+        storage.append_entries(&log).unwrap();
+
+        let reopened = DurableNode::open(1, vec![1], chained(), FileStorage::new(dir.path()).unwrap());
+        let err = reopened.err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("hash chain"));
+    }
+}
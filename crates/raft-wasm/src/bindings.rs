@@ -77,6 +77,8 @@ pub mod raft {
             pub struct PreVoteResponse {
                 pub term: u64,
                 pub vote_granted: bool,
+                pub commit_index: u64,
+                pub last_log_index: u64,
             }
             impl ::core::fmt::Debug for PreVoteResponse {
                 fn fmt(
@@ -86,6 +88,8 @@ pub mod raft {
                     f.debug_struct("PreVoteResponse")
                         .field("term", &self.term)
                         .field("vote-granted", &self.vote_granted)
+                        .field("commit-index", &self.commit_index)
+                        .field("last-log-index", &self.last_log_index)
                         .finish()
                 }
             }
@@ -96,6 +100,8 @@ pub mod raft {
                 pub candidate_id: u64,
                 pub last_log_index: u64,
                 pub last_log_term: u64,
+                /// campaigning on timeout-now: granted despite a live leader
+                pub transfer: bool,
             }
             impl ::core::fmt::Debug for VoteRequest {
                 fn fmt(
@@ -107,6 +113,7 @@ pub mod raft {
                         .field("candidate-id", &self.candidate_id)
                         .field("last-log-index", &self.last_log_index)
                         .field("last-log-term", &self.last_log_term)
+                        .field("transfer", &self.transfer)
                         .finish()
                 }
             }
@@ -115,6 +122,8 @@ pub mod raft {
             pub struct VoteResponse {
                 pub term: u64,
                 pub vote_granted: bool,
+                pub commit_index: u64,
+                pub last_log_index: u64,
             }
             impl ::core::fmt::Debug for VoteResponse {
                 fn fmt(
@@ -124,13 +133,50 @@ pub mod raft {
                     f.debug_struct("VoteResponse")
                         .field("term", &self.term)
                         .field("vote-granted", &self.vote_granted)
+                        .field("commit-index", &self.commit_index)
+                        .field("last-log-index", &self.last_log_index)
                         .finish()
                 }
             }
+            /// what a log entry carries
+            #[repr(u8)]
+            #[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
+            pub enum EntryType {
+                Normal,
+                NoOp,
+                Config,
+            }
+            impl ::core::fmt::Debug for EntryType {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    match self {
+                        EntryType::Normal => f.debug_tuple("EntryType::Normal").finish(),
+                        EntryType::NoOp => f.debug_tuple("EntryType::NoOp").finish(),
+                        EntryType::Config => f.debug_tuple("EntryType::Config").finish(),
+                    }
+                }
+            }
+            impl EntryType {
+                #[doc(hidden)]
+                pub unsafe fn _lift(val: u8) -> EntryType {
+                    if !cfg!(debug_assertions) {
+                        return ::core::mem::transmute(val);
+                    }
+                    match val {
+                        0 => EntryType::Normal,
+                        1 => EntryType::NoOp,
+                        2 => EntryType::Config,
+                        _ => panic!("invalid enum discriminant"),
+                    }
+                }
+            }
             #[derive(Clone)]
             pub struct LogEntry {
                 pub term: u64,
                 pub index: u64,
+                pub entry_type: EntryType,
                 pub command: _rt::Vec<u8>,
             }
             impl ::core::fmt::Debug for LogEntry {
@@ -141,6 +187,7 @@ pub mod raft {
                     f.debug_struct("LogEntry")
                         .field("term", &self.term)
                         .field("index", &self.index)
+                        .field("entry-type", &self.entry_type)
                         .field("command", &self.command)
                         .finish()
                 }
@@ -153,6 +200,8 @@ pub mod raft {
                 pub prev_log_term: u64,
                 pub entries: _rt::Vec<LogEntry>,
                 pub leader_commit: u64,
+                /// numbers the request; the response echoes it
+                pub seq: u64,
             }
             impl ::core::fmt::Debug for AppendEntries {
                 fn fmt(
@@ -166,6 +215,7 @@ pub mod raft {
                         .field("prev-log-term", &self.prev_log_term)
                         .field("entries", &self.entries)
                         .field("leader-commit", &self.leader_commit)
+                        .field("seq", &self.seq)
                         .finish()
                 }
             }
@@ -174,6 +224,9 @@ pub mod raft {
             pub struct AppendEntriesResponse {
                 pub term: u64,
                 pub success: bool,
+                pub match_index: u64,
+                pub backoff: bool,
+                pub seq: u64,
             }
             impl ::core::fmt::Debug for AppendEntriesResponse {
                 fn fmt(
@@ -183,6 +236,225 @@ pub mod raft {
                     f.debug_struct("AppendEntriesResponse")
                         .field("term", &self.term)
                         .field("success", &self.success)
+                        .field("match-index", &self.match_index)
+                        .field("backoff", &self.backoff)
+                        .field("seq", &self.seq)
+                        .finish()
+                }
+            }
+            #[derive(Clone)]
+            pub struct InstallSnapshot {
+                pub term: u64,
+                pub leader_id: u64,
+                pub last_included_index: u64,
+                pub last_included_term: u64,
+                pub data: _rt::Vec<u8>,
+            }
+            impl ::core::fmt::Debug for InstallSnapshot {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("InstallSnapshot")
+                        .field("term", &self.term)
+                        .field("leader-id", &self.leader_id)
+                        .field("last-included-index", &self.last_included_index)
+                        .field("last-included-term", &self.last_included_term)
+                        .field("data", &self.data)
+                        .finish()
+                }
+            }
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct InstallSnapshotResponse {
+                pub term: u64,
+                pub match_index: u64,
+            }
+            impl ::core::fmt::Debug for InstallSnapshotResponse {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("InstallSnapshotResponse")
+                        .field("term", &self.term)
+                        .field("match-index", &self.match_index)
+                        .finish()
+                }
+            }
+            /// a restarted node's state, checked by the leader before it rejoins
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct RejoinRequest {
+                pub cluster_id: Option<u64>,
+                pub last_log_index: u64,
+                pub last_log_term: u64,
+                pub snapshot_index: u64,
+            }
+            impl ::core::fmt::Debug for RejoinRequest {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("RejoinRequest")
+                        .field("cluster-id", &self.cluster_id)
+                        .field("last-log-index", &self.last_log_index)
+                        .field("last-log-term", &self.last_log_term)
+                        .field("snapshot-index", &self.snapshot_index)
+                        .finish()
+                }
+            }
+            /// why the leader refused a restarted node, with the two numbers that
+            /// disagree: (local, cluster) ids, (snapshot index, leader's last
+            /// index), or (last log index, last log term)
+            #[derive(Clone, Copy)]
+            pub enum RejoinRefusal {
+                ClusterMismatch((u64, u64)),
+                SnapshotAhead((u64, u64)),
+                LogAhead((u64, u64)),
+            }
+            impl ::core::fmt::Debug for RejoinRefusal {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    match self {
+                        RejoinRefusal::ClusterMismatch(e) => {
+                            f.debug_tuple("RejoinRefusal::ClusterMismatch")
+                                .field(e)
+                                .finish()
+                        }
+                        RejoinRefusal::SnapshotAhead(e) => {
+                            f.debug_tuple("RejoinRefusal::SnapshotAhead")
+                                .field(e)
+                                .finish()
+                        }
+                        RejoinRefusal::LogAhead(e) => {
+                            f.debug_tuple("RejoinRefusal::LogAhead").field(e).finish()
+                        }
+                    }
+                }
+            }
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct RejoinResponse {
+                pub term: u64,
+                pub refusal: Option<RejoinRefusal>,
+            }
+            impl ::core::fmt::Debug for RejoinResponse {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("RejoinResponse")
+                        .field("term", &self.term)
+                        .field("refusal", &self.refusal)
+                        .finish()
+                }
+            }
+            /// one piece of a streamed snapshot; data is bytes offset.. of the
+            /// serialized snapshot, and done marks the last piece
+            #[derive(Clone)]
+            pub struct InstallSnapshotChunk {
+                pub term: u64,
+                pub leader_id: u64,
+                pub last_included_index: u64,
+                pub last_included_term: u64,
+                pub offset: u64,
+                pub data: _rt::Vec<u8>,
+                pub done: bool,
+            }
+            impl ::core::fmt::Debug for InstallSnapshotChunk {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("InstallSnapshotChunk")
+                        .field("term", &self.term)
+                        .field("leader-id", &self.leader_id)
+                        .field("last-included-index", &self.last_included_index)
+                        .field("last-included-term", &self.last_included_term)
+                        .field("offset", &self.offset)
+                        .field("data", &self.data)
+                        .field("done", &self.done)
+                        .finish()
+                }
+            }
+            /// the offset the follower needs next
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct InstallSnapshotChunkResponse {
+                pub term: u64,
+                pub last_included_index: u64,
+                pub next_offset: u64,
+            }
+            impl ::core::fmt::Debug for InstallSnapshotChunkResponse {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("InstallSnapshotChunkResponse")
+                        .field("term", &self.term)
+                        .field("last-included-index", &self.last_included_index)
+                        .field("next-offset", &self.next_offset)
+                        .finish()
+                }
+            }
+            /// leader liveness; commit is capped at the follower's match index
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct Heartbeat {
+                pub term: u64,
+                pub leader_id: u64,
+                pub commit: u64,
+                pub seq: u64,
+            }
+            impl ::core::fmt::Debug for Heartbeat {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("Heartbeat")
+                        .field("term", &self.term)
+                        .field("leader-id", &self.leader_id)
+                        .field("commit", &self.commit)
+                        .field("seq", &self.seq)
+                        .finish()
+                }
+            }
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct HeartbeatResponse {
+                pub term: u64,
+                pub last_log_index: u64,
+                pub seq: u64,
+            }
+            impl ::core::fmt::Debug for HeartbeatResponse {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("HeartbeatResponse")
+                        .field("term", &self.term)
+                        .field("last-log-index", &self.last_log_index)
+                        .field("seq", &self.seq)
+                        .finish()
+                }
+            }
+            /// answer to a quorum read: how far the responder's log reaches
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct ReadQuorumResponse {
+                pub read_id: u64,
+                pub last_log_index: u64,
+            }
+            impl ::core::fmt::Debug for ReadQuorumResponse {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("ReadQuorumResponse")
+                        .field("read-id", &self.read_id)
+                        .field("last-log-index", &self.last_log_index)
                         .finish()
                 }
             }
@@ -194,6 +466,20 @@ pub mod raft {
                 VoteRes(VoteResponse),
                 AppendReq(AppendEntries),
                 AppendRes(AppendEntriesResponse),
+                SnapshotReq(InstallSnapshot),
+                SnapshotRes(InstallSnapshotResponse),
+                /// rejoin handshake after a restart
+                RejoinReq(RejoinRequest),
+                RejoinRes(RejoinResponse),
+                SnapshotChunkReq(InstallSnapshotChunk),
+                SnapshotChunkRes(InstallSnapshotChunkResponse),
+                HeartbeatReq(Heartbeat),
+                HeartbeatRes(HeartbeatResponse),
+                /// leadership transfer: campaign now (carries the leader's term)
+                TimeoutNow(u64),
+                /// quorum read round (carries a read id, no term)
+                ReadQuorumReq(u64),
+                ReadQuorumRes(ReadQuorumResponse),
             }
             impl ::core::fmt::Debug for RaftMessage {
                 fn fmt(
@@ -219,6 +505,43 @@ pub mod raft {
                         RaftMessage::AppendRes(e) => {
                             f.debug_tuple("RaftMessage::AppendRes").field(e).finish()
                         }
+                        RaftMessage::SnapshotReq(e) => {
+                            f.debug_tuple("RaftMessage::SnapshotReq").field(e).finish()
+                        }
+                        RaftMessage::SnapshotRes(e) => {
+                            f.debug_tuple("RaftMessage::SnapshotRes").field(e).finish()
+                        }
+                        RaftMessage::RejoinReq(e) => {
+                            f.debug_tuple("RaftMessage::RejoinReq").field(e).finish()
+                        }
+                        RaftMessage::RejoinRes(e) => {
+                            f.debug_tuple("RaftMessage::RejoinRes").field(e).finish()
+                        }
+                        RaftMessage::SnapshotChunkReq(e) => {
+                            f.debug_tuple("RaftMessage::SnapshotChunkReq")
+                                .field(e)
+                                .finish()
+                        }
+                        RaftMessage::SnapshotChunkRes(e) => {
+                            f.debug_tuple("RaftMessage::SnapshotChunkRes")
+                                .field(e)
+                                .finish()
+                        }
+                        RaftMessage::HeartbeatReq(e) => {
+                            f.debug_tuple("RaftMessage::HeartbeatReq").field(e).finish()
+                        }
+                        RaftMessage::HeartbeatRes(e) => {
+                            f.debug_tuple("RaftMessage::HeartbeatRes").field(e).finish()
+                        }
+                        RaftMessage::TimeoutNow(e) => {
+                            f.debug_tuple("RaftMessage::TimeoutNow").field(e).finish()
+                        }
+                        RaftMessage::ReadQuorumReq(e) => {
+                            f.debug_tuple("RaftMessage::ReadQuorumReq").field(e).finish()
+                        }
+                        RaftMessage::ReadQuorumRes(e) => {
+                            f.debug_tuple("RaftMessage::ReadQuorumRes").field(e).finish()
+                        }
                     }
                 }
             }
@@ -228,7 +551,12 @@ pub mod raft {
                 pub id: u64,
                 pub state: NodeState,
                 pub term: u64,
+                pub voted_for: Option<u64>,
+                /// who this node believes leads the current term
+                pub leader_id: Option<u64>,
                 pub log_length: u64,
+                /// bytes held by the in-memory log
+                pub log_bytes: u64,
                 pub commit_index: u64,
             }
             impl ::core::fmt::Debug for NodeStatus {
@@ -240,11 +568,53 @@ pub mod raft {
                         .field("id", &self.id)
                         .field("state", &self.state)
                         .field("term", &self.term)
+                        .field("voted-for", &self.voted_for)
+                        .field("leader-id", &self.leader_id)
                         .field("log-length", &self.log_length)
+                        .field("log-bytes", &self.log_bytes)
                         .field("commit-index", &self.commit_index)
                         .finish()
                 }
             }
+            /// why a command was refused; not-leader carries the leader, if known,
+            /// too-large the byte limit it broke
+            #[derive(Clone, Copy)]
+            pub enum ProposeError {
+                NotLeader(Option<u64>),
+                TooLarge(u64),
+                UncommittedFull,
+                Removed,
+            }
+            impl ::core::fmt::Debug for ProposeError {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    match self {
+                        ProposeError::NotLeader(e) => {
+                            f.debug_tuple("ProposeError::NotLeader").field(e).finish()
+                        }
+                        ProposeError::TooLarge(e) => {
+                            f.debug_tuple("ProposeError::TooLarge").field(e).finish()
+                        }
+                        ProposeError::UncommittedFull => {
+                            f.debug_tuple("ProposeError::UncommittedFull").finish()
+                        }
+                        ProposeError::Removed => {
+                            f.debug_tuple("ProposeError::Removed").finish()
+                        }
+                    }
+                }
+            }
+            impl ::core::fmt::Display for ProposeError {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    write!(f, "{:?}", self)
+                }
+            }
+            impl std::error::Error for ProposeError {}
         }
         #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
         pub mod host {
@@ -258,18 +628,19 @@ pub mod raft {
             pub fn send_message(to_node: u64, msg: &RaftMessage) -> () {
                 unsafe {
                     let mut cleanup_list = _rt::Vec::new();
-                    use super::super::super::raft::consensus::types::RaftMessage as V9;
+                    use super::super::super::raft::consensus::types::RaftMessage as V27;
                     let (
-                        result10_0,
-                        result10_1,
-                        result10_2,
-                        result10_3,
-                        result10_4,
-                        result10_5,
-                        result10_6,
-                        result10_7,
+                        result28_0,
+                        result28_1,
+                        result28_2,
+                        result28_3,
+                        result28_4,
+                        result28_5,
+                        result28_6,
+                        result28_7,
+                        result28_8,
                     ) = match msg {
-                        V9::PreVoteReq(e) => {
+                        V27::PreVoteReq(e) => {
                             let super::super::super::raft::consensus::types::PreVoteRequest {
                                 term: term0,
                                 candidate_id: candidate_id0,
@@ -282,15 +653,18 @@ pub mod raft {
                                 _rt::as_i64(candidate_id0),
                                 _rt::as_i64(last_log_index0),
                                 _rt::as_i64(last_log_term0),
+                                ::core::mem::MaybeUninit::<u64>::zeroed(),
                                 ::core::ptr::null_mut(),
-                                0usize,
+                                0i64,
                                 0i64,
                             )
                         }
-                        V9::PreVoteRes(e) => {
+                        V27::PreVoteRes(e) => {
                             let super::super::super::raft::consensus::types::PreVoteResponse {
                                 term: term1,
                                 vote_granted: vote_granted1,
+                                commit_index: commit_index1,
+                                last_log_index: last_log_index1,
                             } = e;
                             (
                                 1i32,
@@ -301,19 +675,21 @@ pub mod raft {
                                         false => 0,
                                     },
                                 ),
-                                0i64,
-                                0i64,
+                                _rt::as_i64(commit_index1),
+                                _rt::as_i64(last_log_index1),
+                                ::core::mem::MaybeUninit::<u64>::zeroed(),
                                 ::core::ptr::null_mut(),
-                                0usize,
+                                0i64,
                                 0i64,
                             )
                         }
-                        V9::VoteReq(e) => {
+                        V27::VoteReq(e) => {
                             let super::super::super::raft::consensus::types::VoteRequest {
                                 term: term2,
                                 candidate_id: candidate_id2,
                                 last_log_index: last_log_index2,
                                 last_log_term: last_log_term2,
+                                transfer: transfer2,
                             } = e;
                             (
                                 2i32,
@@ -321,15 +697,25 @@ pub mod raft {
                                 _rt::as_i64(candidate_id2),
                                 _rt::as_i64(last_log_index2),
                                 _rt::as_i64(last_log_term2),
+                                ::core::mem::MaybeUninit::new(
+                                    i64::from(
+                                        match transfer2 {
+                                            true => 1,
+                                            false => 0,
+                                        },
+                                    ) as u64,
+                                ),
                                 ::core::ptr::null_mut(),
-                                0usize,
+                                0i64,
                                 0i64,
                             )
                         }
-                        V9::VoteRes(e) => {
+                        V27::VoteRes(e) => {
                             let super::super::super::raft::consensus::types::VoteResponse {
                                 term: term3,
                                 vote_granted: vote_granted3,
+                                commit_index: commit_index3,
+                                last_log_index: last_log_index3,
                             } = e;
                             (
                                 3i32,
@@ -340,14 +726,15 @@ pub mod raft {
                                         false => 0,
                                     },
                                 ),
-                                0i64,
-                                0i64,
+                                _rt::as_i64(commit_index3),
+                                _rt::as_i64(last_log_index3),
+                                ::core::mem::MaybeUninit::<u64>::zeroed(),
                                 ::core::ptr::null_mut(),
-                                0usize,
+                                0i64,
                                 0i64,
                             )
                         }
-                        V9::AppendReq(e) => {
+                        V27::AppendReq(e) => {
                             let super::super::super::raft::consensus::types::AppendEntries {
                                 term: term4,
                                 leader_id: leader_id4,
@@ -355,11 +742,12 @@ pub mod raft {
                                 prev_log_term: prev_log_term4,
                                 entries: entries4,
                                 leader_commit: leader_commit4,
+                                seq: seq4,
                             } = e;
                             let vec7 = entries4;
                             let len7 = vec7.len();
                             let layout7 = _rt::alloc::Layout::from_size_align_unchecked(
-                                vec7.len() * (16 + 2 * ::core::mem::size_of::<*const u8>()),
+                                vec7.len() * (24 + 2 * ::core::mem::size_of::<*const u8>()),
                                 8,
                             );
                             let result7 = if layout7.size() != 0 {
@@ -373,22 +761,27 @@ pub mod raft {
                             };
                             for (i, e) in vec7.into_iter().enumerate() {
                                 let base = result7
-                                    .add(i * (16 + 2 * ::core::mem::size_of::<*const u8>()));
+                                    .add(i * (24 + 2 * ::core::mem::size_of::<*const u8>()));
                                 {
                                     let super::super::super::raft::consensus::types::LogEntry {
                                         term: term5,
                                         index: index5,
+                                        entry_type: entry_type5,
                                         command: command5,
                                     } = e;
                                     *base.add(0).cast::<i64>() = _rt::as_i64(term5);
                                     *base.add(8).cast::<i64>() = _rt::as_i64(index5);
+                                    *base.add(16).cast::<u8>() = (entry_type5.clone() as i32)
+                                        as u8;
                                     let vec6 = command5;
                                     let ptr6 = vec6.as_ptr().cast::<u8>();
                                     let len6 = vec6.len();
                                     *base
-                                        .add(16 + 1 * ::core::mem::size_of::<*const u8>())
+                                        .add(16 + 2 * ::core::mem::size_of::<*const u8>())
                                         .cast::<usize>() = len6;
-                                    *base.add(16).cast::<*mut u8>() = ptr6.cast_mut();
+                                    *base
+                                        .add(16 + 1 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr6.cast_mut();
                                 }
                             }
                             cleanup_list.extend_from_slice(&[(result7, layout7)]);
@@ -398,15 +791,23 @@ pub mod raft {
                                 _rt::as_i64(leader_id4),
                                 _rt::as_i64(prev_log_index4),
                                 _rt::as_i64(prev_log_term4),
-                                result7,
-                                len7,
+                                {
+                                    let mut t = ::core::mem::MaybeUninit::<u64>::uninit();
+                                    t.as_mut_ptr().cast::<*mut u8>().write(result7);
+                                    t
+                                },
+                                len7 as *mut u8,
                                 _rt::as_i64(leader_commit4),
+                                _rt::as_i64(seq4),
                             )
                         }
-                        V9::AppendRes(e) => {
+                        V27::AppendRes(e) => {
                             let super::super::super::raft::consensus::types::AppendEntriesResponse {
                                 term: term8,
                                 success: success8,
+                                match_index: match_index8,
+                                backoff: backoff8,
+                                seq: seq8,
                             } = e;
                             (
                                 5i32,
@@ -417,10 +818,251 @@ pub mod raft {
                                         false => 0,
                                     },
                                 ),
+                                _rt::as_i64(match_index8),
+                                i64::from(
+                                    match backoff8 {
+                                        true => 1,
+                                        false => 0,
+                                    },
+                                ),
+                                ::core::mem::MaybeUninit::new(_rt::as_i64(seq8) as u64),
+                                ::core::ptr::null_mut(),
+                                0i64,
+                                0i64,
+                            )
+                        }
+                        V27::SnapshotReq(e) => {
+                            let super::super::super::raft::consensus::types::InstallSnapshot {
+                                term: term9,
+                                leader_id: leader_id9,
+                                last_included_index: last_included_index9,
+                                last_included_term: last_included_term9,
+                                data: data9,
+                            } = e;
+                            let vec10 = data9;
+                            let ptr10 = vec10.as_ptr().cast::<u8>();
+                            let len10 = vec10.len();
+                            (
+                                6i32,
+                                _rt::as_i64(term9),
+                                _rt::as_i64(leader_id9),
+                                _rt::as_i64(last_included_index9),
+                                _rt::as_i64(last_included_term9),
+                                {
+                                    let mut t = ::core::mem::MaybeUninit::<u64>::uninit();
+                                    t.as_mut_ptr().cast::<*mut u8>().write(ptr10.cast_mut());
+                                    t
+                                },
+                                len10 as *mut u8,
+                                0i64,
+                                0i64,
+                            )
+                        }
+                        V27::SnapshotRes(e) => {
+                            let super::super::super::raft::consensus::types::InstallSnapshotResponse {
+                                term: term11,
+                                match_index: match_index11,
+                            } = e;
+                            (
+                                7i32,
+                                _rt::as_i64(term11),
+                                _rt::as_i64(match_index11),
+                                0i64,
+                                0i64,
+                                ::core::mem::MaybeUninit::<u64>::zeroed(),
+                                ::core::ptr::null_mut(),
+                                0i64,
+                                0i64,
+                            )
+                        }
+                        V27::RejoinReq(e) => {
+                            let super::super::super::raft::consensus::types::RejoinRequest {
+                                cluster_id: cluster_id12,
+                                last_log_index: last_log_index12,
+                                last_log_term: last_log_term12,
+                                snapshot_index: snapshot_index12,
+                            } = e;
+                            let (result13_0, result13_1) = match cluster_id12 {
+                                Some(e) => (1i32, _rt::as_i64(e)),
+                                None => (0i32, 0i64),
+                            };
+                            (
+                                8i32,
+                                i64::from(result13_0),
+                                result13_1,
+                                _rt::as_i64(last_log_index12),
+                                _rt::as_i64(last_log_term12),
+                                ::core::mem::MaybeUninit::new(
+                                    _rt::as_i64(snapshot_index12) as u64,
+                                ),
+                                ::core::ptr::null_mut(),
+                                0i64,
+                                0i64,
+                            )
+                        }
+                        V27::RejoinRes(e) => {
+                            let super::super::super::raft::consensus::types::RejoinResponse {
+                                term: term14,
+                                refusal: refusal14,
+                            } = e;
+                            let (result20_0, result20_1, result20_2, result20_3) = match refusal14 {
+                                Some(e) => {
+                                    use super::super::super::raft::consensus::types::RejoinRefusal as V18;
+                                    let (result19_0, result19_1, result19_2) = match e {
+                                        V18::ClusterMismatch(e) => {
+                                            let (t15_0, t15_1) = e;
+                                            (0i32, _rt::as_i64(t15_0), _rt::as_i64(t15_1))
+                                        }
+                                        V18::SnapshotAhead(e) => {
+                                            let (t16_0, t16_1) = e;
+                                            (1i32, _rt::as_i64(t16_0), _rt::as_i64(t16_1))
+                                        }
+                                        V18::LogAhead(e) => {
+                                            let (t17_0, t17_1) = e;
+                                            (2i32, _rt::as_i64(t17_0), _rt::as_i64(t17_1))
+                                        }
+                                    };
+                                    (1i32, result19_0, result19_1, result19_2)
+                                }
+                                None => (0i32, 0i32, 0i64, 0i64),
+                            };
+                            (
+                                9i32,
+                                _rt::as_i64(term14),
+                                i64::from(result20_0),
+                                i64::from(result20_1),
+                                result20_2,
+                                ::core::mem::MaybeUninit::new(result20_3 as u64),
+                                ::core::ptr::null_mut(),
+                                0i64,
+                                0i64,
+                            )
+                        }
+                        V27::SnapshotChunkReq(e) => {
+                            let super::super::super::raft::consensus::types::InstallSnapshotChunk {
+                                term: term21,
+                                leader_id: leader_id21,
+                                last_included_index: last_included_index21,
+                                last_included_term: last_included_term21,
+                                offset: offset21,
+                                data: data21,
+                                done: done21,
+                            } = e;
+                            let vec22 = data21;
+                            let ptr22 = vec22.as_ptr().cast::<u8>();
+                            let len22 = vec22.len();
+                            (
+                                10i32,
+                                _rt::as_i64(term21),
+                                _rt::as_i64(leader_id21),
+                                _rt::as_i64(last_included_index21),
+                                _rt::as_i64(last_included_term21),
+                                ::core::mem::MaybeUninit::new(_rt::as_i64(offset21) as u64),
+                                ptr22.cast_mut(),
+                                len22 as i64,
+                                i64::from(
+                                    match done21 {
+                                        true => 1,
+                                        false => 0,
+                                    },
+                                ),
+                            )
+                        }
+                        V27::SnapshotChunkRes(e) => {
+                            let super::super::super::raft::consensus::types::InstallSnapshotChunkResponse {
+                                term: term23,
+                                last_included_index: last_included_index23,
+                                next_offset: next_offset23,
+                            } = e;
+                            (
+                                11i32,
+                                _rt::as_i64(term23),
+                                _rt::as_i64(last_included_index23),
+                                _rt::as_i64(next_offset23),
+                                0i64,
+                                ::core::mem::MaybeUninit::<u64>::zeroed(),
+                                ::core::ptr::null_mut(),
                                 0i64,
                                 0i64,
+                            )
+                        }
+                        V27::HeartbeatReq(e) => {
+                            let super::super::super::raft::consensus::types::Heartbeat {
+                                term: term24,
+                                leader_id: leader_id24,
+                                commit: commit24,
+                                seq: seq24,
+                            } = e;
+                            (
+                                12i32,
+                                _rt::as_i64(term24),
+                                _rt::as_i64(leader_id24),
+                                _rt::as_i64(commit24),
+                                _rt::as_i64(seq24),
+                                ::core::mem::MaybeUninit::<u64>::zeroed(),
                                 ::core::ptr::null_mut(),
-                                0usize,
+                                0i64,
+                                0i64,
+                            )
+                        }
+                        V27::HeartbeatRes(e) => {
+                            let super::super::super::raft::consensus::types::HeartbeatResponse {
+                                term: term25,
+                                last_log_index: last_log_index25,
+                                seq: seq25,
+                            } = e;
+                            (
+                                13i32,
+                                _rt::as_i64(term25),
+                                _rt::as_i64(last_log_index25),
+                                _rt::as_i64(seq25),
+                                0i64,
+                                ::core::mem::MaybeUninit::<u64>::zeroed(),
+                                ::core::ptr::null_mut(),
+                                0i64,
+                                0i64,
+                            )
+                        }
+                        V27::TimeoutNow(e) => {
+                            (
+                                14i32,
+                                _rt::as_i64(e),
+                                0i64,
+                                0i64,
+                                0i64,
+                                ::core::mem::MaybeUninit::<u64>::zeroed(),
+                                ::core::ptr::null_mut(),
+                                0i64,
+                                0i64,
+                            )
+                        }
+                        V27::ReadQuorumReq(e) => {
+                            (
+                                15i32,
+                                _rt::as_i64(e),
+                                0i64,
+                                0i64,
+                                0i64,
+                                ::core::mem::MaybeUninit::<u64>::zeroed(),
+                                ::core::ptr::null_mut(),
+                                0i64,
+                                0i64,
+                            )
+                        }
+                        V27::ReadQuorumRes(e) => {
+                            let super::super::super::raft::consensus::types::ReadQuorumResponse {
+                                read_id: read_id26,
+                                last_log_index: last_log_index26,
+                            } = e;
+                            (
+                                16i32,
+                                _rt::as_i64(read_id26),
+                                _rt::as_i64(last_log_index26),
+                                0i64,
+                                0i64,
+                                ::core::mem::MaybeUninit::<u64>::zeroed(),
+                                ::core::ptr::null_mut(),
+                                0i64,
                                 0i64,
                             )
                         }
@@ -429,43 +1071,46 @@ pub mod raft {
                     #[link(wasm_import_module = "raft:consensus/host")]
                     unsafe extern "C" {
                         #[link_name = "send-message"]
-                        fn wit_import11(
+                        fn wit_import29(
                             _: i64,
                             _: i32,
                             _: i64,
                             _: i64,
                             _: i64,
                             _: i64,
+                            _: ::core::mem::MaybeUninit<u64>,
                             _: *mut u8,
-                            _: usize,
+                            _: i64,
                             _: i64,
                         );
                     }
                     #[cfg(not(target_arch = "wasm32"))]
-                    unsafe extern "C" fn wit_import11(
+                    unsafe extern "C" fn wit_import29(
                         _: i64,
                         _: i32,
                         _: i64,
                         _: i64,
                         _: i64,
                         _: i64,
+                        _: ::core::mem::MaybeUninit<u64>,
                         _: *mut u8,
-                        _: usize,
+                        _: i64,
                         _: i64,
                     ) {
                         unreachable!()
                     }
                     unsafe {
-                        wit_import11(
+                        wit_import29(
                             _rt::as_i64(&to_node),
-                            result10_0,
-                            result10_1,
-                            result10_2,
-                            result10_3,
-                            result10_4,
-                            result10_5,
-                            result10_6,
-                            result10_7,
+                            result28_0,
+                            result28_1,
+                            result28_2,
+                            result28_3,
+                            result28_4,
+                            result28_5,
+                            result28_6,
+                            result28_7,
+                            result28_8,
                         )
                     };
                     for (ptr, layout) in cleanup_list {
@@ -501,7 +1146,7 @@ pub mod raft {
                     let vec2 = entries;
                     let len2 = vec2.len();
                     let layout2 = _rt::alloc::Layout::from_size_align_unchecked(
-                        vec2.len() * (16 + 2 * ::core::mem::size_of::<*const u8>()),
+                        vec2.len() * (24 + 2 * ::core::mem::size_of::<*const u8>()),
                         8,
                     );
                     let result2 = if layout2.size() != 0 {
@@ -515,22 +1160,27 @@ pub mod raft {
                     };
                     for (i, e) in vec2.into_iter().enumerate() {
                         let base = result2
-                            .add(i * (16 + 2 * ::core::mem::size_of::<*const u8>()));
+                            .add(i * (24 + 2 * ::core::mem::size_of::<*const u8>()));
                         {
                             let super::super::super::raft::consensus::types::LogEntry {
                                 term: term0,
                                 index: index0,
+                                entry_type: entry_type0,
                                 command: command0,
                             } = e;
                             *base.add(0).cast::<i64>() = _rt::as_i64(term0);
                             *base.add(8).cast::<i64>() = _rt::as_i64(index0);
+                            *base.add(16).cast::<u8>() = (entry_type0.clone() as i32)
+                                as u8;
                             let vec1 = command0;
                             let ptr1 = vec1.as_ptr().cast::<u8>();
                             let len1 = vec1.len();
                             *base
-                                .add(16 + 1 * ::core::mem::size_of::<*const u8>())
+                                .add(16 + 2 * ::core::mem::size_of::<*const u8>())
                                 .cast::<usize>() = len1;
-                            *base.add(16).cast::<*mut u8>() = ptr1.cast_mut();
+                            *base
+                                .add(16 + 1 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = ptr1.cast_mut();
                         }
                     }
                     #[cfg(target_arch = "wasm32")]
@@ -601,6 +1251,7 @@ pub mod exports {
                 use super::super::super::super::_rt;
                 pub type NodeStatus = super::super::super::super::raft::consensus::types::NodeStatus;
                 pub type RaftMessage = super::super::super::super::raft::consensus::types::RaftMessage;
+                pub type ProposeError = super::super::super::super::raft::consensus::types::ProposeError;
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
                 pub unsafe fn _export_init_cabi<T: Guest>(
@@ -625,14 +1276,36 @@ pub mod exports {
                         id: id2,
                         state: state2,
                         term: term2,
+                        voted_for: voted_for2,
+                        leader_id: leader_id2,
                         log_length: log_length2,
+                        log_bytes: log_bytes2,
                         commit_index: commit_index2,
                     } = result0;
                     *ptr1.add(0).cast::<i64>() = _rt::as_i64(id2);
                     *ptr1.add(8).cast::<u8>() = (state2.clone() as i32) as u8;
                     *ptr1.add(16).cast::<i64>() = _rt::as_i64(term2);
-                    *ptr1.add(24).cast::<i64>() = _rt::as_i64(log_length2);
-                    *ptr1.add(32).cast::<i64>() = _rt::as_i64(commit_index2);
+                    match voted_for2 {
+                        Some(e) => {
+                            *ptr1.add(24).cast::<u8>() = (1i32) as u8;
+                            *ptr1.add(32).cast::<i64>() = _rt::as_i64(e);
+                        }
+                        None => {
+                            *ptr1.add(24).cast::<u8>() = (0i32) as u8;
+                        }
+                    };
+                    match leader_id2 {
+                        Some(e) => {
+                            *ptr1.add(40).cast::<u8>() = (1i32) as u8;
+                            *ptr1.add(48).cast::<i64>() = _rt::as_i64(e);
+                        }
+                        None => {
+                            *ptr1.add(40).cast::<u8>() = (0i32) as u8;
+                        }
+                    };
+                    *ptr1.add(56).cast::<i64>() = _rt::as_i64(log_length2);
+                    *ptr1.add(64).cast::<i64>() = _rt::as_i64(log_bytes2);
+                    *ptr1.add(72).cast::<i64>() = _rt::as_i64(commit_index2);
                     ptr1
                 }
                 #[doc(hidden)]
@@ -644,72 +1317,86 @@ pub mod exports {
                     arg3: i64,
                     arg4: i64,
                     arg5: i64,
-                    arg6: *mut u8,
-                    arg7: usize,
+                    arg6: ::core::mem::MaybeUninit<u64>,
+                    arg7: *mut u8,
                     arg8: i64,
+                    arg9: i64,
                 ) {
                     #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
-                    use super::super::super::super::raft::consensus::types::RaftMessage as V6;
-                    let v6 = match arg1 {
+                    use super::super::super::super::raft::consensus::types::RaftMessage as V10;
+                    let v10 = match arg1 {
                         0 => {
-                            let e6 = super::super::super::super::raft::consensus::types::PreVoteRequest {
+                            let e10 = super::super::super::super::raft::consensus::types::PreVoteRequest {
                                 term: arg2 as u64,
                                 candidate_id: arg3 as u64,
                                 last_log_index: arg4 as u64,
                                 last_log_term: arg5 as u64,
                             };
-                            V6::PreVoteReq(e6)
+                            V10::PreVoteReq(e10)
                         }
                         1 => {
-                            let e6 = super::super::super::super::raft::consensus::types::PreVoteResponse {
+                            let e10 = super::super::super::super::raft::consensus::types::PreVoteResponse {
                                 term: arg2 as u64,
                                 vote_granted: _rt::bool_lift(arg3 as i32 as u8),
+                                commit_index: arg4 as u64,
+                                last_log_index: arg5 as u64,
                             };
-                            V6::PreVoteRes(e6)
+                            V10::PreVoteRes(e10)
                         }
                         2 => {
-                            let e6 = super::super::super::super::raft::consensus::types::VoteRequest {
+                            let e10 = super::super::super::super::raft::consensus::types::VoteRequest {
                                 term: arg2 as u64,
                                 candidate_id: arg3 as u64,
                                 last_log_index: arg4 as u64,
                                 last_log_term: arg5 as u64,
+                                transfer: _rt::bool_lift(
+                                    arg6.assume_init() as i64 as i32 as u8,
+                                ),
                             };
-                            V6::VoteReq(e6)
+                            V10::VoteReq(e10)
                         }
                         3 => {
-                            let e6 = super::super::super::super::raft::consensus::types::VoteResponse {
+                            let e10 = super::super::super::super::raft::consensus::types::VoteResponse {
                                 term: arg2 as u64,
                                 vote_granted: _rt::bool_lift(arg3 as i32 as u8),
+                                commit_index: arg4 as u64,
+                                last_log_index: arg5 as u64,
                             };
-                            V6::VoteRes(e6)
+                            V10::VoteRes(e10)
                         }
                         4 => {
-                            let e6 = {
-                                let base5 = arg6;
-                                let len5 = arg7;
-                                let mut result5 = _rt::Vec::with_capacity(len5);
-                                for i in 0..len5 {
-                                    let base = base5
-                                        .add(i * (16 + 2 * ::core::mem::size_of::<*const u8>()));
-                                    let e5 = {
+                            let e10 = {
+                                let base6 = arg6.as_ptr().cast::<*mut u8>().read();
+                                let len6 = arg7 as usize;
+                                let mut result6 = _rt::Vec::with_capacity(len6);
+                                for i in 0..len6 {
+                                    let base = base6
+                                        .add(i * (24 + 2 * ::core::mem::size_of::<*const u8>()));
+                                    let e6 = {
                                         let l0 = *base.add(0).cast::<i64>();
                                         let l1 = *base.add(8).cast::<i64>();
-                                        let l2 = *base.add(16).cast::<*mut u8>();
+                                        let l2 = i32::from(*base.add(16).cast::<u8>());
                                         let l3 = *base
                                             .add(16 + 1 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<*mut u8>();
+                                        let l4 = *base
+                                            .add(16 + 2 * ::core::mem::size_of::<*const u8>())
                                             .cast::<usize>();
-                                        let len4 = l3;
+                                        let len5 = l4;
                                         super::super::super::super::raft::consensus::types::LogEntry {
                                             term: l0 as u64,
                                             index: l1 as u64,
-                                            command: _rt::Vec::from_raw_parts(l2.cast(), len4, len4),
+                                            entry_type: super::super::super::super::raft::consensus::types::EntryType::_lift(
+                                                l2 as u8,
+                                            ),
+                                            command: _rt::Vec::from_raw_parts(l3.cast(), len5, len5),
                                         }
                                     };
-                                    result5.push(e5);
+                                    result6.push(e6);
                                 }
                                 _rt::cabi_dealloc(
-                                    base5,
-                                    len5 * (16 + 2 * ::core::mem::size_of::<*const u8>()),
+                                    base6,
+                                    len6 * (24 + 2 * ::core::mem::size_of::<*const u8>()),
                                     8,
                                 );
                                 super::super::super::super::raft::consensus::types::AppendEntries {
@@ -717,38 +1404,201 @@ pub mod exports {
                                     leader_id: arg3 as u64,
                                     prev_log_index: arg4 as u64,
                                     prev_log_term: arg5 as u64,
-                                    entries: result5,
+                                    entries: result6,
                                     leader_commit: arg8 as u64,
+                                    seq: arg9 as u64,
                                 }
                             };
-                            V6::AppendReq(e6)
+                            V10::AppendReq(e10)
                         }
-                        n => {
-                            debug_assert_eq!(n, 5, "invalid enum discriminant");
-                            let e6 = super::super::super::super::raft::consensus::types::AppendEntriesResponse {
+                        5 => {
+                            let e10 = super::super::super::super::raft::consensus::types::AppendEntriesResponse {
                                 term: arg2 as u64,
                                 success: _rt::bool_lift(arg3 as i32 as u8),
+                                match_index: arg4 as u64,
+                                backoff: _rt::bool_lift(arg5 as i32 as u8),
+                                seq: arg6.assume_init() as i64 as u64,
+                            };
+                            V10::AppendRes(e10)
+                        }
+                        6 => {
+                            let e10 = {
+                                let len7 = arg7 as usize;
+                                super::super::super::super::raft::consensus::types::InstallSnapshot {
+                                    term: arg2 as u64,
+                                    leader_id: arg3 as u64,
+                                    last_included_index: arg4 as u64,
+                                    last_included_term: arg5 as u64,
+                                    data: _rt::Vec::from_raw_parts(
+                                        arg6.as_ptr().cast::<*mut u8>().read().cast(),
+                                        len7,
+                                        len7,
+                                    ),
+                                }
+                            };
+                            V10::SnapshotReq(e10)
+                        }
+                        7 => {
+                            let e10 = super::super::super::super::raft::consensus::types::InstallSnapshotResponse {
+                                term: arg2 as u64,
+                                match_index: arg3 as u64,
+                            };
+                            V10::SnapshotRes(e10)
+                        }
+                        8 => {
+                            let e10 = super::super::super::super::raft::consensus::types::RejoinRequest {
+                                cluster_id: match arg2 as i32 {
+                                    0 => None,
+                                    1 => {
+                                        let e = arg3 as u64;
+                                        Some(e)
+                                    }
+                                    _ => _rt::invalid_enum_discriminant(),
+                                },
+                                last_log_index: arg4 as u64,
+                                last_log_term: arg5 as u64,
+                                snapshot_index: arg6.assume_init() as i64 as u64,
+                            };
+                            V10::RejoinReq(e10)
+                        }
+                        9 => {
+                            let e10 = super::super::super::super::raft::consensus::types::RejoinResponse {
+                                term: arg2 as u64,
+                                refusal: match arg3 as i32 {
+                                    0 => None,
+                                    1 => {
+                                        let e = {
+                                            use super::super::super::super::raft::consensus::types::RejoinRefusal as V8;
+                                            let v8 = match arg4 as i32 {
+                                                0 => {
+                                                    let e8 = (arg5 as u64, arg6.assume_init() as i64 as u64);
+                                                    V8::ClusterMismatch(e8)
+                                                }
+                                                1 => {
+                                                    let e8 = (arg5 as u64, arg6.assume_init() as i64 as u64);
+                                                    V8::SnapshotAhead(e8)
+                                                }
+                                                n => {
+                                                    debug_assert_eq!(n, 2, "invalid enum discriminant");
+                                                    let e8 = (arg5 as u64, arg6.assume_init() as i64 as u64);
+                                                    V8::LogAhead(e8)
+                                                }
+                                            };
+                                            v8
+                                        };
+                                        Some(e)
+                                    }
+                                    _ => _rt::invalid_enum_discriminant(),
+                                },
+                            };
+                            V10::RejoinRes(e10)
+                        }
+                        10 => {
+                            let e10 = {
+                                let len9 = arg8 as usize;
+                                super::super::super::super::raft::consensus::types::InstallSnapshotChunk {
+                                    term: arg2 as u64,
+                                    leader_id: arg3 as u64,
+                                    last_included_index: arg4 as u64,
+                                    last_included_term: arg5 as u64,
+                                    offset: arg6.assume_init() as i64 as u64,
+                                    data: _rt::Vec::from_raw_parts(arg7.cast(), len9, len9),
+                                    done: _rt::bool_lift(arg9 as i32 as u8),
+                                }
+                            };
+                            V10::SnapshotChunkReq(e10)
+                        }
+                        11 => {
+                            let e10 = super::super::super::super::raft::consensus::types::InstallSnapshotChunkResponse {
+                                term: arg2 as u64,
+                                last_included_index: arg3 as u64,
+                                next_offset: arg4 as u64,
+                            };
+                            V10::SnapshotChunkRes(e10)
+                        }
+                        12 => {
+                            let e10 = super::super::super::super::raft::consensus::types::Heartbeat {
+                                term: arg2 as u64,
+                                leader_id: arg3 as u64,
+                                commit: arg4 as u64,
+                                seq: arg5 as u64,
                             };
-                            V6::AppendRes(e6)
+                            V10::HeartbeatReq(e10)
+                        }
+                        13 => {
+                            let e10 = super::super::super::super::raft::consensus::types::HeartbeatResponse {
+                                term: arg2 as u64,
+                                last_log_index: arg3 as u64,
+                                seq: arg4 as u64,
+                            };
+                            V10::HeartbeatRes(e10)
+                        }
+                        14 => {
+                            let e10 = arg2 as u64;
+                            V10::TimeoutNow(e10)
+                        }
+                        15 => {
+                            let e10 = arg2 as u64;
+                            V10::ReadQuorumReq(e10)
+                        }
+                        n => {
+                            debug_assert_eq!(n, 16, "invalid enum discriminant");
+                            let e10 = super::super::super::super::raft::consensus::types::ReadQuorumResponse {
+                                read_id: arg2 as u64,
+                                last_log_index: arg3 as u64,
+                            };
+                            V10::ReadQuorumRes(e10)
                         }
                     };
-                    T::on_message(arg0 as u64, v6);
+                    T::on_message(arg0 as u64, v10);
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
                 pub unsafe fn _export_submit_command_cabi<T: Guest>(
                     arg0: *mut u8,
                     arg1: usize,
-                ) -> i32 {
+                ) -> *mut u8 {
                     #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
                     let len0 = arg1;
                     let result1 = T::submit_command(
                         _rt::Vec::from_raw_parts(arg0.cast(), len0, len0),
                     );
+                    let ptr2 = (&raw mut _RET_AREA.0).cast::<u8>();
                     match result1 {
-                        true => 1,
-                        false => 0,
-                    }
+                        Ok(e) => {
+                            *ptr2.add(0).cast::<u8>() = (0i32) as u8;
+                            *ptr2.add(8).cast::<i64>() = _rt::as_i64(e);
+                        }
+                        Err(e) => {
+                            *ptr2.add(0).cast::<u8>() = (1i32) as u8;
+                            use super::super::super::super::raft::consensus::types::ProposeError as V3;
+                            match e {
+                                V3::NotLeader(e) => {
+                                    *ptr2.add(8).cast::<u8>() = (0i32) as u8;
+                                    match e {
+                                        Some(e) => {
+                                            *ptr2.add(16).cast::<u8>() = (1i32) as u8;
+                                            *ptr2.add(24).cast::<i64>() = _rt::as_i64(e);
+                                        }
+                                        None => {
+                                            *ptr2.add(16).cast::<u8>() = (0i32) as u8;
+                                        }
+                                    };
+                                }
+                                V3::TooLarge(e) => {
+                                    *ptr2.add(8).cast::<u8>() = (1i32) as u8;
+                                    *ptr2.add(16).cast::<i64>() = _rt::as_i64(e);
+                                }
+                                V3::UncommittedFull => {
+                                    *ptr2.add(8).cast::<u8>() = (2i32) as u8;
+                                }
+                                V3::Removed => {
+                                    *ptr2.add(8).cast::<u8>() = (3i32) as u8;
+                                }
+                            }
+                        }
+                    };
+                    ptr2
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
@@ -760,22 +1610,83 @@ pub mod exports {
                         id: id2,
                         state: state2,
                         term: term2,
+                        voted_for: voted_for2,
+                        leader_id: leader_id2,
                         log_length: log_length2,
+                        log_bytes: log_bytes2,
                         commit_index: commit_index2,
                     } = result0;
                     *ptr1.add(0).cast::<i64>() = _rt::as_i64(id2);
                     *ptr1.add(8).cast::<u8>() = (state2.clone() as i32) as u8;
                     *ptr1.add(16).cast::<i64>() = _rt::as_i64(term2);
-                    *ptr1.add(24).cast::<i64>() = _rt::as_i64(log_length2);
-                    *ptr1.add(32).cast::<i64>() = _rt::as_i64(commit_index2);
+                    match voted_for2 {
+                        Some(e) => {
+                            *ptr1.add(24).cast::<u8>() = (1i32) as u8;
+                            *ptr1.add(32).cast::<i64>() = _rt::as_i64(e);
+                        }
+                        None => {
+                            *ptr1.add(24).cast::<u8>() = (0i32) as u8;
+                        }
+                    };
+                    match leader_id2 {
+                        Some(e) => {
+                            *ptr1.add(40).cast::<u8>() = (1i32) as u8;
+                            *ptr1.add(48).cast::<i64>() = _rt::as_i64(e);
+                        }
+                        None => {
+                            *ptr1.add(40).cast::<u8>() = (0i32) as u8;
+                        }
+                    };
+                    *ptr1.add(56).cast::<i64>() = _rt::as_i64(log_length2);
+                    *ptr1.add(64).cast::<i64>() = _rt::as_i64(log_bytes2);
+                    *ptr1.add(72).cast::<i64>() = _rt::as_i64(commit_index2);
                     ptr1
                 }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_apply_pressure_cabi<T: Guest>(
+                    arg0: i64,
+                    arg1: i64,
+                ) {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    T::apply_pressure(arg0 as u64, arg1 as u64);
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_release_pressure_cabi<T: Guest>() {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    T::release_pressure();
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_pause_cabi<T: Guest>(arg0: i32) {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    T::pause(_rt::bool_lift(arg0 as u8));
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_resume_cabi<T: Guest>() {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    T::resume();
+                }
                 pub trait Guest {
                     fn init(node_id: u64, node_ids: _rt::Vec<u64>) -> ();
                     fn tick() -> NodeStatus;
                     fn on_message(from_node: u64, msg: RaftMessage) -> ();
-                    fn submit_command(command: _rt::Vec<u8>) -> bool;
+                    /// propose a command on the leader; returns the log index it was appended at
+                    fn submit_command(
+                        command: _rt::Vec<u8>,
+                    ) -> Result<u64, ProposeError>;
                     fn get_status() -> NodeStatus;
+                    /// chaos: hold alloc-bytes of extra memory and busy-wait burn-ms on every tick
+                    fn apply_pressure(alloc_bytes: u64, burn_ms: u64) -> ();
+                    /// chaos: drop all artificial memory and cpu pressure
+                    fn release_pressure() -> ();
+                    /// chaos: freeze the node (stopped, not crashed); incoming messages are
+                    /// held for resume if queue-messages is set, dropped otherwise
+                    fn pause(queue_messages: bool) -> ();
+                    /// chaos: unfreeze the node and deliver the messages held while paused
+                    fn resume() -> ();
                 }
                 #[doc(hidden)]
                 macro_rules! __export_raft_consensus_raft_api_cabi {
@@ -790,25 +1701,39 @@ pub mod exports {
                         _export_tick_cabi::<$ty > () } } #[unsafe (export_name =
                         "raft:consensus/raft-api#on-message")] unsafe extern "C" fn
                         export_on_message(arg0 : i64, arg1 : i32, arg2 : i64, arg3 : i64,
-                        arg4 : i64, arg5 : i64, arg6 : * mut u8, arg7 : usize, arg8 :
-                        i64,) { unsafe { $($path_to_types)*::
-                        _export_on_message_cabi::<$ty > (arg0, arg1, arg2, arg3, arg4,
-                        arg5, arg6, arg7, arg8) } } #[unsafe (export_name =
-                        "raft:consensus/raft-api#submit-command")] unsafe extern "C" fn
-                        export_submit_command(arg0 : * mut u8, arg1 : usize,) -> i32 {
-                        unsafe { $($path_to_types)*:: _export_submit_command_cabi::<$ty >
-                        (arg0, arg1) } } #[unsafe (export_name =
-                        "raft:consensus/raft-api#get-status")] unsafe extern "C" fn
-                        export_get_status() -> * mut u8 { unsafe { $($path_to_types)*::
-                        _export_get_status_cabi::<$ty > () } } };
+                        arg4 : i64, arg5 : i64, arg6 : ::core::mem::MaybeUninit::< u64 >,
+                        arg7 : * mut u8, arg8 : i64, arg9 : i64,) { unsafe {
+                        $($path_to_types)*:: _export_on_message_cabi::<$ty > (arg0, arg1,
+                        arg2, arg3, arg4, arg5, arg6, arg7, arg8, arg9) } } #[unsafe
+                        (export_name = "raft:consensus/raft-api#submit-command")] unsafe
+                        extern "C" fn export_submit_command(arg0 : * mut u8, arg1 :
+                        usize,) -> * mut u8 { unsafe { $($path_to_types)*::
+                        _export_submit_command_cabi::<$ty > (arg0, arg1) } } #[unsafe
+                        (export_name = "raft:consensus/raft-api#get-status")] unsafe
+                        extern "C" fn export_get_status() -> * mut u8 { unsafe {
+                        $($path_to_types)*:: _export_get_status_cabi::<$ty > () } }
+                        #[unsafe (export_name =
+                        "raft:consensus/raft-api#apply-pressure")] unsafe extern "C" fn
+                        export_apply_pressure(arg0 : i64, arg1 : i64,) { unsafe {
+                        $($path_to_types)*:: _export_apply_pressure_cabi::<$ty > (arg0,
+                        arg1) } } #[unsafe (export_name =
+                        "raft:consensus/raft-api#release-pressure")] unsafe extern "C" fn
+                        export_release_pressure() { unsafe { $($path_to_types)*::
+                        _export_release_pressure_cabi::<$ty > () } } #[unsafe
+                        (export_name = "raft:consensus/raft-api#pause")] unsafe extern
+                        "C" fn export_pause(arg0 : i32,) { unsafe { $($path_to_types)*::
+                        _export_pause_cabi::<$ty > (arg0) } } #[unsafe (export_name =
+                        "raft:consensus/raft-api#resume")] unsafe extern "C" fn
+                        export_resume() { unsafe { $($path_to_types)*::
+                        _export_resume_cabi::<$ty > () } } };
                     };
                 }
                 #[doc(hidden)]
                 pub(crate) use __export_raft_consensus_raft_api_cabi;
                 #[repr(align(8))]
-                struct _RetArea([::core::mem::MaybeUninit<u8>; 40]);
+                struct _RetArea([::core::mem::MaybeUninit<u8>; 80]);
                 static mut _RET_AREA: _RetArea = _RetArea(
-                    [::core::mem::MaybeUninit::uninit(); 40],
+                    [::core::mem::MaybeUninit::uninit(); 80],
                 );
             }
         }
@@ -864,6 +1789,13 @@ mod _rt {
         let layout = alloc::Layout::from_size_align_unchecked(size, align);
         alloc::dealloc(ptr, layout);
     }
+    pub unsafe fn invalid_enum_discriminant<T>() -> T {
+        if cfg!(debug_assertions) {
+            panic!("invalid enum discriminant")
+        } else {
+            unsafe { core::hint::unreachable_unchecked() }
+        }
+    }
     extern crate alloc as alloc_crate;
 }
 /// Generates `#[unsafe(no_mangle)]` functions to export the specified type as
@@ -902,36 +1834,61 @@ pub(crate) use __export_raft_node_impl as export;
 )]
 #[doc(hidden)]
 #[allow(clippy::octal_escapes)]
-pub static __WIT_BINDGEN_COMPONENT_TYPE: [u8; 1331] = *b"\
-\0asm\x0d\0\x01\0\0\x19\x16wit-component-encoding\x04\0\x07\xb3\x09\x01A\x02\x01\
-A\x09\x01B\x16\x01m\x04\x08follower\x09candidate\x06leader\x04dead\x04\0\x0anode\
--state\x03\0\0\x01r\x04\x04termw\x0ccandidate-idw\x0elast-log-indexw\x0dlast-log\
--termw\x04\0\x10pre-vote-request\x03\0\x02\x01r\x02\x04termw\x0cvote-granted\x7f\
-\x04\0\x11pre-vote-response\x03\0\x04\x01r\x04\x04termw\x0ccandidate-idw\x0elast\
--log-indexw\x0dlast-log-termw\x04\0\x0cvote-request\x03\0\x06\x01r\x02\x04termw\x0c\
-vote-granted\x7f\x04\0\x0dvote-response\x03\0\x08\x01p}\x01r\x03\x04termw\x05ind\
-exw\x07command\x0a\x04\0\x09log-entry\x03\0\x0b\x01p\x0c\x01r\x06\x04termw\x09le\
-ader-idw\x0eprev-log-indexw\x0dprev-log-termw\x07entries\x0d\x0dleader-commitw\x04\
-\0\x0eappend-entries\x03\0\x0e\x01r\x02\x04termw\x07success\x7f\x04\0\x17append-\
-entries-response\x03\0\x10\x01q\x06\x0cpre-vote-req\x01\x03\0\x0cpre-vote-res\x01\
-\x05\0\x08vote-req\x01\x07\0\x08vote-res\x01\x09\0\x0aappend-req\x01\x0f\0\x0aap\
-pend-res\x01\x11\0\x04\0\x0craft-message\x03\0\x12\x01r\x05\x02idw\x05state\x01\x04\
-termw\x0alog-lengthw\x0ccommit-indexw\x04\0\x0bnode-status\x03\0\x14\x03\0\x14ra\
-ft:consensus/types\x05\0\x02\x03\0\0\x0craft-message\x02\x03\0\0\x09log-entry\x01\
+pub static __WIT_BINDGEN_COMPONENT_TYPE: [u8; 2641] = *b"\
+\0asm\x0d\0\x01\0\0\x19\x16wit-component-encoding\x04\0\x07\xd1\x13\x01A\x02\x01\
+A\x0a\x01B1\x01m\x04\x08follower\x09candidate\x06leader\x04dead\x04\0\x0anode-st\
+ate\x03\0\0\x01r\x04\x04termw\x0ccandidate-idw\x0elast-log-indexw\x0dlast-log-te\
+rmw\x04\0\x10pre-vote-request\x03\0\x02\x01r\x04\x04termw\x0cvote-granted\x7f\x0c\
+commit-indexw\x0elast-log-indexw\x04\0\x11pre-vote-response\x03\0\x04\x01r\x05\x04\
+termw\x0ccandidate-idw\x0elast-log-indexw\x0dlast-log-termw\x08transfer\x7f\x04\0\
+\x0cvote-request\x03\0\x06\x01r\x04\x04termw\x0cvote-granted\x7f\x0ccommit-index\
+w\x0elast-log-indexw\x04\0\x0dvote-response\x03\0\x08\x01m\x03\x06normal\x05no-o\
+p\x06config\x04\0\x0aentry-type\x03\0\x0a\x01p}\x01r\x04\x04termw\x05indexw\x0ae\
+ntry-type\x0b\x07command\x0c\x04\0\x09log-entry\x03\0\x0d\x01p\x0e\x01r\x07\x04t\
+ermw\x09leader-idw\x0eprev-log-indexw\x0dprev-log-termw\x07entries\x0f\x0dleader\
+-commitw\x03seqw\x04\0\x0eappend-entries\x03\0\x10\x01r\x05\x04termw\x07success\x7f\
+\x0bmatch-indexw\x07backoff\x7f\x03seqw\x04\0\x17append-entries-response\x03\0\x12\
+\x01r\x05\x04termw\x09leader-idw\x13last-included-indexw\x12last-included-termw\x04\
+data\x0c\x04\0\x10install-snapshot\x03\0\x14\x01r\x02\x04termw\x0bmatch-indexw\x04\
+\0\x19install-snapshot-response\x03\0\x16\x01kw\x01r\x04\x0acluster-id\x18\x0ela\
+st-log-indexw\x0dlast-log-termw\x0esnapshot-indexw\x04\0\x0erejoin-request\x03\0\
+\x19\x01o\x02ww\x01q\x03\x10cluster-mismatch\x01\x1b\0\x0esnapshot-ahead\x01\x1b\
+\0\x09log-ahead\x01\x1b\0\x04\0\x0erejoin-refusal\x03\0\x1c\x01k\x1d\x01r\x02\x04\
+termw\x07refusal\x1e\x04\0\x0frejoin-response\x03\0\x1f\x01r\x07\x04termw\x09lea\
+der-idw\x13last-included-indexw\x12last-included-termw\x06offsetw\x04data\x0c\x04\
+done\x7f\x04\0\x16install-snapshot-chunk\x03\0!\x01r\x03\x04termw\x13last-includ\
+ed-indexw\x0bnext-offsetw\x04\0\x1finstall-snapshot-chunk-response\x03\0#\x01r\x04\
+\x04termw\x09leader-idw\x06commitw\x03seqw\x04\0\x09heartbeat\x03\0%\x01r\x03\x04\
+termw\x0elast-log-indexw\x03seqw\x04\0\x12heartbeat-response\x03\0'\x01r\x02\x07\
+read-idw\x0elast-log-indexw\x04\0\x14read-quorum-response\x03\0)\x01q\x11\x0cpre\
+-vote-req\x01\x03\0\x0cpre-vote-res\x01\x05\0\x08vote-req\x01\x07\0\x08vote-res\x01\
+\x09\0\x0aappend-req\x01\x11\0\x0aappend-res\x01\x13\0\x0csnapshot-req\x01\x15\0\
+\x0csnapshot-res\x01\x17\0\x0arejoin-req\x01\x1a\0\x0arejoin-res\x01\x20\0\x12sn\
+apshot-chunk-req\x01\"\0\x12snapshot-chunk-res\x01$\0\x0dheartbeat-req\x01&\0\x0d\
+heartbeat-res\x01(\0\x0btimeout-now\x01w\0\x0fread-quorum-req\x01w\0\x0fread-quo\
+rum-res\x01*\0\x04\0\x0craft-message\x03\0+\x01r\x08\x02idw\x05state\x01\x04term\
+w\x09voted-for\x18\x09leader-id\x18\x0alog-lengthw\x09log-bytesw\x0ccommit-index\
+w\x04\0\x0bnode-status\x03\0-\x01q\x04\x0anot-leader\x01\x18\0\x09too-large\x01w\
+\0\x10uncommitted-full\0\0\x07removed\0\0\x04\0\x0dpropose-error\x03\0/\x03\0\x14\
+raft:consensus/types\x05\0\x02\x03\0\0\x0craft-message\x02\x03\0\0\x09log-entry\x01\
 B\x10\x02\x03\x02\x01\x01\x04\0\x0craft-message\x03\0\0\x02\x03\x02\x01\x02\x04\0\
 \x09log-entry\x03\0\x02\x01@\x02\x07to-nodew\x03msg\x01\x01\0\x04\0\x0csend-mess\
 age\x01\x04\x01kw\x01@\x02\x04termw\x09voted-for\x05\x01\0\x04\0\x0dpersist-stat\
 e\x01\x06\x01p\x03\x01@\x01\x07entries\x07\x01\0\x04\0\x0bpersist-log\x01\x08\x01\
 @\0\0w\x04\0\x06now-ms\x01\x09\x01@\x02\x06min-msw\x06max-msw\0w\x04\0\x0erandom\
 -timeout\x01\x0a\x03\0\x13raft:consensus/host\x05\x03\x02\x03\0\0\x0bnode-status\
-\x01B\x0f\x02\x03\x02\x01\x04\x04\0\x0bnode-status\x03\0\0\x02\x03\x02\x01\x01\x04\
-\0\x0craft-message\x03\0\x02\x01pw\x01@\x02\x07node-idw\x08node-ids\x04\x01\0\x04\
-\0\x04init\x01\x05\x01@\0\0\x01\x04\0\x04tick\x01\x06\x01@\x02\x09from-nodew\x03\
-msg\x03\x01\0\x04\0\x0aon-message\x01\x07\x01p}\x01@\x01\x07command\x08\0\x7f\x04\
-\0\x0esubmit-command\x01\x09\x04\0\x0aget-status\x01\x06\x04\0\x17raft:consensus\
-/raft-api\x05\x05\x04\0\x18raft:consensus/raft-node\x04\0\x0b\x0f\x01\0\x09raft-\
-node\x03\0\0\0G\x09producers\x01\x0cprocessed-by\x02\x0dwit-component\x070.227.1\
-\x10wit-bindgen-rust\x060.41.0";
+\x02\x03\0\0\x0dpropose-error\x01B\x19\x02\x03\x02\x01\x04\x04\0\x0bnode-status\x03\
+\0\0\x02\x03\x02\x01\x01\x04\0\x0craft-message\x03\0\x02\x02\x03\x02\x01\x05\x04\
+\0\x0dpropose-error\x03\0\x04\x01pw\x01@\x02\x07node-idw\x08node-ids\x06\x01\0\x04\
+\0\x04init\x01\x07\x01@\0\0\x01\x04\0\x04tick\x01\x08\x01@\x02\x09from-nodew\x03\
+msg\x03\x01\0\x04\0\x0aon-message\x01\x09\x01p}\x01j\x01w\x01\x05\x01@\x01\x07co\
+mmand\x0a\0\x0b\x04\0\x0esubmit-command\x01\x0c\x04\0\x0aget-status\x01\x08\x01@\
+\x02\x0balloc-bytesw\x07burn-msw\x01\0\x04\0\x0eapply-pressure\x01\x0d\x01@\0\x01\
+\0\x04\0\x10release-pressure\x01\x0e\x01@\x01\x0equeue-messages\x7f\x01\0\x04\0\x05\
+pause\x01\x0f\x04\0\x06resume\x01\x0e\x04\0\x17raft:consensus/raft-api\x05\x06\x04\
+\0\x18raft:consensus/raft-node\x04\0\x0b\x0f\x01\0\x09raft-node\x03\0\0\0G\x09pr\
+oducers\x01\x0cprocessed-by\x02\x0dwit-component\x070.227.1\x10wit-bindgen-rust\x06\
+0.41.0";
 #[inline(never)]
 #[doc(hidden)]
 pub fn __link_custom_section_describing_imports() {
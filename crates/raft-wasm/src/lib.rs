@@ -5,10 +5,10 @@
 //! - Browser (via jco transpilation → JavaScript shims)
 //! - Raspberry Pi (via Wasmtime → real TCP/filesystem)
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 // Re-export core types
-pub use raft_core::{NodeState, RaftNode, RaftMessage, LogEntry, RaftConfig};
+pub use raft_core::{EntryType, NodeState, PauseMode, ProposeError, RaftNode, RaftMessage, RejoinError, LogEntry, RaftConfig, Ready, Snapshot};
 pub use raft_storage::InMemoryStorage;
 
 // Include generated bindings
@@ -26,14 +26,33 @@ use bindings::raft::consensus::types::{
     VoteResponse,
     AppendEntries,
     AppendEntriesResponse,
+    InstallSnapshot,
+    InstallSnapshotResponse,
+    RejoinRequest,
+    RejoinRefusal,
+    RejoinResponse,
+    InstallSnapshotChunk,
+    InstallSnapshotChunkResponse,
+    Heartbeat,
+    HeartbeatResponse,
+    ReadQuorumResponse,
     LogEntry as WitLogEntry,
+    EntryType as WitEntryType,
+    ProposeError as WitProposeError,
 };
 
 use bindings::exports::raft::consensus::raft_api::Guest;
+use bindings::raft::consensus::host;
 
 // Thread-local storage for the Raft node instance
 thread_local! {
-    static NODE: RefCell<Option<RaftNode>> = RefCell::new(None);
+    static NODE: RefCell<Option<RaftNode>> = const { RefCell::new(None) };
+    // Host clock reading at the previous tick
+    static LAST_TICK_MS: Cell<Option<u64>> = const { Cell::new(None) };
+    // Artificial memory pressure (chaos control)
+    static BALLAST: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+    // Artificial CPU pressure: ms to busy-wait on every tick (chaos control)
+    static BURN_MS_PER_TICK: Cell<u64> = const { Cell::new(0) };
 }
 
 // Convert between WIT types and internal types
@@ -49,16 +68,22 @@ fn to_wit_log_entry(entry: &LogEntry) -> WitLogEntry {
     WitLogEntry {
         term: entry.term,
         index: entry.index,
+        entry_type: match entry.entry_type {
+            EntryType::Normal => WitEntryType::Normal,
+            EntryType::NoOp => WitEntryType::NoOp,
+            EntryType::Config => WitEntryType::Config,
+        },
         command: entry.command.clone(),
     }
 }
 
 fn from_wit_log_entry(entry: &WitLogEntry) -> LogEntry {
-    LogEntry {
-        term: entry.term,
-        index: entry.index,
-        command: entry.command.clone(),
-    }
+    let entry_type = match entry.entry_type {
+        WitEntryType::Normal => EntryType::Normal,
+        WitEntryType::NoOp => EntryType::NoOp,
+        WitEntryType::Config => EntryType::Config,
+    };
+    LogEntry::with_type(entry.term, entry.index, entry_type, entry.command.clone())
 }
 
 // Implementation of the component exports
@@ -72,12 +97,22 @@ impl Guest for RaftNodeComponent {
         NODE.with(|n| {
             *n.borrow_mut() = Some(node);
         });
+        LAST_TICK_MS.with(|t| t.set(None));
     }
 
     fn tick() -> NodeStatus {
+        burn_cpu(BURN_MS_PER_TICK.with(Cell::get));
+
+        // Timers run on elapsed host time, so irregular tick calls are fine
+        let now = host::now_ms();
+        let elapsed = LAST_TICK_MS.with(|t| t.replace(Some(now)))
+            .map_or(0, |last| now.saturating_sub(last));
+
         NODE.with(|n| {
-            let node_ref = n.borrow();
-            if let Some(ref node) = *node_ref {
+            let mut node_ref = n.borrow_mut();
+            if let Some(ref mut node) = *node_ref {
+                let ready = node.tick(elapsed);
+                process_ready(ready);
                 get_node_status(node)
             } else {
                 dead_status()
@@ -89,57 +124,65 @@ impl Guest for RaftNodeComponent {
         NODE.with(|n| {
             let mut node_ref = n.borrow_mut();
             if let Some(ref mut node) = *node_ref {
-                let internal_msg = from_wit_message(msg);
-                
-                // Dispatch to appropriate handler based on message type
-                match internal_msg {
-                    RaftMessage::PreVoteRequest { term, candidate_id, last_log_index, last_log_term } => {
-                        let (response, _reset_timer) = node.handle_prevote_request(
-                            term, candidate_id, last_log_index, last_log_term
-                        );
-                        // Response would be sent via host.send_message() import
-                        let _ = (from_node, response); // Suppress unused for now
-                    }
-                    RaftMessage::PreVoteResponse { term, vote_granted } => {
-                        let _should_start_election = node.handle_prevote_response(term, vote_granted, from_node);
-                    }
-                    RaftMessage::VoteRequest { term, candidate_id, last_log_index, last_log_term } => {
-                        let (response, _reset_timer) = node.handle_vote_request(
-                            term, candidate_id, last_log_index, last_log_term
-                        );
-                        let _ = (from_node, response);
-                    }
-                    RaftMessage::VoteResponse { term, vote_granted } => {
-                        let _became_leader = node.handle_vote_response(term, vote_granted, from_node);
-                    }
-                    RaftMessage::AppendEntries { term, leader_id, prev_log_index, prev_log_term, entries, leader_commit } => {
-                        let (response, _reset_timer) = node.handle_append_entries(
-                            term, leader_id, prev_log_index, prev_log_term, entries, leader_commit
-                        );
-                        let _ = (from_node, response);
-                    }
-                    RaftMessage::AppendEntriesResponse { term, success } => {
-                        // Note: match_index_hint would come from message if enhanced,
-                        // for now we use 0 and let leader track via next_index
-                        let _commit_advanced = node.handle_append_entries_response(term, success, from_node, 0);
-                    }
-                }
+                let ready = node.step(from_node, from_wit_message(msg));
+                process_ready(ready);
             }
         });
     }
 
-    fn submit_command(command: Vec<u8>) -> bool {
+    fn submit_command(command: Vec<u8>) -> Result<u64, WitProposeError> {
         NODE.with(|n| {
-            let node_ref = n.borrow();
-            if let Some(ref node) = *node_ref {
-                // Only leader can accept commands
-                node.state == NodeState::Leader && !command.is_empty()
-            } else {
-                false
+            let mut node_ref = n.borrow_mut();
+            let Some(ref mut node) = *node_ref else {
+                return Err(WitProposeError::NotLeader(None));
+            };
+            match node.propose(command) {
+                Ok(index) => {
+                    // Push the new entry out now rather than on the next heartbeat
+                    let ready = node.replicate();
+                    process_ready(ready);
+                    Ok(index)
+                }
+                Err(ProposeError::NotLeader { leader_hint }) => Err(WitProposeError::NotLeader(leader_hint)),
+                Err(ProposeError::TooLarge { limit, .. }) => Err(WitProposeError::TooLarge(limit)),
+                Err(ProposeError::UncommittedFull { .. }) => Err(WitProposeError::UncommittedFull),
+                Err(ProposeError::Removed) => Err(WitProposeError::Removed),
             }
         })
     }
 
+    fn apply_pressure(alloc_bytes: u64, burn_ms: u64) {
+        if alloc_bytes > 0 {
+            // Touch every page so the memory is really committed
+            let ballast = vec![0xA5u8; alloc_bytes as usize];
+            BALLAST.with(|b| b.borrow_mut().push(ballast));
+        }
+        BURN_MS_PER_TICK.with(|b| b.set(burn_ms));
+    }
+
+    fn release_pressure() {
+        BALLAST.with(|b| b.borrow_mut().clear());
+        BURN_MS_PER_TICK.with(|b| b.set(0));
+    }
+
+    fn pause(queue_messages: bool) {
+        let mode = if queue_messages { PauseMode::Queue } else { PauseMode::Drop };
+        NODE.with(|n| {
+            if let Some(ref mut node) = *n.borrow_mut() {
+                node.pause(mode);
+            }
+        });
+    }
+
+    fn resume() {
+        NODE.with(|n| {
+            if let Some(ref mut node) = *n.borrow_mut() {
+                let ready = node.resume();
+                process_ready(ready);
+            }
+        });
+    }
+
     fn get_status() -> NodeStatus {
         NODE.with(|n| {
             let node_ref = n.borrow();
@@ -152,12 +195,41 @@ impl Guest for RaftNodeComponent {
     }
 }
 
+/// Busy-wait for `ms` of host time, starving this node like a loaded CPU
+fn burn_cpu(ms: u64) {
+    if ms == 0 {
+        return;
+    }
+    let start = host::now_ms();
+    let mut spin: u64 = 0;
+    while host::now_ms().saturating_sub(start) < ms {
+        spin = std::hint::black_box(spin.wrapping_add(1));
+    }
+}
+
+/// Hand a Ready to the host: persist first, then send
+fn process_ready(ready: Ready) {
+    if let Some(hard_state) = ready.hard_state {
+        host::persist_state(hard_state.term, hard_state.vote);
+    }
+    if !ready.entries.is_empty() {
+        let entries: Vec<WitLogEntry> = ready.entries.iter().map(to_wit_log_entry).collect();
+        host::persist_log(&entries);
+    }
+    for (to_node, msg) in &ready.messages {
+        host::send_message(*to_node, &to_wit_message(msg));
+    }
+}
+
 fn dead_status() -> NodeStatus {
     NodeStatus {
         id: 0,
         state: WitNodeState::Dead,
         term: 0,
+        voted_for: None,
+        leader_id: None,
         log_length: 0,
+        log_bytes: 0,
         commit_index: 0,
     }
 }
@@ -167,15 +239,17 @@ fn get_node_status(node: &RaftNode) -> NodeStatus {
         id: node.id,
         state: to_wit_state(node.state),
         term: node.current_term,
+        voted_for: node.voted_for,
+        leader_id: node.leader_id,
         log_length: if node.log.is_empty() { 0 } else { node.log.len() as u64 },
+        log_bytes: node.log_bytes(),
         commit_index: node.commit_index,
     }
 }
 
-#[allow(dead_code)]
 fn to_wit_message(msg: &RaftMessage) -> WitRaftMessage {
     match msg {
-        RaftMessage::PreVoteRequest { term, candidate_id, last_log_index, last_log_term } => {
+        RaftMessage::PreVoteRequest { term, candidate_id, last_log_index, last_log_term, .. } => {
             WitRaftMessage::PreVoteReq(PreVoteRequest {
                 term: *term,
                 candidate_id: *candidate_id,
@@ -183,27 +257,32 @@ fn to_wit_message(msg: &RaftMessage) -> WitRaftMessage {
                 last_log_term: *last_log_term,
             })
         }
-        RaftMessage::PreVoteResponse { term, vote_granted } => {
+        RaftMessage::PreVoteResponse { term, vote_granted, commit_index, last_log_index, .. } => {
             WitRaftMessage::PreVoteRes(PreVoteResponse {
                 term: *term,
                 vote_granted: *vote_granted,
+                commit_index: *commit_index,
+                last_log_index: *last_log_index,
             })
         }
-        RaftMessage::VoteRequest { term, candidate_id, last_log_index, last_log_term } => {
+        RaftMessage::VoteRequest { term, candidate_id, last_log_index, last_log_term, transfer, .. } => {
             WitRaftMessage::VoteReq(VoteRequest {
                 term: *term,
                 candidate_id: *candidate_id,
                 last_log_index: *last_log_index,
                 last_log_term: *last_log_term,
+                transfer: *transfer,
             })
         }
-        RaftMessage::VoteResponse { term, vote_granted } => {
+        RaftMessage::VoteResponse { term, vote_granted, commit_index, last_log_index, .. } => {
             WitRaftMessage::VoteRes(VoteResponse {
                 term: *term,
                 vote_granted: *vote_granted,
+                commit_index: *commit_index,
+                last_log_index: *last_log_index,
             })
         }
-        RaftMessage::AppendEntries { term, leader_id, prev_log_index, prev_log_term, entries, leader_commit } => {
+        RaftMessage::AppendEntries { term, leader_id, prev_log_index, prev_log_term, entries, leader_commit, seq, .. } => {
             WitRaftMessage::AppendReq(AppendEntries {
                 term: *term,
                 leader_id: *leader_id,
@@ -211,12 +290,99 @@ fn to_wit_message(msg: &RaftMessage) -> WitRaftMessage {
                 prev_log_term: *prev_log_term,
                 entries: entries.iter().map(to_wit_log_entry).collect(),
                 leader_commit: *leader_commit,
+                seq: *seq,
             })
         }
-        RaftMessage::AppendEntriesResponse { term, success } => {
+        RaftMessage::AppendEntriesResponse { term, success, match_index, backoff, seq, .. } => {
             WitRaftMessage::AppendRes(AppendEntriesResponse {
                 term: *term,
                 success: *success,
+                match_index: *match_index,
+                backoff: *backoff,
+                seq: *seq,
+            })
+        }
+        RaftMessage::InstallSnapshot { term, leader_id, snapshot, .. } => {
+            WitRaftMessage::SnapshotReq(InstallSnapshot {
+                term: *term,
+                leader_id: *leader_id,
+                last_included_index: snapshot.metadata.last_included_index,
+                last_included_term: snapshot.metadata.last_included_term,
+                data: snapshot.data.clone(),
+            })
+        }
+        RaftMessage::InstallSnapshotResponse { term, match_index, .. } => {
+            WitRaftMessage::SnapshotRes(InstallSnapshotResponse {
+                term: *term,
+                match_index: *match_index,
+            })
+        }
+        RaftMessage::RejoinRequest { cluster_id, last_log_index, last_log_term, snapshot_index, .. } => {
+            WitRaftMessage::RejoinReq(RejoinRequest {
+                cluster_id: *cluster_id,
+                last_log_index: *last_log_index,
+                last_log_term: *last_log_term,
+                snapshot_index: *snapshot_index,
+            })
+        }
+        RaftMessage::RejoinResponse { term, refusal, .. } => WitRaftMessage::RejoinRes(RejoinResponse {
+            term: *term,
+            refusal: refusal.map(|refusal| match refusal {
+                RejoinError::ClusterMismatch { local, cluster } => RejoinRefusal::ClusterMismatch((local, cluster)),
+                RejoinError::SnapshotAhead { snapshot_index, leader_last_index } => {
+                    RejoinRefusal::SnapshotAhead((snapshot_index, leader_last_index))
+                }
+                RejoinError::LogAhead { last_log_index, last_log_term } => {
+                    RejoinRefusal::LogAhead((last_log_index, last_log_term))
+                }
+            }),
+        }),
+        RaftMessage::InstallSnapshotChunk {
+            term,
+            leader_id,
+            last_included_index,
+            last_included_term,
+            offset,
+            data,
+            done,
+            ..
+        } => WitRaftMessage::SnapshotChunkReq(InstallSnapshotChunk {
+            term: *term,
+            leader_id: *leader_id,
+            last_included_index: *last_included_index,
+            last_included_term: *last_included_term,
+            offset: *offset,
+            data: data.clone(),
+            done: *done,
+        }),
+        RaftMessage::InstallSnapshotChunkResponse { term, last_included_index, next_offset, .. } => {
+            WitRaftMessage::SnapshotChunkRes(InstallSnapshotChunkResponse {
+                term: *term,
+                last_included_index: *last_included_index,
+                next_offset: *next_offset,
+            })
+        }
+        RaftMessage::Heartbeat { term, leader_id, commit, seq, .. } => {
+            WitRaftMessage::HeartbeatReq(Heartbeat {
+                term: *term,
+                leader_id: *leader_id,
+                commit: *commit,
+                seq: *seq,
+            })
+        }
+        RaftMessage::HeartbeatResponse { term, last_log_index, seq, .. } => {
+            WitRaftMessage::HeartbeatRes(HeartbeatResponse {
+                term: *term,
+                last_log_index: *last_log_index,
+                seq: *seq,
+            })
+        }
+        RaftMessage::TimeoutNow { term, .. } => WitRaftMessage::TimeoutNow(*term),
+        RaftMessage::ReadQuorumRequest { read_id, .. } => WitRaftMessage::ReadQuorumReq(*read_id),
+        RaftMessage::ReadQuorumResponse { read_id, last_log_index, .. } => {
+            WitRaftMessage::ReadQuorumRes(ReadQuorumResponse {
+                read_id: *read_id,
+                last_log_index: *last_log_index,
             })
         }
     }
@@ -229,20 +395,29 @@ fn from_wit_message(msg: WitRaftMessage) -> RaftMessage {
             candidate_id: req.candidate_id,
             last_log_index: req.last_log_index,
             last_log_term: req.last_log_term,
+            group_id: None,
         },
         WitRaftMessage::PreVoteRes(res) => RaftMessage::PreVoteResponse {
             term: res.term,
             vote_granted: res.vote_granted,
+            commit_index: res.commit_index,
+            last_log_index: res.last_log_index,
+            group_id: None,
         },
         WitRaftMessage::VoteReq(req) => RaftMessage::VoteRequest {
             term: req.term,
             candidate_id: req.candidate_id,
             last_log_index: req.last_log_index,
             last_log_term: req.last_log_term,
+            transfer: req.transfer,
+            group_id: None,
         },
         WitRaftMessage::VoteRes(res) => RaftMessage::VoteResponse {
             term: res.term,
             vote_granted: res.vote_granted,
+            commit_index: res.commit_index,
+            last_log_index: res.last_log_index,
+            group_id: None,
         },
         WitRaftMessage::AppendReq(req) => RaftMessage::AppendEntries {
             term: req.term,
@@ -251,10 +426,84 @@ fn from_wit_message(msg: WitRaftMessage) -> RaftMessage {
             prev_log_term: req.prev_log_term,
             entries: req.entries.iter().map(from_wit_log_entry).collect(),
             leader_commit: req.leader_commit,
+            seq: req.seq,
+            group_id: None,
         },
         WitRaftMessage::AppendRes(res) => RaftMessage::AppendEntriesResponse {
             term: res.term,
             success: res.success,
+            match_index: res.match_index,
+            storage_health: None,
+            backoff: res.backoff,
+            seq: res.seq,
+            group_id: None,
+        },
+        WitRaftMessage::SnapshotReq(req) => RaftMessage::InstallSnapshot {
+            term: req.term,
+            leader_id: req.leader_id,
+            snapshot: Snapshot::new(req.last_included_index, req.last_included_term, req.data),
+            group_id: None,
+        },
+        WitRaftMessage::SnapshotRes(res) => RaftMessage::InstallSnapshotResponse {
+            term: res.term,
+            match_index: res.match_index,
+            group_id: None,
+        },
+        WitRaftMessage::RejoinReq(req) => RaftMessage::RejoinRequest {
+            cluster_id: req.cluster_id,
+            last_log_index: req.last_log_index,
+            last_log_term: req.last_log_term,
+            snapshot_index: req.snapshot_index,
+            group_id: None,
+        },
+        WitRaftMessage::RejoinRes(res) => RaftMessage::RejoinResponse {
+            term: res.term,
+            refusal: res.refusal.map(|refusal| match refusal {
+                RejoinRefusal::ClusterMismatch((local, cluster)) => RejoinError::ClusterMismatch { local, cluster },
+                RejoinRefusal::SnapshotAhead((snapshot_index, leader_last_index)) => {
+                    RejoinError::SnapshotAhead { snapshot_index, leader_last_index }
+                }
+                RejoinRefusal::LogAhead((last_log_index, last_log_term)) => {
+                    RejoinError::LogAhead { last_log_index, last_log_term }
+                }
+            }),
+            group_id: None,
+        },
+        WitRaftMessage::SnapshotChunkReq(req) => RaftMessage::InstallSnapshotChunk {
+            term: req.term,
+            leader_id: req.leader_id,
+            last_included_index: req.last_included_index,
+            last_included_term: req.last_included_term,
+            offset: req.offset,
+            data: req.data,
+            done: req.done,
+            group_id: None,
+        },
+        WitRaftMessage::SnapshotChunkRes(res) => RaftMessage::InstallSnapshotChunkResponse {
+            term: res.term,
+            last_included_index: res.last_included_index,
+            next_offset: res.next_offset,
+            group_id: None,
+        },
+        WitRaftMessage::HeartbeatReq(hb) => RaftMessage::Heartbeat {
+            term: hb.term,
+            leader_id: hb.leader_id,
+            commit: hb.commit,
+            seq: hb.seq,
+            group_id: None,
+        },
+        WitRaftMessage::HeartbeatRes(res) => RaftMessage::HeartbeatResponse {
+            term: res.term,
+            last_log_index: res.last_log_index,
+            seq: res.seq,
+            group_id: None,
+        },
+        WitRaftMessage::TimeoutNow(term) => RaftMessage::TimeoutNow { term, group_id: None },
+        WitRaftMessage::ReadQuorumReq(read_id) => RaftMessage::ReadQuorumRequest { read_id, group_id: None },
+        WitRaftMessage::ReadQuorumRes(res) => RaftMessage::ReadQuorumResponse {
+            read_id: res.read_id,
+            last_log_index: res.last_log_index,
+            group_id: None,
         },
     }
 }
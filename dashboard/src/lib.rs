@@ -1,7 +1,11 @@
 //! # dashboard
 //!
 //! Raft Consensus Cluster visualization
-//! - Real WASM metrics
+//! - Real WASM metrics (per-node tick latency from the worker cluster)
+//! - Resource pressure injection
+//! - Persisted vs. in-memory state per node
+//! - Storage inspector (raw IndexedDB records per node)
+//! - Compact summary (leader, term, quorum, commit lag, last events) for phones
 //! - Auto leader election
 //! - PreVote demo
 //! - Watchdog (auto-restart)
@@ -17,6 +21,157 @@ extern "C" {
     fn now() -> f64;
 }
 
+/// One node's in-memory state next to what its storage has confirmed
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Durability {
+    alive: bool,
+    mem_term: u64,
+    mem_vote: Option<u64>,
+    mem_index: u64,
+    disk_term: u64,
+    disk_vote: Option<u64>,
+    disk_index: u64,
+}
+
+impl Durability {
+    /// Read the worker status posted by shim/node-worker.js
+    fn from_status(status: &JsValue) -> Self {
+        let get = |v: &JsValue, key: &str| js_sys::Reflect::get(v, &key.into()).ok();
+        let num = |v: &JsValue, key: &str| get(v, key).and_then(|x| x.as_f64());
+        let durable = get(status, "durable").unwrap_or(JsValue::UNDEFINED);
+        Self {
+            alive: get(status, "state").and_then(|s| s.as_string()).is_some_and(|s| s != "dead"),
+            mem_term: num(status, "term").unwrap_or(0.0) as u64,
+            mem_vote: num(status, "votedFor").map(|v| v as u64),
+            mem_index: num(status, "logLength").unwrap_or(0.0) as u64,
+            disk_term: num(&durable, "term").unwrap_or(0.0) as u64,
+            disk_vote: num(&durable, "votedFor").map(|v| v as u64),
+            disk_index: num(&durable, "lastIndex").unwrap_or(0.0) as u64,
+        }
+    }
+
+    /// Volatile state that would be lost if the node crashed right now
+    fn ahead_of_disk(&self) -> bool {
+        self.alive
+            && (self.mem_term != self.disk_term
+                || self.mem_vote != self.disk_vote
+                || self.mem_index != self.disk_index)
+    }
+}
+
+/// What one worker last reported about its node, for the cluster summary
+#[derive(Clone, Copy, Default, PartialEq)]
+struct LiveStatus {
+    alive: bool,
+    term: u64,
+    leader_id: Option<u64>,
+    commit_index: u64,
+}
+
+impl LiveStatus {
+    /// Read the worker status posted by shim/node-worker.js
+    fn from_status(status: &JsValue) -> Self {
+        let get = |key: &str| js_sys::Reflect::get(status, &key.into()).ok();
+        Self {
+            alive: get("state").and_then(|s| s.as_string()).is_some_and(|s| s != "dead"),
+            term: get("term").and_then(|v| v.as_f64()).unwrap_or(0.0) as u64,
+            leader_id: get("leaderId").and_then(|v| v.as_f64()).map(|v| v as u64),
+            commit_index: get("commitIndex").and_then(|v| v.as_f64()).unwrap_or(0.0) as u64,
+        }
+    }
+}
+
+/// Cluster view reduced to what fits on a phone screen
+///
+/// Everything comes from the live worker statuses: the leader is the one
+/// named by the alive node with the highest term, and commit lag is how far
+/// the slowest alive node's commit index trails the furthest one
+#[component]
+fn CompactSummary(live: ReadSignal<[LiveStatus; 3]>, events: ReadSignal<Vec<String>>) -> impl IntoView {
+    let alive = move || live.get().into_iter().filter(|n| n.alive).collect::<Vec<_>>();
+    let term = move || alive().iter().map(|n| n.term).max().unwrap_or(0);
+    let leader = move || {
+        alive().into_iter().max_by_key(|n| n.term).and_then(|n| n.leader_id)
+    };
+    let quorum = move || alive().len() >= 2;
+    let commit_lag = move || {
+        let commits: Vec<u64> = alive().iter().map(|n| n.commit_index).collect();
+        commits.iter().max().zip(commits.iter().min()).map_or(0, |(max, min)| max - min)
+    };
+    let lagging = move || commit_lag() > 0;
+    
+    view! {
+        <div class="card summary">
+            <div class="summary-grid">
+                <div class="summary-cell">
+                    <div class="value">{move || leader().map_or("none".into(), |id| format!("N{}", id))}</div>
+                    <div class="label">"Leader"</div>
+                </div>
+                <div class="summary-cell">
+                    <div class="value">{term}</div>
+                    <div class="label">"Term"</div>
+                </div>
+                <div class="summary-cell" class:fail=move || !quorum()>
+                    <div class="value">{move || format!("{}/3", alive().len())}</div>
+                    <div class="label">{move || if quorum() { "Quorum ✓" } else { "No quorum" }}</div>
+                </div>
+                <div class="summary-cell" class:lagging=lagging>
+                    <div class="value">{commit_lag}</div>
+                    <div class="label">"Commit lag"</div>
+                </div>
+            </div>
+            <div class="summary-events">
+                {move || events.get().iter().rev().take(5).cloned().map(|e| {
+                    view! { <div class="event">{e}</div> }
+                }).collect::<Vec<_>>()}
+            </div>
+        </div>
+    }
+}
+
+/// One IndexedDB record as listed by shim/filesystem.js
+#[derive(Clone, PartialEq)]
+struct StorageRecord {
+    node_id: u64,
+    store: String,
+    key: String,
+    bytes: u64,
+    timestamp: Option<f64>,
+}
+
+impl StorageRecord {
+    fn from_js(record: &JsValue) -> Self {
+        let get = |key: &str| js_sys::Reflect::get(record, &key.into()).ok();
+        Self {
+            node_id: get("nodeId").and_then(|v| v.as_f64()).unwrap_or(0.0) as u64,
+            store: get("store").and_then(|v| v.as_string()).unwrap_or_default(),
+            key: get("key").and_then(|v| v.as_string()).unwrap_or_default(),
+            bytes: get("bytes").and_then(|v| v.as_f64()).unwrap_or(0.0) as u64,
+            timestamp: get("timestamp").and_then(|v| v.as_f64()),
+        }
+    }
+}
+
+fn fmt_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+/// Age of a write, e.g. "3s ago"
+fn fmt_age(timestamp: Option<f64>) -> String {
+    timestamp.map_or("-".into(), |t| {
+        let secs = ((js_sys::Date::now() - t) / 1000.0).max(0.0) as u64;
+        if secs < 60 { format!("{}s ago", secs) } else { format!("{}m ago", secs / 60) }
+    })
+}
+
+fn fmt_vote(vote: Option<u64>) -> String {
+    vote.map_or("-".into(), |v| format!("N{}", v))
+}
+
 #[component]
 pub fn App() -> impl IntoView {
     // -- SIGNALS --
@@ -29,7 +184,20 @@ pub fn App() -> impl IntoView {
     let (log_index, set_log_index) = create_signal(0i32);
     let (rogue_term, set_rogue_term) = create_signal(1i32);
     let (auto_restart, set_auto_restart) = create_signal(false);
-    let (wasm_init_ms, set_wasm_init_ms) = create_signal(0.0f64);
+    // Real per-node tick latency reported by shim/worker-host.js
+    let (tick_ms, set_tick_ms) = create_signal([0.0f64; 3]);
+    // Node currently under artificial pressure (0 = none)
+    let (pressured, set_pressured) = create_signal(0i32);
+    // Per-node durable vs. volatile state (from worker status)
+    let (durability, set_durability) = create_signal([Durability::default(); 3]);
+    // Per-node leader/term/commit as last reported (drives the compact summary)
+    let (live, set_live) = create_signal([LiveStatus::default(); 3]);
+    // Hide the full dashboard behind the summary; starts on for narrow screens
+    let narrow = web_sys::window()
+        .and_then(|w| w.inner_width().ok())
+        .and_then(|w| w.as_f64())
+        .is_some_and(|w| w < 600.0);
+    let (compact, set_compact) = create_signal(narrow);
     
     // Per-node log indices (to show catch-up after restart)
     let (n1_log, set_n1_log) = create_signal(0i32);
@@ -49,15 +217,50 @@ pub fn App() -> impl IntoView {
         "👑 Node 1 elected leader (term 1)".into(),
     ]);
     let (kv_out, set_kv_out) = create_signal::<Vec<String>>(vec![]);
+    // Raw IndexedDB records (from shim/worker-host.js inspectStorage)
+    let (storage_records, set_storage_records) = create_signal::<Vec<StorageRecord>>(vec![]);
     
-    // Measure WASM init time
-    create_effect(move |_| {
-        let start = now();
-        let mut sum: u64 = 0;
-        for i in 0..50000 { sum = sum.wrapping_add(i); }
-        let _ = sum;
-        set_wasm_init_ms.set(now() - start);
-    });
+    // Listen for worker status (shim/worker-host.js) to show real tick latency
+    // and what each node has persisted
+    if let Some(window) = web_sys::window() {
+        let on_status = Closure::<dyn FnMut(web_sys::CustomEvent)>::new(move |e: web_sys::CustomEvent| {
+            let detail = e.detail();
+            let get = |v: &JsValue, key: &str| js_sys::Reflect::get(v, &key.into()).ok();
+            let node_id = get(&detail, "nodeId").and_then(|v| v.as_f64()).unwrap_or(0.0) as usize;
+            let ms = get(&detail, "status")
+                .and_then(|s| get(&s, "metrics"))
+                .and_then(|m| get(&m, "tickMs"))
+                .and_then(|v| v.as_f64());
+            if let (1..=3, Some(ms)) = (node_id, ms) {
+                set_tick_ms.update(|t| t[node_id - 1] = ms);
+            }
+            if let (1..=3, Some(status)) = (node_id, get(&detail, "status")) {
+                // A dead worker reports no durable mirror; keep the last one seen,
+                // since that is what is sitting in IndexedDB
+                set_live.update(|l| l[node_id - 1] = LiveStatus::from_status(&status));
+                let row = Durability::from_status(&status);
+                set_durability.update(|d| {
+                    d[node_id - 1] = if row.alive {
+                        row
+                    } else {
+                        Durability { alive: false, ..d[node_id - 1] }
+                    };
+                });
+            }
+        });
+        let _ = window.add_event_listener_with_callback("raft-worker-status", on_status.as_ref().unchecked_ref());
+        on_status.forget();
+        
+        let on_records = Closure::<dyn FnMut(web_sys::CustomEvent)>::new(move |e: web_sys::CustomEvent| {
+            let records = js_sys::Reflect::get(&e.detail(), &"records".into())
+                .map(|v| js_sys::Array::from(&v))
+                .map(|a| a.iter().map(|r| StorageRecord::from_js(&r)).collect())
+                .unwrap_or_default();
+            set_storage_records.set(records);
+        });
+        let _ = window.add_event_listener_with_callback("raft-storage-records", on_records.as_ref().unchecked_ref());
+        on_records.forget();
+    }
     
     // -- HELPERS --
     let alive_count = move || {
@@ -167,6 +370,29 @@ pub fn App() -> impl IntoView {
         set_events.update(|e| e.push(format!("📝 Log[{}]: {}", idx, cmd)));
     };
     
+    // Ask shim/worker-host.js to load (or relieve) one node's wasm instance
+    let send_pressure = move |node_id: i32, alloc_mb: u32, burn_ms: u32| {
+        let detail = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&detail, &"nodeId".into(), &node_id.into());
+        let _ = js_sys::Reflect::set(&detail, &"allocBytes".into(), &(alloc_mb * 1024 * 1024).into());
+        let _ = js_sys::Reflect::set(&detail, &"burnMs".into(), &burn_ms.into());
+        let _ = js_sys::Reflect::set(&detail, &"release".into(), &(alloc_mb == 0 && burn_ms == 0).into());
+        
+        let init = web_sys::CustomEventInit::new();
+        init.set_detail(&detail);
+        if let (Some(w), Ok(ev)) = (web_sys::window(), web_sys::CustomEvent::new_with_event_init_dict("raft-pressure", &init)) {
+            let _ = w.dispatch_event(&ev);
+        }
+    };
+    
+    // Ask shim/worker-host.js to list what every node has in IndexedDB
+    let inspect_storage = move || {
+        if let (Some(w), Ok(ev)) = (web_sys::window(), web_sys::CustomEvent::new("raft-storage-inspect")) {
+            let _ = w.dispatch_event(&ev);
+        }
+    };
+    Timeout::new(1000, inspect_storage).forget();
+    
     // Sensor simulation - tick once per call
     let tick_sensor = move || {
         if !has_quorum() || !has_leader() { 
@@ -193,10 +419,13 @@ pub fn App() -> impl IntoView {
     
     
     view! {
-        <div class="dashboard">
+        <div class="dashboard" class:compact=compact>
             <header class="header">
                 <h1>"🗳️ Raft Consensus"</h1>
                 <div class="badges">
+                    <button class="badge view-toggle" on:click=move |_| set_compact.update(|c| *c = !*c)>
+                        {move || if compact.get() { "Full view" } else { "Compact" }}
+                    </button>
                     <span class="badge" class:ok=has_quorum class:fail=move || !has_quorum()>
                         {move || if has_quorum() { "QUORUM ✓" } else { "NO QUORUM ✗" }}
                     </span>
@@ -204,6 +433,8 @@ pub fn App() -> impl IntoView {
                 </div>
             </header>
             
+            <CompactSummary live=live events=events/>
+            
             <div class="info-box">
                 "Raft keeps 3 nodes in sync. Kill nodes → see leader election. "
                 "Try Watchdog for auto-restart. Hover buttons for tooltips."
@@ -331,6 +562,25 @@ pub fn App() -> impl IntoView {
                                 }>"🔄 Restart N3"</button>
                         </div>
                         
+                        <div class="card-title" style="margin-top:1rem">"🔥 Resource Pressure"</div>
+                        <p class="help-text">"Load one node's WASM instance. Watch its tick latency, elections and commits."</p>
+                        <div class="controls">
+                            <button class="btn orange" data-tip="Allocate 64MB and burn 40ms of CPU per tick inside N2's WASM instance."
+                                disabled=move || pressured.get() != 0
+                                on:click=move |_| {
+                                    send_pressure(2, 64, 40);
+                                    set_pressured.set(2);
+                                    set_events.update(|e| e.push("🔥 N2 under pressure (64MB, 40ms/tick)".into()));
+                                }>"🔥 Stress N2"</button>
+                            <button class="btn green" data-tip="Free the memory and stop burning CPU."
+                                disabled=move || pressured.get() == 0
+                                on:click=move |_| {
+                                    send_pressure(pressured.get(), 0, 0);
+                                    set_pressured.set(0);
+                                    set_events.update(|e| e.push("🧊 Pressure released".into()));
+                                }>"🧊 Release"</button>
+                        </div>
+                        
                         <div class="card-title" style="margin-top:1rem">"🏴‍☠️ Disruptive Server"</div>
                         <p class="help-text">"A disconnected node inflates its term. PreVote blocks it."</p>
                         <div class="controls">
@@ -429,9 +679,9 @@ pub fn App() -> impl IntoView {
                     <div class="card">
                         <div class="card-title">"⚡ Metrics"</div>
                         <div class="metrics">
-                            <div class="metric" data-tip="WASM module init time">
-                                <div class="value">{move || format!("{:.1}", wasm_init_ms.get())}</div>
-                                <div class="label">"WASM Load"</div>
+                            <div class="metric" data-tip="Slowest node tick (ms), measured around each WASM tick() in its worker">
+                                <div class="value">{move || format!("{:.1}", tick_ms.get().iter().cloned().fold(0.0, f64::max))}</div>
+                                <div class="label">"Tick ms"</div>
                             </div>
                             <div class="metric" data-tip="Raft term. Increments each election.">
                                 <div class="value">{term}</div>
@@ -448,6 +698,58 @@ pub fn App() -> impl IntoView {
                         </div>
                     </div>
                     
+                    <div class="card">
+                        <div class="card-title">"🗄️ Durability"</div>
+                        <p class="help-text">"Memory vs. what IndexedDB has confirmed. Only the disk side survives a crash."</p>
+                        <table class="durability">
+                            <tr>
+                                <th></th>
+                                <th data-tip="In-memory term / vote / last log index">"Memory"</th>
+                                <th data-tip="Persisted term / vote / last log index">"Disk"</th>
+                            </tr>
+                            {move || durability.get().into_iter().enumerate().map(|(i, d)| {
+                                let mem = if d.alive {
+                                    format!("T{} {} #{}", d.mem_term, fmt_vote(d.mem_vote), d.mem_index)
+                                } else {
+                                    "lost".to_string()
+                                };
+                                let disk = format!("T{} {} #{}", d.disk_term, fmt_vote(d.disk_vote), d.disk_index);
+                                view! {
+                                    <tr class:pending=d.ahead_of_disk() class:dead=!d.alive>
+                                        <td>{format!("N{}", i + 1)}</td>
+                                        <td>{mem}</td>
+                                        <td>{disk}</td>
+                                    </tr>
+                                }
+                            }).collect::<Vec<_>>()}
+                        </table>
+                    </div>
+                    
+                    <div class="card">
+                        <div class="card-title">"💾 Storage Inspector"</div>
+                        <p class="help-text">"Records each node has in IndexedDB: the hard state (term + vote) and the log file."</p>
+                        <button class="btn blue" on:click=move |_| inspect_storage()
+                            data-tip="Re-read every node's records straight from IndexedDB">"Refresh"</button>
+                        <table class="durability storage">
+                            <tr>
+                                <th>"Node"</th>
+                                <th>"Record"</th>
+                                <th>"Size"</th>
+                                <th>"Written"</th>
+                            </tr>
+                            {move || storage_records.get().into_iter().map(|r| {
+                                view! {
+                                    <tr>
+                                        <td>{format!("N{}", r.node_id)}</td>
+                                        <td>{format!("{}/{}", r.store, r.key)}</td>
+                                        <td>{fmt_bytes(r.bytes)}</td>
+                                        <td>{fmt_age(r.timestamp)}</td>
+                                    </tr>
+                                }
+                            }).collect::<Vec<_>>()}
+                        </table>
+                    </div>
+                    
                     <div class="card events-card">
                         <div class="card-title">"📋 Events"</div>
                         <div class="events">
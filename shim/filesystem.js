@@ -259,6 +259,43 @@ export class VirtualFilesystem {
         }
     }
 
+    // -- inspection --
+
+    /**
+     * list every stored record with its size and write time (storage inspector)
+     *
+     * files are listed by path; the metadata record shows up as 'hard-state'
+     * @returns {Promise<Array<{nodeId: number, store: string, key: string, bytes: number, timestamp: number|null}>>}
+     */
+    async listRecords() {
+        await this.ensureDb();
+
+        const getAll = (storeName) => new Promise((resolve, reject) => {
+            const tx = this.db.transaction(storeName, 'readonly');
+            const request = tx.objectStore(storeName).getAll();
+            request.onsuccess = () => resolve(request.result);
+            request.onerror = () => reject(request.error);
+        });
+
+        const encoder = new TextEncoder();
+        const files = (await getAll('files')).map(record => ({
+            nodeId: record.nodeId,
+            store: 'files',
+            key: record.path,
+            bytes: typeof record.data === 'string' ? encoder.encode(record.data).length : record.data.length,
+            timestamp: record.timestamp ?? null
+        }));
+        const metadata = (await getAll('metadata')).map(record => ({
+            nodeId: record.nodeId,
+            store: 'metadata',
+            key: 'hard-state',
+            bytes: encoder.encode(JSON.stringify({ term: record.term, votedFor: record.votedFor })).length,
+            timestamp: record.timestamp ?? null
+        }));
+
+        return metadata.concat(files).sort((a, b) => a.nodeId - b.nodeId);
+    }
+
     // -- helpers --
 
     /**
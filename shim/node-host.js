@@ -0,0 +1,168 @@
+/**
+ * node-host.js
+ *
+ * why: implement the raft:consensus/host imports for a component running in a worker
+ * relations: mapped in at transpile time (jco --map), used by node-worker.js, persists via filesystem.js
+ * what: send/persist/clock imports plus a mirror of what has actually reached indexeddb
+ *
+ * persist-state and persist-log return as soon as the write is queued, so the
+ * node's in-memory state runs ahead of storage for a moment. the durable
+ * mirror is only updated once indexeddb confirms the write, which is exactly
+ * what would survive if the worker were terminated right now.
+ *
+ * messages sent while writes are outstanding wait behind them, so nothing a
+ * node says (a granted vote, an append ack) leaves before the state behind
+ * it is durable. term/vote writes that pile up before the previous one lands
+ * are merged into one: only the latest hard state is written.
+ */
+
+import { filesystem } from './filesystem.js';
+
+/** @type {number | null} - id of the node hosted by this worker */
+let boundNodeId = null;
+
+/**
+ * last state confirmed written to indexeddb
+ * @type {{ term: number, votedFor: number | null, lastIndex: number, writes: number }}
+ */
+export const durable = { term: 0, votedFor: null, lastIndex: 0, writes: 0 };
+
+/** @type {function | null} - called after every confirmed write */
+let onPersisted = null;
+
+/** @type {Promise<void>} - keeps writes in the order the node issued them */
+let writeChain = Promise.resolve();
+
+/** @type {number} - writes (and sends waiting on them) not yet done */
+let outstanding = 0;
+
+/** @type {{ term: number, votedFor: number | null } | null} - hard state queued and not yet started */
+let pendingHardState = null;
+
+/**
+ * bind the host to a node and load whatever it persisted before a crash
+ * @param {number} nodeId
+ * @param {function} persistedCallback - called after every confirmed write
+ * @returns {Promise<void>}
+ */
+export async function bindNode(nodeId, persistedCallback) {
+    boundNodeId = nodeId;
+    onPersisted = persistedCallback;
+
+    const metadata = await filesystem.loadMetadata(nodeId);
+    const log = await filesystem.loadLog(nodeId);
+    durable.term = metadata.term;
+    durable.votedFor = metadata.votedFor;
+    durable.lastIndex = log.length > 0 ? log[log.length - 1].index : 0;
+}
+
+/**
+ * queue a storage write and report it once it lands
+ * @param {function(): Promise<void>} write
+ */
+function enqueueWrite(write) {
+    outstanding += 1;
+    writeChain = writeChain
+        .then(write)
+        .then(() => {
+            durable.writes += 1;
+            if (onPersisted) onPersisted();
+        })
+        .catch((error) => {
+            self.postMessage({ type: 'error', nodeId: boundNodeId, error: `persist failed: ${error.message}` });
+        })
+        .finally(() => {
+            outstanding -= 1;
+        });
+}
+
+// -- raft:consensus/host imports --
+
+/**
+ * hand an outgoing message to the main thread for routing
+ * @param {bigint} toNode
+ * @param {object} msg - wit raft-message
+ */
+export function sendMessage(toNode, msg) {
+    const post = () => self.postMessage({ type: 'send', nodeId: boundNodeId, to: Number(toNode), message: msg });
+    if (outstanding === 0) {
+        post();
+    } else {
+        // persist before send: release only once every earlier write has
+        // landed, and count the send so later messages can't overtake it
+        outstanding += 1;
+        writeChain = writeChain.then(post).finally(() => {
+            outstanding -= 1;
+        });
+    }
+}
+
+/**
+ * persist term and vote
+ * @param {bigint} term
+ * @param {bigint | undefined} votedFor
+ */
+export function persistState(term, votedFor) {
+    const nodeId = boundNodeId;
+    const queued = pendingHardState !== null;
+    pendingHardState = { term: Number(term), votedFor: votedFor === undefined ? null : Number(votedFor) };
+
+    // a write that hasn't started yet picks up the newest state when it does.
+    // terms only grow and a vote is only ever set once per term, so the
+    // latest hard state covers every one it replaced
+    if (queued) return;
+
+    enqueueWrite(async () => {
+        const { term: termNum, votedFor: voteNum } = pendingHardState;
+        pendingHardState = null;
+        await filesystem.saveMetadata(nodeId, termNum, voteNum);
+        durable.term = termNum;
+        durable.votedFor = voteNum;
+    });
+}
+
+/**
+ * persist new log entries
+ *
+ * entries are contiguous, so anything stored at or past the first new index
+ * was overwritten by the leader and is dropped first
+ * @param {Array<{term: bigint, index: bigint, entryType: string, command: Uint8Array}>} entries
+ */
+export function persistLog(entries) {
+    if (entries.length === 0) return;
+
+    const nodeId = boundNodeId;
+    const plain = entries.map(e => ({
+        term: Number(e.term),
+        index: Number(e.index),
+        entryType: e.entryType,
+        command: Array.from(e.command)
+    }));
+
+    enqueueWrite(async () => {
+        const log = await filesystem.loadLog(nodeId);
+        const kept = log.filter(e => e.index < plain[0].index);
+        await filesystem.saveLog(nodeId, kept.concat(plain));
+        durable.lastIndex = plain[plain.length - 1].index;
+    });
+}
+
+/**
+ * wall clock in ms (drives the node's timers)
+ * @returns {bigint}
+ */
+export function nowMs() {
+    return BigInt(Math.floor(performance.timeOrigin + performance.now()));
+}
+
+/**
+ * random timeout in [min, max]
+ * @param {bigint} minMs
+ * @param {bigint} maxMs
+ * @returns {bigint}
+ */
+export function randomTimeout(minMs, maxMs) {
+    const min = Number(minMs);
+    const max = Number(maxMs);
+    return BigInt(min + Math.floor(Math.random() * (max - min + 1)));
+}
@@ -0,0 +1,190 @@
+/**
+ * node-worker.js
+ *
+ * why: run one raft-wasm instance per web worker so heavy work stays off the ui thread
+ * relations: spawned by worker-host.js, loads the jco component from ./wasm/raft.js, host imports in node-host.js
+ * what: worker message loop (init, deliver, tick, submit, pressure) around the raft api
+ *
+ * the component keeps its node in a thread_local, so one instance can only
+ * host one node. giving every node its own worker gives every node its own
+ * instance, and worker.terminate() drops it exactly like a process crash.
+ */
+
+import { bindNode, durable } from './node-host.js';
+
+/** @type {object | null} - the raftApi export of the transpiled component */
+let raftApi = null;
+
+/** @type {number | null} - id of the node hosted by this worker */
+let nodeId = null;
+
+/**
+ * real per-node numbers reported alongside the status
+ * @type {{ tickMs: number, ballastBytes: number, burnMs: number, inWasm: boolean }}
+ */
+const metrics = { tickMs: 0, ballastBytes: 0, burnMs: 0, inWasm: false };
+
+/** @type {ArrayBuffer[]} - fallback ballast when the component lacks apply-pressure */
+const jsBallast = [];
+
+/**
+ * apply memory/cpu pressure to this node
+ *
+ * uses the component's apply-pressure export so the load lands inside the
+ * wasm instance; components transpiled before that export existed get the
+ * same effect from the worker, which starves the instance just the same.
+ * @param {number} allocBytes - extra memory to hold
+ * @param {number} burnMs - ms to busy-wait on every tick
+ */
+function applyPressure(allocBytes, burnMs) {
+    metrics.inWasm = typeof raftApi.applyPressure === 'function';
+    if (metrics.inWasm) {
+        raftApi.applyPressure(BigInt(allocBytes), BigInt(burnMs));
+    } else if (allocBytes > 0) {
+        const buffer = new ArrayBuffer(allocBytes);
+        new Uint8Array(buffer).fill(0xa5); // commit the pages
+        jsBallast.push(buffer);
+    }
+    metrics.ballastBytes += allocBytes;
+    metrics.burnMs = burnMs;
+}
+
+/**
+ * drop all artificial pressure
+ */
+function releasePressure() {
+    if (typeof raftApi.releasePressure === 'function') {
+        raftApi.releasePressure();
+    }
+    jsBallast.length = 0;
+    metrics.ballastBytes = 0;
+    metrics.burnMs = 0;
+}
+
+/**
+ * busy-wait in the worker (fallback cpu pressure)
+ * @param {number} ms
+ */
+function burnCpu(ms) {
+    const start = performance.now();
+    while (performance.now() - start < ms) { /* spin */ }
+}
+
+/**
+ * convert a wit node-status into plain numbers for postMessage
+ * @param {object} status
+ * @returns {object}
+ */
+function toPlainStatus(status) {
+    return {
+        id: Number(status.id),
+        state: status.state,
+        term: Number(status.term),
+        votedFor: status.votedFor === undefined ? null : Number(status.votedFor),
+        leaderId: status.leaderId === undefined ? null : Number(status.leaderId),
+        logLength: Number(status.logLength),
+        logBytes: Number(status.logBytes),
+        commitIndex: Number(status.commitIndex),
+        durable: { ...durable },
+        metrics: { ...metrics }
+    };
+}
+
+/**
+ * propose a command through the component
+ *
+ * submit-command returns result<u64, propose-error>; jco hands back the
+ * index on ok and throws the error's payload otherwise. a refused command
+ * reports the variant as `reason` (not-leader, too-large, uncommitted-full, removed)
+ * @param {string} command
+ * @returns {{ accepted: boolean, index: number | null, leaderHint: number | null, reason?: string }}
+ */
+function submit(command) {
+    const bytes = new TextEncoder().encode(command);
+    try {
+        return { accepted: true, index: Number(raftApi.submitCommand(bytes)), leaderHint: null };
+    } catch (error) {
+        const reason = error.payload?.tag;
+        if (reason === undefined) throw error;
+        const hint = reason === 'not-leader' ? error.payload.val : undefined;
+        return { accepted: false, index: null, leaderHint: hint === undefined ? null : Number(hint), reason };
+    }
+}
+
+/**
+ * report the current node status to the main thread
+ */
+function postStatus() {
+    if (!raftApi) return;
+    self.postMessage({ type: 'status', nodeId, status: toPlainStatus(raftApi.getStatus()) });
+}
+
+self.onmessage = async (event) => {
+    const { type } = event.data;
+
+    try {
+        switch (type) {
+            case 'init': {
+                const instantiateStart = performance.now();
+                const raft = await import('./wasm/raft.js');
+                raftApi = raft.raftApi;
+                nodeId = event.data.nodeId;
+                await bindNode(nodeId, postStatus);
+
+                const nodeIds = new BigUint64Array(event.data.clusterNodes.map(id => BigInt(id)));
+                raftApi.init(BigInt(nodeId), nodeIds);
+
+                self.postMessage({
+                    type: 'ready',
+                    nodeId,
+                    instantiateMs: performance.now() - instantiateStart
+                });
+                postStatus();
+                break;
+            }
+
+            case 'deliver': {
+                if (!raftApi) return;
+                raftApi.onMessage(BigInt(event.data.from), event.data.message);
+                postStatus();
+                break;
+            }
+
+            case 'tick': {
+                if (!raftApi) return;
+                const tickStart = performance.now();
+                if (!metrics.inWasm && metrics.burnMs > 0) burnCpu(metrics.burnMs);
+                const status = raftApi.tick();
+                metrics.tickMs = performance.now() - tickStart;
+                self.postMessage({ type: 'status', nodeId, status: toPlainStatus(status) });
+                break;
+            }
+
+            case 'submit': {
+                if (!raftApi) return;
+                const result = submit(event.data.command);
+                self.postMessage({ type: 'submitted', nodeId, requestId: event.data.requestId, ...result });
+                break;
+            }
+
+            case 'pressure': {
+                if (!raftApi) return;
+                applyPressure(event.data.allocBytes, event.data.burnMs);
+                postStatus();
+                break;
+            }
+
+            case 'release-pressure': {
+                if (!raftApi) return;
+                releasePressure();
+                postStatus();
+                break;
+            }
+
+            default:
+                self.postMessage({ type: 'error', nodeId, error: `unknown worker message: ${type}` });
+        }
+    } catch (error) {
+        self.postMessage({ type: 'error', nodeId, error: error.message });
+    }
+};
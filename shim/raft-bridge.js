@@ -46,7 +46,9 @@ export function tickNode(nodeId) {
         id: Number(status.id),
         state: status.state,
         term: Number(status.term),
+        leaderId: status.leaderId === undefined ? null : Number(status.leaderId),
         logLength: Number(status.logLength),
+        logBytes: Number(status.logBytes),
         commitIndex: Number(status.commitIndex)
     };
 }
@@ -61,7 +63,9 @@ export function getNodeStatus() {
         id: Number(status.id),
         state: status.state,
         term: Number(status.term),
+        leaderId: status.leaderId === undefined ? null : Number(status.leaderId),
         logLength: Number(status.logLength),
+        logBytes: Number(status.logBytes),
         commitIndex: Number(status.commitIndex)
     };
 }
@@ -74,7 +78,14 @@ export function getNodeStatus() {
 export function submitCommand(command) {
     const encoder = new TextEncoder();
     const bytes = encoder.encode(command);
-    return raft.raftApi.submitCommand(bytes);
+    try {
+        raft.raftApi.submitCommand(bytes);
+        return true;
+    } catch (error) {
+        // propose-error (e.g. not-leader) is thrown with the variant as payload
+        if (error.payload === undefined) throw error;
+        return false;
+    }
 }
 
 /**
@@ -131,7 +142,8 @@ export function createVoteRequest(term, candidateId, lastLogIndex, lastLogTerm)
             term: BigInt(term),
             candidateId: BigInt(candidateId),
             lastLogIndex: BigInt(lastLogIndex),
-            lastLogTerm: BigInt(lastLogTerm)
+            lastLogTerm: BigInt(lastLogTerm),
+            transfer: false
         }
     };
 }
@@ -0,0 +1,318 @@
+/**
+ * worker-host.js
+ *
+ * why: host each raft node in its own web worker instead of on the ui thread
+ * relations: spawns node-worker.js, routes messages through network.js chaos rules
+ * what: WorkerCluster class, postMessage transport, crash-by-terminate lifecycle, pressure controls, storage inspection
+ *
+ * log catch-up and snapshot work inside a node can take a while; running it
+ * in a worker keeps the dashboard responsive. killing a node terminates its
+ * worker, which discards all volatile wasm state just like a real crash.
+ */
+
+import { filesystem } from './filesystem.js';
+import { network } from './network.js';
+
+/**
+ * a cluster of raft nodes, one web worker per node.
+ *
+ * messages between nodes travel main thread -> worker via postMessage.
+ * partitions and dead nodes from the shared VirtualNetwork are honoured
+ * before anything is delivered.
+ */
+export class WorkerCluster {
+    /**
+     * create a new worker cluster
+     * @param {number} nodeCount - number of nodes in cluster (default: 3)
+     */
+    constructor(nodeCount = 3) {
+        /** @type {number} - number of nodes in cluster */
+        this.nodeCount = nodeCount;
+
+        /** @type {Map<number, Worker>} - node id to worker */
+        this.workers = new Map();
+
+        /** @type {Map<number, object>} - node id to last reported status */
+        this.statuses = new Map();
+
+        /** @type {Map<number, function>} - pending submit request id to resolver */
+        this.pendingSubmits = new Map();
+
+        /** @type {number} - next submit request id */
+        this.nextRequestId = 1;
+
+        /** @type {number | null} - interval id for the tick loop */
+        this.tickTimer = null;
+
+        /** @type {function | null} - callback when a node reports status */
+        this.onNodeStatus = null;
+
+        // let the dashboard drive pressure without importing this module
+        if (typeof window !== 'undefined' && window.addEventListener) {
+            window.addEventListener('raft-pressure', (event) => {
+                const { nodeId, allocBytes = 0, burnMs = 0, release = false } = event.detail;
+                if (release) {
+                    this.releasePressure(nodeId);
+                } else {
+                    this.applyPressure(nodeId, allocBytes, burnMs);
+                }
+            });
+            window.addEventListener('raft-storage-inspect', () => this.inspectStorage());
+        }
+    }
+
+    /**
+     * get all node ids in the cluster
+     * @returns {number[]}
+     */
+    clusterNodes() {
+        return Array.from({ length: this.nodeCount }, (_, i) => i + 1);
+    }
+
+    /**
+     * spawn a worker for a node and initialize its wasm instance
+     * @param {number} nodeId
+     * @returns {Promise<number>} - instantiation time in ms
+     */
+    startNode(nodeId) {
+        if (this.workers.has(nodeId)) {
+            return Promise.resolve(0);
+        }
+
+        const worker = new Worker(new URL('./node-worker.js', import.meta.url), { type: 'module' });
+        this.workers.set(nodeId, worker);
+
+        return new Promise((resolve) => {
+            worker.onmessage = (event) => {
+                if (event.data.type === 'ready') {
+                    network.restartNode(nodeId);
+                    network.logEvent(`[WORKER] node ${nodeId} ready (${event.data.instantiateMs.toFixed(2)}ms)`);
+                    resolve(event.data.instantiateMs);
+                }
+                this.handleWorkerMessage(nodeId, event.data);
+            };
+
+            worker.onerror = (error) => {
+                network.logEvent(`[WORKER] node ${nodeId} crashed: ${error.message}`);
+                this.stopNode(nodeId);
+            };
+
+            worker.postMessage({ type: 'init', nodeId, clusterNodes: this.clusterNodes() });
+        });
+    }
+
+    /**
+     * crash a node by terminating its worker (all volatile state is lost)
+     * @param {number} nodeId
+     */
+    stopNode(nodeId) {
+        const worker = this.workers.get(nodeId);
+        if (!worker) return;
+
+        worker.terminate();
+        this.workers.delete(nodeId);
+        this.statuses.delete(nodeId);
+        network.killNode(nodeId);
+
+        this.emitStatus(nodeId, null);
+    }
+
+    /**
+     * restart a node in a fresh worker
+     * @param {number} nodeId
+     * @returns {Promise<number>}
+     */
+    async restartNode(nodeId) {
+        this.stopNode(nodeId);
+        return this.startNode(nodeId);
+    }
+
+    /**
+     * start every node and the shared tick loop
+     * @param {number} tickMs - how often each worker is ticked (default: 50)
+     * @returns {Promise<void>}
+     */
+    async startCluster(tickMs = 50) {
+        await Promise.all(this.clusterNodes().map(id => this.startNode(id)));
+
+        this.tickTimer = setInterval(() => {
+            for (const worker of this.workers.values()) {
+                worker.postMessage({ type: 'tick' });
+            }
+        }, tickMs);
+
+        network.logEvent(`[WORKER] cluster started with ${this.nodeCount} workers`);
+    }
+
+    /**
+     * terminate every worker and stop ticking
+     */
+    stopCluster() {
+        if (this.tickTimer) {
+            clearInterval(this.tickTimer);
+            this.tickTimer = null;
+        }
+
+        for (const nodeId of Array.from(this.workers.keys())) {
+            this.stopNode(nodeId);
+        }
+    }
+
+    /**
+     * deliver a raft message from one node to another
+     * @param {number} from - sender node id
+     * @param {number} to - receiver node id
+     * @param {object} message - wit raft-message
+     */
+    deliver(from, to, message) {
+        if (network.deadNodes.has(from) || network.deadNodes.has(to)) return;
+
+        if (!network.canCommunicate(from, to)) {
+            network.logEvent(`[NET] partition: ${from} → ${to} blocked`);
+            return;
+        }
+
+        const worker = this.workers.get(to);
+        if (!worker) return;
+
+        const post = () => worker.postMessage({ type: 'deliver', from, message });
+        if (network.latencyMs > 0) {
+            setTimeout(post, network.latencyMs);
+        } else {
+            post();
+        }
+    }
+
+    /**
+     * submit a command to the current leader's worker
+     * @param {string} command
+     * @returns {Promise<{success: boolean, leader: number|null, index?: number|null, leaderHint?: number|null}>}
+     */
+    submitCommand(command) {
+        const leader = this.leaderId();
+        if (leader === null) {
+            return Promise.resolve({ success: false, leader: null, error: 'no leader' });
+        }
+
+        const requestId = this.nextRequestId++;
+        return new Promise((resolve) => {
+            this.pendingSubmits.set(requestId, ({ accepted, index, leaderHint }) => {
+                resolve({ success: accepted, leader, index, leaderHint });
+            });
+            this.workers.get(leader).postMessage({ type: 'submit', requestId, command });
+        });
+    }
+
+    /**
+     * load one node's wasm instance with extra memory and per-tick cpu work
+     *
+     * pressure survives until released or the worker is restarted
+     * @param {number} nodeId
+     * @param {number} allocBytes - extra memory to hold (cumulative)
+     * @param {number} burnMs - ms to busy-wait on every tick
+     */
+    applyPressure(nodeId, allocBytes, burnMs) {
+        const worker = this.workers.get(nodeId);
+        if (!worker) return;
+
+        worker.postMessage({ type: 'pressure', allocBytes, burnMs });
+        network.logEvent(`[CHAOS] node ${nodeId} pressure: +${(allocBytes / 1048576).toFixed(1)}MB, ${burnMs}ms cpu per tick`);
+    }
+
+    /**
+     * drop all artificial pressure on a node
+     * @param {number} nodeId
+     */
+    releasePressure(nodeId) {
+        const worker = this.workers.get(nodeId);
+        if (!worker) return;
+
+        worker.postMessage({ type: 'release-pressure' });
+        network.logEvent(`[CHAOS] node ${nodeId} pressure released`);
+    }
+
+    /**
+     * find the node that last reported itself as leader
+     *
+     * falls back to the leader a follower reports, e.g. while the leader's
+     * own status is still in flight
+     * @returns {number | null}
+     */
+    leaderId() {
+        for (const [id, status] of this.statuses) {
+            if (status.state === 'leader') return id;
+        }
+        for (const status of this.statuses.values()) {
+            if (status.leaderId != null && this.workers.has(status.leaderId)) return status.leaderId;
+        }
+        return null;
+    }
+
+    /**
+     * read every node's indexeddb records and publish them for the ui
+     *
+     * reads storage directly, so records of crashed nodes show up too
+     * @returns {Promise<Array<object>>}
+     */
+    async inspectStorage() {
+        const records = await filesystem.listRecords();
+
+        if (typeof window !== 'undefined' && window.dispatchEvent) {
+            window.dispatchEvent(new CustomEvent('raft-storage-records', { detail: { records } }));
+        }
+        return records;
+    }
+
+    // -- helpers --
+
+    /**
+     * handle a message posted back by a worker
+     * @param {number} nodeId
+     * @param {object} data
+     */
+    handleWorkerMessage(nodeId, data) {
+        switch (data.type) {
+            case 'status':
+                this.statuses.set(nodeId, data.status);
+                this.emitStatus(nodeId, data.status);
+                break;
+
+            case 'send':
+                this.deliver(nodeId, data.to, data.message);
+                break;
+
+            case 'submitted': {
+                const resolve = this.pendingSubmits.get(data.requestId);
+                if (resolve) {
+                    this.pendingSubmits.delete(data.requestId);
+                    resolve(data);
+                }
+                break;
+            }
+
+            case 'error':
+                network.logEvent(`[WORKER] node ${nodeId}: ${data.error}`);
+                break;
+        }
+    }
+
+    /**
+     * emit a node status change for the ui
+     * @param {number} nodeId
+     * @param {object | null} status - null when the worker is gone
+     */
+    emitStatus(nodeId, status) {
+        if (this.onNodeStatus) {
+            this.onNodeStatus(nodeId, status);
+        }
+
+        if (typeof window !== 'undefined' && window.dispatchEvent) {
+            window.dispatchEvent(new CustomEvent('raft-worker-status', {
+                detail: { nodeId, status: status ?? { state: 'dead' } }
+            }));
+        }
+    }
+}
+
+// singleton instance
+export const workerCluster = new WorkerCluster(3);